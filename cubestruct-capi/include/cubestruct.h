@@ -0,0 +1,73 @@
+/*
+ * C interface to cubestruct: 3x3x3 cube states, scrambles and solving, the same as in
+ * PuzzleTime.
+ *
+ * Moves are strings in WCA notation, separated by spaces (e.g. "R U R' U2"). Strings
+ * returned by these functions are owned by the caller, and must be freed with
+ * cubestruct_string_free(). Functions that return pointers return NULL on failure.
+ *
+ * Functions only ever get added to this header, so a program built against one version
+ * works with any later version that has the same CUBESTRUCT_CAPI_VERSION.
+ */
+#ifndef CUBESTRUCT_H
+#define CUBESTRUCT_H
+
+#include <stdbool.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Changes if a function's behavior or signature ever changes incompatibly */
+#define CUBESTRUCT_CAPI_VERSION 1
+
+/* CUBESTRUCT_CAPI_VERSION of the library, to check against the header */
+uint32_t cubestruct_capi_version(void);
+
+/* A cube's state */
+typedef struct CubestructCube CubestructCube;
+
+/* A solved cube. Free with cubestruct_cube_free(). */
+CubestructCube *cubestruct_cube_new(void);
+
+/* A copy of `cube` */
+CubestructCube *cubestruct_cube_clone(const CubestructCube *cube);
+
+/* Parse the format of cubestruct_cube_facelets(). NULL if the string isn't valid. */
+CubestructCube *cubestruct_cube_from_facelets(const char *facelets);
+
+/* Does nothing if `cube` is NULL */
+void cubestruct_cube_free(CubestructCube *cube);
+
+/* Turn `cube` with `moves`. Returns false, leaving the cube unchanged, if `moves` isn't
+ * valid notation. */
+bool cubestruct_cube_apply(CubestructCube *cube, const char *moves);
+
+bool cubestruct_cube_is_solved(const CubestructCube *cube);
+
+bool cubestruct_cube_equal(const CubestructCube *a, const CubestructCube *b);
+
+/* Each of the 54 stickers as the letter of the face it belongs on, face by face in URFDLB
+ * order */
+char *cubestruct_cube_facelets(const CubestructCube *cube);
+
+/* Moves that solve the cube, usually in the low 20s. NULL if the state can't be reached
+ * by turning (e.g. a twisted corner). */
+char *cubestruct_cube_solve(const CubestructCube *cube);
+
+/* A scramble to a random state */
+char *cubestruct_random_state_scramble(void);
+
+/* The scramble at `index` of the stream of scrambles made from `seed`. The same on every
+ * platform, so that everyone can get the same scrambles. */
+char *cubestruct_seeded_scramble(uint64_t seed, uint64_t index);
+
+/* Does nothing if `s` is NULL */
+void cubestruct_string_free(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* CUBESTRUCT_H */
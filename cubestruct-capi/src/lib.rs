@@ -0,0 +1,180 @@
+//! C interface to cubestruct, declared in `include/cubestruct.h`, for timers and apps that
+//! aren't written in Rust. The header is the documentation; this only adds how each
+//! function's pointers have to be valid.
+use cubestruct::{CubieCube, Move, MoveSequence};
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+/// `CUBESTRUCT_CAPI_VERSION` in the header
+const CAPI_VERSION: u32 = 1;
+
+/// Opaque to C
+pub struct CubestructCube(CubieCube);
+
+fn into_c_string(s: String) -> *mut c_char {
+    // move notation and facelets never contain NUL
+    CString::new(s).unwrap().into_raw()
+}
+
+fn render(moves: &[Move]) -> *mut c_char {
+    into_c_string(MoveSequence::new(moves).to_string())
+}
+
+/// # Safety
+///
+/// `s` must be a valid NUL-terminated string
+unsafe fn str_arg<'a>(s: *const c_char) -> Option<&'a str> {
+    CStr::from_ptr(s).to_str().ok()
+}
+
+#[no_mangle]
+pub extern "C" fn cubestruct_capi_version() -> u32 {
+    CAPI_VERSION
+}
+
+#[no_mangle]
+pub extern "C" fn cubestruct_cube_new() -> *mut CubestructCube {
+    Box::into_raw(Box::new(CubestructCube(CubieCube::SOLVED)))
+}
+
+/// # Safety
+///
+/// `cube` must be a valid cube
+#[no_mangle]
+pub unsafe extern "C" fn cubestruct_cube_clone(cube: *const CubestructCube) -> *mut CubestructCube {
+    Box::into_raw(Box::new(CubestructCube((*cube).0)))
+}
+
+/// # Safety
+///
+/// `facelets` must be a valid NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn cubestruct_cube_from_facelets(
+    facelets: *const c_char,
+) -> *mut CubestructCube {
+    match str_arg(facelets).and_then(CubieCube::from_facelet_string) {
+        Some(cube) => Box::into_raw(Box::new(CubestructCube(cube))),
+        None => ptr::null_mut(),
+    }
+}
+
+/// # Safety
+///
+/// `cube` must be NULL or a cube that hasn't been freed yet
+#[no_mangle]
+pub unsafe extern "C" fn cubestruct_cube_free(cube: *mut CubestructCube) {
+    if !cube.is_null() {
+        drop(Box::from_raw(cube));
+    }
+}
+
+/// # Safety
+///
+/// `cube` must be a valid cube, and `moves` a valid NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn cubestruct_cube_apply(
+    cube: *mut CubestructCube,
+    moves: *const c_char,
+) -> bool {
+    match str_arg(moves).map(cubestruct::parse_moves) {
+        Some(Ok(moves)) => {
+            (*cube).0.apply_moves(&moves);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// # Safety
+///
+/// `cube` must be a valid cube
+#[no_mangle]
+pub unsafe extern "C" fn cubestruct_cube_is_solved(cube: *const CubestructCube) -> bool {
+    (*cube).0 == CubieCube::SOLVED
+}
+
+/// # Safety
+///
+/// `a` and `b` must be valid cubes
+#[no_mangle]
+pub unsafe extern "C" fn cubestruct_cube_equal(
+    a: *const CubestructCube,
+    b: *const CubestructCube,
+) -> bool {
+    (*a).0 == (*b).0
+}
+
+/// # Safety
+///
+/// `cube` must be a valid cube
+#[no_mangle]
+pub unsafe extern "C" fn cubestruct_cube_facelets(cube: *const CubestructCube) -> *mut c_char {
+    into_c_string((*cube).0.to_facelet_string())
+}
+
+/// # Safety
+///
+/// `cube` must be a valid cube
+#[no_mangle]
+pub unsafe extern "C" fn cubestruct_cube_solve(cube: *const CubestructCube) -> *mut c_char {
+    match (*cube).0.solve() {
+        Some(solution) => render(&solution),
+        None => ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn cubestruct_random_state_scramble() -> *mut c_char {
+    render(&cubestruct::random_state_scramble())
+}
+
+#[no_mangle]
+pub extern "C" fn cubestruct_seeded_scramble(seed: u64, index: u64) -> *mut c_char {
+    render(&cubestruct::seeded_scramble::<CubieCube>(seed, index))
+}
+
+/// # Safety
+///
+/// `s` must be NULL or a string returned by this library that hasn't been freed yet
+#[no_mangle]
+pub unsafe extern "C" fn cubestruct_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn take_string(s: *mut c_char) -> String {
+        let owned = unsafe { CStr::from_ptr(s) }.to_str().unwrap().to_string();
+        unsafe { cubestruct_string_free(s) };
+        owned
+    }
+
+    #[test]
+    fn cube() {
+        unsafe {
+            let cube = cubestruct_cube_new();
+            assert!(cubestruct_cube_apply(cube, c"R U R' U'".as_ptr()));
+            assert!(!cubestruct_cube_apply(cube, c"R Q".as_ptr()));
+            assert!(!cubestruct_cube_is_solved(cube));
+
+            let facelets = CString::new(take_string(cubestruct_cube_facelets(cube))).unwrap();
+            let parsed = cubestruct_cube_from_facelets(facelets.as_ptr());
+            assert!(cubestruct_cube_equal(cube, parsed));
+            cubestruct_cube_free(parsed);
+            assert!(cubestruct_cube_from_facelets(c"UUU".as_ptr()).is_null());
+
+            let solution = CString::new(take_string(cubestruct_cube_solve(cube))).unwrap();
+            assert!(cubestruct_cube_apply(cube, solution.as_ptr()));
+            assert!(cubestruct_cube_is_solved(cube));
+            cubestruct_cube_free(cube);
+        }
+        assert_eq!(
+            take_string(cubestruct_seeded_scramble(7, 3)),
+            take_string(cubestruct_seeded_scramble(7, 3))
+        );
+    }
+}
@@ -0,0 +1,153 @@
+//! Python bindings for cubestruct, so that exported solves can be analyzed with the same
+//! cube math as the app. Moves are passed back and forth as strings in WCA notation.
+//!
+//! ```python
+//! import cubestruct
+//!
+//! cube = cubestruct.Cube("R U R' U'")
+//! cube.apply("U R U' R'")
+//! assert cube.is_solved()
+//! print(cubestruct.Cube(cubestruct.random_state_scramble()).solve())
+//! ```
+use cubestruct::{CubieCube, Move, MoveSequence};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+fn parse(moves: &str) -> PyResult<Vec<Move>> {
+    cubestruct::parse_moves(moves).map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+fn render(moves: &[Move]) -> String {
+    MoveSequence::new(moves).to_string()
+}
+
+/// A 3x3x3 cube's state
+#[pyclass(eq)]
+#[derive(Debug, Clone, PartialEq)]
+struct Cube(CubieCube);
+
+#[pymethods]
+impl Cube {
+    /// A solved cube with `moves` applied
+    #[new]
+    #[pyo3(signature = (moves = ""))]
+    fn new(moves: &str) -> PyResult<Self> {
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_moves(&parse(moves)?);
+        Ok(Self(cube))
+    }
+
+    /// Parse the format of `facelets()`
+    #[staticmethod]
+    fn from_facelets(facelets: &str) -> PyResult<Self> {
+        CubieCube::from_facelet_string(facelets)
+            .map(Self)
+            .ok_or_else(|| PyValueError::new_err("not a cube's facelets"))
+    }
+
+    fn apply(&mut self, moves: &str) -> PyResult<()> {
+        self.0.apply_moves(&parse(moves)?);
+        Ok(())
+    }
+
+    fn is_solved(&self) -> bool {
+        self.0 == CubieCube::SOLVED
+    }
+
+    /// Whether the state can be reached by turning (as opposed to e.g. reassembling a cube
+    /// with a twisted corner)
+    fn is_possible(&self) -> bool {
+        self.0.is_possible_state()
+    }
+
+    /// Moves that solve the cube, usually in the low 20s, or `None` if it isn't possible
+    fn solve(&self) -> Option<String> {
+        self.0.solve().as_deref().map(render)
+    }
+
+    /// The move that turns this state into `other`, or `None` if it takes more than one
+    fn move_to(&self, other: &Cube) -> Option<String> {
+        self.0.move_to(&other.0).map(|moov| moov.to_string())
+    }
+
+    fn inverse(&self) -> Self {
+        Self(self.0.inverse())
+    }
+
+    /// Each sticker as the letter of the face it belongs on, face by face in URFDLB order
+    fn facelets(&self) -> String {
+        self.0.to_facelet_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Cube.from_facelets('{}')", self.0.to_facelet_string())
+    }
+}
+
+/// Moves in a standard form, e.g. with extra spaces removed
+#[pyfunction]
+fn parse_moves(moves: &str) -> PyResult<String> {
+    Ok(render(&parse(moves)?))
+}
+
+/// A scramble to a random state, like the app's
+#[pyfunction]
+fn random_state_scramble() -> String {
+    render(&cubestruct::random_state_scramble())
+}
+
+/// A scramble of random moves
+#[pyfunction]
+fn random_move_scramble() -> String {
+    render(&cubestruct::random_move_scramble())
+}
+
+/// The scramble at `index` (counting from 0) of the session seeded with `seed`, the same as
+/// the desktop app's with that scramble seed
+#[pyfunction]
+fn seeded_scramble(seed: &str, index: u64) -> String {
+    render(&cubestruct::seeded_scramble::<CubieCube>(
+        cubestruct::seed_from_text(seed),
+        index,
+    ))
+}
+
+/// Moves that solve the cube after `scramble`
+#[pyfunction]
+fn solve(scramble: &str) -> PyResult<String> {
+    let cube = Cube::new(scramble)?;
+    // cubes turned from solved can always be solved
+    Ok(cube.solve().unwrap())
+}
+
+#[pymodule]
+#[pyo3(name = "cubestruct")]
+fn python_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Cube>()?;
+    m.add_function(wrap_pyfunction!(parse_moves, m)?)?;
+    m.add_function(wrap_pyfunction!(random_state_scramble, m)?)?;
+    m.add_function(wrap_pyfunction!(random_move_scramble, m)?)?;
+    m.add_function(wrap_pyfunction!(seeded_scramble, m)?)?;
+    m.add_function(wrap_pyfunction!(solve, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cube() {
+        let mut cube = Cube::new("R U R' U'").unwrap();
+        assert!(!cube.is_solved());
+        assert_eq!(Cube::from_facelets(&cube.facelets()).unwrap(), cube);
+        cube.apply("U R U' R'").unwrap();
+        assert!(cube.is_solved());
+        assert!(Cube::new("R Q").is_err());
+
+        let mut scrambled = Cube::new(&random_state_scramble()).unwrap();
+        scrambled.apply(&scrambled.solve().unwrap()).unwrap();
+        assert!(scrambled.is_solved());
+        assert_eq!(parse_moves("R  U2 ").unwrap(), "R U2");
+    }
+}
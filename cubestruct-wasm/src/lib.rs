@@ -0,0 +1,118 @@
+//! cubestruct as a WebAssembly module with a small JavaScript API, for web tools that have
+//! to agree with the desktop app, e.g. showing the scrambles of a seeded session. Build it
+//! with `wasm-pack build --target web`. Moves are strings in WCA notation.
+//!
+//! ```js
+//! import init, { Cube, seededScramble } from "./pkg/cubestruct_wasm.js";
+//!
+//! await init();
+//! // the same scramble as the desktop app's first one with the scramble seed "league"
+//! const scramble = seededScramble("league", 0);
+//! const cube = new Cube(scramble);
+//! console.log(cube.facelets(), cube.solve());
+//! ```
+//!
+//! Solving builds the solver's tables the first time, which takes a few seconds, so it's
+//! best done in a worker.
+use cubestruct::{CubieCube, Move, MoveSequence};
+use wasm_bindgen::prelude::*;
+
+fn parse(moves: &str) -> Result<Vec<Move>, JsError> {
+    cubestruct::parse_moves(moves).map_err(|e| JsError::new(&e.to_string()))
+}
+
+fn render(moves: &[Move]) -> String {
+    MoveSequence::new(moves).to_string()
+}
+
+/// A 3x3x3 cube's state
+#[wasm_bindgen]
+pub struct Cube(CubieCube);
+
+#[wasm_bindgen]
+impl Cube {
+    /// A solved cube, with `moves` applied if given
+    #[wasm_bindgen(constructor)]
+    pub fn new(moves: Option<String>) -> Result<Cube, JsError> {
+        let mut cube = CubieCube::SOLVED;
+        if let Some(moves) = moves {
+            cube.apply_moves(&parse(&moves)?);
+        }
+        Ok(Self(cube))
+    }
+
+    /// Parse the format of `facelets()`
+    #[wasm_bindgen(js_name = fromFacelets)]
+    pub fn from_facelets(facelets: &str) -> Result<Cube, JsError> {
+        CubieCube::from_facelet_string(facelets)
+            .map(Self)
+            .ok_or_else(|| JsError::new("Not a cube's facelets"))
+    }
+
+    pub fn apply(&mut self, moves: &str) -> Result<(), JsError> {
+        self.0.apply_moves(&parse(moves)?);
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = isSolved)]
+    pub fn is_solved(&self) -> bool {
+        self.0 == CubieCube::SOLVED
+    }
+
+    pub fn equals(&self, other: &Cube) -> bool {
+        self.0 == other.0
+    }
+
+    /// Moves that solve the cube, usually in the low 20s, or `undefined` if the state can't
+    /// be reached by turning
+    pub fn solve(&self) -> Option<String> {
+        self.0.solve().as_deref().map(render)
+    }
+
+    /// Each sticker as the letter of the face it belongs on, face by face in URFDLB order
+    pub fn facelets(&self) -> String {
+        self.0.to_facelet_string()
+    }
+}
+
+/// Moves in a standard form, e.g. with extra spaces removed
+#[wasm_bindgen(js_name = parseMoves)]
+pub fn parse_moves(moves: &str) -> Result<String, JsError> {
+    Ok(render(&parse(moves)?))
+}
+
+/// A scramble to a random state
+#[wasm_bindgen(js_name = randomStateScramble)]
+pub fn random_state_scramble() -> String {
+    render(&cubestruct::random_state_scramble())
+}
+
+/// The scramble at `index` (counting from 0) of the session seeded with `seed`, the same as
+/// the desktop app's with that scramble seed
+#[wasm_bindgen(js_name = seededScramble)]
+pub fn seeded_scramble(seed: &str, index: u32) -> String {
+    render(&cubestruct::seeded_scramble::<CubieCube>(
+        cubestruct::seed_from_text(seed),
+        u64::from(index),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // errors can only be made in a JavaScript engine, so only what succeeds is tested here
+    #[test]
+    fn cube() {
+        let scramble = seeded_scramble("league", 0);
+        assert_eq!(scramble, seeded_scramble("league", 0));
+        assert_ne!(scramble, seeded_scramble("league", 1));
+
+        let mut cube = Cube::new(Some(scramble)).unwrap();
+        let copy = Cube::from_facelets(&cube.facelets()).unwrap();
+        assert!(cube.equals(&copy));
+        cube.apply(&cube.solve().unwrap()).unwrap();
+        assert!(cube.is_solved());
+        assert_eq!(parse_moves("R  U2 ").unwrap(), "R U2");
+    }
+}
@@ -0,0 +1,132 @@
+//! Benchmarks of the cube operations, as a baseline for changes to how cubes are
+//! represented. Run with `cargo bench --features bench`.
+//!
+//! Criterion keeps the results in `target/criterion`, and reports the change from the
+//! previous run. To compare against a fixed point instead (e.g. from before a redesign),
+//! save it with `cargo bench --features bench -- --save-baseline <name>` and compare later
+//! runs to it with `-- --baseline <name>`.
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use cubestruct::{bench, CubieCube, Move};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::hint::black_box;
+
+/// The same cubes on every run, so that runs can be compared
+fn cubes(n: usize) -> Vec<CubieCube> {
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    (0..n)
+        .map(|_| CubieCube::random_possible_with(&mut rng))
+        .collect()
+}
+
+fn moves(c: &mut Criterion) {
+    let cube = cubes(1)[0];
+    c.bench_function("apply_move", |b| {
+        b.iter(|| {
+            let mut cube = black_box(cube);
+            cube.apply_move(black_box(Move::R));
+            cube
+        })
+    });
+    let scramble = cubestruct::seeded_scramble::<CubieCube>(0, 0);
+    c.bench_function("apply_moves/scramble", |b| {
+        b.iter(|| {
+            let mut cube = CubieCube::SOLVED;
+            cube.apply_moves(black_box(&scramble));
+            cube
+        })
+    });
+}
+
+fn multiplication(c: &mut Criterion) {
+    let [a, b] = cubes(2)[..] else { unreachable!() };
+    c.bench_function("multiply", |bencher| {
+        bencher.iter(|| black_box(a) * black_box(b))
+    });
+    c.bench_function("inverse", |bencher| bencher.iter(|| black_box(a).inverse()));
+}
+
+fn coordinates(c: &mut Criterion) {
+    let cube = cubes(1)[0];
+    // phase 2 coordinates are only meaningful in its subgroup
+    let mut phase2_cube = CubieCube::SOLVED;
+    phase2_cube.apply_moves(&{
+        use Move::*;
+        [U, R2, D, F2, Ui, L2, B2, D2, R2, U2]
+    });
+
+    let mut group = c.benchmark_group("coordinates");
+    group.bench_function("get/phase1", |b| {
+        b.iter(|| bench::phase1_coords(black_box(&cube)))
+    });
+    let coords = bench::phase1_coords(&cube);
+    group.bench_function("set/phase1", |b| {
+        b.iter(|| {
+            let mut cube = CubieCube::SOLVED;
+            bench::set_phase1_coords(&mut cube, black_box(coords));
+            cube
+        })
+    });
+    group.bench_function("get/phase2", |b| {
+        b.iter(|| bench::phase2_coords(black_box(&phase2_cube)))
+    });
+    let coords = bench::phase2_coords(&phase2_cube);
+    group.bench_function("set/phase2", |b| {
+        b.iter(|| {
+            let mut cube = CubieCube::SOLVED;
+            bench::set_phase2_coords(&mut cube, black_box(coords));
+            cube
+        })
+    });
+    group.finish();
+}
+
+fn facelets(c: &mut Criterion) {
+    let cube = cubes(1)[0];
+    let mut group = c.benchmark_group("facelets");
+    group.bench_function("from_cubie_cube", |b| {
+        b.iter(|| black_box(&cube).to_facelet_cube())
+    });
+    // what happens for every smart cube packet
+    group.bench_function("to_cubie_cube", |b| {
+        b.iter_batched_ref(
+            || cube.to_facelet_cube(),
+            |facelets| facelets.to_cubie_cube().unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+    let string = cube.to_facelet_string();
+    group.bench_function("from_string", |b| {
+        b.iter(|| CubieCube::from_facelet_string(black_box(&string)))
+    });
+    group.finish();
+}
+
+fn solving(c: &mut Criterion) {
+    // building the tables is a one-time cost that isn't part of solving
+    cubestruct::init_solver_tables(|_| {});
+    let cubes = cubes(10);
+    let mut group = c.benchmark_group("solving");
+    group.sample_size(10);
+    group.bench_function("solve/10 cubes", |b| {
+        b.iter(|| {
+            for cube in &cubes {
+                black_box(cube.solve());
+            }
+        })
+    });
+    group.bench_function("random_state_scramble", |b| {
+        b.iter(cubestruct::random_state_scramble)
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    moves,
+    multiplication,
+    coordinates,
+    facelets,
+    solving
+);
+criterion_main!(benches);
@@ -0,0 +1,28 @@
+//! Converting stickers to a cube, which happens for every smart cube packet, with arbitrary
+//! input. Run with `cargo fuzz run facelets`.
+#![no_main]
+
+use cubestruct::{Color, CubieCube, FaceletCube};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // as each sticker's color, like a smart cube reports them
+    if let Some(stickers) = data.get(..54) {
+        let mut builder = FaceletCube::builder();
+        for (i, &byte) in stickers.iter().enumerate() {
+            let colors = Color::all();
+            builder.set(colors[i / 9], i % 9, colors[byte as usize % colors.len()]);
+        }
+        let facelets = builder.build().unwrap();
+        if let Ok(cube) = facelets.to_cubie_cube() {
+            assert_eq!(cube.to_facelet_cube(), facelets);
+        }
+    }
+
+    // as a facelet string
+    if let Ok(s) = std::str::from_utf8(data) {
+        if let Some(cube) = CubieCube::from_facelet_string(s) {
+            assert_eq!(cube.to_facelet_string(), s);
+        }
+    }
+});
@@ -0,0 +1,36 @@
+//! Internals that the benchmarks in `benches/` measure, which aren't otherwise public. Only
+//! built with the `bench` feature.
+use crate::cubie::{Corners, Edges};
+use crate::CubieCube;
+
+/// Phase 1's coordinates: corner orientation, edge orientation and the positions of the
+/// equator edges
+pub fn phase1_coords(cube: &CubieCube) -> [u16; 3] {
+    [
+        cube.get_ori_coord::<Corners>(),
+        cube.get_ori_coord::<Edges>(),
+        cube.get_udslice_coord(),
+    ]
+}
+
+pub fn set_phase1_coords(cube: &mut CubieCube, [corner_ori, edge_ori, udslice]: [u16; 3]) {
+    cube.set_ori_coord::<Corners>(corner_ori);
+    cube.set_ori_coord::<Edges>(edge_ori);
+    cube.set_udslice_coord(udslice);
+}
+
+/// Phase 2's coordinates: corner permutation, U and D layer edge permutation and equator
+/// edge permutation. Only meaningful for cubes in the phase 2 subgroup.
+pub fn phase2_coords(cube: &CubieCube) -> [u16; 3] {
+    [
+        cube.get_corner_perm_coord(),
+        cube.get_ud_edge_perm_coord(),
+        cube.get_slice_perm_coord(),
+    ]
+}
+
+pub fn set_phase2_coords(cube: &mut CubieCube, [corner_perm, ud_edge_perm, slice_perm]: [u16; 3]) {
+    cube.set_corner_perm_coord(corner_perm);
+    cube.set_ud_edge_perm_coord(ud_edge_perm);
+    cube.set_slice_perm_coord(slice_perm);
+}
@@ -0,0 +1,24 @@
+//! Generates the corner pattern database, so that it can be distributed prebuilt instead
+//! of being generated by each program that uses it. Run with
+//! `cargo run --release --bin pattern-db -- <output file>`.
+use cubestruct::CornerPatternDb;
+use std::fs::File;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let Some(path) = std::env::args_os().nth(1) else {
+        eprintln!("Usage: pattern-db <output file>");
+        return ExitCode::FAILURE;
+    };
+
+    let db = CornerPatternDb::generate(|fraction| {
+        eprintln!("{:.1}% of states reached", fraction * 100.0);
+    });
+    match File::create(&path).and_then(|file| db.write(file)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Couldn't write {}: {e}", path.to_string_lossy());
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -1,13 +1,24 @@
 use crate::cubie::*;
+use crate::search::{distance_table, Coords};
 use crate::{CubieCube, Move};
 use std::ops::{Index, IndexMut, Range};
 use std::sync::OnceLock;
 
-const NUM_CORNER_ORIS: u16 = 2187;
+pub(crate) const NUM_CORNER_ORIS: u16 = 2187;
 const NUM_EDGE_ORIS: u16 = 2048;
 const NUM_UDSLICES: u16 = 495;
+pub(crate) const NUM_CORNER_PERMS: u16 = 40320;
+const NUM_UD_EDGE_PERMS: u16 = 40320;
+const NUM_SLICE_PERMS: u16 = 24;
 
-#[derive(Debug)]
+/// The moves that keep a cube in the phase 2 subgroup
+pub(crate) const PHASE2_MOVES: [Move; 10] = {
+    use Move::*;
+    [U, Ui, U2, D, Di, D2, R2, L2, F2, B2]
+};
+
+/// Phase 1 coordinates. Any cube can be described by these.
+#[derive(Debug, Clone)]
 pub struct CoordCube {
     /// Corner orientation ("twist" coordinate)
     /// Used in phase 1
@@ -25,7 +36,7 @@ impl CoordCube {
     pub(crate) const EDGE_ORI_RANGE: Range<u16> = 0..NUM_EDGE_ORIS;
     pub(crate) const UDSLICE_RANGE: Range<u16> = 0..NUM_UDSLICES;
 
-    fn from_cubie_cube(cubie_cube: &CubieCube) -> Self {
+    pub(crate) fn from_cubie_cube(cubie_cube: &CubieCube) -> Self {
         Self {
             corner_ori: cubie_cube.get_ori_coord::<Corners>(),
             edge_ori: cubie_cube.get_ori_coord::<Edges>(),
@@ -33,6 +44,7 @@ impl CoordCube {
         }
     }
 
+    #[allow(dead_code)]
     fn to_cubie_cube(&self) -> CubieCube {
         // XXX: this pattern is here as a reminder to keep
         // this method up to date as new coords are added :-)
@@ -49,8 +61,12 @@ impl CoordCube {
         ret.set_udslice_coord(*udslice);
         ret
     }
+}
 
-    pub fn apply_move(&mut self, moov: Move) {
+impl Coords for CoordCube {
+    type Move = Move;
+
+    fn apply_move(&mut self, moov: Move) {
         // XXX: this pattern is here as a reminder to keep
         // this method up to date as new coords are added :-)
         #[deny(unused_variables)]
@@ -64,6 +80,120 @@ impl CoordCube {
         *edge_ori = edge_ori_move_table()[moov][*edge_ori as usize];
         *udslice = udslice_move_table()[moov][*udslice as usize];
     }
+
+    /// A lower bound on the number of moves needed to reach the phase 2 subgroup
+    fn distance_bound(&self) -> u8 {
+        let udslice_count = NUM_UDSLICES as usize;
+        let twist = corner_ori_udslice_pruning_table()
+            [self.corner_ori as usize * udslice_count + self.udslice as usize];
+        let flip = edge_ori_udslice_pruning_table()
+            [self.edge_ori as usize * udslice_count + self.udslice as usize];
+        twist.max(flip)
+    }
+}
+
+/// Phase 2 coordinates. Only cubes in the phase 2 subgroup (all pieces oriented and the
+/// equator edges in the equator) can be described by these.
+#[derive(Debug, Copy, Clone)]
+pub struct Phase2Cube {
+    corner_perm: u16,
+    /// Permutation of the U and D layer edges
+    ud_edge_perm: u16,
+    /// Permutation of the equator edges
+    slice_perm: u16,
+}
+
+impl Phase2Cube {
+    pub(crate) fn from_cubie_cube(cubie_cube: &CubieCube) -> Self {
+        Self {
+            corner_perm: cubie_cube.get_corner_perm_coord(),
+            ud_edge_perm: cubie_cube.get_ud_edge_perm_coord(),
+            slice_perm: cubie_cube.get_slice_perm_coord(),
+        }
+    }
+}
+
+impl Coords for Phase2Cube {
+    type Move = Move;
+
+    /// `moov` must be one of [`PHASE2_MOVES`]
+    fn apply_move(&mut self, moov: Move) {
+        debug_assert!(PHASE2_MOVES.contains(&moov), "{moov} isn't a phase 2 move");
+
+        #[deny(unused_variables)]
+        let Self {
+            corner_perm,
+            ud_edge_perm,
+            slice_perm,
+        } = self;
+
+        *corner_perm = corner_perm_move_table()[moov][*corner_perm as usize];
+        *ud_edge_perm = ud_edge_perm_move_table()[moov][*ud_edge_perm as usize];
+        *slice_perm = slice_perm_move_table()[moov][*slice_perm as usize];
+    }
+
+    /// A lower bound on the number of phase 2 moves needed to solve the cube
+    fn distance_bound(&self) -> u8 {
+        let slice_count = NUM_SLICE_PERMS as usize;
+        let corners = corner_perm_slice_pruning_table()
+            [self.corner_perm as usize * slice_count + self.slice_perm as usize];
+        let edges = ud_edge_perm_slice_pruning_table()
+            [self.ud_edge_perm as usize * slice_count + self.slice_perm as usize];
+        corners.max(edges)
+    }
+}
+
+/// Builds each of the solver's tables, in the order they depend on each other
+pub(crate) const TABLES: [fn(); 10] = [
+    || {
+        udslice_move_table();
+    },
+    || {
+        edge_ori_move_table();
+    },
+    || {
+        corner_ori_move_table();
+    },
+    || {
+        corner_perm_move_table();
+    },
+    || {
+        ud_edge_perm_move_table();
+    },
+    || {
+        slice_perm_move_table();
+    },
+    || {
+        corner_ori_udslice_pruning_table();
+    },
+    || {
+        edge_ori_udslice_pruning_table();
+    },
+    || {
+        corner_perm_slice_pruning_table();
+    },
+    || {
+        ud_edge_perm_slice_pruning_table();
+    },
+];
+
+static CORNER_ORI_UDSLICE_PRUNING: OnceLock<Vec<u8>> = OnceLock::new();
+static EDGE_ORI_UDSLICE_PRUNING: OnceLock<Vec<u8>> = OnceLock::new();
+static CORNER_PERM_SLICE_PRUNING: OnceLock<Vec<u8>> = OnceLock::new();
+static UD_EDGE_PERM_SLICE_PRUNING: OnceLock<Vec<u8>> = OnceLock::new();
+
+/// The pruning tables, which are most of the time building the tables takes, with how many
+/// entries each has. They can be set from a file instead of being built (see
+/// [`crate::table_cache`]).
+pub(crate) fn pruning_tables() -> [(&'static OnceLock<Vec<u8>>, usize); 4] {
+    let phase1 = |size_a: u16| size_a as usize * NUM_UDSLICES as usize;
+    let phase2 = |size_a: u16| size_a as usize * NUM_SLICE_PERMS as usize;
+    [
+        (&CORNER_ORI_UDSLICE_PRUNING, phase1(NUM_CORNER_ORIS)),
+        (&EDGE_ORI_UDSLICE_PRUNING, phase1(NUM_EDGE_ORIS)),
+        (&CORNER_PERM_SLICE_PRUNING, phase2(NUM_CORNER_PERMS)),
+        (&UD_EDGE_PERM_SLICE_PRUNING, phase2(NUM_UD_EDGE_PERMS)),
+    ]
 }
 
 fn udslice_move_table() -> &'static MoveTable<[u16; NUM_UDSLICES as usize]> {
@@ -100,7 +230,7 @@ fn edge_ori_move_table() -> &'static MoveTable<[u16; NUM_EDGE_ORIS as usize]> {
     })
 }
 
-fn corner_ori_move_table() -> &'static MoveTable<[u16; NUM_CORNER_ORIS as usize]> {
+pub(crate) fn corner_ori_move_table() -> &'static MoveTable<[u16; NUM_CORNER_ORIS as usize]> {
     static TABLE: OnceLock<MoveTable<[u16; NUM_CORNER_ORIS as usize]>> = OnceLock::new();
 
     TABLE.get_or_init(|| {
@@ -117,8 +247,141 @@ fn corner_ori_move_table() -> &'static MoveTable<[u16; NUM_CORNER_ORIS as usize]
     })
 }
 
+pub(crate) fn corner_perm_move_table() -> &'static MoveTable<Vec<u16>> {
+    static TABLE: OnceLock<MoveTable<Vec<u16>>> = OnceLock::new();
+
+    TABLE.get_or_init(|| {
+        // too big for the stack
+        let mut tbl = MoveTable(std::array::from_fn(|_| vec![0; NUM_CORNER_PERMS as usize]));
+        let mut cc = CubieCube::SOLVED;
+        for coord in 0..NUM_CORNER_PERMS {
+            for moov in Move::all() {
+                cc.set_corner_perm_coord(coord);
+                cc.apply_move(moov);
+                tbl[moov][coord as usize] = cc.get_corner_perm_coord();
+            }
+        }
+        tbl
+    })
+}
+
+/// Only has entries for [`PHASE2_MOVES`]
+fn ud_edge_perm_move_table() -> &'static MoveTable<Vec<u16>> {
+    static TABLE: OnceLock<MoveTable<Vec<u16>>> = OnceLock::new();
+
+    TABLE.get_or_init(|| {
+        // too big for the stack
+        let mut tbl = MoveTable(std::array::from_fn(|_| vec![0; NUM_UD_EDGE_PERMS as usize]));
+        let mut cc = CubieCube::SOLVED;
+        for coord in 0..NUM_UD_EDGE_PERMS {
+            for moov in PHASE2_MOVES {
+                cc.set_ud_edge_perm_coord(coord);
+                cc.apply_move(moov);
+                tbl[moov][coord as usize] = cc.get_ud_edge_perm_coord();
+            }
+        }
+        tbl
+    })
+}
+
+/// Only has entries for [`PHASE2_MOVES`]
+fn slice_perm_move_table() -> &'static MoveTable<[u16; NUM_SLICE_PERMS as usize]> {
+    static TABLE: OnceLock<MoveTable<[u16; NUM_SLICE_PERMS as usize]>> = OnceLock::new();
+
+    TABLE.get_or_init(|| {
+        let mut tbl = MoveTable([[0; NUM_SLICE_PERMS as usize]; 18]);
+        let mut cc = CubieCube::SOLVED;
+        for coord in 0..NUM_SLICE_PERMS {
+            for moov in PHASE2_MOVES {
+                cc.set_slice_perm_coord(coord);
+                cc.apply_move(moov);
+                tbl[moov][coord as usize] = cc.get_slice_perm_coord();
+            }
+        }
+        tbl
+    })
+}
+
+fn corner_ori_udslice_pruning_table() -> &'static [u8] {
+    CORNER_ORI_UDSLICE_PRUNING.get_or_init(|| {
+        pruning_table(
+            (0, CubieCube::SOLVED.get_udslice_coord()),
+            (NUM_CORNER_ORIS, NUM_UDSLICES),
+            &Move::all(),
+            |moov, (corner_ori, udslice)| {
+                (
+                    corner_ori_move_table()[moov][corner_ori as usize],
+                    udslice_move_table()[moov][udslice as usize],
+                )
+            },
+        )
+    })
+}
+
+fn edge_ori_udslice_pruning_table() -> &'static [u8] {
+    EDGE_ORI_UDSLICE_PRUNING.get_or_init(|| {
+        pruning_table(
+            (0, CubieCube::SOLVED.get_udslice_coord()),
+            (NUM_EDGE_ORIS, NUM_UDSLICES),
+            &Move::all(),
+            |moov, (edge_ori, udslice)| {
+                (
+                    edge_ori_move_table()[moov][edge_ori as usize],
+                    udslice_move_table()[moov][udslice as usize],
+                )
+            },
+        )
+    })
+}
+
+fn corner_perm_slice_pruning_table() -> &'static [u8] {
+    CORNER_PERM_SLICE_PRUNING.get_or_init(|| {
+        pruning_table(
+            (0, 0),
+            (NUM_CORNER_PERMS, NUM_SLICE_PERMS),
+            &PHASE2_MOVES,
+            |moov, (corner_perm, slice_perm)| {
+                (
+                    corner_perm_move_table()[moov][corner_perm as usize],
+                    slice_perm_move_table()[moov][slice_perm as usize],
+                )
+            },
+        )
+    })
+}
+
+fn ud_edge_perm_slice_pruning_table() -> &'static [u8] {
+    UD_EDGE_PERM_SLICE_PRUNING.get_or_init(|| {
+        pruning_table(
+            (0, 0),
+            (NUM_UD_EDGE_PERMS, NUM_SLICE_PERMS),
+            &PHASE2_MOVES,
+            |moov, (ud_edge_perm, slice_perm)| {
+                (
+                    ud_edge_perm_move_table()[moov][ud_edge_perm as usize],
+                    slice_perm_move_table()[moov][slice_perm as usize],
+                )
+            },
+        )
+    })
+}
+
+/// [`distance_table()`] for pairs of coordinates, indexed by `a * size_b + b`
+fn pruning_table(
+    solved: (u16, u16),
+    (size_a, size_b): (u16, u16),
+    moves: &[Move],
+    apply_move: impl Fn(Move, (u16, u16)) -> (u16, u16),
+) -> Vec<u8> {
+    let size_b = size_b as usize;
+    let index = |(a, b): (u16, u16)| a as usize * size_b + b as usize;
+    distance_table(index(solved), size_a as usize * size_b, moves, |moov, i| {
+        index(apply_move(moov, ((i / size_b) as u16, (i % size_b) as u16)))
+    })
+}
+
 #[derive(Debug)]
-struct MoveTable<T>([T; 18]);
+pub(crate) struct MoveTable<T>([T; 18]);
 
 impl<T> Index<Move> for MoveTable<T> {
     type Output = T;
@@ -147,9 +410,9 @@ pub(crate) fn udslice_bitmask_to_coord(bitmask: u16) -> u16 {
     TABLE.get_or_init(|| {
         let mut ret = [0; 0xf01];
         let mut idx = 0;
-        for i in 0usize..=0xf00 {
+        for (i, entry) in ret.iter_mut().enumerate() {
             if i.count_ones() == 4 {
-                ret[i] = idx;
+                *entry = idx;
                 idx += 1;
             }
         }
@@ -175,6 +438,27 @@ pub(crate) fn udslice_coord_to_bitmask(coord: u16) -> u16 {
     })[coord as usize]
 }
 
+/// Lehmer code of a permutation of `0..N`
+pub(crate) fn perm_to_coord<const N: usize>(perm: [u8; N]) -> u16 {
+    let mut coord = 0;
+    for (i, &x) in perm.iter().enumerate() {
+        let smaller_after = perm[i + 1..].iter().filter(|&&y| y < x).count();
+        coord = coord * (N - i) as u16 + smaller_after as u16;
+    }
+    coord
+}
+
+pub(crate) fn coord_to_perm<const N: usize>(mut coord: u16) -> [u8; N] {
+    let mut digits = [0; N];
+    for (i, digit) in digits.iter_mut().enumerate().rev() {
+        *digit = (coord % (N - i) as u16) as usize;
+        coord /= (N - i) as u16;
+    }
+
+    let mut unused = (0..N as u8).collect::<Vec<_>>();
+    digits.map(|digit| unused.remove(digit))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -207,6 +491,15 @@ mod tests {
         }
     }
 
+    #[test]
+    fn perm_to_from_coord() {
+        for coord in 0..NUM_CORNER_PERMS {
+            assert_eq!(perm_to_coord(coord_to_perm::<8>(coord)), coord);
+        }
+        assert_eq!(coord_to_perm::<4>(0), [0, 1, 2, 3]);
+        assert_eq!(coord_to_perm::<4>(23), [3, 2, 1, 0]);
+    }
+
     #[test]
     fn assert_valid_coord_ranges_for_random_cubie_cubes() {
         for _ in 0..1000 {
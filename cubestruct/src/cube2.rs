@@ -0,0 +1,192 @@
+//! The 2x2x2 cube
+use crate::coord_cube::{
+    corner_ori_move_table, corner_perm_move_table, NUM_CORNER_ORIS, NUM_CORNER_PERMS,
+};
+use crate::cubie::*;
+use crate::puzzle::{cube_net, Net, Puzzle};
+use crate::search::{distance_table, ida_star, Coords};
+use crate::{Color, CubieCube, Move};
+use rand::Rng;
+use std::sync::OnceLock;
+
+/// Moves that scrambles are made of. They don't move the `DLB` corner, so every state
+/// reachable with them has it solved.
+const SCRAMBLE_MOVES: [Move; 9] = {
+    use Move::*;
+    [U, Ui, U2, R, Ri, R2, F, Fi, F2]
+};
+
+/// The WCA doesn't allow scrambles to states that can be solved in fewer moves than this
+const MIN_SCRAMBLE_DISTANCE: usize = 4;
+
+/// A 2x2x2 cube. It turns like a 3x3x3 without the edges and centers, so it uses the same
+/// [`Move`]s and stores its state like the corners of a [`CubieCube`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Cube2 {
+    corners: Corners,
+}
+
+impl Cube2 {
+    /// The 3x3x3 with these corners and solved edges
+    fn to_cubie_cube(self) -> CubieCube {
+        CubieCube::try_new(self.corners, CubieCube::SOLVED.edge_cubies()).unwrap()
+    }
+
+    /// A uniformly random state with the `DLB` corner solved
+    fn random_state<R: Rng>(rng: &mut R) -> Self {
+        let mut cube = CubieCube::SOLVED;
+        loop {
+            cube.set_corner_perm_coord(rng.gen_range(0..NUM_CORNER_PERMS));
+            cube.set_ori_coord::<Corners>(rng.gen_range(0..NUM_CORNER_ORIS));
+            let dlb = CornerCubicle::C4;
+            if cube[dlb] == CornerCubie::new(dlb, CornerOrientation::O0) {
+                return Self {
+                    corners: cube.corner_cubies(),
+                };
+            }
+        }
+    }
+
+    /// An optimal solution using [`SCRAMBLE_MOVES`], if the `DLB` corner is solved
+    fn solve_with_dlb_solved(&self) -> Option<Vec<Move>> {
+        let cube = self.to_cubie_cube();
+        let coords = CornerCoords {
+            perm: cube.get_corner_perm_coord(),
+            ori: cube.get_ori_coord::<Corners>(),
+        };
+        let mut moves = Vec::new();
+        // every state can be solved in 11 moves
+        ida_star(
+            coords,
+            &SCRAMBLE_MOVES,
+            11,
+            &mut moves,
+            &mut |_, _| true,
+            &|| false,
+        )
+        .then_some(moves)
+    }
+}
+
+impl Puzzle for Cube2 {
+    type Move = Move;
+
+    fn solved() -> Self {
+        Self {
+            corners: CubieCube::SOLVED.corner_cubies(),
+        }
+    }
+
+    fn is_solved(&self) -> bool {
+        let facelets = self.to_cubie_cube().to_facelet_cube();
+        Color::all().into_iter().all(|face| {
+            let [a, _, b, _, _, _, c, _, d] = facelets.get_face(face);
+            a == b && b == c && c == d
+        })
+    }
+
+    fn apply_move(&mut self, moov: Move) {
+        let mut cube = self.to_cubie_cube();
+        cube.apply_move(moov);
+        self.corners = cube.corner_cubies();
+    }
+
+    fn random_state_scramble_with<R: Rng>(rng: &mut R) -> Vec<Move> {
+        loop {
+            let solution = Self::random_state(rng)
+                .solve_with_dlb_solved()
+                .expect("DLB is solved");
+            if solution.len() >= MIN_SCRAMBLE_DISTANCE {
+                return solution.into_iter().rev().map(Move::inverse).collect();
+            }
+        }
+    }
+
+    fn net(&self) -> Net {
+        let facelets = self.to_cubie_cube().to_facelet_cube();
+        cube_net(|face| {
+            let [a, _, b, _, _, _, c, _, d] = facelets.get_face(face);
+            [a, b, c, d]
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct CornerCoords {
+    perm: u16,
+    ori: u16,
+}
+
+impl Coords for CornerCoords {
+    type Move = Move;
+
+    fn apply_move(&mut self, moov: Move) {
+        self.perm = corner_perm_move_table()[moov][self.perm as usize];
+        self.ori = corner_ori_move_table()[moov][self.ori as usize];
+    }
+
+    /// A lower bound on the number of [`SCRAMBLE_MOVES`] needed to solve the corners
+    fn distance_bound(&self) -> u8 {
+        u8::max(
+            perm_distance_table()[self.perm as usize],
+            ori_distance_table()[self.ori as usize],
+        )
+    }
+}
+
+fn perm_distance_table() -> &'static [u8] {
+    static TABLE: OnceLock<Vec<u8>> = OnceLock::new();
+
+    TABLE.get_or_init(|| {
+        distance_table(0, NUM_CORNER_PERMS.into(), &SCRAMBLE_MOVES, |moov, perm| {
+            corner_perm_move_table()[moov][perm].into()
+        })
+    })
+}
+
+fn ori_distance_table() -> &'static [u8] {
+    static TABLE: OnceLock<Vec<u8>> = OnceLock::new();
+
+    TABLE.get_or_init(|| {
+        distance_table(0, NUM_CORNER_ORIS.into(), &SCRAMBLE_MOVES, |moov, ori| {
+            corner_ori_move_table()[moov][ori].into()
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scramble_moves_keep_dlb_solved() {
+        let dlb = CornerCubicle::C4;
+        for moov in SCRAMBLE_MOVES {
+            let mut cube = CubieCube::SOLVED;
+            cube.apply_move(moov);
+            assert_eq!(cube[dlb], CornerCubie::new(dlb, CornerOrientation::O0));
+        }
+    }
+
+    #[test]
+    fn solved_in_any_orientation() {
+        use Move::*;
+        let mut cube = Cube2::solved();
+        cube.apply_moves(&[R, Li]);
+        assert!(cube.is_solved());
+        cube.apply_move(U);
+        assert!(!cube.is_solved());
+    }
+
+    #[test]
+    fn random_state_scrambles_are_valid() {
+        for _ in 0..20 {
+            let scramble = Cube2::random_state_scramble();
+            assert!((MIN_SCRAMBLE_DISTANCE..=11).contains(&scramble.len()));
+            let mut cube = Cube2::solved();
+            cube.apply_moves(&scramble);
+            assert!(!cube.is_solved());
+            assert_eq!(cube.solve_with_dlb_solved().unwrap().len(), scramble.len());
+        }
+    }
+}
@@ -8,7 +8,7 @@ pub type Edges = CubicleArray<EdgeCubie, 12>;
 
 pub trait Cubicle: fmt::Debug + Eq + Copy {
     /// Enumerate all values of the type
-    fn all() -> impl Iterator<Item = Self> + DoubleEndedIterator + ExactSizeIterator;
+    fn all() -> impl DoubleEndedIterator<Item = Self> + ExactSizeIterator;
 
     fn as_u8(self) -> u8;
 }
@@ -189,7 +189,7 @@ pub enum CornerCubicle {
 }
 
 impl Cubicle for CornerCubicle {
-    fn all() -> impl Iterator<Item = Self> + DoubleEndedIterator + ExactSizeIterator {
+    fn all() -> impl DoubleEndedIterator<Item = Self> + ExactSizeIterator {
         use CornerCubicle::*;
         [C0, C1, C2, C3, C4, C5, C6, C7].into_iter()
     }
@@ -303,7 +303,7 @@ pub enum EdgeCubicle {
 }
 
 impl Cubicle for EdgeCubicle {
-    fn all() -> impl Iterator<Item = Self> + DoubleEndedIterator + ExactSizeIterator {
+    fn all() -> impl DoubleEndedIterator<Item = Self> + ExactSizeIterator {
         use EdgeCubicle::*;
         [C0, C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11].into_iter()
     }
@@ -331,7 +331,7 @@ impl Orientation for EdgeOrientation {
     /// Edge orientations are their own inverse:
     /// - Flipping a flipped edge flips it back to O0.
     /// - By not flipping an unflipped edge you stay at the unflipped
-    /// orientation
+    ///   orientation
     fn inverse(self) -> Self {
         self
     }
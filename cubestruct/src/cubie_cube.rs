@@ -1,9 +1,45 @@
 use crate::cubie::*;
-use crate::facelet_cube::FaceletCube;
+use crate::facelet_cube::{Color, FaceletCube};
 use crate::iter_2cycles::perm_2cycles;
-use crate::Move;
+use crate::{CornerState, EdgeState, Move};
 use std::ops::{Index, IndexMut, Mul, MulAssign};
 
+/// The corner cubicles on the given face
+fn face_corners(face: Color) -> [CornerCubicle; 4] {
+    use CornerCubicle::*;
+    match face {
+        Color::White => [C0, C1, C2, C3],
+        Color::Yellow => [C4, C5, C6, C7],
+        Color::Blue => [C0, C1, C4, C5],
+        Color::Green => [C2, C3, C6, C7],
+        Color::Orange => [C0, C2, C4, C6],
+        Color::Red => [C1, C3, C5, C7],
+    }
+}
+
+/// The edge cubicles on the given face
+fn face_edges(face: Color) -> [EdgeCubicle; 4] {
+    use EdgeCubicle::*;
+    match face {
+        Color::White => [C0, C1, C2, C3],
+        Color::Yellow => [C8, C9, C10, C11],
+        Color::Blue => [C0, C4, C5, C8],
+        Color::Green => [C3, C6, C7, C11],
+        Color::Orange => [C1, C4, C6, C9],
+        Color::Red => [C2, C5, C7, C10],
+    }
+}
+
+/// The steps of a CFOP solve, in the order they are reached
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum CfopStage {
+    Scrambled,
+    Cross,
+    F2l,
+    Oll,
+    Solved,
+}
+
 /// Corner cubicle numbering:
 /// ```text
 /// ┌──┬──┬──┐  ┌──┬──┬──┐  ┌──┬──┬──┐
@@ -60,6 +96,11 @@ impl CubieCube {
     }
 
     pub fn random_possible() -> Self {
+        Self::random_possible_with(&mut rand::thread_rng())
+    }
+
+    /// Like [`Self::random_possible()`], with the randomness coming from `rng`
+    pub fn random_possible_with<R: rand::Rng>(rng: &mut R) -> Self {
         fn aux<C: Cubies, R: rand::Rng>(cubies: &mut C, rng: &mut R) {
             let mut total_ori = C::Orientation::zero();
             for cubicle in C::Cubicle::all().skip(1) {
@@ -76,10 +117,9 @@ impl CubieCube {
             mut corners,
             mut edges,
         } = Self::SOLVED;
-        let mut rng = rand::thread_rng();
 
-        aux(&mut corners, &mut rng);
-        aux(&mut edges, &mut rng);
+        aux(&mut corners, rng);
+        aux(&mut edges, rng);
 
         if (perm_2cycles(corners).count() + perm_2cycles(edges).count()) & 1 == 1 {
             edges.swap(EdgeCubicle::C0, EdgeCubicle::C1);
@@ -88,6 +128,67 @@ impl CubieCube {
         Self { corners, edges }
     }
 
+    /// Like [`Self::random_possible_with()`], but with the pieces that live in the `corners`
+    /// and `edges` cubicles solved. It's uniformly random among the states where they are.
+    pub(crate) fn random_possible_with_solved<R: rand::Rng>(
+        rng: &mut R,
+        corners: &[CornerCubicle],
+        edges: &[EdgeCubicle],
+    ) -> Self {
+        /// Returns the cubicles of the pieces that aren't solved
+        fn aux<C: Cubies, R: rand::Rng>(
+            cubies: &mut C,
+            solved: &[C::Cubicle],
+            rng: &mut R,
+        ) -> Vec<C::Cubicle> {
+            use rand::seq::SliceRandom;
+
+            let free: Vec<_> = C::Cubicle::all()
+                .filter(|cubicle| !solved.contains(cubicle))
+                .collect();
+            let mut places = free.clone();
+            places.shuffle(rng);
+            let mut total_ori = C::Orientation::zero();
+            for (&home, &place) in free.iter().zip(&places).skip(1) {
+                let o = C::Orientation::random(rng);
+                total_ori = total_ori.add(o);
+                cubies[home] = C::Cubie::new(place, o);
+            }
+            if let (Some(&home), Some(&place)) = (free.first(), places.first()) {
+                cubies[home] = C::Cubie::new(place, total_ori.inverse());
+            }
+            free
+        }
+
+        let mut cube = Self::SOLVED;
+        let free_corners = aux(&mut cube.corners, corners, rng);
+        let free_edges = aux(&mut cube.edges, edges, rng);
+
+        // swapping two unsolved pieces fixes the parity
+        if (perm_2cycles(cube.corners).count() + perm_2cycles(cube.edges).count()) & 1 == 1 {
+            match (&free_corners[..], &free_edges[..]) {
+                (_, [a, b, ..]) => cube.edges.swap(*a, *b),
+                ([a, b, ..], _) => cube.corners.swap(*a, *b),
+                _ => unreachable!("a single piece can't be out of place"),
+            }
+        }
+        cube
+    }
+
+    /// Cubicles of the pieces that turning `face` moves
+    pub(crate) fn cubicles_on(face: Color) -> (Vec<CornerCubicle>, Vec<EdgeCubicle>) {
+        let mut turned = Self::SOLVED;
+        turned.apply_move(Move::from_face_and_amount(face.face(), 1).unwrap());
+        (
+            CornerCubicle::all()
+                .filter(|&c| turned[c] != Self::SOLVED[c])
+                .collect(),
+            EdgeCubicle::all()
+                .filter(|&c| turned[c] != Self::SOLVED[c])
+                .collect(),
+        )
+    }
+
     pub fn is_possible_state(&self) -> bool {
         fn is_zero_ori<C: Cubies>(cubies: C) -> bool {
             cubies
@@ -148,22 +249,10 @@ impl CubieCube {
     /// Returns a new CubieCube that is the inverse of `self`
     #[must_use]
     pub fn inverse(&self) -> Self {
-        fn aux<C: Cubies>(cubies: C, ret: &mut C) {
-            for (current, home) in cubies.into_iter().zip(C::Cubicle::all()) {
-                // The cubie that lives in `home` now has state `current`.
-                // So, the inverse has to put `current` back at `home`
-                ret[current.cubicle()] = C::Cubie::new(home, current.orientation().inverse());
-            }
+        Self {
+            corners: invert_cubies(self.corners),
+            edges: invert_cubies(self.edges),
         }
-
-        // Doesn't actually need to be `SOLVED`, but we just use that as initialization
-        // to avoid MaybeUninit
-        let mut ret = Self::SOLVED;
-
-        aux(self.corners, &mut ret.corners);
-        aux(self.edges, &mut ret.edges);
-
-        ret
     }
 
     pub fn apply_move(&mut self, moov: Move) {
@@ -220,11 +309,104 @@ impl CubieCube {
                 *self *= BMOVE * BMOVE * BMOVE;
             }
             Move::B2 => {
-                *self = BMOVE * BMOVE;
+                *self *= BMOVE * BMOVE;
             }
         }
     }
 
+    /// Whether the four edges around the `face` center are all in their home cubicles
+    /// with no flip, i.e. the cross on that face is solved
+    pub fn is_cross_solved(&self, face: Color) -> bool {
+        face_edges(face)
+            .into_iter()
+            .all(|home| self.is_edge_solved(home))
+    }
+
+    /// How far through a CFOP solve the cube is, on whichever face is furthest along
+    pub fn cfop_stage(&self) -> CfopStage {
+        if *self == Self::SOLVED {
+            return CfopStage::Solved;
+        }
+        Color::all()
+            .into_iter()
+            .map(|face| self.cfop_stage_on(face))
+            .max()
+            .unwrap()
+    }
+
+    /// How far through a CFOP solve with the cross on `face` the cube is
+    fn cfop_stage_on(&self, face: Color) -> CfopStage {
+        if !self.is_cross_solved(face) {
+            return CfopStage::Scrambled;
+        }
+
+        let last_layer = face.opposite();
+        let last_corners = face_corners(last_layer);
+        let last_edges = face_edges(last_layer);
+        let f2l_solved = CornerCubicle::all()
+            .filter(|c| !last_corners.contains(c))
+            .all(|home| self.corners[home] == CornerCubie::new(home, CornerOrientation::O0))
+            && EdgeCubicle::all()
+                .filter(|c| !last_edges.contains(c))
+                .all(|home| self.is_edge_solved(home));
+        if !f2l_solved {
+            return CfopStage::Cross;
+        }
+
+        let last_face = self.to_facelet_cube().get_face(last_layer);
+        if last_face.iter().all(|&c| c == last_layer) {
+            CfopStage::Oll
+        } else {
+            CfopStage::F2l
+        }
+    }
+
+    fn is_edge_solved(&self, home: EdgeCubicle) -> bool {
+        self.edges[home] == EdgeCubie::new(home, EdgeOrientation::O0)
+    }
+
+    /// Any face whose cross is solved (see [`Self::is_cross_solved()`])
+    pub fn solved_cross(&self) -> Option<Color> {
+        Color::all()
+            .into_iter()
+            .find(|&face| self.is_cross_solved(face))
+    }
+
+    /// The state of the corners alone
+    pub fn corners(&self) -> CornerState {
+        CornerState(self.corners)
+    }
+
+    /// The state of the edges alone
+    pub fn edges(&self) -> EdgeState {
+        EdgeState(self.edges)
+    }
+
+    pub(crate) const fn corner_cubies(&self) -> Corners {
+        self.corners
+    }
+
+    pub(crate) const fn edge_cubies(&self) -> Edges {
+        self.edges
+    }
+
+    /// Apply each of the given moves in order
+    pub fn apply_moves(&mut self, moves: &[Move]) {
+        for &moov in moves {
+            self.apply_move(moov);
+        }
+    }
+
+    /// The move that turns `self` into `other`, if they're one move apart. Smart cubes only
+    /// report states, so this is how their turns are worked out.
+    pub fn move_to(&self, other: &CubieCube) -> Option<Move> {
+        Move::all().into_iter().find(|&moov| {
+            let mut turned = *self;
+            turned.apply_move(moov);
+            turned == *other
+        })
+    }
+
     pub(crate) fn set_ori_coord<C: Cubies>(&mut self, coord: u16)
     where
         Self: Index<C::Cubicle, Output = C::Cubie> + IndexMut<C::Cubicle>,
@@ -316,6 +498,62 @@ impl CubieCube {
             self.edges.swap(EdgeCubicle::C0, EdgeCubicle::C1);
         }
     }
+
+    pub(crate) fn get_corner_perm_coord(&self) -> u16 {
+        let mut corners = self.corners.into_iter();
+        crate::coord_cube::perm_to_coord::<8>(std::array::from_fn(|_| {
+            corners.next().unwrap().cubicle().as_u8()
+        }))
+    }
+
+    /// Doesn't maintain possibleness
+    pub(crate) fn set_corner_perm_coord(&mut self, coord: u16) {
+        let perm = crate::coord_cube::coord_to_perm::<8>(coord);
+        for (home, loc) in CornerCubicle::all().zip(perm) {
+            self.corners[home].set_cubicle(CornerCubicle::all().nth(loc.into()).unwrap());
+        }
+    }
+
+    /// Permutation of the 8 edges of the U and D layers. Only meaningful when they're all
+    /// in the U and D layers, i.e. in phase 2.
+    pub(crate) fn get_ud_edge_perm_coord(&self) -> u16 {
+        let mut edges = UD_EDGES.into_iter();
+        crate::coord_cube::perm_to_coord::<8>(std::array::from_fn(|_| {
+            let loc = self.edges[edges.next().unwrap()].cubicle();
+            debug_assert!(UD_EDGES.contains(&loc), "Edge not in U/D layers");
+            UD_EDGES.iter().position(|&c| c == loc).unwrap_or(0) as u8
+        }))
+    }
+
+    /// Puts the equator edges in their home cubicles. Doesn't maintain possibleness.
+    pub(crate) fn set_ud_edge_perm_coord(&mut self, coord: u16) {
+        let perm = crate::coord_cube::coord_to_perm::<8>(coord);
+        for (home, loc) in UD_EDGES.into_iter().zip(perm) {
+            self.edges[home].set_cubicle(UD_EDGES[usize::from(loc)]);
+        }
+        for home in SLICE_EDGES {
+            self.edges[home].set_cubicle(home);
+        }
+    }
+
+    /// Permutation of the 4 equator edges. Only meaningful when they're all in the
+    /// equator, i.e. in phase 2.
+    pub(crate) fn get_slice_perm_coord(&self) -> u16 {
+        let mut edges = SLICE_EDGES.into_iter();
+        crate::coord_cube::perm_to_coord::<4>(std::array::from_fn(|_| {
+            let loc = self.edges[edges.next().unwrap()].cubicle();
+            debug_assert!(SLICE_EDGES.contains(&loc), "Edge not in equator");
+            SLICE_EDGES.iter().position(|&c| c == loc).unwrap_or(0) as u8
+        }))
+    }
+
+    /// Leaves the U and D layer edges where they are. Doesn't maintain possibleness.
+    pub(crate) fn set_slice_perm_coord(&mut self, coord: u16) {
+        let perm = crate::coord_cube::coord_to_perm::<4>(coord);
+        for (home, loc) in SLICE_EDGES.into_iter().zip(perm) {
+            self.edges[home].set_cubicle(SLICE_EDGES[usize::from(loc)]);
+        }
+    }
 }
 
 impl Index<CornerCubicle> for CubieCube {
@@ -348,27 +586,39 @@ impl Mul<CubieCube> for CubieCube {
     type Output = Self;
 
     fn mul(self, rhs: CubieCube) -> Self::Output {
-        fn aux<C: Cubies>(lhs: C, rhs: C, ret: &mut C) {
-            for (lhs_state, home) in lhs.into_iter().zip(C::Cubicle::all()) {
-                // `home` goes to `lhs_state` by `lhs`.
-                // `lhs_state` goes to `rhs_state` by `rhs`.
-                let rhs_state = rhs[lhs_state.cubicle()];
-                ret[home] = C::Cubie::new(
-                    rhs_state.cubicle(),
-                    lhs_state.orientation().add(rhs_state.orientation()),
-                );
-            }
+        Self {
+            corners: multiply_cubies(self.corners, rhs.corners),
+            edges: multiply_cubies(self.edges, rhs.edges),
         }
+    }
+}
 
-        // Doesn't actually need to be `SOLVED`, but we just use that as initialization
-        // to avoid MaybeUninit
-        let mut ret = CubieCube::SOLVED;
-
-        aux(self.corners, rhs.corners, &mut ret.corners);
-        aux(self.edges, rhs.edges, &mut ret.edges);
+/// The cubies of one kind after `lhs` and then `rhs`
+pub(crate) fn multiply_cubies<C: Cubies>(lhs: C, rhs: C) -> C {
+    // every cubicle is overwritten, `lhs` is just the initialization
+    let mut ret = lhs;
+    for (lhs_state, home) in lhs.into_iter().zip(C::Cubicle::all()) {
+        // `home` goes to `lhs_state` by `lhs`.
+        // `lhs_state` goes to `rhs_state` by `rhs`.
+        let rhs_state = rhs[lhs_state.cubicle()];
+        ret[home] = C::Cubie::new(
+            rhs_state.cubicle(),
+            lhs_state.orientation().add(rhs_state.orientation()),
+        );
+    }
+    ret
+}
 
-        ret
+/// The cubies of one kind that undo `cubies`
+pub(crate) fn invert_cubies<C: Cubies>(cubies: C) -> C {
+    // every cubicle is overwritten, `cubies` is just the initialization
+    let mut ret = cubies;
+    for (current, home) in cubies.into_iter().zip(C::Cubicle::all()) {
+        // The cubie that lives in `home` now has state `current`.
+        // So, the inverse has to put `current` back at `home`
+        ret[current.cubicle()] = C::Cubie::new(home, current.orientation().inverse());
     }
+    ret
 }
 
 impl MulAssign<CubieCube> for CubieCube {
@@ -388,9 +638,21 @@ fn all_cubies_seen<C: Cubies>(cubies: C) -> bool {
     for i in cubies {
         seen[i.cubicle()] = true;
     }
-    seen.into_iter().all(|x| x == true)
+    seen.into_iter().all(|x| x)
 }
 
+/// Home cubicles of the edges in the U and D layers
+const UD_EDGES: [EdgeCubicle; 8] = {
+    use EdgeCubicle::*;
+    [C0, C1, C2, C3, C8, C9, C10, C11]
+};
+
+/// Home cubicles of the edges in the equator (E slice)
+const SLICE_EDGES: [EdgeCubicle; 4] = {
+    use EdgeCubicle::*;
+    [C4, C5, C6, C7]
+};
+
 const RMOVE: CubieCube = CubieCube {
     corners: CubicleArray::new([
         CornerCubie::new(CornerCubicle::C0, CornerOrientation::O0),
@@ -615,6 +877,16 @@ mod tests {
         }
     }
 
+    #[test]
+    fn moves_between_states() {
+        let mut turned = TPERM;
+        turned.apply_move(Move::Fi);
+        assert_eq!(TPERM.move_to(&turned), Some(Move::Fi));
+        assert_eq!(turned.move_to(&TPERM), Some(Move::F));
+        assert_eq!(TPERM.move_to(&TPERM), None);
+        assert_eq!(CubieCube::SOLVED.move_to(&TPERM), None);
+    }
+
     #[test]
     fn move_application() {
         use Move::*;
@@ -630,6 +902,77 @@ mod tests {
         assert_eq!(rmove, RMOVE);
         rmove.apply_move(Ri);
         assert_eq!(rmove, CubieCube::SOLVED);
+
+        for moov in Move::all() {
+            let mut cube = TPERM;
+            cube.apply_moves(&[moov, moov, moov, moov]);
+            assert_eq!(cube, TPERM, "{moov} isn't order 4");
+        }
+    }
+
+    #[test]
+    fn half_turns() {
+        use Move::*;
+        for moov in [L2, R2, D2, U2, F2, B2] {
+            let mut cube = TPERM;
+            cube.apply_move(moov);
+            assert_ne!(cube, TPERM, "{moov} didn't change the cube");
+            cube.apply_move(moov);
+            assert_eq!(cube, TPERM, "{moov} twice isn't the identity");
+        }
+    }
+
+    #[test]
+    fn cross_detection() {
+        use Move::*;
+        for face in Color::all() {
+            assert!(CubieCube::SOLVED.is_cross_solved(face));
+        }
+
+        // (move, face being turned, opposite face)
+        let turns = [
+            (L, Color::Orange, Color::Red),
+            (R, Color::Red, Color::Orange),
+            (U, Color::White, Color::Yellow),
+            (D, Color::Yellow, Color::White),
+            (F, Color::Green, Color::Blue),
+            (B, Color::Blue, Color::Green),
+        ];
+        for (moov, turned, opposite) in turns {
+            let mut cube = CubieCube::SOLVED;
+            cube.apply_move(moov);
+            assert!(!cube.is_cross_solved(turned), "{moov}");
+            assert!(cube.is_cross_solved(opposite), "{moov}");
+            for face in Color::all() {
+                if face != turned && face != opposite {
+                    assert!(!cube.is_cross_solved(face), "{moov} {face:?}");
+                }
+            }
+        }
+
+        assert!(TPERM.is_cross_solved(Color::Yellow));
+        assert!(!TPERM.is_cross_solved(Color::White));
+        assert_eq!(RMOVE.solved_cross(), Some(Color::Orange));
+    }
+
+    #[test]
+    fn cfop_stages() {
+        use Move::*;
+        let stage = |moves: &[Move]| {
+            let mut cube = CubieCube::SOLVED;
+            cube.apply_moves(moves);
+            cube.cfop_stage()
+        };
+
+        assert_eq!(CubieCube::SOLVED.cfop_stage(), CfopStage::Solved);
+        assert_eq!(stage(&[U]), CfopStage::Oll);
+        assert_eq!(TPERM.cfop_stage(), CfopStage::Oll);
+        assert_eq!(RMOVE.cfop_stage(), CfopStage::Oll);
+        // sune
+        assert_eq!(stage(&[R, U, Ri, U, R, U2, Ri]), CfopStage::F2l);
+        // sexy move
+        assert_eq!(stage(&[R, U, Ri, Ui]), CfopStage::Cross);
+        assert_eq!(stage(&[R, U, F, D, L2, B]), CfopStage::Scrambled);
     }
 
     #[test]
@@ -110,8 +110,15 @@ impl FaceletCube {
             ])
         };
 
+        /// `(home cubicle, colors, facelet indices)`
+        type CubieFacelets<C> = (
+            <C as Cubies>::Cubicle,
+            <C as CubiesExt>::FaceletArray<Color>,
+            <C as CubiesExt>::FaceletArray<usize>,
+        );
+
         fn aux<C: CubiesExt>(
-            map: C::CubicleArray<(C::Cubicle, C::FaceletArray<Color>, C::FaceletArray<usize>)>,
+            map: C::CubicleArray<CubieFacelets<C>>,
             output_cubies: &mut C,
             facelet_cube: &FaceletCube,
         ) -> Result<(), FaceletConversionError> {
@@ -142,6 +149,12 @@ impl FaceletCube {
             Ok(())
         }
 
+        for face in Color::all() {
+            if self.faces[face as usize][4] != face {
+                return Err(FaceletConversionError::WrongCenter { face });
+            }
+        }
+
         // another yucky hack to avoid MaybeUninit (because logic error is easier to debug than UB)
         let mut corners =
             CubicleArray::new([CornerCubie::new(CornerCubicle::C0, CornerOrientation::O0); 8]);
@@ -308,12 +321,61 @@ impl FaceletCube {
     }
 }
 
+/// The faces of a facelet string in their usual URFDLB order, with the letter of each
+const FACELET_STRING_FACES: [(Color, char); 6] = [
+    (Color::White, 'U'),
+    (Color::Red, 'R'),
+    (Color::Green, 'F'),
+    (Color::Yellow, 'D'),
+    (Color::Orange, 'L'),
+    (Color::Blue, 'B'),
+];
+
+impl CubieCube {
+    /// Each sticker as the letter of the face it belongs on, in the usual URFDLB order, with
+    /// each face laid out as in [`FaceletCube::get_face()`]
+    pub fn to_facelet_string(&self) -> String {
+        let letter = |color| {
+            FACELET_STRING_FACES
+                .iter()
+                .find(|&&(c, _)| c == color)
+                .unwrap()
+                .1
+        };
+        let facelets = self.to_facelet_cube();
+        FACELET_STRING_FACES
+            .iter()
+            .flat_map(|&(face, _)| facelets.get_face(face))
+            .map(letter)
+            .collect()
+    }
+
+    /// Parse a string made by [`Self::to_facelet_string()`]. `None` if it's malformed or
+    /// the stickers don't make up a cube.
+    pub fn from_facelet_string(s: &str) -> Option<Self> {
+        let chars: Vec<char> = s.chars().collect();
+        if chars.len() != 6 * 9 {
+            return None;
+        }
+        let mut builder = FaceletCube::builder();
+        for (&(face, _), stickers) in FACELET_STRING_FACES.iter().zip(chars.chunks(9)) {
+            for (i, &letter) in stickers.iter().enumerate() {
+                let &(color, _) = FACELET_STRING_FACES.iter().find(|&&(_, l)| l == letter)?;
+                builder.set(face, i, color);
+            }
+        }
+        builder.build()?.to_cubie_cube().ok()
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum FaceletConversionError {
     #[error("The cubie that lives in {cubicle:?} was not found in the FaceletCube")]
     CornerCubieNotFound { cubicle: CornerCubicle },
     #[error("The cubie that lives in {cubicle:?} was not found in the FaceletCube")]
     EdgeCubieNotFound { cubicle: EdgeCubicle },
+    #[error("The center of the {face:?} face is another color")]
+    WrongCenter { face: Color },
     #[error("CubieCube::try_new() failed")]
     CubieCubeConstruction(CubieCubeConstructionError),
 }
@@ -333,7 +395,7 @@ pub struct FaceletCubeBuilder {
 impl FaceletCubeBuilder {
     /// Returns `None` if not all faces were initialized
     pub fn build(self) -> Option<FaceletCube> {
-        if self.initialized.into_iter().flatten().any(|x| x == false) {
+        if self.initialized.into_iter().flatten().any(|x| !x) {
             None
         } else {
             Some(FaceletCube { faces: self.faces })
@@ -360,7 +422,28 @@ pub enum Color {
 }
 
 impl Color {
-    fn all() -> [Self; 6] {
+    /// Letter of the face this is the center of, in the standard orientation
+    pub(crate) fn face(self) -> char {
+        FACELET_STRING_FACES
+            .iter()
+            .find(|&&(c, _)| c == self)
+            .unwrap()
+            .1
+    }
+
+    /// The color of the center across the cube from this one
+    pub fn opposite(self) -> Self {
+        match self {
+            Self::Orange => Self::Red,
+            Self::Red => Self::Orange,
+            Self::Yellow => Self::White,
+            Self::White => Self::Yellow,
+            Self::Green => Self::Blue,
+            Self::Blue => Self::Green,
+        }
+    }
+
+    pub fn all() -> [Self; 6] {
         [
             Self::Orange,
             Self::Red,
@@ -372,68 +455,11 @@ impl Color {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn facelet_cube_conversions() {
-        assert_eq!(
-            CubieCube::SOLVED.to_facelet_cube().to_cubie_cube().unwrap(),
-            CubieCube::SOLVED
-        );
-
-        assert_eq!(TPERM, TPERM.to_cubie_cube().unwrap().to_facelet_cube());
-        assert_eq!(RMOVE, RMOVE.to_cubie_cube().unwrap().to_facelet_cube());
-    }
-
-    const TPERM: FaceletCube = {
-        use Color::*;
-        FaceletCube {
-            faces: [
-                [
-                    Orange, Red, Orange, Orange, Orange, Orange, Orange, Orange, Orange,
-                ],
-                [Blue, Orange, Green, Red, Red, Red, Red, Red, Red],
-                [
-                    Yellow, Yellow, Yellow, Yellow, Yellow, Yellow, Yellow, Yellow, Yellow,
-                ],
-                [
-                    White, White, White, White, White, White, White, White, White,
-                ],
-                [Green, Green, Red, Green, Green, Green, Green, Green, Green],
-                [Red, Blue, Blue, Blue, Blue, Blue, Blue, Blue, Blue],
-            ],
-        }
-    };
-
-    const RMOVE: FaceletCube = {
-        use Color::*;
-        FaceletCube {
-            faces: [
-                [
-                    Orange, Orange, Orange, Orange, Orange, Orange, Orange, Orange, Orange,
-                ],
-                [Red, Red, Red, Red, Red, Red, Red, Red, Red],
-                [
-                    Yellow, Yellow, Blue, Yellow, Yellow, Blue, Yellow, Yellow, Blue,
-                ],
-                [
-                    White, White, Green, White, White, Green, White, White, Green,
-                ],
-                [
-                    Green, Green, Yellow, Green, Green, Yellow, Green, Green, Yellow,
-                ],
-                [White, Blue, Blue, White, Blue, Blue, White, Blue, Blue],
-            ],
-        }
-    };
-}
-
 ////////////////////////////////
 // TODO: Remove all the stuff below here once we get a good 3d rendering thing going
 ////////////////////////////////
 
+#[allow(dead_code)]
 impl Color {
     fn emoji(self) -> &'static str {
         match self {
@@ -447,6 +473,7 @@ impl Color {
     }
 }
 
+#[allow(dead_code)]
 const TMPL: [&str; 7] = [
     "┌──┬──┬──┐",
     "│⬛│⬛│⬛",
@@ -456,8 +483,10 @@ const TMPL: [&str; 7] = [
     "│⬛│⬛│⬛",
     "└──┴──┴──┘",
 ];
+#[allow(dead_code)]
 const TMPLSPACE: &str = "          ";
 
+#[allow(dead_code)]
 fn print_template_line(lnr: usize, facelet_colors: [Color; 9]) {
     if TMPL[lnr].contains("⬛") {
         let x = TMPL[lnr]
@@ -473,6 +502,7 @@ fn print_template_line(lnr: usize, facelet_colors: [Color; 9]) {
     }
 }
 
+#[allow(dead_code)]
 fn println_render_cube(render: &FaceletCube) {
     for i in 0..7 {
         print!("{TMPLSPACE}");
@@ -492,3 +522,136 @@ fn println_render_cube(render: &FaceletCube) {
         println!();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn any_facelet_cube() -> impl Strategy<Value = FaceletCube> {
+        let color = proptest::sample::select(Color::all().to_vec());
+        proptest::array::uniform6(proptest::array::uniform9(color))
+            .prop_map(|faces| FaceletCube { faces })
+    }
+
+    fn any_possible_cube() -> impl Strategy<Value = CubieCube> {
+        any::<u64>()
+            .prop_map(|seed| CubieCube::random_possible_with(&mut ChaCha8Rng::seed_from_u64(seed)))
+    }
+
+    /// A face and an index on it
+    fn any_facelet() -> impl Strategy<Value = (usize, usize)> {
+        (0..6usize, 0..9usize)
+    }
+
+    proptest! {
+        #[test]
+        fn round_trip(cube in any_possible_cube()) {
+            prop_assert_eq!(cube.to_facelet_cube().to_cubie_cube().unwrap(), cube);
+        }
+
+        /// Smart cubes report their stickers, so any arrangement has to be handled
+        #[test]
+        fn arbitrary_facelets(facelets in any_facelet_cube()) {
+            // only real pieces in their cubicles convert, so nothing is lost
+            if let Ok(cube) = facelets.to_cubie_cube() {
+                prop_assert_eq!(cube.to_facelet_cube(), facelets);
+            }
+        }
+
+        #[test]
+        fn swapped_stickers(cube in any_possible_cube(), a in any_facelet(), b in any_facelet()) {
+            let mut facelets = cube.to_facelet_cube();
+            let color_a = facelets.faces[a.0][a.1];
+            let color_b = facelets.faces[b.0][b.1];
+            prop_assume!(color_a != color_b);
+            facelets.faces[a.0][a.1] = color_b;
+            facelets.faces[b.0][b.1] = color_a;
+            // at best it's a flipped edge
+            prop_assert!(!facelets
+                .to_cubie_cube()
+                .is_ok_and(|cube| cube.is_possible_state()));
+        }
+    }
+
+    #[test]
+    fn facelet_cube_conversions() {
+        assert_eq!(
+            CubieCube::SOLVED.to_facelet_cube().to_cubie_cube().unwrap(),
+            CubieCube::SOLVED
+        );
+
+        assert_eq!(TPERM, TPERM.to_cubie_cube().unwrap().to_facelet_cube());
+        assert_eq!(RMOVE, RMOVE.to_cubie_cube().unwrap().to_facelet_cube());
+    }
+
+    #[test]
+    fn wrong_centers() {
+        let mut facelets = CubieCube::SOLVED.to_facelet_cube();
+        facelets.faces[Color::Red as usize][4] = Color::Orange;
+        facelets.faces[Color::Orange as usize][4] = Color::Red;
+        assert!(matches!(
+            facelets.to_cubie_cube(),
+            Err(FaceletConversionError::WrongCenter { .. })
+        ));
+    }
+
+    #[test]
+    fn facelet_strings() {
+        assert_eq!(
+            CubieCube::SOLVED.to_facelet_string(),
+            "UUUUUUUUURRRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB"
+        );
+        let tperm = TPERM.to_cubie_cube().unwrap();
+        assert_eq!(
+            CubieCube::from_facelet_string(&tperm.to_facelet_string()),
+            Some(tperm)
+        );
+        assert_eq!(CubieCube::from_facelet_string("UUU"), None);
+        assert_eq!(CubieCube::from_facelet_string(&"X".repeat(54)), None);
+    }
+
+    const TPERM: FaceletCube = {
+        use Color::*;
+        FaceletCube {
+            faces: [
+                [
+                    Orange, Red, Orange, Orange, Orange, Orange, Orange, Orange, Orange,
+                ],
+                [Blue, Orange, Green, Red, Red, Red, Red, Red, Red],
+                [
+                    Yellow, Yellow, Yellow, Yellow, Yellow, Yellow, Yellow, Yellow, Yellow,
+                ],
+                [
+                    White, White, White, White, White, White, White, White, White,
+                ],
+                [Green, Green, Red, Green, Green, Green, Green, Green, Green],
+                [Red, Blue, Blue, Blue, Blue, Blue, Blue, Blue, Blue],
+            ],
+        }
+    };
+
+    const RMOVE: FaceletCube = {
+        use Color::*;
+        FaceletCube {
+            faces: [
+                [
+                    Orange, Orange, Orange, Orange, Orange, Orange, Orange, Orange, Orange,
+                ],
+                [Red, Red, Red, Red, Red, Red, Red, Red, Red],
+                [
+                    Yellow, Yellow, Blue, Yellow, Yellow, Blue, Yellow, Yellow, Blue,
+                ],
+                [
+                    White, White, Green, White, White, Green, White, White, Green,
+                ],
+                [
+                    Green, Green, Yellow, Green, Green, Yellow, Green, Green, Yellow,
+                ],
+                [White, Blue, Blue, White, Blue, Blue, White, Blue, Blue],
+            ],
+        }
+    };
+}
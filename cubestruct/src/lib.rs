@@ -1,15 +1,50 @@
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench;
 mod coord_cube;
+mod cube2;
 mod cubie;
 mod cubie_cube;
 mod facelet_cube;
 mod iter_2cycles;
+mod memo;
+mod move_sequence;
+mod niss;
+mod notation;
+mod optimal;
+mod pattern_db;
+mod puzzle;
+mod rotation;
+mod scramble;
+mod search;
+mod solver;
+mod sub_state;
+mod table_cache;
 
-pub use cubie_cube::CubieCube;
-pub use facelet_cube::{Color, FaceletCube};
+pub use cube2::Cube2;
+pub use cubie_cube::{CfopStage, CubieCube};
+pub use facelet_cube::{Color, FaceletConversionError, FaceletCube};
+pub use memo::Memo;
+pub use move_sequence::{InverseStyle, MoveSequence};
+pub use niss::{invert, simplify, Niss};
+pub use notation::{parse_alg, parse_moves, FmcSolution, NotationError};
+pub use pattern_db::{CornerPatternDb, PatternDbError, PATTERN_DB_VERSION};
+pub use puzzle::{Net, Puzzle, Sticker};
+pub use rotation::{reorient, Rotation};
+pub use scramble::{
+    block_scramble, block_scramble_with, case_scramble, case_setup, random_move_scramble,
+    random_state_scramble, scramble_batch, scramble_to_state, seed_from_text, seeded_scramble,
+    seeded_scramble_batch, SolvedBlock, RANDOM_MOVE_SCRAMBLE_LEN,
+};
+pub use solver::{init_solver_tables, solver_tables_ready, CancelToken, Solutions, SolverContext};
+pub use sub_state::{CornerState, EdgeState};
+pub use table_cache::{
+    read_solver_tables, write_solver_tables, SolverTablesError, SOLVER_TABLES_VERSION,
+};
 
 use std::fmt;
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub enum Move {
     Li,
     L,
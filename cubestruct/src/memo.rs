@@ -0,0 +1,221 @@
+//! Blindfolded memorization: tracing the cycles of a state into a sequence of sticker letters.
+//!
+//! Letters use the [Speffz](https://www.speedsolving.com/wiki/index.php/Speffz) scheme, with
+//! white on top and green in front. The corner buffer is UFR and the edge buffer is UF.
+use crate::cubie::*;
+use crate::cubie_cube::CubieCube;
+use crate::facelet_cube::Color;
+
+/// Memo for a state, as Speffz letters
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Memo {
+    /// Corner targets, in the order they're shot to from the buffer (including cycle breaks)
+    pub corners: Vec<char>,
+    /// Edge targets, in the order they're shot to from the buffer (including cycle breaks)
+    pub edges: Vec<char>,
+    /// Corners that are in their home cubicle but twisted, given as the letter of the
+    /// sticker where the piece's U/D color currently is
+    pub twisted_corners: Vec<char>,
+    /// Edges that are in their home cubicle but flipped, given as the letter of the sticker
+    /// where the piece's U/D (or F/B, for E-slice edges) color currently is
+    pub flipped_edges: Vec<char>,
+}
+
+impl Memo {
+    /// Whether there's an odd number of targets, i.e. a parity algorithm is needed
+    pub fn has_parity(&self) -> bool {
+        self.edges.len() % 2 == 1
+    }
+}
+
+/// Home cubicle and sticker of the sticker currently at each position of each cubicle
+type StickerState<const N: usize, const K: usize> = [[(usize, usize); K]; N];
+
+const CORNER_BUFFER: CornerCubicle = CornerCubicle::C3;
+const EDGE_BUFFER: EdgeCubicle = EdgeCubicle::C3;
+
+/// `(face, index in face)` of each corner cubicle's stickers, starting from the U/D sticker
+/// and going clockwise (the same order as corner orientations)
+const CORNER_STICKERS: [[(Color, usize); 3]; 8] = {
+    use Color::*;
+    [
+        [(White, 0), (Orange, 0), (Blue, 2)],
+        [(White, 2), (Blue, 0), (Red, 2)],
+        [(White, 6), (Green, 0), (Orange, 2)],
+        [(White, 8), (Red, 0), (Green, 2)],
+        [(Yellow, 6), (Blue, 8), (Orange, 6)],
+        [(Yellow, 8), (Red, 8), (Blue, 6)],
+        [(Yellow, 0), (Orange, 8), (Green, 6)],
+        [(Yellow, 2), (Green, 8), (Red, 6)],
+    ]
+};
+
+/// `(face, index in face)` of each edge cubicle's stickers, starting from the U/D
+/// (or F/B) sticker
+const EDGE_STICKERS: [[(Color, usize); 2]; 12] = {
+    use Color::*;
+    [
+        [(White, 1), (Blue, 1)],
+        [(White, 3), (Orange, 1)],
+        [(White, 5), (Red, 1)],
+        [(White, 7), (Green, 1)],
+        [(Blue, 5), (Orange, 3)],
+        [(Blue, 3), (Red, 5)],
+        [(Green, 3), (Orange, 5)],
+        [(Green, 5), (Red, 3)],
+        [(Yellow, 7), (Blue, 7)],
+        [(Yellow, 3), (Orange, 7)],
+        [(Yellow, 5), (Red, 7)],
+        [(Yellow, 1), (Green, 7)],
+    ]
+};
+
+fn speffz_letter((face, index): (Color, usize)) -> char {
+    let face_offset = match face {
+        Color::White => 0,
+        Color::Orange => 4,
+        Color::Green => 8,
+        Color::Red => 12,
+        Color::Blue => 16,
+        Color::Yellow => 20,
+    };
+    // clockwise around the face, starting from the top-left (or top) sticker
+    let offset = match index {
+        0 | 1 => 0,
+        2 | 5 => 1,
+        8 | 7 => 2,
+        6 | 3 => 3,
+        _ => unreachable!("Centers don't have a letter"),
+    };
+    char::from(b'A' + face_offset + offset)
+}
+
+fn sticker_state<C: Cubies, const N: usize, const K: usize>(cubies: C) -> StickerState<N, K> {
+    let mut state = [[(0, 0); K]; N];
+    for (home, cubie) in C::Cubicle::all().zip(cubies) {
+        let cubicle = cubie.cubicle().as_u8() as usize;
+        let ori = cubie.orientation().as_u8() as usize;
+        for (i, sticker) in state[cubicle].iter_mut().enumerate() {
+            *sticker = (home.as_u8() as usize, (i + K - ori) % K);
+        }
+    }
+    state
+}
+
+/// Returns `(targets, misoriented pieces)`
+fn trace<const N: usize, const K: usize>(
+    mut state: StickerState<N, K>,
+    buffer: usize,
+    stickers: &[[(Color, usize); K]; N],
+) -> (Vec<char>, Vec<char>) {
+    let letter = |(cubicle, sticker): (usize, usize)| speffz_letter(stickers[cubicle][sticker]);
+    let in_home = |state: &StickerState<N, K>, cubicle: usize| state[cubicle][0].0 == cubicle;
+
+    // Put the piece in the buffer at `target` (the buffer's first sticker going to
+    // `target`), and bring the piece that was there into the buffer
+    let shoot = |state: &mut StickerState<N, K>, (cubicle, sticker): (usize, usize)| {
+        let old = *state;
+        for k in 0..K {
+            state[cubicle][(sticker + k) % K] = old[buffer][k];
+            state[buffer][k] = old[cubicle][(sticker + k) % K];
+        }
+    };
+
+    let mut targets = Vec::new();
+    loop {
+        let target = state[buffer][0];
+        if target.0 != buffer {
+            targets.push(letter(target));
+            shoot(&mut state, target);
+            continue;
+        }
+
+        // The buffer piece is back in the buffer, so start a new cycle from the first
+        // unsolved piece (if there are any left)
+        let Some(next) = (0..N).find(|&cubicle| cubicle != buffer && !in_home(&state, cubicle))
+        else {
+            break;
+        };
+        targets.push(letter((next, 0)));
+        shoot(&mut state, (next, 0));
+    }
+
+    let misoriented = (0..N)
+        .filter(|&cubicle| cubicle != buffer && state[cubicle][0] != (cubicle, 0))
+        .map(|cubicle| {
+            let sticker = state[cubicle].iter().position(|&s| s == (cubicle, 0));
+            letter((cubicle, sticker.unwrap()))
+        })
+        .collect();
+
+    (targets, misoriented)
+}
+
+impl CubieCube {
+    /// Memo for solving this state blindfolded, using Speffz letters and UFR/UF buffers
+    pub fn memo(&self) -> Memo {
+        let (corners, twisted_corners) = trace(
+            sticker_state::<_, 8, 3>(self.corner_cubies()),
+            CORNER_BUFFER as usize,
+            &CORNER_STICKERS,
+        );
+        let (edges, flipped_edges) = trace(
+            sticker_state::<_, 12, 2>(self.edge_cubies()),
+            EDGE_BUFFER as usize,
+            &EDGE_STICKERS,
+        );
+
+        Memo {
+            corners,
+            edges,
+            twisted_corners,
+            flipped_edges,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Move;
+
+    #[test]
+    fn buffers() {
+        assert_eq!(
+            speffz_letter(CORNER_STICKERS[CORNER_BUFFER as usize][0]),
+            'C'
+        );
+        assert_eq!(speffz_letter(EDGE_STICKERS[EDGE_BUFFER as usize][0]), 'C');
+    }
+
+    #[test]
+    fn solved_has_no_memo() {
+        let memo = CubieCube::SOLVED.memo();
+        assert!(memo.corners.is_empty());
+        assert!(memo.edges.is_empty());
+        assert!(memo.twisted_corners.is_empty());
+        assert!(memo.flipped_edges.is_empty());
+        assert!(!memo.has_parity());
+    }
+
+    #[test]
+    fn tperm() {
+        use Move::*;
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_moves(&[R, U, Ri, Ui, Ri, F, R2, Ui, Ri, Ui, R, U, Ri, Fi]);
+        let memo = cube.memo();
+        // UBR <-> UFR
+        assert_eq!(memo.corners, ['B']);
+        // UL <-> UR, with a cycle break into UL
+        assert_eq!(memo.edges, ['D', 'B', 'D']);
+        assert!(memo.has_parity());
+    }
+
+    #[test]
+    fn parity_matches() {
+        for _ in 0..1000 {
+            let memo = CubieCube::random_possible().memo();
+            assert_eq!(memo.corners.len() % 2, memo.edges.len() % 2, "{memo:?}");
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! Writing out sequences of moves, e.g. scrambles, the same way everywhere they're shown
+use crate::Move;
+use std::fmt;
+
+/// How counterclockwise turns are written
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum InverseStyle {
+    /// `R'`, as in WCA notation
+    #[default]
+    Prime,
+    /// `Ri`, for places where apostrophes are awkward (e.g. file names)
+    I,
+}
+
+/// A sequence of moves, formatted by its [`fmt::Display`] impl. By default moves are
+/// separated by single spaces on one line, like [`Move`]'s own notation.
+#[derive(Debug, Copy, Clone)]
+pub struct MoveSequence<'a> {
+    moves: &'a [Move],
+    group_size: Option<usize>,
+    wide_spacing: bool,
+    line_width: Option<usize>,
+    inverse_style: InverseStyle,
+}
+
+impl<'a> MoveSequence<'a> {
+    pub fn new(moves: &'a [Move]) -> Self {
+        Self {
+            moves,
+            group_size: None,
+            wide_spacing: false,
+            line_width: None,
+            inverse_style: InverseStyle::Prime,
+        }
+    }
+
+    /// Split the moves into groups of `size`, with twice the usual space between groups.
+    /// Wrapping keeps groups on one line where they fit.
+    pub fn grouped(self, size: usize) -> Self {
+        Self {
+            group_size: Some(size.max(1)),
+            ..self
+        }
+    }
+
+    /// Two spaces between moves instead of one, which is easier to read at a glance
+    pub fn wide_spacing(self, wide: bool) -> Self {
+        Self {
+            wide_spacing: wide,
+            ..self
+        }
+    }
+
+    /// Start a new line wherever the next move (or group) would make the line longer than
+    /// `width` characters
+    pub fn wrapped(self, width: usize) -> Self {
+        Self {
+            line_width: Some(width),
+            ..self
+        }
+    }
+
+    pub fn inverse_style(self, style: InverseStyle) -> Self {
+        Self {
+            inverse_style: style,
+            ..self
+        }
+    }
+
+    fn write_move(&self, moov: Move) -> String {
+        let (face, amount) = moov.face_and_amount();
+        match (amount, self.inverse_style) {
+            (3, InverseStyle::I) => format!("{face}i"),
+            _ => moov.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for MoveSequence<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let space = if self.wide_spacing { "  " } else { " " };
+        let join = |moves: &[Move]| {
+            moves
+                .iter()
+                .map(|&moov| self.write_move(moov))
+                .collect::<Vec<_>>()
+                .join(space)
+        };
+        // the pieces that are kept together on a line
+        let (units, separator) = match self.group_size {
+            Some(size) => (
+                self.moves.chunks(size).map(join).collect::<Vec<_>>(),
+                space.repeat(2),
+            ),
+            None => (
+                self.moves
+                    .iter()
+                    .map(|&moov| self.write_move(moov))
+                    .collect(),
+                space.to_string(),
+            ),
+        };
+
+        let mut line_len = 0;
+        for (i, unit) in units.iter().enumerate() {
+            if i > 0 {
+                match self.line_width {
+                    Some(width) if line_len + separator.len() + unit.len() > width => {
+                        f.write_str("\n")?;
+                        line_len = 0;
+                    }
+                    _ => {
+                        f.write_str(&separator)?;
+                        line_len += separator.len();
+                    }
+                }
+            }
+            f.write_str(unit)?;
+            line_len += unit.len();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_moves;
+
+    #[test]
+    fn formatting() {
+        let moves = parse_moves("R U' F2 L D' B R2 U").unwrap();
+        let sequence = MoveSequence::new(&moves);
+        assert_eq!(sequence.to_string(), "R U' F2 L D' B R2 U");
+        assert_eq!(
+            sequence.wide_spacing(true).to_string(),
+            "R  U'  F2  L  D'  B  R2  U"
+        );
+        assert_eq!(sequence.grouped(3).to_string(), "R U' F2  L D' B  R2 U");
+        assert_eq!(
+            sequence.inverse_style(InverseStyle::I).to_string(),
+            "R Ui F2 L Di B R2 U"
+        );
+        assert_eq!(MoveSequence::new(&[]).to_string(), "");
+    }
+
+    #[test]
+    fn wrapping() {
+        let moves = parse_moves("R U' F2 L D' B R2 U").unwrap();
+        let sequence = MoveSequence::new(&moves);
+        assert_eq!(sequence.wrapped(8).to_string(), "R U' F2\nL D' B\nR2 U");
+        assert_eq!(
+            sequence.grouped(3).wrapped(12).to_string(),
+            "R U' F2\nL D' B  R2 U"
+        );
+        // a move is never split, even if it doesn't fit
+        assert_eq!(sequence.wrapped(1).to_string().lines().count(), 8);
+        for width in 1..30 {
+            let wrapped = sequence.wrapped(width).to_string();
+            let rejoined = wrapped.lines().collect::<Vec<_>>().join(" ");
+            assert_eq!(parse_moves(&rejoined).unwrap(), moves);
+        }
+    }
+}
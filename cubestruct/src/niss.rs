@@ -0,0 +1,179 @@
+//! NISS ("normal inverse scramble switch"), the fewest moves technique of finding part of a
+//! solution on the inverse scramble. Moves found on the inverse are undone at the end of
+//! the final solution, where they can cancel with the moves found on the normal scramble.
+use crate::{CubieCube, Move};
+
+/// The moves that undo `moves`
+pub fn invert(moves: &[Move]) -> Vec<Move> {
+    moves.iter().rev().map(|moov| moov.inverse()).collect()
+}
+
+/// `moves` with turns of the same face merged or cancelled, including across turns of the
+/// opposite face (which commute with them), e.g. `R L R'` becomes `L`. The result counts
+/// the moves as they'd be written in a final fewest moves solution.
+pub fn simplify(moves: &[Move]) -> Vec<Move> {
+    let mut simplified: Vec<Move> = Vec::with_capacity(moves.len());
+    for &moov in moves {
+        // the moves at the end on the same axis, which `moov` can be moved next to
+        let same_axis = simplified
+            .iter()
+            .rev()
+            .take_while(|m| m.axis() == moov.axis())
+            .count();
+        let start = simplified.len() - same_axis;
+        match simplified[start..]
+            .iter()
+            .position(|m| m.face() == moov.face())
+        {
+            Some(i) => {
+                let (face, amount) = simplified.remove(start + i).face_and_amount();
+                // `None` if they cancel out
+                if let Some(merged) =
+                    Move::from_face_and_amount(face, amount + moov.face_and_amount().1)
+                {
+                    simplified.insert(start + i, merged);
+                }
+            }
+            None => simplified.push(moov),
+        }
+    }
+    simplified
+}
+
+/// A fewest moves attempt that switches between the normal and inverse scramble
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Niss {
+    scramble: Vec<Move>,
+    /// Found on the normal scramble
+    normal: Vec<Move>,
+    /// Found on the inverse scramble
+    inverse: Vec<Move>,
+    on_inverse: bool,
+}
+
+impl Niss {
+    /// Start on the normal scramble
+    pub fn new(scramble: &[Move]) -> Self {
+        Self {
+            scramble: scramble.to_vec(),
+            normal: Vec::new(),
+            inverse: Vec::new(),
+            on_inverse: false,
+        }
+    }
+
+    /// Start with `normal` and `inverse` already found, on the normal scramble
+    pub fn with_moves(scramble: &[Move], normal: &[Move], inverse: &[Move]) -> Self {
+        Self {
+            normal: normal.to_vec(),
+            inverse: inverse.to_vec(),
+            ..Self::new(scramble)
+        }
+    }
+
+    /// Whether moves are being found on the inverse scramble
+    pub fn on_inverse(&self) -> bool {
+        self.on_inverse
+    }
+
+    /// Switch between the normal and inverse scramble
+    pub fn switch(&mut self) {
+        self.on_inverse = !self.on_inverse;
+    }
+
+    /// Add `moves` to the ones found on the current scramble
+    pub fn apply(&mut self, moves: &[Move]) {
+        if self.on_inverse {
+            self.inverse.extend_from_slice(moves);
+        } else {
+            self.normal.extend_from_slice(moves);
+        }
+    }
+
+    pub fn normal(&self) -> &[Move] {
+        &self.normal
+    }
+
+    pub fn inverse(&self) -> &[Move] {
+        &self.inverse
+    }
+
+    /// Moves that take a solved cube to the position being worked on: the moves found on
+    /// the other scramble undone as premoves, then the current scramble and the moves found
+    /// on it. Doing them on a cube is how to carry on after switching.
+    pub fn setup(&self) -> Vec<Move> {
+        let (scramble, this, other) = if self.on_inverse {
+            (invert(&self.scramble), &self.inverse, &self.normal)
+        } else {
+            (self.scramble.clone(), &self.normal, &self.inverse)
+        };
+        let mut setup = invert(other);
+        setup.extend(scramble);
+        setup.extend_from_slice(this);
+        setup
+    }
+
+    /// The final solution to the normal scramble: the normal moves followed by the inverse
+    /// moves undone, simplified where they meet
+    pub fn solution(&self) -> Vec<Move> {
+        let mut moves = self.normal.clone();
+        moves.extend(invert(&self.inverse));
+        simplify(&moves)
+    }
+
+    /// Number of moves in [`Self::solution()`]
+    pub fn move_count(&self) -> usize {
+        self.solution().len()
+    }
+
+    pub fn is_solved(&self) -> bool {
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_moves(&self.setup());
+        cube == CubieCube::SOLVED
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_moves;
+
+    fn moves(s: &str) -> Vec<Move> {
+        parse_moves(s).unwrap()
+    }
+
+    #[test]
+    fn simplification() {
+        assert_eq!(simplify(&moves("R R")), moves("R2"));
+        assert_eq!(simplify(&moves("R U U' R'")), []);
+        assert_eq!(simplify(&moves("R L R'")), moves("L"));
+        assert_eq!(simplify(&moves("R L2 R L2")), moves("R2"));
+        assert_eq!(simplify(&moves("R U R'")), moves("R U R'"));
+    }
+
+    #[test]
+    fn switching() {
+        let scramble = moves("R U F");
+        let mut niss = Niss::new(&scramble);
+        niss.apply(&moves("F'"));
+        assert!(!niss.is_solved());
+        niss.switch();
+        assert!(niss.on_inverse());
+        // the normal moves are premoves on the inverse scramble
+        assert_eq!(niss.setup(), moves("F F' U' R'"));
+        niss.apply(&moves("R U"));
+        assert!(niss.is_solved());
+        assert_eq!(niss.solution(), moves("F' U' R'"));
+        niss.switch();
+        assert!(niss.is_solved());
+    }
+
+    #[test]
+    fn cancellation_where_they_meet() {
+        // the inverse R' is undone as R, which merges with the normal R2
+        let niss = Niss::with_moves(&moves("R U F"), &moves("F' U' R2"), &moves("R'"));
+        assert!(niss.is_solved());
+        assert_eq!(niss.solution(), moves("F' U' R'"));
+        assert_eq!(niss.move_count(), 3);
+    }
+}
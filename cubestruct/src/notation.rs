@@ -0,0 +1,428 @@
+//! Parsing of move notation, including the extra notation allowed in fewest moves solutions
+use crate::niss::{invert, simplify};
+use crate::{CubieCube, Move, Niss};
+use std::str::FromStr;
+
+#[derive(thiserror::Error, Debug, Eq, PartialEq)]
+pub enum NotationError {
+    #[error("Unknown move \"{0}\"")]
+    UnknownMove(String),
+    #[error("Unbalanced parentheses")]
+    UnbalancedParentheses,
+    #[error("Parentheses can't be nested")]
+    NestedParentheses,
+}
+
+const FACES: [char; 6] = ['U', 'D', 'L', 'R', 'F', 'B'];
+
+fn face_index(face: char) -> usize {
+    FACES.iter().position(|&f| f == face).unwrap()
+}
+
+impl Move {
+    /// The face that is turned and the number of clockwise quarter turns (1-3)
+    pub(crate) fn face_and_amount(self) -> (char, u8) {
+        use Move::*;
+        match self {
+            U => ('U', 1),
+            U2 => ('U', 2),
+            Ui => ('U', 3),
+            D => ('D', 1),
+            D2 => ('D', 2),
+            Di => ('D', 3),
+            L => ('L', 1),
+            L2 => ('L', 2),
+            Li => ('L', 3),
+            R => ('R', 1),
+            R2 => ('R', 2),
+            Ri => ('R', 3),
+            F => ('F', 1),
+            F2 => ('F', 2),
+            Fi => ('F', 3),
+            B => ('B', 1),
+            B2 => ('B', 2),
+            Bi => ('B', 3),
+        }
+    }
+
+    /// `None` if `amount` is a multiple of 4 (i.e. not a move)
+    pub(crate) fn from_face_and_amount(face: char, amount: u8) -> Option<Self> {
+        Self::all()
+            .into_iter()
+            .find(|m| m.face_and_amount() == (face, amount % 4))
+    }
+
+    /// The face that is turned (`U`, `D`, `L`, `R`, `F` or `B`)
+    pub(crate) fn face(self) -> char {
+        self.face_and_amount().0
+    }
+
+    /// Moves on opposite faces have the same axis: 0 for U/D, 1 for L/R, 2 for F/B
+    pub(crate) fn axis(self) -> usize {
+        face_index(self.face()) / 2
+    }
+
+    /// The move that undoes this one
+    #[must_use]
+    pub fn inverse(self) -> Self {
+        let (face, amount) = self.face_and_amount();
+        Self::from_face_and_amount(face, 4 - amount).unwrap()
+    }
+}
+
+/// Parses the amount suffix of a move (`""`, `"'"`, `"2"` or `"2'"`)
+fn parse_amount(suffix: &str) -> Option<u8> {
+    match suffix {
+        "" => Some(1),
+        "'" => Some(3),
+        "2" | "2'" => Some(2),
+        _ => None,
+    }
+}
+
+impl FromStr for Move {
+    type Err = NotationError;
+
+    /// Parses a single face turn, like `R`, `U'` or `F2`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || NotationError::UnknownMove(s.to_string());
+        let mut chars = s.chars();
+        let face = chars.next().filter(|c| FACES.contains(c)).ok_or_else(err)?;
+        let amount = parse_amount(chars.as_str()).ok_or_else(err)?;
+        Self::from_face_and_amount(face, amount).ok_or_else(err)
+    }
+}
+
+/// Parses a whitespace-separated sequence of face turns
+pub fn parse_moves(s: &str) -> Result<Vec<Move>, NotationError> {
+    s.split_whitespace().map(str::parse).collect()
+}
+
+/// Which face of the cube (in the scramble orientation) each face in the notation currently
+/// refers to, after any rotations
+#[derive(Debug, Copy, Clone)]
+struct Frame([char; 6]);
+
+impl Frame {
+    const IDENTITY: Self = Self(FACES);
+
+    fn physical(&self, face: char) -> char {
+        self.0[face_index(face)]
+    }
+
+    /// Apply `amount` clockwise quarter rotations of the whole cube around the axis of
+    /// `axis` (`x`, `y` or `z`)
+    fn rotate(&mut self, axis: char, amount: u8) {
+        // `(a, b)` means that after the rotation, face `a` is where `b` was before
+        let cycle: [(char, char); 4] = match axis {
+            'x' => [('F', 'D'), ('U', 'F'), ('B', 'U'), ('D', 'B')],
+            'y' => [('F', 'R'), ('R', 'B'), ('B', 'L'), ('L', 'F')],
+            'z' => [('U', 'L'), ('L', 'D'), ('D', 'R'), ('R', 'U')],
+            _ => unreachable!(),
+        };
+        for _ in 0..amount {
+            let old = *self;
+            for (to, from) in cycle {
+                self.0[face_index(to)] = old.physical(from);
+            }
+        }
+    }
+}
+
+/// A fewest moves solution, possibly using NISS (moves in parentheses are done on the
+/// inverse scramble). Outer block turns (`Rw`) and rotations (`x`, `y`, `z`) are allowed.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FmcSolution {
+    /// Face turns in the scramble orientation, with the inverse parts already undone
+    /// and appended to the normal parts, simplified where they meet
+    moves: Vec<Move>,
+    /// Face turns outside of parentheses, in the scramble orientation
+    normal: Vec<Move>,
+    /// Face turns in parentheses, in the scramble orientation
+    inverse: Vec<Move>,
+}
+
+impl FmcSolution {
+    /// The whole solution as face turns, to be applied after the scramble
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    /// Number of moves according to the WCA regulations: face turns and outer block turns
+    /// count as 1, rotations don't count. Moves that cancel where the inverse part is
+    /// appended to the normal part (see [`crate::simplify`]) aren't counted.
+    pub fn move_count(&self) -> usize {
+        self.moves.len()
+    }
+
+    /// The solution's normal and inverse parts, to carry on with on `scramble`
+    pub fn niss(&self, scramble: &[Move]) -> Niss {
+        Niss::with_moves(scramble, &self.normal, &self.inverse)
+    }
+
+    /// Whether this solves the given scramble
+    pub fn solves(&self, scramble: &[Move]) -> bool {
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_moves(scramble);
+        cube.apply_moves(&self.moves);
+        cube == CubieCube::SOLVED
+    }
+}
+
+/// Moves and orientation of the normal or inverse part of a solution
+#[derive(Debug)]
+struct Part {
+    moves: Vec<Move>,
+    frame: Frame,
+}
+
+impl Part {
+    fn push_token(&mut self, token: &str) -> Result<(), NotationError> {
+        let err = || NotationError::UnknownMove(token.to_string());
+        let mut chars = token.chars();
+        let first = chars.next().ok_or_else(err)?;
+
+        if matches!(first, 'x' | 'y' | 'z') {
+            let amount = parse_amount(chars.as_str()).ok_or_else(err)?;
+            self.frame.rotate(first, amount);
+            return Ok(());
+        }
+
+        // `Rw` or `r` turns the opposite face, then rotates
+        let rest = chars.as_str();
+        let (face, suffix, wide) = if let Some(suffix) = rest.strip_prefix('w') {
+            (first, suffix, true)
+        } else if first.is_ascii_lowercase() {
+            (first.to_ascii_uppercase(), rest, true)
+        } else {
+            (first, rest, false)
+        };
+        if !FACES.contains(&face) {
+            return Err(err());
+        }
+        let amount = parse_amount(suffix).ok_or_else(err)?;
+
+        if wide {
+            let (opposite, axis, clockwise) = match face {
+                'R' => ('L', 'x', true),
+                'L' => ('R', 'x', false),
+                'U' => ('D', 'y', true),
+                'D' => ('U', 'y', false),
+                'F' => ('B', 'z', true),
+                'B' => ('F', 'z', false),
+                _ => unreachable!(),
+            };
+            let physical = self.frame.physical(opposite);
+            self.moves
+                .push(Move::from_face_and_amount(physical, amount).unwrap());
+            self.frame
+                .rotate(axis, if clockwise { amount } else { 4 - amount });
+        } else {
+            let physical = self.frame.physical(face);
+            self.moves
+                .push(Move::from_face_and_amount(physical, amount).unwrap());
+        }
+        Ok(())
+    }
+
+    /// Pushes `token` if it's a slice move (`M`, `E` or `S`), and returns whether it was
+    fn push_slice(&mut self, token: &str) -> Result<bool, NotationError> {
+        let mut chars = token.chars();
+        // a slice turns the same way as the face it follows, so it's the same as turning
+        // both outer faces the other way and rotating, e.g. `M` is `R L' x'`
+        let (follows, opposite, axis, clockwise) = match chars.next() {
+            Some('M') => ('L', 'R', 'x', false),
+            Some('E') => ('D', 'U', 'y', false),
+            Some('S') => ('F', 'B', 'z', true),
+            _ => return Ok(false),
+        };
+        let amount = parse_amount(chars.as_str())
+            .ok_or_else(|| NotationError::UnknownMove(token.to_string()))?;
+        for (face, amount) in [(opposite, amount), (follows, 4 - amount)] {
+            let physical = self.frame.physical(face);
+            self.moves
+                .push(Move::from_face_and_amount(physical, amount).unwrap());
+        }
+        self.frame
+            .rotate(axis, if clockwise { amount } else { 4 - amount });
+        Ok(true)
+    }
+}
+
+impl FromStr for FmcSolution {
+    type Err = NotationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut normal = Part {
+            moves: Vec::new(),
+            frame: Frame::IDENTITY,
+        };
+        let mut inverse = Part {
+            moves: Vec::new(),
+            frame: Frame::IDENTITY,
+        };
+        let mut in_parens = false;
+
+        for word in s.split_whitespace() {
+            let mut token = word;
+            while let Some(rest) = token.strip_prefix('(') {
+                if in_parens {
+                    return Err(NotationError::NestedParentheses);
+                }
+                in_parens = true;
+                token = rest;
+            }
+            let mut closing = 0;
+            while let Some(rest) = token.strip_suffix(')') {
+                closing += 1;
+                token = rest;
+            }
+
+            if !token.is_empty() {
+                let part = if in_parens { &mut inverse } else { &mut normal };
+                part.push_token(token)?;
+            }
+
+            for _ in 0..closing {
+                if !in_parens {
+                    return Err(NotationError::UnbalancedParentheses);
+                }
+                in_parens = false;
+            }
+        }
+        if in_parens {
+            return Err(NotationError::UnbalancedParentheses);
+        }
+
+        let mut moves = normal.moves.clone();
+        moves.extend(invert(&inverse.moves));
+
+        Ok(Self {
+            moves: simplify(&moves),
+            normal: normal.moves,
+            inverse: inverse.moves,
+        })
+    }
+}
+
+/// Parses an algorithm the way they're usually written: like a fewest moves solution, except
+/// that slice moves (`M`, `E`, `S`) are allowed and parentheses only group moves. Returns
+/// the face turns in the starting orientation.
+pub fn parse_alg(s: &str) -> Result<Vec<Move>, NotationError> {
+    let mut part = Part {
+        moves: Vec::new(),
+        frame: Frame::IDENTITY,
+    };
+    // algs copied from elsewhere often use typographic primes
+    let s = s.replace(['’', '′'], "'");
+    for token in s.split(|c: char| c.is_whitespace() || c == '(' || c == ')') {
+        if !token.is_empty() && !part.push_slice(token)? {
+            part.push_token(token)?;
+        }
+    }
+    Ok(part.moves)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_move() {
+        for moov in Move::all() {
+            assert_eq!(moov.to_string().parse::<Move>(), Ok(moov));
+            assert_eq!(moov.inverse().inverse(), moov);
+        }
+        assert_eq!("R2'".parse::<Move>(), Ok(Move::R2));
+        assert!("R3".parse::<Move>().is_err());
+        assert!("M".parse::<Move>().is_err());
+        assert!("".parse::<Move>().is_err());
+        assert_eq!(
+            parse_moves("  R U'  F2 "),
+            Ok(vec![Move::R, Move::Ui, Move::F2])
+        );
+    }
+
+    #[test]
+    fn fmc_solutions() {
+        use Move::*;
+        let scramble = [R, U, F];
+
+        let linear: FmcSolution = "F' U' R'".parse().unwrap();
+        assert!(linear.solves(&scramble));
+        assert_eq!(linear.move_count(), 3);
+
+        let wrong: FmcSolution = "F' U R'".parse().unwrap();
+        assert!(!wrong.solves(&scramble));
+
+        // the inverse scramble is F' U' R', which is solved by R U F
+        let niss: FmcSolution = "F' (R U)".parse().unwrap();
+        assert!(niss.solves(&scramble));
+        assert_eq!(niss.move_count(), 3);
+        assert_eq!(niss.moves(), [Fi, Ui, Ri]);
+
+        // after y, F is the original R face
+        let rotated: FmcSolution = "F' U' y F'".parse().unwrap();
+        assert!(rotated.solves(&scramble));
+        assert_eq!(rotated.move_count(), 3);
+
+        // Rw' is L' with an x', after which U is the original B face
+        let wide: FmcSolution = "Rw' U'".parse().unwrap();
+        assert_eq!(wide.moves(), [Li, Bi]);
+        assert!(wide.solves(&[B, L]));
+        assert_eq!(wide.move_count(), 2);
+        assert_eq!("r' U'".parse::<FmcSolution>(), Ok(wide));
+        let undone: FmcSolution = "Rw' x U'".parse().unwrap();
+        assert_eq!(undone.moves(), [Li, Ui]);
+
+        // the inverse R' is undone as R, which merges with R2 into the R' that's written
+        let cancelled: FmcSolution = "F' U' R2 (R')".parse().unwrap();
+        assert!(cancelled.solves(&scramble));
+        assert_eq!(cancelled.move_count(), 3);
+        assert_eq!(cancelled.niss(&scramble).normal(), [Fi, Ui, R2]);
+    }
+
+    #[test]
+    fn fmc_errors() {
+        assert_eq!(
+            "R (U".parse::<FmcSolution>(),
+            Err(NotationError::UnbalancedParentheses)
+        );
+        assert_eq!(
+            "R U)".parse::<FmcSolution>(),
+            Err(NotationError::UnbalancedParentheses)
+        );
+        assert_eq!(
+            "(R (U))".parse::<FmcSolution>(),
+            Err(NotationError::NestedParentheses)
+        );
+        assert_eq!(
+            "R M".parse::<FmcSolution>(),
+            Err(NotationError::UnknownMove("M".into()))
+        );
+    }
+
+    #[test]
+    fn algs() {
+        use Move::*;
+        assert_eq!(parse_alg("(R U R' U')"), Ok(vec![R, U, Ri, Ui]));
+        assert_eq!(parse_alg("R U′ R’"), Ok(vec![R, Ui, Ri]));
+        // M' is R' L x, after which U is the original F face
+        assert_eq!(parse_alg("M' U"), Ok(vec![Ri, L, F]));
+        assert_eq!(parse_alg("M2 U"), Ok(vec![R2, L2, D]));
+        // E is U D' y', after which F and B are the original L and R faces
+        assert_eq!(parse_alg("E S"), Ok(vec![U, Di, R, Li]));
+        assert_eq!(parse_alg("r U"), parse_alg("Rw U"));
+        assert_eq!(
+            parse_alg("R Q"),
+            Err(NotationError::UnknownMove("Q".into()))
+        );
+
+        // the H perm only swaps U layer edges
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_moves(&parse_alg("M2 U M2 U2 M2 U M2").unwrap());
+        assert_ne!(cube, CubieCube::SOLVED);
+        cube.apply_moves(&parse_alg("M2 U' M2 U2 M2 U' M2").unwrap());
+        assert_eq!(cube, CubieCube::SOLVED);
+    }
+}
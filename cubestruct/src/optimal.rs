@@ -0,0 +1,145 @@
+//! Optimal solver: an IDA* search over the whole cube, pruned with a [`CornerPatternDb`] and
+//! the two-phase solver's phase 1 tables (which are lower bounds on the whole solution too,
+//! since it has to pass through the phase 2 subgroup). Unlike the two-phase solver, the
+//! first solution it finds is as short as possible, but for a random state that can take
+//! a very long time. Like phase 1, each depth is searched on rayon's thread pool, with each
+//! first move searched separately.
+use crate::coord_cube::{corner_ori_move_table, corner_perm_move_table, CoordCube};
+use crate::cubie::Corners;
+use crate::search::{search_depth, Coords};
+use crate::{CancelToken, CornerPatternDb, CubieCube, Move};
+use rayon::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// God's number: every state can be solved in this many moves
+const MAX_OPTIMAL_LENGTH: u8 = 20;
+
+impl CubieCube {
+    /// A shortest sequence of moves that solves the cube, or `None` if this isn't a possible
+    /// state or `cancel` was cancelled first
+    pub fn solve_optimal(&self, db: &CornerPatternDb, cancel: &CancelToken) -> Option<Vec<Move>> {
+        if !self.is_possible_state() {
+            return None;
+        }
+
+        let start = OptimalCoords {
+            db,
+            cube: *self,
+            corner_perm: self.get_corner_perm_coord(),
+            corner_ori: self.get_ori_coord::<Corners>(),
+            phase1: CoordCube::from_cubie_cube(self),
+        };
+        (start.distance_bound()..=MAX_OPTIMAL_LENGTH)
+            .take_while(|_| !cancel.is_cancelled())
+            .find_map(|depth| search_in_parallel(&start, depth, cancel))
+    }
+}
+
+/// Search for solutions exactly `depth` moves long, like one iteration of IDA*. The
+/// solution with the earliest first move wins, as in the two-phase solver's phase 1, so
+/// it's the same one a single thread would find.
+fn search_in_parallel(start: &OptimalCoords, depth: u8, cancel: &CancelToken) -> Option<Vec<Move>> {
+    // the distance bound is only 0 when it's solved
+    if depth == 0 {
+        return Some(Vec::new());
+    }
+    let moves = Move::all();
+    // index into `moves` of the earliest solution found so far
+    let best = AtomicUsize::new(usize::MAX);
+    (0..moves.len()).into_par_iter().find_map_first(|i| {
+        if i > best.load(Ordering::Relaxed) {
+            return None;
+        }
+        // no need to keep going once a solution with an earlier first move is found
+        let stopped = || best.load(Ordering::Relaxed) < i || cancel.is_cancelled();
+        let mut next = start.clone();
+        next.apply_move(moves[i]);
+        let mut path = vec![moves[i]];
+        let found = search_depth(
+            next,
+            &moves,
+            depth - 1,
+            &mut path,
+            &mut |_, _| true,
+            &stopped,
+        );
+        if found {
+            best.fetch_min(i, Ordering::Relaxed);
+        }
+        found.then_some(path)
+    })
+}
+
+#[derive(Debug, Clone)]
+struct OptimalCoords<'a> {
+    db: &'a CornerPatternDb,
+    /// To tell when it's solved, which the coordinates below can't
+    cube: CubieCube,
+    corner_perm: u16,
+    corner_ori: u16,
+    phase1: CoordCube,
+}
+
+impl Coords for OptimalCoords<'_> {
+    type Move = Move;
+
+    fn apply_move(&mut self, moov: Move) {
+        self.cube.apply_move(moov);
+        self.corner_perm = corner_perm_move_table()[moov][self.corner_perm as usize];
+        self.corner_ori = corner_ori_move_table()[moov][self.corner_ori as usize];
+        self.phase1.apply_move(moov);
+    }
+
+    fn distance_bound(&self) -> u8 {
+        let bound = self
+            .db
+            .distance_of(self.corner_perm, self.corner_ori)
+            .max(self.phase1.distance_bound());
+        // solved corners in the phase 2 subgroup aren't necessarily solved edges
+        if bound == 0 && self.cube != CubieCube::SOLVED {
+            1
+        } else {
+            bound
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shortest_solutions() {
+        // a database that knows nothing is still a valid lower bound, and takes much less
+        // time to make than a real one
+        let db = CornerPatternDb::empty();
+        let cancel = CancelToken::new();
+
+        assert_eq!(
+            CubieCube::SOLVED.solve_optimal(&db, &cancel),
+            Some(Vec::new())
+        );
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_moves(&[Move::R, Move::U, Move::Ui, Move::F]);
+        assert_eq!(
+            cube.solve_optimal(&db, &cancel),
+            Some(vec![Move::Fi, Move::Ri])
+        );
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_moves(&[Move::R, Move::U, Move::Ri, Move::Ui]);
+        let solution = cube.solve_optimal(&db, &cancel).unwrap();
+        assert_eq!(solution.len(), 4);
+        cube.apply_moves(&solution);
+        assert_eq!(cube, CubieCube::SOLVED);
+
+        let mut cube = CubieCube::SOLVED;
+        cube.set_corner_perm_coord(1);
+        assert_eq!(cube.solve_optimal(&db, &cancel), None);
+        let cancelled = CancelToken::new();
+        cancelled.cancel();
+        assert_eq!(
+            CubieCube::random_possible().solve_optimal(&db, &cancelled),
+            None
+        );
+    }
+}
@@ -0,0 +1,351 @@
+//! Pattern databases: the exact number of moves needed to solve a subset of the pieces, for every
+//! state of that subset. They're a lower bound on the moves needed to solve the whole cube, which
+//! is what the optimal solver ([`CubieCube::solve_optimal()`]) prunes its search with. They take a
+//! while to generate and are large, so they can be saved to a file and memory-mapped back in.
+//!
+//! # File format
+//!
+//! All integers are little endian.
+//!
+//! | Offset | Size | Contents                                                   |
+//! |--------|------|------------------------------------------------------------|
+//! | 0      | 8    | The magic bytes `CSTRPDB\0`                                |
+//! | 8      | 4    | Format version ([`PATTERN_DB_VERSION`])                    |
+//! | 12     | 4    | Which pieces the database is for (1 = all 8 corners)       |
+//! | 16     | 8    | Number of entries                                          |
+//! | 24     |      | The entries, two per byte with the first in the low 4 bits |
+//!
+//! Each entry is the number of moves needed to solve the pieces. For the corners, the entry
+//! at index `perm * 2187 + ori` is for the state with corner permutation coordinate `perm`
+//! and corner orientation coordinate `ori`, and it takes at most 11 moves.
+use crate::coord_cube::{
+    corner_ori_move_table, corner_perm_move_table, NUM_CORNER_ORIS, NUM_CORNER_PERMS,
+};
+use crate::cubie::Corners;
+use crate::{CubieCube, Move};
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Version of the file format written by this version of cubestruct. Files of other
+/// versions can't be opened.
+pub const PATTERN_DB_VERSION: u32 = 1;
+
+const MAGIC: &[u8; 8] = b"CSTRPDB\0";
+const HEADER_LEN: usize = 24;
+const CORNERS_KIND: u32 = 1;
+const NUM_CORNER_STATES: usize = NUM_CORNER_PERMS as usize * NUM_CORNER_ORIS as usize;
+/// Entry value of states that haven't been reached yet while generating
+const UNVISITED: u8 = 0xf;
+
+#[derive(thiserror::Error, Debug)]
+pub enum PatternDbError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Not a pattern database")]
+    NotAPatternDb,
+    #[error("Pattern database is version {0}, but only version {PATTERN_DB_VERSION} is supported")]
+    UnsupportedVersion(u32),
+    #[error("Pattern database is for different pieces")]
+    WrongKind,
+    #[error("Pattern database is the wrong size (truncated?)")]
+    WrongSize,
+}
+
+/// Pattern database of all 8 corners
+#[derive(Debug)]
+pub struct CornerPatternDb {
+    /// The whole file, including the header
+    bytes: Bytes,
+}
+
+impl CornerPatternDb {
+    /// Generate the database, calling `progress` with the fraction of states that have been
+    /// reached after each depth. Takes tens of seconds in release builds.
+    pub fn generate(mut progress: impl FnMut(f32)) -> Self {
+        let mut bytes = header(CORNERS_KIND, NUM_CORNER_STATES);
+        bytes.resize(
+            HEADER_LEN + NUM_CORNER_STATES.div_ceil(2),
+            UNVISITED | UNVISITED << 4,
+        );
+        let entries = &mut bytes[HEADER_LEN..];
+        set_entry(entries, 0, 0);
+
+        let perm_table = corner_perm_move_table();
+        let ori_table = corner_ori_move_table();
+        let neighbors = |index: usize| {
+            let perm = index / NUM_CORNER_ORIS as usize;
+            let ori = index % NUM_CORNER_ORIS as usize;
+            Move::all().map(|moov| {
+                perm_table[moov][perm] as usize * NUM_CORNER_ORIS as usize
+                    + ori_table[moov][ori] as usize
+            })
+        };
+
+        let mut visited = 1;
+        let mut depth = 0;
+        while visited < NUM_CORNER_STATES {
+            // once most states have been reached, it's faster to look for the unreached
+            // states next to the frontier than to expand the frontier
+            let backwards = visited > NUM_CORNER_STATES / 2;
+            for index in 0..NUM_CORNER_STATES {
+                if backwards {
+                    if entry(entries, index) == UNVISITED
+                        && neighbors(index)
+                            .into_iter()
+                            .any(|next| entry(entries, next) == depth)
+                    {
+                        set_entry(entries, index, depth + 1);
+                        visited += 1;
+                    }
+                } else if entry(entries, index) == depth {
+                    for next in neighbors(index) {
+                        if entry(entries, next) == UNVISITED {
+                            set_entry(entries, next, depth + 1);
+                            visited += 1;
+                        }
+                    }
+                }
+            }
+            depth += 1;
+            progress(visited as f32 / NUM_CORNER_STATES as f32);
+        }
+
+        Self {
+            bytes: Bytes::Owned(bytes),
+        }
+    }
+
+    /// Memory-map a database file written by [`Self::write()`]. To read it into memory
+    /// instead, use [`Self::from_vec()`].
+    ///
+    /// # Safety
+    ///
+    /// The file mustn't be modified or truncated, by this process or any other, until the
+    /// database is dropped. Its contents are read straight from the mapping, so a change
+    /// would change memory that Rust assumes is immutable, and truncating it would make
+    /// reading the mapping fault.
+    pub unsafe fn open(path: impl AsRef<Path>) -> Result<Self, PatternDbError> {
+        let file = File::open(path)?;
+        // SAFETY: the caller makes sure the file isn't changed while it's mapped
+        let bytes = unsafe { Bytes::map(&file)? };
+        Self::from_bytes(bytes)
+    }
+
+    /// Load a database from the contents of a file written by [`Self::write()`]
+    pub fn from_vec(bytes: Vec<u8>) -> Result<Self, PatternDbError> {
+        Self::from_bytes(Bytes::Owned(bytes))
+    }
+
+    fn from_bytes(bytes: Bytes) -> Result<Self, PatternDbError> {
+        check_header(&bytes, CORNERS_KIND, NUM_CORNER_STATES)?;
+        Ok(Self { bytes })
+    }
+
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        writer.write_all(&self.bytes)
+    }
+
+    /// The number of moves needed to solve the corners of `cube`
+    pub fn distance(&self, cube: &CubieCube) -> u8 {
+        self.distance_of(
+            cube.get_corner_perm_coord(),
+            cube.get_ori_coord::<Corners>(),
+        )
+    }
+
+    /// [`Self::distance()`] from the corner permutation and orientation coordinates
+    pub(crate) fn distance_of(&self, perm: u16, ori: u16) -> u8 {
+        let index = perm as usize * NUM_CORNER_ORIS as usize + ori as usize;
+        entry(&self.bytes[HEADER_LEN..], index)
+    }
+
+    /// A database where every entry is 0, which is still a lower bound
+    #[cfg(test)]
+    pub(crate) fn empty() -> Self {
+        let mut bytes = header(CORNERS_KIND, NUM_CORNER_STATES);
+        bytes.resize(HEADER_LEN + NUM_CORNER_STATES.div_ceil(2), 0);
+        Self {
+            bytes: Bytes::Owned(bytes),
+        }
+    }
+}
+
+fn entry(entries: &[u8], index: usize) -> u8 {
+    (entries[index / 2] >> (index % 2 * 4)) & 0xf
+}
+
+fn set_entry(entries: &mut [u8], index: usize, value: u8) {
+    let shift = index % 2 * 4;
+    let byte = &mut entries[index / 2];
+    *byte = (*byte & !(0xf << shift)) | value << shift;
+}
+
+fn header(kind: u32, num_entries: usize) -> Vec<u8> {
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    header.extend_from_slice(MAGIC);
+    header.extend_from_slice(&PATTERN_DB_VERSION.to_le_bytes());
+    header.extend_from_slice(&kind.to_le_bytes());
+    header.extend_from_slice(&(num_entries as u64).to_le_bytes());
+    header
+}
+
+fn check_header(bytes: &[u8], kind: u32, num_entries: usize) -> Result<(), PatternDbError> {
+    if !bytes.starts_with(MAGIC) {
+        return Err(PatternDbError::NotAPatternDb);
+    }
+    let Some(header) = bytes.get(..HEADER_LEN) else {
+        return Err(PatternDbError::WrongSize);
+    };
+    let u32_at = |i: usize| u32::from_le_bytes(header[i..i + 4].try_into().unwrap());
+
+    let version = u32_at(8);
+    if version != PATTERN_DB_VERSION {
+        return Err(PatternDbError::UnsupportedVersion(version));
+    }
+    if u32_at(12) != kind {
+        return Err(PatternDbError::WrongKind);
+    }
+    let len = u64::from_le_bytes(header[16..24].try_into().unwrap());
+    if len != num_entries as u64 || bytes.len() != HEADER_LEN + num_entries.div_ceil(2) {
+        return Err(PatternDbError::WrongSize);
+    }
+    Ok(())
+}
+
+/// Contents of a database file, either in memory or mapped from the file
+#[derive(Debug)]
+enum Bytes {
+    Owned(Vec<u8>),
+    #[cfg(unix)]
+    Mapped {
+        ptr: *mut libc::c_void,
+        len: usize,
+    },
+}
+
+// SAFETY: the mapping is read-only and private, and only unmapped on drop
+#[cfg(unix)]
+unsafe impl Send for Bytes {}
+#[cfg(unix)]
+unsafe impl Sync for Bytes {}
+
+impl Bytes {
+    /// # Safety
+    ///
+    /// `file` mustn't be changed while the mapping is alive (see [`CornerPatternDb::open()`])
+    #[cfg(unix)]
+    unsafe fn map(file: &File) -> io::Result<Self> {
+        use std::os::fd::AsRawFd;
+
+        let len = file.metadata()?.len() as usize;
+        if len == 0 {
+            // mmap fails for empty files
+            return Ok(Self::Owned(Vec::new()));
+        }
+        // SAFETY: a new mapping is created, so no existing memory is affected
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self::Mapped { ptr, len })
+    }
+
+    /// Reads the whole file where memory-mapping isn't supported
+    #[cfg(not(unix))]
+    unsafe fn map(mut file: &File) -> io::Result<Self> {
+        use std::io::Read;
+
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        Ok(Self::Owned(bytes))
+    }
+}
+
+impl std::ops::Deref for Bytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Self::Owned(bytes) => bytes,
+            // SAFETY: the mapping is `len` bytes long and lives as long as `self`
+            #[cfg(unix)]
+            Self::Mapped { ptr, len } => unsafe {
+                std::slice::from_raw_parts(*ptr as *const u8, *len)
+            },
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Bytes {
+    fn drop(&mut self) {
+        if let Self::Mapped { ptr, len } = *self {
+            // SAFETY: the mapping came from `mmap` and nothing borrows it anymore
+            unsafe {
+                libc::munmap(ptr, len);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packed_entries() {
+        let mut entries = [0xff; 2];
+        set_entry(&mut entries, 1, 3);
+        set_entry(&mut entries, 2, 11);
+        assert_eq!(entries, [0x3f, 0xfb]);
+        assert_eq!(entry(&entries, 0), 0xf);
+        assert_eq!(entry(&entries, 1), 3);
+        assert_eq!(entry(&entries, 2), 11);
+    }
+
+    #[test]
+    fn bad_files() {
+        let valid = |num_entries| {
+            let mut bytes = header(CORNERS_KIND, num_entries);
+            bytes.resize(HEADER_LEN + num_entries.div_ceil(2), 0);
+            bytes
+        };
+        assert!(check_header(&valid(5), CORNERS_KIND, 5).is_ok());
+        assert!(matches!(
+            check_header(b"hello", CORNERS_KIND, 5),
+            Err(PatternDbError::NotAPatternDb)
+        ));
+        assert!(matches!(
+            check_header(&valid(5), CORNERS_KIND + 1, 5),
+            Err(PatternDbError::WrongKind)
+        ));
+        assert!(matches!(
+            check_header(&valid(5)[..HEADER_LEN + 2], CORNERS_KIND, 5),
+            Err(PatternDbError::WrongSize)
+        ));
+        assert!(matches!(
+            check_header(&valid(5)[..12], CORNERS_KIND, 5),
+            Err(PatternDbError::WrongSize)
+        ));
+        let mut future = valid(5);
+        future[8] = 2;
+        assert!(matches!(
+            check_header(&future, CORNERS_KIND, 5),
+            Err(PatternDbError::UnsupportedVersion(2))
+        ));
+        assert!(matches!(
+            CornerPatternDb::from_vec(valid(5)),
+            Err(PatternDbError::WrongSize)
+        ));
+    }
+}
@@ -0,0 +1,141 @@
+//! Things every puzzle can do, so that code which works with scrambles (e.g. the timer and
+//! trainers) can be written once for all of them
+use crate::{Color, CubieCube, Move};
+use rand::Rng;
+use std::fmt;
+
+/// A puzzle's state. Implemented by [`CubieCube`] for the 3x3x3 and [`Cube2`](crate::Cube2)
+/// for the 2x2x2.
+pub trait Puzzle: Clone + Eq + fmt::Debug + Send + Sync + 'static {
+    type Move: Copy + Eq + fmt::Debug + fmt::Display + Send + Sync + 'static;
+
+    fn solved() -> Self;
+
+    /// Whether the puzzle is solved. For puzzles without fixed centers this is true in any
+    /// orientation, not just the one [`Self::solved()`] is in.
+    fn is_solved(&self) -> bool;
+
+    fn apply_move(&mut self, moov: Self::Move);
+
+    /// Apply each of the given moves in order
+    fn apply_moves(&mut self, moves: &[Self::Move]) {
+        for &moov in moves {
+            self.apply_move(moov);
+        }
+    }
+
+    /// A scramble to a uniformly random state, as the WCA requires
+    fn random_state_scramble() -> Vec<Self::Move> {
+        Self::random_state_scramble_with(&mut rand::thread_rng())
+    }
+
+    /// Like [`Self::random_state_scramble()`], with the randomness coming from `rng`. The
+    /// same random numbers always give the same scramble.
+    fn random_state_scramble_with<R: Rng>(rng: &mut R) -> Vec<Self::Move>;
+
+    /// A flat diagram of the puzzle's stickers
+    fn net(&self) -> Net;
+}
+
+/// A flat diagram of a puzzle's stickers. Coordinates go right and down from the top left
+/// corner, in units of the puzzle's choosing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Net {
+    pub width: f64,
+    pub height: f64,
+    pub stickers: Vec<Sticker>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sticker {
+    /// Corners of the sticker's outline, in order around it
+    pub outline: Vec<(f64, f64)>,
+    pub color: Color,
+}
+
+/// `(face, column, row)` of each face in a cube's net, in units of faces
+const CUBE_FACE_POSITIONS: [(Color, f64, f64); 6] = [
+    (Color::White, 1.0, 0.0),
+    (Color::Orange, 0.0, 1.0),
+    (Color::Green, 1.0, 1.0),
+    (Color::Red, 2.0, 1.0),
+    (Color::Blue, 3.0, 1.0),
+    (Color::Yellow, 1.0, 2.0),
+];
+
+/// Net of an NxNxN cube, with faces laid out like [`FaceletCube::get_face()`] shows.
+/// `face_stickers` gives the colors of a face's stickers, row by row. Units are stickers.
+///
+/// [`FaceletCube::get_face()`]: crate::FaceletCube::get_face()
+pub(crate) fn cube_net<const N: usize>(face_stickers: impl Fn(Color) -> [Color; N]) -> Net {
+    let size = (N as f64).sqrt() as usize;
+    let mut stickers = Vec::with_capacity(6 * N);
+    for (face, face_x, face_y) in CUBE_FACE_POSITIONS {
+        for (i, color) in face_stickers(face).into_iter().enumerate() {
+            let x = face_x * size as f64 + (i % size) as f64;
+            let y = face_y * size as f64 + (i / size) as f64;
+            stickers.push(Sticker {
+                outline: vec![(x, y), (x + 1.0, y), (x + 1.0, y + 1.0), (x, y + 1.0)],
+                color,
+            });
+        }
+    }
+    Net {
+        width: 4.0 * size as f64,
+        height: 3.0 * size as f64,
+        stickers,
+    }
+}
+
+impl Puzzle for CubieCube {
+    type Move = Move;
+
+    fn solved() -> Self {
+        Self::SOLVED
+    }
+
+    fn is_solved(&self) -> bool {
+        *self == Self::SOLVED
+    }
+
+    fn apply_move(&mut self, moov: Move) {
+        CubieCube::apply_move(self, moov);
+    }
+
+    fn random_state_scramble_with<R: Rng>(rng: &mut R) -> Vec<Move> {
+        crate::scramble_to_state(&CubieCube::random_possible_with(rng)).unwrap()
+    }
+
+    fn net(&self) -> Net {
+        let facelets = self.to_facelet_cube();
+        cube_net(|face| facelets.get_face(face))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_moves;
+
+    #[test]
+    fn cube_net_layout() {
+        let net = CubieCube::SOLVED.net();
+        assert_eq!((net.width, net.height), (12.0, 9.0));
+        assert_eq!(net.stickers.len(), 54);
+        // the top left sticker of the front face
+        let front = &net.stickers[2 * 9];
+        assert_eq!(front.color, Color::Green);
+        assert_eq!(front.outline[0], (3.0, 3.0));
+    }
+
+    #[test]
+    fn solved_after_inverse() {
+        let moves = parse_moves("R U R' F2 D' L B").unwrap();
+        let mut cube = <CubieCube as Puzzle>::solved();
+        Puzzle::apply_moves(&mut cube, &moves);
+        assert!(!cube.is_solved());
+        let inverse: Vec<_> = moves.iter().rev().map(|m| m.inverse()).collect();
+        Puzzle::apply_moves(&mut cube, &inverse);
+        assert!(cube.is_solved());
+    }
+}
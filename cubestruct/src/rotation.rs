@@ -0,0 +1,283 @@
+//! Whole-cube rotations, for cubes that aren't held white top, green front
+use crate::{Color, CubieCube, FaceletCube, Move};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::fmt;
+
+/// A rotation of the whole cube, as in WCA notation
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Rotation {
+    Xi,
+    X,
+    X2,
+    Yi,
+    Y,
+    Y2,
+    Zi,
+    Z,
+    Z2,
+}
+
+impl Rotation {
+    pub fn all() -> [Self; 9] {
+        use Rotation::*;
+        [Xi, X, X2, Yi, Y, Y2, Zi, Z, Z2]
+    }
+
+    /// Rotations that turn a cube held with white on top and green in front so that `top`
+    /// is on top and `front` is in front. `None` if `top` and `front` aren't adjacent.
+    pub fn to_orientation(top: Color, front: Color) -> Option<Vec<Self>> {
+        // first bring `top` to the top, then turn it to get the right front
+        let tops: [&[Self]; 6] = [
+            &[],
+            &[Self::X],
+            &[Self::X2],
+            &[Self::Xi],
+            &[Self::Z],
+            &[Self::Zi],
+        ];
+        let fronts: [&[Self]; 4] = [&[], &[Self::Y], &[Self::Y2], &[Self::Yi]];
+        tops.into_iter()
+            .flat_map(|t| fronts.into_iter().map(move |f| [t, f].concat()))
+            .find(|rotations| {
+                let mut facelets = CubieCube::SOLVED.to_facelet_cube();
+                for &rotation in rotations {
+                    facelets.rotate(rotation);
+                }
+                facelets.get_face(Color::White)[4] == top
+                    && facelets.get_face(Color::Green)[4] == front
+            })
+    }
+
+    /// Rotations to a uniformly random one of the cube's 24 orientations (possibly none),
+    /// e.g. to add after scrambles for practicing color neutrality
+    pub fn random_orientation() -> Vec<Self> {
+        Self::random_orientation_with(&mut rand::thread_rng())
+    }
+
+    /// Like [`Self::random_orientation()`], with the randomness coming from `rng`
+    pub fn random_orientation_with<R: Rng>(rng: &mut R) -> Vec<Self> {
+        let orientations: Vec<_> = Color::all()
+            .into_iter()
+            .flat_map(|top| Color::all().map(|front| Self::to_orientation(top, front)))
+            .flatten()
+            .collect();
+        orientations.choose(rng).unwrap().clone()
+    }
+
+    /// `x` and `y` quarter turns that make up this rotation, in order. `true` means `x`.
+    fn quarter_turns(self) -> &'static [(bool, u8)] {
+        match self {
+            Self::X => &[(true, 1)],
+            Self::X2 => &[(true, 2)],
+            Self::Xi => &[(true, 3)],
+            Self::Y => &[(false, 1)],
+            Self::Y2 => &[(false, 2)],
+            Self::Yi => &[(false, 3)],
+            Self::Z => &[(true, 3), (false, 3), (true, 1)],
+            Self::Z2 => &[(true, 2), (false, 2)],
+            Self::Zi => &[(true, 3), (false, 1), (true, 1)],
+        }
+    }
+}
+
+impl fmt::Display for Rotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Xi => write!(f, "x'"),
+            Self::X => write!(f, "x"),
+            Self::X2 => write!(f, "x2"),
+            Self::Yi => write!(f, "y'"),
+            Self::Y => write!(f, "y"),
+            Self::Y2 => write!(f, "y2"),
+            Self::Zi => write!(f, "z'"),
+            Self::Z => write!(f, "z"),
+            Self::Z2 => write!(f, "z2"),
+        }
+    }
+}
+
+/// Index of a facelet after turning its face clockwise (see [`FaceletCube::get_face()`])
+fn clockwise(i: usize) -> usize {
+    let (row, col) = (i / 3, i % 3);
+    col * 3 + (2 - row)
+}
+
+fn counterclockwise(i: usize) -> usize {
+    let (row, col) = (i / 3, i % 3);
+    (2 - col) * 3 + row
+}
+
+/// `(from, to, index mapping)`: where the facelets of a face go in a rotation
+type FaceMove = (Color, Color, fn(usize) -> usize);
+
+impl FaceletCube {
+    /// Move the facelets (including centers) to where they are after `rotation`. Faces are
+    /// still named by the colors of the cube's solved state, so e.g. after `x` the `White`
+    /// face has green stickers.
+    pub fn rotate(&mut self, rotation: Rotation) {
+        use Color::*;
+
+        for &(is_x, amount) in rotation.quarter_turns() {
+            for _ in 0..amount {
+                let mut builder = FaceletCube::builder();
+                let cycle: [FaceMove; 6] = if is_x {
+                    [
+                        (Green, White, |i| i),
+                        (White, Blue, |i| 8 - i),
+                        (Blue, Yellow, |i| 8 - i),
+                        (Yellow, Green, |i| i),
+                        (Red, Red, clockwise),
+                        (Orange, Orange, counterclockwise),
+                    ]
+                } else {
+                    [
+                        (Green, Orange, |i| i),
+                        (Orange, Blue, |i| i),
+                        (Blue, Red, |i| i),
+                        (Red, Green, |i| i),
+                        (White, White, clockwise),
+                        (Yellow, Yellow, counterclockwise),
+                    ]
+                };
+                for (from, to, map) in cycle {
+                    for (i, color) in self.get_face(from).into_iter().enumerate() {
+                        builder.set(to, map(i), color);
+                    }
+                }
+                *self = builder.build().unwrap();
+            }
+        }
+    }
+}
+
+impl CubieCube {
+    /// The state of the cube after turning it over by `rotation`. Since a `CubieCube`
+    /// doesn't track centers, this is relative to the new position of the cube: e.g. after
+    /// `y`, a cube that was scrambled with `R` is the same as a solved cube after `F`.
+    pub fn rotate(&mut self, rotation: Rotation) {
+        let mut rotated = self.to_facelet_cube();
+        rotated.rotate(rotation);
+
+        // rename the colors to the faces their centers are on now
+        let new_face = |color| {
+            Color::all()
+                .into_iter()
+                .find(|&face| rotated.get_face(face)[4] == color)
+                .unwrap()
+        };
+        let mut builder = FaceletCube::builder();
+        for face in Color::all() {
+            for (i, color) in rotated.get_face(face).into_iter().enumerate() {
+                builder.set(face, i, new_face(color));
+            }
+        }
+
+        *self = builder.build().unwrap().to_cubie_cube().unwrap();
+    }
+}
+
+impl Move {
+    /// The move that turns the same layer as `self` once the cube has been turned over by
+    /// `rotation`, e.g. `R` becomes `F` after `y`
+    pub fn rotated(self, rotation: Rotation) -> Self {
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_move(self);
+        cube.rotate(rotation);
+        Move::all()
+            .into_iter()
+            .find(|&moov| {
+                let mut after = CubieCube::SOLVED;
+                after.apply_move(moov);
+                after == cube
+            })
+            .unwrap()
+    }
+}
+
+/// `moves`, written for a cube held in the standard orientation (white top, green front),
+/// as the moves that do the same to a cube that's been turned over by `rotations` first,
+/// e.g. to scramble the same state in every orientation
+pub fn reorient(moves: &[Move], rotations: &[Rotation]) -> Vec<Move> {
+    moves
+        .iter()
+        .map(|&moov| {
+            rotations
+                .iter()
+                .fold(moov, |moov, &rotation| moov.rotated(rotation))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn after(moov: Move) -> CubieCube {
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_move(moov);
+        cube
+    }
+
+    #[test]
+    fn rotated_moves() {
+        let cases = [
+            (Move::R, Rotation::Y, Move::F),
+            (Move::U, Rotation::X, Move::B),
+            (Move::U, Rotation::Z, Move::R),
+            (Move::U, Rotation::Zi, Move::L),
+            (Move::F, Rotation::Z2, Move::F),
+            (Move::Ri, Rotation::X2, Move::Ri),
+            (Move::U2, Rotation::Z2, Move::D2),
+        ];
+        for (moov, rotation, expected) in cases {
+            let mut cube = after(moov);
+            cube.rotate(rotation);
+            assert_eq!(cube, after(expected), "{moov} then {rotation}");
+        }
+    }
+
+    #[test]
+    fn reoriented_moves() {
+        let scramble = crate::parse_moves("R U F' D2 L B").unwrap();
+        let mut expected = CubieCube::SOLVED;
+        expected.apply_moves(&scramble);
+        for _ in 0..10 {
+            let rotations = Rotation::random_orientation();
+            let mut cube = CubieCube::SOLVED;
+            cube.apply_moves(&reorient(&scramble, &rotations));
+            // turning it back over shows the same state as scrambling without rotating
+            for rotation in rotations.iter().rev() {
+                for _ in 0..3 {
+                    cube.rotate(*rotation);
+                }
+            }
+            assert_eq!(cube, expected, "{rotations:?}");
+        }
+    }
+
+    #[test]
+    fn orientations() {
+        let mut count = 0;
+        for top in Color::all() {
+            for front in Color::all() {
+                let Some(rotations) = Rotation::to_orientation(top, front) else {
+                    continue;
+                };
+                count += 1;
+                let mut facelets = CubieCube::SOLVED.to_facelet_cube();
+                for rotation in rotations {
+                    facelets.rotate(rotation);
+                }
+                assert_eq!(facelets.get_face(Color::White)[4], top);
+                assert_eq!(facelets.get_face(Color::Green)[4], front);
+            }
+        }
+        assert_eq!(count, 24);
+        assert_eq!(
+            Rotation::to_orientation(Color::White, Color::Green),
+            Some(Vec::new())
+        );
+        assert_eq!(Rotation::to_orientation(Color::White, Color::Yellow), None);
+    }
+}
@@ -0,0 +1,325 @@
+//! Scramble generation
+use crate::cubie::{CornerCubicle, EdgeCubicle};
+use crate::{Color, CubieCube, Move, Puzzle};
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rayon::prelude::*;
+use std::ops::Range;
+
+/// Length of scrambles generated by [`random_move_scramble`]
+pub const RANDOM_MOVE_SCRAMBLE_LEN: usize = 25;
+
+/// A scramble of random moves. No face is turned twice in a row, and opposite faces
+/// aren't turned in an `A B A` pattern (since that's the same as `A2 B`).
+pub fn random_move_scramble() -> Vec<Move> {
+    let mut rng = rand::thread_rng();
+    let mut scramble: Vec<Move> = Vec::with_capacity(RANDOM_MOVE_SCRAMBLE_LEN);
+    while scramble.len() < RANDOM_MOVE_SCRAMBLE_LEN {
+        let moov = *Move::all().choose(&mut rng).unwrap();
+        let allowed = match scramble[..] {
+            [] => true,
+            [.., prev] if prev.face() == moov.face() => false,
+            [.., before, prev] => !(before.axis() == prev.axis() && prev.axis() == moov.axis()),
+            [_] => true,
+        };
+        if allowed {
+            scramble.push(moov);
+        }
+    }
+    scramble
+}
+
+/// A scramble to a uniformly random state, as the WCA requires. The first call can take a
+/// while, since it builds the solver's tables.
+pub fn random_state_scramble() -> Vec<Move> {
+    CubieCube::random_state_scramble()
+}
+
+/// `n` random-state scrambles of the puzzle `P`, generated on all of the CPU's cores. That's
+/// quicker than generating them one after another, since a single solve can't keep every
+/// core busy and they share the tables, which are only built once.
+pub fn scramble_batch<P: Puzzle>(n: usize) -> Vec<Vec<P::Move>> {
+    batch(n, |_| P::random_state_scramble())
+}
+
+/// The random-state scramble of the puzzle `P` at position `index` in the stream of
+/// scrambles picked by `seed`. It's the same for everyone using the same seed (and version
+/// of cubestruct), so people can race each other on the same scrambles.
+pub fn seeded_scramble<P: Puzzle>(seed: u64, index: u64) -> Vec<P::Move> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    // each scramble gets its own stream, so they can be generated in any order
+    rng.set_stream(index);
+    P::random_state_scramble_with(&mut rng)
+}
+
+/// A seed for [`seeded_scramble`] from text that people agree on, e.g. the name of a race.
+/// It's hashed with 64-bit FNV-1a, which (unlike `std`'s hashers) is guaranteed to be the
+/// same on every machine and version.
+pub fn seed_from_text(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100_0000_01b3)
+    })
+}
+
+/// The [`seeded_scramble`]s at each of `indices`, in order, generated on all of the CPU's
+/// cores like [`scramble_batch`]
+pub fn seeded_scramble_batch<P: Puzzle>(seed: u64, indices: Range<u64>) -> Vec<Vec<P::Move>> {
+    let start = indices.start;
+    let n = indices.end.saturating_sub(start) as usize;
+    batch(n, |i| seeded_scramble::<P>(seed, start + i as u64))
+}
+
+/// `generate(i)` for each `i` in `0..n`, run on rayon's thread pool. The solves' own
+/// searches share the pool too, so there are never more threads than cores.
+fn batch<T: Send>(n: usize, generate: impl Fn(usize) -> T + Sync) -> Vec<T> {
+    (0..n).into_par_iter().map(&generate).collect()
+}
+
+/// Pieces that a practice scramble from [`block_scramble`] leaves solved
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SolvedBlock {
+    /// The cross on the face with this center
+    Cross(Color),
+    /// The cross on the face with this center, and one of the corner and edge pairs next
+    /// to it (chosen at random)
+    XCross(Color),
+    /// The 1x2x3 block on `side`, along its edge with `bottom`, e.g. Roux's first block
+    /// with `Orange` and `Yellow`
+    Block123 { side: Color, bottom: Color },
+}
+
+impl SolvedBlock {
+    /// Cubicles of the pieces in the block, or `None` if it isn't a block (a
+    /// [`Self::Block123`] whose sides aren't next to each other)
+    fn cubicles<R: Rng>(self, rng: &mut R) -> Option<(Vec<CornerCubicle>, Vec<EdgeCubicle>)> {
+        let on = CubieCube::cubicles_on;
+        let opposite = |face| {
+            let (corners, _) = on(face);
+            Color::all()
+                .into_iter()
+                .find(|&other| other != face && on(other).0.iter().all(|c| !corners.contains(c)))
+                .unwrap()
+        };
+        match self {
+            Self::Cross(face) => Some((Vec::new(), on(face).1)),
+            Self::XCross(face) => {
+                let (corners, cross) = on(face);
+                let corner = *corners.choose(rng).unwrap();
+                // the edge between the corner's other two faces
+                let sides: Vec<_> = Color::all()
+                    .into_iter()
+                    .filter(|&side| side != face && on(side).0.contains(&corner))
+                    .collect();
+                let edge = on(sides[0])
+                    .1
+                    .into_iter()
+                    .find(|edge| on(sides[1]).1.contains(edge))
+                    .unwrap();
+                let mut edges = cross;
+                edges.push(edge);
+                Some((vec![corner], edges))
+            }
+            Self::Block123 { side, bottom } => {
+                let (side_corners, side_edges) = on(side);
+                let (bottom_corners, _) = on(bottom);
+                if side == bottom || opposite(side) == bottom {
+                    return None;
+                }
+                let (_, top_edges) = on(opposite(bottom));
+                Some((
+                    side_corners
+                        .into_iter()
+                        .filter(|c| bottom_corners.contains(c))
+                        .collect(),
+                    side_edges
+                        .into_iter()
+                        .filter(|e| !top_edges.contains(e))
+                        .collect(),
+                ))
+            }
+        }
+    }
+}
+
+/// A scramble to a uniformly random state where `block` is solved, for practicing what
+/// comes after it (or for cross skips). `None` if `block` isn't a block. Like
+/// [`random_state_scramble`], the first call can take a while.
+pub fn block_scramble(block: SolvedBlock) -> Option<Vec<Move>> {
+    block_scramble_with(block, &mut rand::thread_rng())
+}
+
+/// Like [`block_scramble`], with the randomness coming from `rng`
+pub fn block_scramble_with<R: Rng>(block: SolvedBlock, rng: &mut R) -> Option<Vec<Move>> {
+    let (corners, edges) = block.cubicles(rng)?;
+    let state = CubieCube::random_possible_with_solved(rng, &corners, &edges);
+    scramble_to_state(&state)
+}
+
+/// A scramble that takes a solved cube to `state`, or `None` if it isn't a possible state
+pub fn scramble_to_state(state: &CubieCube) -> Option<Vec<Move>> {
+    Some(crate::invert(&state.solve()?))
+}
+
+/// Moves that set up the case that `alg` solves. There's a random `U` turn before and after
+/// it, so that the case has to be recognized from any angle and the pre-AUF (the `U` turn
+/// needed before the alg) practiced along with it.
+pub fn case_setup(alg: &[Move]) -> Vec<Move> {
+    let mut rng = rand::thread_rng();
+    let mut setup: Vec<Move> = Vec::with_capacity(alg.len() + 2);
+    let mut push = |moov: Move| {
+        let (face, amount) = moov.face_and_amount();
+        match setup.last() {
+            Some(&prev) if prev.face() == face => {
+                let total = prev.face_and_amount().1 + amount;
+                setup.pop();
+                setup.extend(Move::from_face_and_amount(face, total));
+            }
+            _ => setup.push(moov),
+        }
+    };
+    let auf = |amount: u8| Move::from_face_and_amount('U', amount);
+    auf(rng.gen_range(0..4)).into_iter().for_each(&mut push);
+    alg.iter()
+        .rev()
+        .map(|&moov| moov.inverse())
+        .for_each(&mut push);
+    auf(rng.gen_range(0..4)).into_iter().for_each(&mut push);
+    setup
+}
+
+/// A scramble to the case that `alg` solves (see [`case_setup`]), found by the solver so
+/// that it doesn't give the alg away. Like [`random_state_scramble`], the first call can
+/// take a while.
+pub fn case_scramble(alg: &[Move]) -> Vec<Move> {
+    let mut state = CubieCube::SOLVED;
+    state.apply_moves(&case_setup(alg));
+    scramble_to_state(&state).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scrambled(scramble: &[Move]) -> CubieCube {
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_moves(scramble);
+        cube
+    }
+
+    #[test]
+    fn block_scrambles() {
+        for face in Color::all() {
+            let cube = scrambled(&block_scramble(SolvedBlock::Cross(face)).unwrap());
+            assert!(cube.is_cross_solved(face), "{face:?}");
+            assert_ne!(cube, CubieCube::SOLVED);
+        }
+
+        let cube = scrambled(&block_scramble(SolvedBlock::XCross(Color::Yellow)).unwrap());
+        assert!(cube.is_cross_solved(Color::Yellow));
+        assert!(cube.corners().solved_count() >= 1);
+        assert!(cube.edges().solved_count() >= 5);
+
+        let roux = SolvedBlock::Block123 {
+            side: Color::Orange,
+            bottom: Color::Yellow,
+        };
+        let (corners, edges) = roux.cubicles(&mut rand::thread_rng()).unwrap();
+        assert_eq!((corners.len(), edges.len()), (2, 3));
+        let cube = scrambled(&block_scramble(roux).unwrap());
+        assert!(corners.iter().all(|&c| cube[c] == CubieCube::SOLVED[c]));
+        assert!(edges.iter().all(|&e| cube[e] == CubieCube::SOLVED[e]));
+
+        let not_adjacent = SolvedBlock::Block123 {
+            side: Color::Orange,
+            bottom: Color::Red,
+        };
+        assert_eq!(block_scramble(not_adjacent), None);
+    }
+
+    #[test]
+    fn no_redundant_moves() {
+        for _ in 0..100 {
+            let scramble = random_move_scramble();
+            assert_eq!(scramble.len(), RANDOM_MOVE_SCRAMBLE_LEN);
+            for pair in scramble.windows(2) {
+                assert_ne!(pair[0].face(), pair[1].face());
+            }
+            for triple in scramble.windows(3) {
+                assert!(triple.iter().any(|m| m.axis() != triple[0].axis()));
+            }
+        }
+    }
+
+    #[test]
+    fn random_state_scrambles_are_valid() {
+        for _ in 0..5 {
+            let scramble = random_state_scramble();
+            assert!(scramble.len() <= 30);
+            for pair in scramble.windows(2) {
+                assert_ne!(pair[0].face(), pair[1].face());
+            }
+            let mut state = CubieCube::SOLVED;
+            state.apply_moves(&scramble);
+            assert!(state.is_possible_state());
+        }
+    }
+
+    #[test]
+    fn batches() {
+        let scrambles = scramble_batch::<CubieCube>(3);
+        assert_eq!(scrambles.len(), 3);
+        assert_ne!(scrambles[0], scrambles[1]);
+        assert!(scramble_batch::<crate::Cube2>(0).is_empty());
+    }
+
+    #[test]
+    fn seeded_scrambles() {
+        use crate::Cube2;
+        let batch = seeded_scramble_batch::<Cube2>(42, 3..6);
+        assert_eq!(batch.len(), 3);
+        for (i, scramble) in (3..6).zip(&batch) {
+            assert_eq!(*scramble, seeded_scramble::<Cube2>(42, i));
+        }
+        assert_ne!(batch[0], batch[1]);
+        assert_ne!(batch[0], seeded_scramble::<Cube2>(43, 3));
+        assert_eq!(
+            seeded_scramble::<CubieCube>(7, 0),
+            seeded_scramble::<CubieCube>(7, 0)
+        );
+    }
+
+    #[test]
+    fn text_seeds() {
+        // known FNV-1a hashes, since other people's copies have to agree on them
+        assert_eq!(seed_from_text(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(seed_from_text("a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(seed_from_text("foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn case_scrambles() {
+        use Move::*;
+        let tperm = [R, U, Ri, Ui, Ri, F, R2, Ui, Ri, Ui, R, U, Ri, Fi];
+        let solved_with_aufs = |scramble: &[Move]| {
+            (0..4).any(|pre| {
+                (0..4).any(|post| {
+                    let mut cube = CubieCube::SOLVED;
+                    cube.apply_moves(scramble);
+                    cube.apply_moves(&[U].repeat(pre));
+                    cube.apply_moves(&tperm);
+                    cube.apply_moves(&[U].repeat(post));
+                    cube == CubieCube::SOLVED
+                })
+            })
+        };
+        for _ in 0..10 {
+            let setup = case_setup(&tperm);
+            assert!(solved_with_aufs(&setup));
+            for pair in setup.windows(2) {
+                assert_ne!(pair[0].face(), pair[1].face());
+            }
+        }
+        assert!(solved_with_aufs(&case_scramble(&tperm)));
+    }
+}
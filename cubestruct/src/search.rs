@@ -0,0 +1,201 @@
+//! Searches that solvers are built from. A solver describes its puzzle's states with
+//! coordinates, and supplies how moves change them and a lower bound on the distance to the
+//! goal (usually looked up in a [`distance_table()`]). The search loops themselves don't
+//! depend on the puzzle.
+use crate::Move;
+
+/// A move of a puzzle that can be searched over
+pub(crate) trait SearchMove: Copy {
+    /// Whether `self` shouldn't be searched after `prev`, because the same state can be
+    /// reached with fewer moves or in another order that is searched
+    fn is_redundant_after(self, prev: Self) -> bool;
+}
+
+impl SearchMove for Move {
+    /// Turning the same face twice could be done in one move, and moves of opposite faces
+    /// commute so only one order is needed
+    fn is_redundant_after(self, prev: Self) -> bool {
+        prev.face() == self.face() || (prev.axis() == self.axis() && prev.face() > self.face())
+    }
+}
+
+/// A puzzle state, as the coordinates that a search moves through
+pub(crate) trait Coords: Clone {
+    type Move: SearchMove;
+
+    fn apply_move(&mut self, moov: Self::Move);
+
+    /// A lower bound on the number of moves needed to reach the goal, which must be 0
+    /// only at the goal
+    fn distance_bound(&self) -> u8;
+}
+
+/// Iterative deepening A*. Tries each depth from `start`'s distance bound up to
+/// `max_depth`, and returns `true` once a goal state is found that `accept` also accepts,
+/// with the moves to it appended to `path`. `path` may start with the moves before
+/// `start`, so that the first move isn't redundant after them. `accept` can extend the
+/// path, e.g. with a search for a later phase, and must leave it unchanged if it returns
+/// `false`. The search gives up, returning `false`, once `stopped` returns `true`.
+pub(crate) fn ida_star<C: Coords>(
+    start: C,
+    moves: &[C::Move],
+    max_depth: u8,
+    path: &mut Vec<C::Move>,
+    accept: &mut impl FnMut(&C, &mut Vec<C::Move>) -> bool,
+    stopped: &impl Fn() -> bool,
+) -> bool {
+    (start.distance_bound()..=max_depth)
+        .any(|depth| search_depth(start.clone(), moves, depth, path, accept, stopped))
+}
+
+/// Search for goal states exactly `depth` moves away, like one iteration of [`ida_star()`]
+pub(crate) fn search_depth<C: Coords>(
+    coords: C,
+    moves: &[C::Move],
+    depth: u8,
+    path: &mut Vec<C::Move>,
+    accept: &mut impl FnMut(&C, &mut Vec<C::Move>) -> bool,
+    stopped: &impl Fn() -> bool,
+) -> bool {
+    if stopped() {
+        return false;
+    }
+    let bound = coords.distance_bound();
+    if depth == 0 {
+        return bound == 0 && accept(&coords, path);
+    }
+    if bound > depth {
+        return false;
+    }
+
+    for &moov in moves {
+        if path
+            .last()
+            .is_some_and(|&prev| moov.is_redundant_after(prev))
+        {
+            continue;
+        }
+        let mut next = coords.clone();
+        next.apply_move(moov);
+        path.push(moov);
+        if search_depth(next, moves, depth - 1, path, accept, stopped) {
+            return true;
+        }
+        path.pop();
+    }
+    false
+}
+
+/// Number of moves to get from `solved` to each of `size` coordinates, found by a
+/// breadth-first search. Unreachable coordinates are `u8::MAX`.
+pub(crate) fn distance_table<M: Copy>(
+    solved: usize,
+    size: usize,
+    moves: &[M],
+    apply_move: impl Fn(M, usize) -> usize,
+) -> Vec<u8> {
+    let mut table = vec![u8::MAX; size];
+    table[solved] = 0;
+    let mut frontier = vec![solved];
+    let mut depth = 0;
+    while !frontier.is_empty() {
+        depth += 1;
+        let mut next = Vec::new();
+        for coord in frontier {
+            for &moov in moves {
+                let moved = apply_move(moov, coord);
+                if table[moved] == u8::MAX {
+                    table[moved] = depth;
+                    next.push(moved);
+                }
+            }
+        }
+        frontier = next;
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A counter that moves go up or down by 1 or 2, with the goal at 0
+    #[derive(Debug, Copy, Clone)]
+    struct Counter(i32);
+
+    impl SearchMove for i32 {
+        fn is_redundant_after(self, prev: Self) -> bool {
+            self == -prev
+        }
+    }
+
+    impl Coords for Counter {
+        type Move = i32;
+
+        fn apply_move(&mut self, moov: i32) {
+            self.0 += moov;
+        }
+
+        fn distance_bound(&self) -> u8 {
+            self.0.unsigned_abs().div_ceil(2) as u8
+        }
+    }
+
+    #[test]
+    fn finds_shortest_path() {
+        let mut path = Vec::new();
+        assert!(ida_star(
+            Counter(5),
+            &[-2, -1, 1, 2],
+            10,
+            &mut path,
+            &mut |_, _| true,
+            &|| false
+        ));
+        assert_eq!(path.len(), 3);
+        assert_eq!(path.iter().sum::<i32>(), -5);
+    }
+
+    #[test]
+    fn rejected_goals_keep_searching() {
+        // only accept paths that end going up
+        let mut path = Vec::new();
+        assert!(ida_star(
+            Counter(1),
+            &[-2, -1, 1, 2],
+            10,
+            &mut path,
+            &mut |_, path| path.last().is_some_and(|&m| m > 0),
+            &|| false
+        ));
+        assert_eq!(path, [-2, 1]);
+        assert!(!ida_star(
+            Counter(1),
+            &[-2, -1, 1, 2],
+            10,
+            &mut Vec::new(),
+            &mut |_, _| false,
+            &|| false
+        ));
+    }
+
+    #[test]
+    fn stopping() {
+        let mut path = Vec::new();
+        assert!(!ida_star(
+            Counter(5),
+            &[-2, -1, 1, 2],
+            10,
+            &mut path,
+            &mut |_, _| true,
+            &|| true
+        ));
+        assert!(path.is_empty());
+    }
+
+    #[test]
+    fn distances() {
+        let table = distance_table(0, 6, &[1, 2], |moov, coord| (coord + moov) % 6);
+        assert_eq!(table, [0, 1, 1, 2, 2, 3]);
+    }
+}
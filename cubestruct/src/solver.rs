@@ -0,0 +1,456 @@
+//! Two-phase solver (Kociemba's algorithm). Phase 1 brings the cube into the subgroup
+//! generated by `U, D, R2, L2, F2, B2`, and phase 2 solves it using only those moves.
+//! Phase 1 is searched on rayon's thread pool, with each first move searched separately.
+use crate::coord_cube::{CoordCube, Phase2Cube, PHASE2_MOVES, TABLES};
+use crate::search::{ida_star, search_depth, Coords};
+use crate::{CubieCube, Move, Puzzle};
+use rayon::prelude::*;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+static TABLES_READY: AtomicBool = AtomicBool::new(false);
+
+/// Every cube can be brought into the phase 2 subgroup in this many moves
+const MAX_PHASE1_DEPTH: u8 = 12;
+/// Every cube in the phase 2 subgroup can be solved in this many phase 2 moves
+const MAX_PHASE2_DEPTH: u8 = 18;
+
+impl CubieCube {
+    /// A sequence of moves that solves the cube, or `None` if this isn't a possible state.
+    /// It's the first solution the two-phase search finds, so it isn't optimal and can be
+    /// up to 30 moves long; [`Self::solve_until()`] keeps searching for shorter ones.
+    pub fn solve(&self) -> Option<Vec<Move>> {
+        self.solve_within(MAX_PHASE1_DEPTH + MAX_PHASE2_DEPTH)
+    }
+
+    /// Like [`Self::solve()`], but only finds solutions of at most `max_length` moves. It
+    /// can return `None` when there is a solution that short, since the two-phase
+    /// algorithm doesn't try every solution.
+    pub fn solve_within(&self, max_length: u8) -> Option<Vec<Move>> {
+        self.solve_cancellable(max_length, &CancelToken::new())
+    }
+
+    /// Like [`Self::solve()`], but keeps searching for shorter solutions until the two-phase
+    /// algorithm can't find any more or `cancel` is cancelled, and returns the shortest one
+    /// found. `None` if this isn't a possible state, or if it was cancelled before the
+    /// first solution was found.
+    pub fn solve_until(&self, cancel: &CancelToken) -> Option<Vec<Move>> {
+        self.solutions(cancel.clone()).last()
+    }
+
+    /// Each solution found while searching for shorter ones, like [`Self::solve_until()`]
+    /// does, as it's found. Each one is shorter than the one before it, starting with the
+    /// solution from [`Self::solve()`]. Every call to `next` searches until the next
+    /// solution is found, so the later ones can take a long time; the iterator ends once
+    /// there are none shorter to find, or `cancel` is cancelled.
+    pub fn solutions(&self, cancel: CancelToken) -> Solutions {
+        Solutions {
+            cube: *self,
+            max_length: Some(MAX_PHASE1_DEPTH + MAX_PHASE2_DEPTH),
+            cancel,
+        }
+    }
+
+    /// [`Self::solve_within()`], giving up once `cancel` is cancelled
+    fn solve_cancellable(&self, max_length: u8, cancel: &CancelToken) -> Option<Vec<Move>> {
+        if !self.is_possible_state() {
+            return None;
+        }
+
+        let coords = CoordCube::from_cubie_cube(self);
+        (coords.distance_bound()..=MAX_PHASE1_DEPTH.min(max_length))
+            .find_map(|depth| phase1(self, &coords, depth, max_length, cancel))
+    }
+
+    /// Length of the shortest solution [`Self::solve_until()`] finds in `time`, as a rough
+    /// measure of how hard the state is to solve. It's an upper bound on the length of an
+    /// optimal solution, which gets closer the more time it's given (the first solution
+    /// found isn't much of one: random-state scrambles are made from it, so it's about as
+    /// long as they are). `None` if this isn't a possible state.
+    pub fn solution_length_bound(&self, time: Duration) -> Option<u8> {
+        let cancel = CancelToken::with_deadline(Instant::now() + time);
+        // if it ran out of time before the first solution, that one's still a bound
+        let solution = self.solve_until(&cancel).or_else(|| self.solve())?;
+        Some(solution.len() as u8)
+    }
+}
+
+/// Iterator returned by [`CubieCube::solutions()`]
+#[derive(Debug, Clone)]
+pub struct Solutions {
+    cube: CubieCube,
+    /// Of the next solution, or `None` once there can't be any more
+    max_length: Option<u8>,
+    cancel: CancelToken,
+}
+
+impl Iterator for Solutions {
+    type Item = Vec<Move>;
+
+    fn next(&mut self) -> Option<Vec<Move>> {
+        let solution = self.cube.solve_cancellable(self.max_length?, &self.cancel);
+        self.max_length = solution
+            .as_ref()
+            .and_then(|solution| (solution.len() as u8).checked_sub(1));
+        solution
+    }
+}
+
+/// Stops a solve that was given it, either from another thread with [`Self::cancel()`] or
+/// once a deadline has passed. Clones share whether they've been cancelled, so one can be
+/// kept to cancel a solve that's running with another.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+impl CancelToken {
+    /// A token that's only cancelled by [`Self::cancel()`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A token that's also cancelled once `deadline` has passed
+    pub fn with_deadline(deadline: Instant) -> Self {
+        Self {
+            deadline: Some(deadline),
+            ..Self::default()
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+/// Build the solver's tables, calling `progress` with the fraction that's done after each
+/// one. Solving builds them when they're first needed, which takes a few seconds, so this
+/// is for building them ahead of time (e.g. on a background thread).
+pub fn init_solver_tables(mut progress: impl FnMut(f32)) {
+    for (i, build) in TABLES.iter().enumerate() {
+        build();
+        progress((i + 1) as f32 / TABLES.len() as f32);
+    }
+    TABLES_READY.store(true, Ordering::Release);
+}
+
+/// Whether [`init_solver_tables`] has finished, so solving won't have to wait for tables
+pub fn solver_tables_ready() -> bool {
+    TABLES_READY.load(Ordering::Acquire)
+}
+
+/// Proof that the solver's tables are built, through which solving and random-state
+/// scrambling never have to wait for them. It's `Send + Sync` and cheap to clone, so one
+/// context can be shared by every thread or task that solves.
+///
+/// The tables themselves are built once per process and shared by every context, so
+/// making another one after the first doesn't build them again.
+#[derive(Debug, Clone)]
+pub struct SolverContext(());
+
+impl SolverContext {
+    /// Build the solver's tables if they aren't already (see [`init_solver_tables`])
+    pub fn new(progress: impl FnMut(f32)) -> Self {
+        if !solver_tables_ready() {
+            init_solver_tables(progress);
+        }
+        Self(())
+    }
+
+    /// A context if the tables have already been built, without building them
+    pub fn get() -> Option<Self> {
+        solver_tables_ready().then_some(Self(()))
+    }
+
+    /// See [`CubieCube::solve`]
+    pub fn solve(&self, cube: &CubieCube) -> Option<Vec<Move>> {
+        cube.solve()
+    }
+
+    /// See [`CubieCube::solve_within`]
+    pub fn solve_within(&self, cube: &CubieCube, max_length: u8) -> Option<Vec<Move>> {
+        cube.solve_within(max_length)
+    }
+
+    /// See [`CubieCube::solve_until`]
+    pub fn solve_until(&self, cube: &CubieCube, cancel: &CancelToken) -> Option<Vec<Move>> {
+        cube.solve_until(cancel)
+    }
+
+    /// See [`CubieCube::solutions`]
+    pub fn solutions(&self, cube: &CubieCube, cancel: CancelToken) -> Solutions {
+        cube.solutions(cancel)
+    }
+
+    /// See [`CubieCube::solution_length_bound`]
+    pub fn solution_length_bound(&self, cube: &CubieCube, time: Duration) -> Option<u8> {
+        cube.solution_length_bound(time)
+    }
+
+    /// See [`crate::scramble_to_state`]
+    pub fn scramble_to_state(&self, state: &CubieCube) -> Option<Vec<Move>> {
+        crate::scramble_to_state(state)
+    }
+
+    /// See [`crate::random_state_scramble`]
+    pub fn random_state_scramble(&self) -> Vec<Move> {
+        crate::random_state_scramble()
+    }
+
+    /// See [`crate::case_scramble`]
+    pub fn case_scramble(&self, alg: &[Move]) -> Vec<Move> {
+        crate::case_scramble(alg)
+    }
+
+    /// See [`crate::block_scramble`]
+    pub fn block_scramble(&self, block: crate::SolvedBlock) -> Option<Vec<Move>> {
+        crate::block_scramble(block)
+    }
+
+    /// See [`crate::scramble_batch`]
+    pub fn scramble_batch<P: Puzzle>(&self, n: usize) -> Vec<Vec<P::Move>> {
+        crate::scramble_batch::<P>(n)
+    }
+
+    /// See [`crate::seeded_scramble`]
+    pub fn seeded_scramble<P: Puzzle>(&self, seed: u64, index: u64) -> Vec<P::Move> {
+        crate::seeded_scramble::<P>(seed, index)
+    }
+
+    /// See [`crate::seeded_scramble_batch`]
+    pub fn seeded_scramble_batch<P: Puzzle>(
+        &self,
+        seed: u64,
+        indices: Range<u64>,
+    ) -> Vec<Vec<P::Move>> {
+        crate::seeded_scramble_batch::<P>(seed, indices)
+    }
+}
+
+/// Search for solutions whose phase 1 is exactly `depth` moves. Each first move is searched
+/// after as a separate task, and the solution with the earliest first move wins, so it's
+/// the same solution a single thread searching in order would find.
+fn phase1(
+    cube: &CubieCube,
+    coords: &CoordCube,
+    depth: u8,
+    max_length: u8,
+    cancel: &CancelToken,
+) -> Option<Vec<Move>> {
+    let first_moves = Move::all();
+    // index into `first_moves` of the earliest solution found so far
+    let best = AtomicUsize::new(usize::MAX);
+
+    // searches the solutions that start with `path`, which are after the `i`th first move
+    let search = |path: &mut Vec<Move>, start: CoordCube, i: usize| {
+        // no need to keep going once a solution with an earlier first move is found
+        let stopped = || best.load(Ordering::Relaxed) < i || cancel.is_cancelled();
+        search_depth(
+            start,
+            &first_moves,
+            depth - path.len() as u8,
+            path,
+            &mut |_, moves| {
+                // a phase 1 solution ending in a phase 2 move would have been found at a
+                // shallower depth already
+                moves.last().is_none_or(|m| !PHASE2_MOVES.contains(m))
+                    && phase2(cube, moves, max_length, &stopped)
+            },
+            &stopped,
+        )
+    };
+    if depth == 0 {
+        let mut moves = Vec::new();
+        return search(&mut moves, coords.clone(), 0).then_some(moves);
+    }
+
+    (0..first_moves.len()).into_par_iter().find_map_first(|i| {
+        if i > best.load(Ordering::Relaxed) {
+            return None;
+        }
+        let mut start = coords.clone();
+        start.apply_move(first_moves[i]);
+        let mut moves = vec![first_moves[i]];
+        let found = search(&mut moves, start, i);
+        if found {
+            best.fetch_min(i, Ordering::Relaxed);
+        }
+        found.then_some(moves)
+    })
+}
+
+/// Solve phase 2 from the state after the phase 1 solution `moves`, appending to it
+fn phase2(
+    cube: &CubieCube,
+    moves: &mut Vec<Move>,
+    max_length: u8,
+    stopped: &impl Fn() -> bool,
+) -> bool {
+    let mut cube = *cube;
+    cube.apply_moves(moves);
+    ida_star(
+        Phase2Cube::from_cubie_cube(&cube),
+        &PHASE2_MOVES,
+        MAX_PHASE2_DEPTH.min(max_length - moves.len() as u8),
+        moves,
+        &mut |_, _| true,
+        stopped,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn solved_needs_no_moves() {
+        assert_eq!(CubieCube::SOLVED.solve(), Some(Vec::new()));
+    }
+
+    #[test]
+    fn solutions_solve() {
+        for _ in 0..5 {
+            let cube = CubieCube::random_possible();
+            let solution = cube.solve().unwrap();
+            let mut solved = cube;
+            solved.apply_moves(&solution);
+            assert_eq!(
+                solved,
+                CubieCube::SOLVED,
+                "{solution:?} doesn't solve {cube:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn same_solution_every_time() {
+        let cube = CubieCube::random_possible();
+        assert_eq!(cube.solve(), cube.solve());
+    }
+
+    #[test]
+    fn length_budgets() {
+        let cube = CubieCube::random_possible();
+        let length = cube.solve().unwrap().len() as u8;
+        let solution = cube.solve_within(length - 1);
+        assert!(solution.is_none_or(|s| s.len() < length as usize));
+        assert_eq!(cube.solve_within(0), None);
+        assert_eq!(CubieCube::SOLVED.solve_within(0), Some(Vec::new()));
+    }
+
+    #[test]
+    fn length_bounds() {
+        let time = Duration::from_millis(200);
+        assert_eq!(CubieCube::SOLVED.solution_length_bound(time), Some(0));
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_moves(&[Move::R]);
+        assert_eq!(cube.solution_length_bound(time), Some(1));
+
+        // a random-state scramble is the inverse of the first solution, so the bound should
+        // be no longer than it, and with any time to search usually shorter
+        let scramble = crate::random_state_scramble();
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_moves(&scramble);
+        assert!(cube.solution_length_bound(time).unwrap() as usize <= scramble.len());
+        // even when there's no time to search for shorter ones
+        assert!(
+            cube.solution_length_bound(Duration::ZERO).unwrap()
+                <= MAX_PHASE1_DEPTH + MAX_PHASE2_DEPTH
+        );
+    }
+
+    #[test]
+    fn table_init_progress() {
+        let mut progress = Vec::new();
+        init_solver_tables(|fraction| progress.push(fraction));
+        assert_eq!(progress.len(), TABLES.len());
+        assert!(progress.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(progress.last(), Some(&1.0));
+        assert!(solver_tables_ready());
+    }
+
+    #[test]
+    fn shared_context() {
+        fn send_sync<T: Send + Sync>(_: &T) {}
+
+        let context = Arc::new(SolverContext::new(|_| {}));
+        send_sync(&context);
+        assert!(SolverContext::get().is_some());
+        let cubes: Vec<_> = (0..4).map(|_| CubieCube::random_possible()).collect();
+        thread::scope(|scope| {
+            for cube in &cubes {
+                let context = Arc::clone(&context);
+                scope.spawn(move || {
+                    let mut solved = *cube;
+                    solved.apply_moves(&context.solve(cube).unwrap());
+                    assert_eq!(solved, CubieCube::SOLVED);
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn shorter_solutions() {
+        let cube = CubieCube::random_possible();
+        let first = cube.solve().unwrap();
+        // enough time to find a few shorter ones, but not to run out of them
+        let cancel = CancelToken::with_deadline(Instant::now() + Duration::from_secs(2));
+        let best = cube.solve_until(&cancel).unwrap();
+        assert!(best.len() <= first.len());
+        let mut solved = cube;
+        solved.apply_moves(&best);
+        assert_eq!(solved, CubieCube::SOLVED);
+
+        // nothing's shorter than the one move that solves it
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_moves(&[Move::R]);
+        assert_eq!(cube.solve_until(&CancelToken::new()), Some(vec![Move::Ri]));
+    }
+
+    #[test]
+    fn solution_stream() {
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_moves(&[Move::R, Move::U, Move::F]);
+        let solutions: Vec<_> = cube.solutions(CancelToken::new()).collect();
+        assert_eq!(solutions.first(), cube.solve().as_ref());
+        assert!(solutions
+            .windows(2)
+            .all(|pair| pair[0].len() > pair[1].len()));
+        assert_eq!(solutions.last().map(Vec::len), Some(3));
+
+        assert_eq!(CubieCube::SOLVED.solutions(CancelToken::new()).count(), 1);
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        assert_eq!(cube.solutions(cancel).next(), None);
+    }
+
+    #[test]
+    fn cancelled_solves() {
+        let cancel = CancelToken::new();
+        let running = cancel.clone();
+        cancel.cancel();
+        assert!(running.is_cancelled());
+        assert_eq!(CubieCube::random_possible().solve_until(&running), None);
+        assert!(CancelToken::with_deadline(Instant::now()).is_cancelled());
+    }
+
+    #[test]
+    fn impossible_states() {
+        // two corners swapped
+        let mut cube = CubieCube::SOLVED;
+        cube.set_corner_perm_coord(1);
+        assert_eq!(cube.solve(), None);
+        assert_eq!(cube.solution_length_bound(Duration::ZERO), None);
+    }
+}
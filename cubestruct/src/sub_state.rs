@@ -0,0 +1,111 @@
+//! The corners or edges of a cube on their own, for methods that solve one before the other
+//! (e.g. corners first) and for filtering trainer cases by what they leave unsolved
+use crate::cubie::{Corners, Cubicle, Cubie, Cubies, Edges, Orientation};
+use crate::cubie_cube::{invert_cubies, multiply_cubies};
+use crate::CubieCube;
+use std::ops::{Mul, MulAssign};
+
+/// The corners of a [`CubieCube`] (see [`CubieCube::corners()`]), ignoring its edges.
+/// Multiplying them is the same as multiplying whole cubes and then taking the corners.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct CornerState(pub(crate) Corners);
+
+/// The edges of a [`CubieCube`] (see [`CubieCube::edges()`]), ignoring its corners.
+/// Multiplying them is the same as multiplying whole cubes and then taking the edges.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct EdgeState(pub(crate) Edges);
+
+macro_rules! sub_state {
+    ($state:ident, $cubies:ident) => {
+        impl $state {
+            pub const SOLVED: Self = Self(CubieCube::SOLVED.$cubies());
+
+            pub fn is_solved(&self) -> bool {
+                *self == Self::SOLVED
+            }
+
+            /// Number of pieces in their home position with the right orientation
+            pub fn solved_count(&self) -> usize {
+                solved_count(self.0)
+            }
+
+            /// Whether the orientations add up the way they do on a cube that can be
+            /// solved by turning. (Permutation parity is shared between corners and edges,
+            /// so it can only be checked on a whole [`CubieCube`].)
+            pub fn has_possible_orientation(&self) -> bool {
+                self.0
+                    .into_iter()
+                    .map(|cubie| cubie.orientation())
+                    .fold(Orientation::zero(), Orientation::add)
+                    == Orientation::zero()
+            }
+
+            #[must_use]
+            pub fn inverse(&self) -> Self {
+                Self(invert_cubies(self.0))
+            }
+        }
+
+        impl Mul for $state {
+            type Output = Self;
+
+            fn mul(self, rhs: Self) -> Self {
+                Self(multiply_cubies(self.0, rhs.0))
+            }
+        }
+
+        impl MulAssign for $state {
+            fn mul_assign(&mut self, rhs: Self) {
+                *self = *self * rhs;
+            }
+        }
+    };
+}
+
+sub_state!(CornerState, corner_cubies);
+sub_state!(EdgeState, edge_cubies);
+
+fn solved_count<C: Cubies>(cubies: C) -> usize {
+    cubies
+        .into_iter()
+        .zip(C::Cubicle::all())
+        .filter(|&(cubie, home)| cubie == C::Cubie::new(home, C::Orientation::zero()))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Move;
+
+    #[test]
+    fn parts_of_cubes() {
+        let a = CubieCube::random_possible();
+        let b = CubieCube::random_possible();
+        assert_eq!((a * b).corners(), a.corners() * b.corners());
+        assert_eq!((a * b).edges(), a.edges() * b.edges());
+        assert_eq!(a.inverse().corners(), a.corners().inverse());
+        assert_eq!(a.inverse().edges(), a.edges().inverse());
+        assert!((a.corners() * a.corners().inverse()).is_solved());
+        assert!(a.corners().has_possible_orientation());
+        assert!(a.edges().has_possible_orientation());
+    }
+
+    #[test]
+    fn solved_pieces() {
+        assert_eq!(CornerState::SOLVED.solved_count(), 8);
+        assert_eq!(EdgeState::SOLVED.solved_count(), 12);
+
+        // a commutator that only cycles three corners
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_moves(&crate::parse_moves("R U' L' U R' U' L U").unwrap());
+        assert!(cube.edges().is_solved());
+        assert!(!cube.corners().is_solved());
+        assert_eq!(cube.corners().solved_count(), 5);
+
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_move(Move::U);
+        assert_eq!(cube.corners().solved_count(), 4);
+        assert_eq!(cube.edges().solved_count(), 8);
+    }
+}
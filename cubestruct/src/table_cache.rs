@@ -0,0 +1,140 @@
+//! Saving the solver's pruning tables to a file, so that they only have to be built once
+//! rather than every time a program that solves starts.
+//!
+//! # File format
+//!
+//! All integers are little endian.
+//!
+//! | Offset | Size | Contents                                                   |
+//! |--------|------|------------------------------------------------------------|
+//! | 0      | 8    | The magic bytes `CSTRTBL\0`                                |
+//! | 8      | 4    | Format version ([`SOLVER_TABLES_VERSION`])                 |
+//! | 12     | 8    | 64-bit FNV-1a hash of everything after the header          |
+//! | 20     |      | Each pruning table in turn, one byte per entry             |
+//!
+//! The number and sizes of the tables are fixed by the version, so they aren't stored.
+use crate::coord_cube::{pruning_tables, TABLES};
+use std::io::{self, Write};
+
+/// Version of the file format written by this version of cubestruct. Files of other
+/// versions can't be read, and should be written again.
+pub const SOLVER_TABLES_VERSION: u32 = 1;
+
+const MAGIC: &[u8; 8] = b"CSTRTBL\0";
+const HEADER_LEN: usize = 20;
+
+#[derive(thiserror::Error, Debug)]
+pub enum SolverTablesError {
+    #[error("Not a solver tables file")]
+    NotATablesFile,
+    #[error(
+        "Solver tables file is version {0}, but only version {SOLVER_TABLES_VERSION} is supported"
+    )]
+    UnsupportedVersion(u32),
+    #[error("Solver tables file is the wrong size (truncated?)")]
+    WrongSize,
+    #[error("Solver tables file is corrupted")]
+    Corrupted,
+}
+
+/// Write the solver's pruning tables, building the solver's tables first if they aren't
+/// already
+pub fn write_solver_tables(mut writer: impl Write) -> io::Result<()> {
+    for build in TABLES {
+        build();
+    }
+    let tables = pruning_tables().map(|(table, _)| table.get().unwrap().as_slice());
+
+    writer.write_all(MAGIC)?;
+    writer.write_all(&SOLVER_TABLES_VERSION.to_le_bytes())?;
+    writer.write_all(&fnv1a(tables).to_le_bytes())?;
+    for table in tables {
+        writer.write_all(table)?;
+    }
+    Ok(())
+}
+
+/// Use the pruning tables from the contents of a file written by [`write_solver_tables()`]
+/// instead of building them. Nothing is used unless the whole file is valid.
+///
+/// [`crate::init_solver_tables()`] still has to be called afterwards, but it only builds the
+/// rest of the tables, which takes a fraction of the time.
+pub fn read_solver_tables(bytes: &[u8]) -> Result<(), SolverTablesError> {
+    if !bytes.starts_with(MAGIC) {
+        return Err(SolverTablesError::NotATablesFile);
+    }
+    let Some(header) = bytes.get(..HEADER_LEN) else {
+        return Err(SolverTablesError::WrongSize);
+    };
+    let version = u32::from_le_bytes(header[8..12].try_into().unwrap());
+    if version != SOLVER_TABLES_VERSION {
+        return Err(SolverTablesError::UnsupportedVersion(version));
+    }
+    let hash = u64::from_le_bytes(header[12..20].try_into().unwrap());
+
+    let locks = pruning_tables();
+    let mut rest = &bytes[HEADER_LEN..];
+    let mut tables = Vec::with_capacity(locks.len());
+    for (_, len) in locks {
+        if rest.len() < len {
+            return Err(SolverTablesError::WrongSize);
+        }
+        let (table, after) = rest.split_at(len);
+        tables.push(table);
+        rest = after;
+    }
+    if !rest.is_empty() {
+        return Err(SolverTablesError::WrongSize);
+    }
+    if fnv1a(tables.iter().copied()) != hash {
+        return Err(SolverTablesError::Corrupted);
+    }
+
+    for ((lock, _), table) in locks.into_iter().zip(tables) {
+        // if it's been built already, that's the same table anyway
+        let _ = lock.set(table.to_vec());
+    }
+    Ok(())
+}
+
+fn fnv1a<'a>(tables: impl IntoIterator<Item = &'a [u8]>) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+    for byte in tables.into_iter().flatten() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut file = Vec::new();
+        write_solver_tables(&mut file).unwrap();
+        assert!(read_solver_tables(&file).is_ok());
+
+        assert!(matches!(
+            read_solver_tables(b"hello"),
+            Err(SolverTablesError::NotATablesFile)
+        ));
+        assert!(matches!(
+            read_solver_tables(&file[..file.len() - 1]),
+            Err(SolverTablesError::WrongSize)
+        ));
+        let mut corrupted = file.clone();
+        corrupted[HEADER_LEN + 1000] ^= 1;
+        assert!(matches!(
+            read_solver_tables(&corrupted),
+            Err(SolverTablesError::Corrupted)
+        ));
+        let mut future = file;
+        future[8] = 2;
+        assert!(matches!(
+            read_solver_tables(&future),
+            Err(SolverTablesError::UnsupportedVersion(2))
+        ));
+    }
+}
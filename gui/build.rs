@@ -23,6 +23,19 @@ fn main() {
         );
     }
 
+    let schema_out = std::process::Command::new("glib-compile-schemas")
+        .arg("--strict")
+        .arg(format!("--targetdir={}", std::env::var("OUT_DIR").unwrap()))
+        .arg(input_dir)
+        .output()
+        .expect("failed to compile gsettings schemas; is `glib-compile-schemas` installed?");
+    if schema_out.status.code().unwrap() != 0 {
+        panic!(
+            "Schema compilation failed:\n{}",
+            std::str::from_utf8(&schema_out.stderr).unwrap(),
+        );
+    }
+
     glib_build_tools::compile_resources(
         &["resources"],
         "resources/resources.gresource.xml",
@@ -0,0 +1,13 @@
+#!/bin/sh
+# Regenerate the translation template from the files in POTFILES.in. Run from the gui
+# directory. Needs xgettext 0.24 or later, for Rust support.
+set -e
+pot=po/puzzletime.pot
+xgettext --from-code=UTF-8 --add-comments --language=Rust \
+	--keyword=gettext_f --keyword=ngettext_f:1,2 --keyword='gettext_noop!' \
+	--package-name=puzzletime --output=$pot \
+	$(grep '\.rs$' po/POTFILES.in)
+# Blueprint files are close enough to C for xgettext
+xgettext --from-code=UTF-8 --add-comments --language=C --keyword=_ --join-existing \
+	--output=$pot \
+	$(grep '\.blp$' po/POTFILES.in)
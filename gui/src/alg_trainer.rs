@@ -0,0 +1,378 @@
+//! Alg practice: each scramble sets up a random case from a set (like the PLLs), and the
+//! times for each case are tracked. The sets are made by the user and kept in the settings.
+use crate::prelude::*;
+use crate::settings::settings;
+use crate::timer::{render_average, render_time, Precision};
+use cubestruct::{CubieCube, Move};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// The PLL cases, with a common alg for each
+const PLL: [(&str, &str); 21] = [
+    ("Aa", "x R' U R' D2 R U' R' D2 R2 x'"),
+    ("Ab", "x R2 D2 R U R' D2 R U' R x'"),
+    ("E", "x' R U' R' D R U R' D' R U R' D R U' R' D' x"),
+    ("F", "R' U' F' R U R' U' R' F R2 U' R' U' R U R' U R"),
+    ("Ga", "R2 U R' U R' U' R U' R2 U' D R' U R D'"),
+    ("Gb", "R' U' R U D' R2 U R' U R U' R U' R2 D"),
+    ("Gc", "R2 U' R U' R U R' U R2 U D' R U' R' D"),
+    ("Gd", "R U R' U' D R2 U' R U' R' U R' U R2 D'"),
+    ("H", "M2 U M2 U2 M2 U M2"),
+    ("Ja", "x R2 F R F' R U2 r' U r U2 x'"),
+    ("Jb", "R U R' F' R U R' U' R' F R2 U' R'"),
+    (
+        "Na",
+        "R U R' U R U R' F' R U R' U' R' F R2 U' R' U2 R U' R'",
+    ),
+    ("Nb", "R' U R U' R' F' U' F R U R' F R' F' R U' R"),
+    ("Ra", "R U' R' U' R U R D R' U' R D' R' U2 R'"),
+    ("Rb", "R2 F R U R U' R' F' R U2 R' U2 R"),
+    ("T", "R U R' U' R' F R2 U' R' U' R U R' F'"),
+    ("Ua", "M2 U M U2 M' U M2"),
+    ("Ub", "M2 U' M U2 M' U' M2"),
+    ("V", "R' U R' U' y R' F' R2 U' R' U R' F R F"),
+    ("Y", "F R U' R' U' R U R' F' R U R' U' R' F R F'"),
+    ("Z", "M' U M2 U M2 U M' U2 M2"),
+];
+
+/// A case and the alg that solves it
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct Case {
+    name: String,
+    alg: Vec<Move>,
+}
+
+impl Case {
+    /// Parses a line of a set, like `T: R U R' U' …` or just the alg, in which case it's
+    /// named after its `number`
+    fn parse(line: &str, number: usize) -> Result<Self, String> {
+        let (name, alg) = match line.split_once(':') {
+            Some((name, alg)) => (name.trim().to_owned(), alg),
+            None => (
+                gettext_f("Case {number}", &[("number", &number.to_string())]),
+                line,
+            ),
+        };
+        let alg = cubestruct::parse_alg(alg).map_err(|e| e.to_string())?;
+        if alg.is_empty() {
+            return Err(gettext("No moves"));
+        }
+        Ok(Self { name, alg })
+    }
+
+    /// Whether `scramble` sets up this case, give or take a `U` turn before and after it
+    fn set_up_by(&self, scramble: &[Move]) -> bool {
+        let mut state = CubieCube::SOLVED;
+        state.apply_moves(scramble);
+        let aufs = [None, Some(Move::U), Some(Move::U2), Some(Move::Ui)];
+        aufs.iter().any(|pre| {
+            aufs.iter().any(|post| {
+                let mut cube = state;
+                cube.apply_moves(pre.as_slice());
+                cube.apply_moves(&self.alg);
+                cube.apply_moves(post.as_slice());
+                cube == CubieCube::SOLVED
+            })
+        })
+    }
+}
+
+/// Name given to the case on `line`, if it has one
+fn line_name(line: &str) -> Option<&str> {
+    line.split_once(':').map(|(name, _)| name.trim())
+}
+
+/// The lines of a set's text that aren't blank
+fn case_lines(text: &str) -> impl Iterator<Item = &str> {
+    text.lines().map(str::trim).filter(|line| !line.is_empty())
+}
+
+/// The sets in the settings, as their names and lines
+fn sets() -> Vec<(String, Vec<String>)> {
+    settings().value("trainer-sets").get().unwrap_or_default()
+}
+
+fn save_sets(sets: &[(String, Vec<String>)]) {
+    settings()
+        .set_value("trainer-sets", &sets.to_variant())
+        .unwrap();
+}
+
+#[derive(Debug)]
+pub struct AlgTrainer {
+    root: gtk::Box,
+    set_dropdown: gtk::DropDown,
+    /// Model of `set_dropdown`
+    set_names: gtk::StringList,
+    edit_button: gtk::Button,
+    case_label: gtk::Label,
+    last_label: gtk::Label,
+    mean_label: gtk::Label,
+    count_label: gtk::Label,
+    /// Cases of the chosen set
+    cases: Vec<Case>,
+    /// Times for each case, by name
+    results: HashMap<String, Vec<Duration>>,
+}
+
+impl AlgTrainer {
+    pub fn new(tx: EventSender) -> Self {
+        let builder =
+            gtk::Builder::from_resource("/io/github/flying_toast/PuzzleTime/alg-trainer.ui");
+        let set_dropdown: gtk::DropDown = builder.object("set_dropdown").unwrap();
+        let edit_button: gtk::Button = builder.object("edit_button").unwrap();
+        let new_button: gtk::Button = builder.object("new_button").unwrap();
+
+        let set_names = gtk::StringList::new(&[]);
+        set_dropdown.set_model(Some(&set_names));
+        set_dropdown.connect_selected_notify(|dropdown| {
+            if let Some(name) = dropdown.selected_item().and_downcast::<gtk::StringObject>() {
+                if settings().string("trainer-set") != name.string() {
+                    settings()
+                        .set_string("trainer-set", &name.string())
+                        .unwrap();
+                }
+            }
+        });
+        let set_dropdown2 = set_dropdown.clone();
+        edit_button.connect_clicked(move |button| {
+            if let Some(name) = set_dropdown2
+                .selected_item()
+                .and_downcast::<gtk::StringObject>()
+            {
+                edit_dialog(Some(&name.string())).present(button);
+            }
+        });
+        new_button.connect_clicked(|button| edit_dialog(None).present(button));
+        for key in ["trainer-sets", "trainer-set"] {
+            let tx = tx.clone();
+            settings().connect_changed(Some(key), move |_, _| {
+                send_evt(tx.clone(), Event::TrainerSetChanged);
+            });
+        }
+
+        let mut this = Self {
+            root: builder.object("root").unwrap(),
+            set_dropdown,
+            set_names,
+            edit_button,
+            case_label: builder.object("case_label").unwrap(),
+            last_label: builder.object("last_label").unwrap(),
+            mean_label: builder.object("mean_label").unwrap(),
+            count_label: builder.object("count_label").unwrap(),
+            cases: Vec::new(),
+            results: HashMap::new(),
+        };
+        this.load_set();
+        this
+    }
+
+    pub fn widget(&self) -> &gtk::Box {
+        &self.root
+    }
+
+    pub fn set_visible(&self, visible: bool) {
+        self.root.set_visible(visible);
+    }
+
+    /// Show the sets in the settings, and load the cases of the chosen one (or the first,
+    /// if none is chosen)
+    pub fn load_set(&mut self) {
+        let sets = sets();
+        let names = sets
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        self.set_names.splice(0, self.set_names.n_items(), &names);
+        let current = settings().string("trainer-set");
+        let chosen = sets
+            .iter()
+            .position(|(name, _)| name == current.as_str())
+            .or((!sets.is_empty()).then_some(0));
+        self.set_dropdown
+            .set_selected(chosen.map_or(gtk::INVALID_LIST_POSITION, |idx| idx as u32));
+        self.set_dropdown.set_visible(!sets.is_empty());
+        self.edit_button.set_visible(!sets.is_empty());
+        self.cases = chosen
+            .map(|idx| {
+                // lines are checked before they're saved
+                sets[idx]
+                    .1
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, line)| Case::parse(line, i + 1).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+    }
+
+    /// Algs of the chosen set's cases, for [`crate::timer::Timer::set_case_algs`]. Empty if
+    /// there's no set.
+    pub fn case_algs(&self) -> Vec<Vec<Move>> {
+        self.cases.iter().map(|case| case.alg.clone()).collect()
+    }
+
+    /// Call when the timer stops, with the scramble that was solved. Returns `false` if the
+    /// scramble isn't one of the set's cases (e.g. it was from before the set was changed),
+    /// in which case the attempt isn't recorded.
+    pub fn finish(&mut self, scramble: &[Move], time: Duration) -> bool {
+        let Some(case) = self.cases.iter().find(|case| case.set_up_by(scramble)) else {
+            return false;
+        };
+        let times = self.results.entry(case.name.clone()).or_default();
+        times.push(time);
+
+        self.case_label.set_label(&case.name);
+        self.last_label
+            .set_label(&render_time(&time, Precision::Hundredths));
+        let mean = times.iter().sum::<Duration>() / times.len() as u32;
+        self.mean_label.set_label(&render_average(&mean));
+        self.count_label.set_label(&times.len().to_string());
+        true
+    }
+}
+
+fn buffer_text(buffer: &gtk::TextBuffer) -> String {
+    buffer
+        .text(&buffer.start_iter(), &buffer.end_iter(), false)
+        .into()
+}
+
+/// Dialog for editing the set called `name`, or making a new one if `None`
+fn edit_dialog(name: Option<&str>) -> adw::Dialog {
+    let builder =
+        gtk::Builder::from_resource("/io/github/flying_toast/PuzzleTime/trainer-set-dialog.ui");
+    let root: adw::Dialog = builder.object("root").unwrap();
+    let name_row: adw::EntryRow = builder.object("name_row").unwrap();
+    let algs_view: gtk::TextView = builder.object("algs_view").unwrap();
+    let error_label: gtk::Label = builder.object("error_label").unwrap();
+    let pll_box: gtk::FlowBox = builder.object("pll_box").unwrap();
+    let delete_group: adw::PreferencesGroup = builder.object("delete_group").unwrap();
+    let cancel_button: gtk::Button = builder.object("cancel_button").unwrap();
+    let save_button: gtk::Button = builder.object("save_button").unwrap();
+    let delete_button: gtk::Button = builder.object("delete_button").unwrap();
+
+    let buffer = algs_view.buffer();
+    let old_name = name.and_then(|name| sets().into_iter().find(|(n, _)| n == name));
+    if let Some((name, lines)) = &old_name {
+        root.set_title(&gettext("Edit Trainer Set"));
+        name_row.set_text(name);
+        buffer.set_text(&lines.join("\n"));
+        delete_group.set_visible(true);
+    } else {
+        root.set_title(&gettext("New Trainer Set"));
+    }
+    let old_name = old_name.map(|(name, _)| name);
+
+    for (case, alg) in PLL {
+        let toggle = gtk::ToggleButton::with_label(case);
+        toggle.set_active(
+            case_lines(&buffer_text(&buffer)).any(|line| line_name(line) == Some(case)),
+        );
+        let buffer = buffer.clone();
+        toggle.connect_toggled(move |toggle| {
+            let text = buffer_text(&buffer);
+            let mut lines = case_lines(&text)
+                .filter(|line| line_name(line) != Some(case))
+                .collect::<Vec<_>>();
+            let added = format!("{case}: {alg}");
+            if toggle.is_active() {
+                lines.push(&added);
+            }
+            buffer.set_text(&lines.join("\n"));
+        });
+        pll_box.append(&toggle);
+    }
+
+    let root2 = root.clone();
+    cancel_button.connect_clicked(move |_| {
+        root2.close();
+    });
+
+    let root2 = root.clone();
+    let old_name2 = old_name.clone();
+    delete_button.connect_clicked(move |_| {
+        let mut sets = sets();
+        sets.retain(|(name, _)| Some(name) != old_name2.as_ref());
+        save_sets(&sets);
+        root2.close();
+    });
+
+    let root2 = root.clone();
+    save_button.connect_clicked(move |_| {
+        let name = name_row.text().trim().to_owned();
+        let text = buffer_text(&buffer);
+        let lines = case_lines(&text).map(str::to_owned).collect::<Vec<_>>();
+        let mut sets = sets();
+        let error = if name.is_empty() {
+            Some(gettext("The set needs a name"))
+        } else if sets
+            .iter()
+            .any(|(n, _)| *n == name && Some(n) != old_name.as_ref())
+        {
+            Some(gettext_f(
+                "There's already a set called “{name}”",
+                &[("name", &name)],
+            ))
+        } else if lines.is_empty() {
+            Some(gettext("The set needs at least one case"))
+        } else {
+            lines.iter().enumerate().find_map(|(i, line)| {
+                let e = Case::parse(line, i + 1).err()?;
+                Some(gettext_f(
+                    "Line {number}: {error}",
+                    &[("number", &(i + 1).to_string()), ("error", &e)],
+                ))
+            })
+        };
+        if let Some(error) = error {
+            error_label.set_label(&error);
+            error_label.set_visible(true);
+            return;
+        }
+
+        match sets
+            .iter_mut()
+            .find(|(n, _)| Some(&*n) == old_name.as_ref())
+        {
+            Some(set) => *set = (name.clone(), lines),
+            None => sets.push((name.clone(), lines)),
+        }
+        save_sets(&sets);
+        settings().set_string("trainer-set", &name).unwrap();
+        root2.close();
+    });
+
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pll_cases() {
+        for (number, (name, alg)) in PLL.into_iter().enumerate() {
+            let case = Case::parse(&format!("{name}: {alg}"), number + 1).unwrap();
+            assert_eq!(case.name, name);
+            let setup = cubestruct::case_setup(&case.alg);
+            assert!(case.set_up_by(&setup));
+            // each case is only set up by its own scrambles
+            for (other, alg) in PLL {
+                let other_case = Case::parse(alg, 1).unwrap();
+                assert_eq!(other_case.set_up_by(&setup), other == name);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_lines() {
+        let case = Case::parse("R U R' U'", 3).unwrap();
+        assert_eq!(case.name, "Case 3");
+        assert_eq!(case.alg.len(), 4);
+        assert!(Case::parse("Sexy:", 1).is_err());
+        assert!(Case::parse("T: R Q", 1).is_err());
+        assert_eq!(line_name(" T : R U"), Some("T"));
+        assert_eq!(case_lines("a\n\n  b  \n").collect::<Vec<_>>(), ["a", "b"]);
+    }
+}
@@ -0,0 +1,176 @@
+//! Animations of smart cube solves, for sharing: the cube's net going from the scrambled
+//! state to solved as it was turned, with the time counting up underneath. Rendered as GIFs,
+//! which can be posted almost anywhere.
+use crate::puzzle_diagram::PuzzleDiagram;
+use crate::timer::{render_time, Precision};
+use cubestruct::{CubieCube, Move};
+use gtk::cairo;
+use std::time::Duration;
+
+const WIDTH: u16 = 480;
+const HEIGHT: u16 = 400;
+const MARGIN: f64 = 24.0;
+/// Height of the time under the net
+const LABEL_HEIGHT: f64 = 56.0;
+const FONT: &str = "Cantarell";
+
+/// How long the scrambled state is shown before the first turn
+const INTRO: Duration = Duration::from_secs(1);
+/// How long the solved state is shown before the animation loops
+const OUTRO: Duration = Duration::from_secs(2);
+/// Turns closer together than this are shown in the same frame, since most viewers slow down
+/// GIF frames shorter than 2 centiseconds
+const MIN_FRAME: Duration = Duration::from_millis(20);
+
+/// A smart cube solve, as needed to animate it
+#[derive(Debug, Clone)]
+pub struct SolveAnimation {
+    /// State of the cube when the solve started
+    pub start: CubieCube,
+    /// Each turn, with when it was done as a time since the start
+    pub turns: Vec<(Move, Duration)>,
+    /// The solve's time, shown at the end
+    pub time: Duration,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct Frame {
+    state: CubieCube,
+    /// Time since the start of the solve, shown under the net
+    at: Duration,
+    shown_for: Duration,
+}
+
+impl SolveAnimation {
+    pub fn render_gif(&self) -> anyhow::Result<Vec<u8>> {
+        let mut gif = Vec::new();
+        let mut encoder = gif::Encoder::new(&mut gif, WIDTH, HEIGHT, &[])?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+        for frame in self.frames() {
+            let mut rgba = draw_frame(&frame)?;
+            let mut gif_frame = gif::Frame::from_rgba_speed(WIDTH, HEIGHT, &mut rgba, 10);
+            gif_frame.delay = (frame.shown_for.as_millis() / 10)
+                .try_into()
+                .unwrap_or(u16::MAX);
+            encoder.write_frame(&gif_frame)?;
+        }
+        drop(encoder);
+        Ok(gif)
+    }
+
+    fn frames(&self) -> Vec<Frame> {
+        let mut frames = vec![Frame {
+            state: self.start,
+            at: Duration::ZERO,
+            shown_for: INTRO,
+        }];
+        let mut state = self.start;
+        for &(moov, at) in &self.turns {
+            state.apply_move(moov);
+            let last = frames.last_mut().unwrap();
+            // the intro is never merged into, so the scrambled state is always shown
+            if frames.len() > 1 && at.saturating_sub(last.at) < MIN_FRAME {
+                last.state = state;
+            } else {
+                if frames.len() > 1 {
+                    last.shown_for = at.saturating_sub(last.at);
+                }
+                frames.push(Frame {
+                    state,
+                    at,
+                    shown_for: OUTRO,
+                });
+            }
+        }
+        // the last turn usually comes a little before the timer is stopped
+        if let [_, .., last] = frames.as_mut_slice() {
+            last.at = self.time.max(last.at);
+        }
+        frames
+    }
+}
+
+/// Returns the frame's pixels as RGBA
+fn draw_frame(frame: &Frame) -> anyhow::Result<Vec<u8>> {
+    let (width, height) = (f64::from(WIDTH), f64::from(HEIGHT));
+    let mut surface =
+        cairo::ImageSurface::create(cairo::Format::Rgb24, WIDTH.into(), HEIGHT.into())?;
+    {
+        let cr = cairo::Context::new(&surface)?;
+        cr.set_source_rgb(0.14, 0.14, 0.16);
+        cr.paint()?;
+
+        cr.save()?;
+        cr.translate(MARGIN, MARGIN);
+        frame.state.draw(
+            &cr,
+            width - 2.0 * MARGIN,
+            height - 2.0 * MARGIN - LABEL_HEIGHT,
+        );
+        cr.restore()?;
+
+        let time = render_time(&frame.at, Precision::Hundredths);
+        cr.set_source_rgb(1.0, 1.0, 1.0);
+        cr.select_font_face(FONT, cairo::FontSlant::Normal, cairo::FontWeight::Bold);
+        cr.set_font_size(36.0);
+        let extents = cr.text_extents(&time)?;
+        cr.move_to(
+            (width - extents.width()) / 2.0 - extents.x_bearing(),
+            height - MARGIN,
+        );
+        cr.show_text(&time)?;
+    }
+
+    surface.flush();
+    let stride = surface.stride() as usize;
+    let data = surface.data()?;
+    let mut rgba = Vec::with_capacity(usize::from(WIDTH) * usize::from(HEIGHT) * 4);
+    for row in data.chunks(stride) {
+        // each pixel is a native-endian u32 of 0xXXRRGGBB
+        for pixel in row[..usize::from(WIDTH) * 4].chunks_exact(4) {
+            let [_, r, g, b] = u32::from_ne_bytes(pixel.try_into().unwrap()).to_be_bytes();
+            rgba.extend([r, g, b, 0xff]);
+        }
+    }
+    Ok(rgba)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_timing() {
+        let millis = Duration::from_millis;
+        let animation = SolveAnimation {
+            start: CubieCube::SOLVED,
+            turns: vec![
+                (Move::R, millis(400)),
+                // too soon after the R to get a frame of its own
+                (Move::U, millis(410)),
+                (Move::Ui, millis(900)),
+                (Move::Ri, millis(1_300)),
+            ],
+            time: millis(1_450),
+        };
+        let frames = animation.frames();
+        let timing = frames
+            .iter()
+            .map(|frame| (frame.at, frame.shown_for))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            timing,
+            [
+                (Duration::ZERO, INTRO),
+                (millis(400), millis(500)),
+                (millis(900), millis(400)),
+                (millis(1_450), OUTRO),
+            ]
+        );
+
+        let mut state = CubieCube::SOLVED;
+        state.apply_moves(&[Move::R, Move::U]);
+        assert_eq!(frames[1].state, state);
+        assert_eq!(frames[3].state, CubieCube::SOLVED);
+    }
+}
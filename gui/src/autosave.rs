@@ -0,0 +1,454 @@
+//! Keeps the session saved as it goes, so that it can be recovered if the app or machine
+//! crashes. The file is removed when the app quits normally, so it only exists on startup
+//! if the last run didn't end cleanly.
+use crate::prelude::*;
+use crate::settings::APP_ID;
+use crate::stats::{Hand, Penalties, Penalty};
+use crate::timer::{render_moveseq, ScrambleSeed};
+use cubestruct::Move;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// A solve, as saved
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SavedSolve {
+    /// Without any penalty
+    pub time: Duration,
+    /// When the solve was finished, in seconds since the Unix epoch
+    pub date: i64,
+    pub penalties: Penalties,
+    pub memo_time: Option<Duration>,
+    /// Manual splits, as times since the start
+    pub splits: Vec<Duration>,
+    /// When each CFOP stage was reached in a smart cube solve, as times since the start
+    pub stages: Vec<Duration>,
+    /// Each turn of a smart cube solve, with when it was done as a time since the start
+    pub turns: Vec<(Move, Duration)>,
+    pub hand: Hand,
+    pub scramble: Vec<Move>,
+    pub comment: String,
+    pub attachment: Option<String>,
+    /// Name of the cube it was done on, from the user's list of cubes
+    pub cube: Option<String>,
+    /// Length of the solver's solution to the scramble
+    pub scramble_difficulty: Option<u8>,
+}
+
+/// Everything needed to pick the session back up
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct SessionState {
+    pub solves: Vec<SavedSolve>,
+    /// The scramble that was displayed
+    pub scramble: Vec<Move>,
+    /// When the running solve started, in microseconds since the Unix epoch (as returned
+    /// by [`glib::real_time()`]). `None` if no solve was running.
+    pub solve_started: Option<i64>,
+    /// Where the session's scrambles come from, if they're seeded
+    pub scramble_seed: Option<ScrambleSeed>,
+}
+
+impl SessionState {
+    pub fn serialize(&self) -> String {
+        let mut out = String::new();
+        if !self.scramble.is_empty() {
+            out.push_str(&format!(
+                "scramble\t{}\n",
+                render_moveseq(&self.scramble, false)
+            ));
+        }
+        if let Some(started) = self.solve_started {
+            out.push_str(&format!("started\t{started}\n"));
+        }
+        if let Some(seed) = &self.scramble_seed {
+            // the text is last so that it can contain tabs
+            out.push_str(&format!(
+                "seed\t{}\t{}\n",
+                seed.position,
+                seed.text.replace('\n', " ")
+            ));
+        }
+        for solve in &self.solves {
+            out.push_str(&format!(
+                "solve\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                solve.time.as_micros(),
+                solve.date,
+                penalty_name(solve.penalties.kind()),
+                solve
+                    .memo_time
+                    .map_or(String::new(), |memo| memo.as_micros().to_string()),
+                hand_name(solve.hand),
+                if solve.scramble.is_empty() {
+                    String::new()
+                } else {
+                    render_moveseq(&solve.scramble, false)
+                },
+                solve.comment.replace(['\t', '\n'], " "),
+                // newlines would break the format, and can't be in a sensible link anyway
+                solve.attachment.as_deref().unwrap_or("").replace('\n', ""),
+            ));
+            // details added since the format was made are on lines of their own after the
+            // solve they're about, so that older versions skip them
+            if let Some(cube) = &solve.cube {
+                out.push_str(&format!("cube\t{}\n", cube.replace('\n', " ")));
+            }
+            if let Some(moves) = solve.scramble_difficulty {
+                out.push_str(&format!("difficulty\t{moves}\n"));
+            }
+            // the solve line has the kind of penalty, which older versions read as a single
+            // +2, so the time penalties are only listed when they're something else
+            if solve.penalties.added != Penalties::of(solve.penalties.kind()).added {
+                let added = solve
+                    .penalties
+                    .added
+                    .iter()
+                    .map(u32::to_string)
+                    .collect::<Vec<_>>();
+                out.push_str(&format!("penalties\t{}\n", added.join(" ")));
+            }
+            if !solve.splits.is_empty() {
+                let splits = solve
+                    .splits
+                    .iter()
+                    .map(|split| split.as_micros().to_string())
+                    .collect::<Vec<_>>();
+                out.push_str(&format!("splits\t{}\n", splits.join(" ")));
+            }
+            if !solve.stages.is_empty() {
+                let stages = solve
+                    .stages
+                    .iter()
+                    .map(|stage| stage.as_micros().to_string())
+                    .collect::<Vec<_>>();
+                out.push_str(&format!("stages\t{}\n", stages.join(" ")));
+            }
+            if !solve.turns.is_empty() {
+                out.push_str(&format!("turns\t{}\n", render_turns(&solve.turns)));
+            }
+        }
+        out
+    }
+
+    /// Lines that can't be parsed are skipped, so that one bad solve doesn't lose the
+    /// whole session
+    pub fn deserialize(s: &str) -> Self {
+        let mut state = Self::default();
+        // whether the last solve line was parsed, so that the lines after it can be
+        // applied to it
+        let mut solve_parsed = false;
+        for line in s.lines() {
+            let Some((kind, rest)) = line.split_once('\t') else {
+                continue;
+            };
+            match kind {
+                "scramble" => {
+                    if let Ok(scramble) = cubestruct::parse_moves(rest) {
+                        state.scramble = scramble;
+                    }
+                }
+                "started" => state.solve_started = rest.parse().ok(),
+                "seed" => {
+                    state.scramble_seed = rest.split_once('\t').and_then(|(position, text)| {
+                        Some(ScrambleSeed {
+                            text: text.to_string(),
+                            position: position.parse().ok()?,
+                        })
+                    });
+                }
+                "solve" => {
+                    let solve = parse_solve(rest);
+                    solve_parsed = solve.is_some();
+                    state.solves.extend(solve);
+                }
+                "cube" => {
+                    if let Some(solve) = state.solves.last_mut().filter(|_| solve_parsed) {
+                        solve.cube = Some(rest.to_string());
+                    }
+                }
+                "difficulty" => {
+                    if let Some(solve) = state.solves.last_mut().filter(|_| solve_parsed) {
+                        solve.scramble_difficulty = rest.parse().ok();
+                    }
+                }
+                "penalties" => {
+                    if let Some(solve) = state.solves.last_mut().filter(|_| solve_parsed) {
+                        solve.penalties.added = rest
+                            .split(' ')
+                            .map_while(|secs| secs.parse().ok())
+                            .collect();
+                    }
+                }
+                "splits" => {
+                    if let Some(solve) = state.solves.last_mut().filter(|_| solve_parsed) {
+                        solve.splits = rest
+                            .split(' ')
+                            .map_while(|split| split.parse().ok().map(Duration::from_micros))
+                            .collect();
+                    }
+                }
+                "stages" => {
+                    if let Some(solve) = state.solves.last_mut().filter(|_| solve_parsed) {
+                        solve.stages = rest
+                            .split(' ')
+                            .map_while(|stage| stage.parse().ok().map(Duration::from_micros))
+                            .collect();
+                    }
+                }
+                "turns" => {
+                    if let Some(solve) = state.solves.last_mut().filter(|_| solve_parsed) {
+                        solve.turns = parse_turns(rest);
+                    }
+                }
+                _ => {}
+            }
+        }
+        state
+    }
+}
+
+fn parse_solve(s: &str) -> Option<SavedSolve> {
+    let micros = |s: &str| s.parse().ok().map(Duration::from_micros);
+    // the attachment is last so that it can contain tabs
+    let fields = s.splitn(8, '\t').collect::<Vec<_>>();
+    let &[time, date, penalty, memo_time, hand, scramble, comment, attachment] = fields.as_slice()
+    else {
+        return None;
+    };
+
+    Some(SavedSolve {
+        time: micros(time)?,
+        date: date.parse().ok()?,
+        penalties: [Penalty::None, Penalty::Dnf, Penalty::Plus2]
+            .into_iter()
+            .find(|&p| penalty_name(p) == penalty)
+            .map(Penalties::of)?,
+        memo_time: if memo_time.is_empty() {
+            None
+        } else {
+            Some(micros(memo_time)?)
+        },
+        splits: Vec::new(),
+        stages: Vec::new(),
+        turns: Vec::new(),
+        hand: Hand::ALL.into_iter().find(|&h| hand_name(h) == hand)?,
+        scramble: cubestruct::parse_moves(scramble).ok()?,
+        comment: comment.to_string(),
+        attachment: (!attachment.is_empty()).then(|| attachment.to_string()),
+        cube: None,
+        scramble_difficulty: None,
+    })
+}
+
+/// Turns with when they were done, like `R'@350 U2@700`
+pub fn render_turns(turns: &[(Move, Duration)]) -> String {
+    turns
+        .iter()
+        .map(|(moov, at)| format!("{moov}@{}", at.as_millis()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Turns as rendered by [`render_turns`], up to the first that can't be parsed
+pub fn parse_turns(s: &str) -> Vec<(Move, Duration)> {
+    s.split(' ')
+        .map_while(|turn| {
+            let (moov, at) = turn.split_once('@')?;
+            Some((moov.parse().ok()?, Duration::from_millis(at.parse().ok()?)))
+        })
+        .collect()
+}
+
+pub fn penalty_name(penalty: Penalty) -> &'static str {
+    match penalty {
+        Penalty::None => "none",
+        Penalty::Dnf => "dnf",
+        Penalty::Plus2 => "plus2",
+    }
+}
+
+fn hand_name(hand: Hand) -> &'static str {
+    match hand {
+        Hand::Both => "both",
+        Hand::Left => "left",
+        Hand::Right => "right",
+    }
+}
+
+fn path() -> PathBuf {
+    glib::user_data_dir().join(APP_ID).join("autosave")
+}
+
+/// Saved session from a run that didn't quit cleanly, if there is one
+pub fn load() -> Option<SessionState> {
+    let contents = std::fs::read_to_string(path()).ok()?;
+    Some(SessionState::deserialize(&contents))
+}
+
+/// Remove the saved session, e.g. when quitting normally
+pub fn clear() {
+    if let Err(e) = std::fs::remove_file(path()) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            tracing::warn!("Failed to remove autosave file: {e}");
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Autosave {
+    /// Contents of the last successful write, to avoid rewriting the file when nothing
+    /// changed (a failed write is tried again next time)
+    last_written: Option<String>,
+}
+
+impl Autosave {
+    pub fn update(&mut self, state: &SessionState) {
+        let contents = state.serialize();
+        if self.last_written.as_ref() == Some(&contents) {
+            return;
+        }
+
+        match write_file(&path(), &contents) {
+            Ok(()) => self.last_written = Some(contents),
+            Err(e) => tracing::warn!("Failed to write autosave file: {e}"),
+        }
+    }
+}
+
+/// Write `contents` to `path` atomically, so a crash while saving doesn't corrupt the last
+/// save, creating its directory if needed
+pub fn write_file(path: &Path, contents: &str) -> Result<(), String> {
+    std::fs::create_dir_all(path.parent().unwrap())
+        .map_err(|e| e.to_string())
+        .and_then(|()| {
+            glib::file_set_contents(path, contents.as_bytes()).map_err(|e| e.to_string())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let state = SessionState {
+            solves: vec![
+                SavedSolve {
+                    time: Duration::from_micros(12_345_678),
+                    date: 1_700_000_000,
+                    penalties: Penalties::of(Penalty::Plus2),
+                    memo_time: None,
+                    splits: vec![Duration::from_millis(2_310), Duration::from_millis(9_800)],
+                    stages: vec![
+                        Duration::from_millis(1_900),
+                        Duration::from_millis(7_450),
+                        Duration::from_millis(10_020),
+                    ],
+                    turns: vec![
+                        (Move::Ri, Duration::from_millis(350)),
+                        (Move::U2, Duration::from_millis(700)),
+                    ],
+                    hand: Hand::Both,
+                    scramble: vec![Move::R, Move::Ui, Move::F2],
+                    comment: String::new(),
+                    attachment: None,
+                    cube: Some("GAN 12".into()),
+                    scramble_difficulty: Some(19),
+                },
+                SavedSolve {
+                    time: Duration::from_secs(95),
+                    date: 1_700_000_100,
+                    penalties: Penalties {
+                        dnf: false,
+                        added: vec![2, 2],
+                    },
+                    memo_time: Some(Duration::from_secs(40)),
+                    splits: Vec::new(),
+                    stages: Vec::new(),
+                    turns: Vec::new(),
+                    hand: Hand::Left,
+                    scramble: vec![Move::D],
+                    comment: "#pb full step".into(),
+                    attachment: Some("/home/me/pb\tclip.mp4".into()),
+                    cube: None,
+                    scramble_difficulty: None,
+                },
+            ],
+            scramble: vec![Move::L, Move::B2],
+            solve_started: Some(1_700_000_000_000_000),
+            scramble_seed: Some(ScrambleSeed {
+                text: "friday\trace".into(),
+                position: 12,
+            }),
+        };
+        assert_eq!(SessionState::deserialize(&state.serialize()), state);
+        assert_eq!(
+            SessionState::deserialize(&SessionState::default().serialize()),
+            SessionState::default()
+        );
+    }
+
+    #[test]
+    fn stacked_penalties() {
+        let solve = |penalties| SavedSolve {
+            time: Duration::from_secs(10),
+            date: 0,
+            penalties,
+            memo_time: None,
+            splits: Vec::new(),
+            stages: Vec::new(),
+            turns: Vec::new(),
+            hand: Hand::Both,
+            scramble: vec![Move::R],
+            comment: String::new(),
+            attachment: None,
+            cube: None,
+            scramble_difficulty: None,
+        };
+        let state = SessionState {
+            solves: vec![
+                solve(Penalties {
+                    dnf: false,
+                    added: vec![2, 2],
+                }),
+                solve(Penalties {
+                    dnf: true,
+                    added: vec![5],
+                }),
+            ],
+            ..Default::default()
+        };
+        let serialized = state.serialize();
+        assert_eq!(SessionState::deserialize(&serialized), state);
+        // older versions see the kind of penalty
+        assert!(serialized.contains("\tplus2\t"));
+        assert!(serialized.contains("penalties\t2 2\n"));
+        assert!(serialized.contains("\tdnf\t"));
+        assert!(serialized.contains("penalties\t5\n"));
+    }
+
+    #[test]
+    fn skips_bad_lines() {
+        let state = SessionState::deserialize(
+            "scramble\tR U\nsolve\tnonsense\ncube\tTornado\nsolve\t1000000\t0\tdnf\t\tright\tF\t\t\n",
+        );
+        assert_eq!(state.scramble, vec![Move::R, Move::U]);
+        assert_eq!(state.solve_started, None);
+        assert_eq!(
+            state.solves,
+            vec![SavedSolve {
+                time: Duration::from_secs(1),
+                date: 0,
+                penalties: Penalties::of(Penalty::Dnf),
+                memo_time: None,
+                splits: Vec::new(),
+                stages: Vec::new(),
+                turns: Vec::new(),
+                hand: Hand::Right,
+                scramble: vec![Move::F],
+                comment: String::new(),
+                attachment: None,
+                cube: None,
+                scramble_difficulty: None,
+            }]
+        );
+    }
+}
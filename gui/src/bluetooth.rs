@@ -1,11 +1,15 @@
+use crate::drivers;
+use crate::i18n::gettext_noop;
 use crate::prelude::*;
+use crate::runtime::tokio;
+use crate::settings::settings;
 use futures::stream::StreamExt;
-use smartcube::{BluetoothManager, DeviceId, SmartcubeEvent};
-use std::cell::RefCell;
+use smartcube::{BluetoothError, BluetoothManager, DeviceId, Driver, SmartcubeEvent};
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::sync::OnceLock;
-use tokio::runtime::Runtime;
+use std::time::Duration;
+use tracing::Instrument;
 
 #[derive(Debug)]
 pub struct Bluetooth {
@@ -13,9 +17,14 @@ pub struct Bluetooth {
     tx: EventSender,
     device_listbox: gtk::ListBox,
     manager: Option<BluetoothManager>,
+    /// Loaded on the first [`Self::maybe_init`], since plugins can only be loaded once
+    drivers: Option<&'static [&'static dyn Driver]>,
     known_devices: HashMap<DeviceId, DeviceInfo>,
     did_init: bool,
     toaster: adw::ToastOverlay,
+    stack: gtk::Stack,
+    devices_page: gtk::Box,
+    error_page: adw::StatusPage,
 }
 
 #[derive(Debug)]
@@ -23,11 +32,8 @@ struct DeviceInfo {
     spinner: gtk::Spinner,
     switch: gtk::Switch,
     device: smartcube::Device,
-}
-
-fn tokio() -> &'static Runtime {
-    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
-    RUNTIME.get_or_init(|| Runtime::new().unwrap())
+    /// Whether it's being disconnected for being idle
+    idle: Cell<bool>,
 }
 
 impl Bluetooth {
@@ -37,8 +43,11 @@ impl Bluetooth {
         let dialog: adw::Dialog = builder.object("root").unwrap();
         let tx2 = tx.clone();
         dialog.connect_closed(move |_| {
-            send_evt(tx2.clone(), Event::StopBluetoothScan);
+            send_evt(tx2.clone(), BluetoothEvent::StopScan);
         });
+        let retry_button: gtk::Button = builder.object("retry_button").unwrap();
+        let tx2 = tx.clone();
+        retry_button.connect_clicked(move |_| send_evt(tx2.clone(), BluetoothEvent::Retry));
         Self {
             tx,
             toaster,
@@ -47,6 +56,10 @@ impl Bluetooth {
             known_devices: HashMap::new(),
             did_init: false,
             manager: None,
+            drivers: None,
+            stack: builder.object("stack").unwrap(),
+            devices_page: builder.object("devices_page").unwrap(),
+            error_page: builder.object("error_page").unwrap(),
         }
     }
 
@@ -54,45 +67,88 @@ impl Bluetooth {
         &self.dialog
     }
 
+    /// `None` until Bluetooth is initialized
+    pub fn manager(&self) -> Option<&BluetoothManager> {
+        self.manager.as_ref()
+    }
+
     pub fn maybe_init(&mut self) {
         if self.did_init {
             return;
         }
         self.did_init = true;
+        let drivers = *self.drivers.get_or_insert_with(|| {
+            let (drivers, errors) = drivers::load();
+            for e in errors {
+                tracing::warn!("Failed to load driver plugin: {e}");
+                self.toaster.add_toast(adw::Toast::new(&gettext_f(
+                    "Couldn't load smart cube driver: {error}",
+                    &[("error", &e.to_string())],
+                )));
+            }
+            drivers
+        });
         let mut tx = self.tx.clone();
         tokio().spawn(async move {
-            let manager = smartcube::init_bluetooth(&[&qiyi_smartcube::Driver]).await;
-            tx.send(Event::BluetoothInitialized(manager)).await.unwrap();
+            let manager = smartcube::init_bluetooth(drivers).await;
+            tx.send(BluetoothEvent::Initialized(manager).into())
+                .await
+                .unwrap();
         });
     }
 
-    pub fn manager_ready(&mut self, manager: BluetoothManager) {
+    pub fn manager_ready(&mut self, manager: Result<BluetoothManager, BluetoothError>) {
+        let manager = match manager {
+            Ok(manager) => manager,
+            Err(e) => {
+                // try again from the start next time
+                self.did_init = false;
+                self.show_error(&e);
+                return;
+            }
+        };
         let mut tx = self.tx.clone();
         let manager2 = manager.clone();
         tokio().spawn(async move {
-            let mut events = std::pin::pin!(manager2.events().await);
-            manager2.start_scan().await;
+            let events = match manager2.events().await {
+                Ok(events) => events,
+                Err(e) => {
+                    tx.send(BluetoothEvent::Failed(e).into()).await.unwrap();
+                    return;
+                }
+            };
+            let mut events = std::pin::pin!(events);
+            if let Err(e) = manager2.start_scan().await {
+                tx.send(BluetoothEvent::Failed(e).into()).await.unwrap();
+            }
             while let Some(evt) = events.next().await {
                 match evt {
-                    smartcube::ConnectionEvent::Discovery(dev) => {
-                        tx.send(Event::BluetoothDeviceDiscoverd(dev)).await.unwrap()
-                    }
-                    smartcube::ConnectionEvent::Connect(id) => {
-                        tx.send(Event::BluetoothDeviceConnected(id)).await.unwrap()
-                    }
+                    smartcube::ConnectionEvent::Discovery(dev) => tx
+                        .send(BluetoothEvent::DeviceDiscovered(dev).into())
+                        .await
+                        .unwrap(),
+                    smartcube::ConnectionEvent::Connect(id) => tx
+                        .send(BluetoothEvent::DeviceConnected(id).into())
+                        .await
+                        .unwrap(),
                     smartcube::ConnectionEvent::Disconnect(id) => tx
-                        .send(Event::BluetoothDeviceDisconnected(id))
+                        .send(BluetoothEvent::DeviceDisconnected(id).into())
                         .await
                         .unwrap(),
                 }
             }
-            panic!("Manager event stream ended");
+            tracing::error!("Bluetooth manager's event stream ended");
         });
         assert!(self.manager.is_none());
         self.manager = Some(manager);
     }
 
     pub fn add_discovered_device(&mut self, dev: smartcube::Device) {
+        tracing::info!(
+            device = dev.local_name(),
+            driver = dev.driver_name(),
+            "Discovered",
+        );
         let row = adw::ActionRow::builder()
             .activatable(true)
             .title(dev.local_name())
@@ -108,6 +164,7 @@ impl Bluetooth {
                 spinner: spinner.clone(),
                 device: dev.clone(),
                 switch: switch.clone(),
+                idle: Cell::new(false),
             },
         );
         let app_tx = self.tx.clone();
@@ -122,13 +179,33 @@ impl Bluetooth {
                     task_handle.borrow().is_none(),
                     "Tried to connect to device but it already has a running task"
                 );
-                *task_handle.borrow_mut() = Some(tokio().spawn(async move {
-                    let mut events = dev.connect().await;
+                let idle_minutes = settings().uint("idle-disconnect");
+                // so that what the driver logs says which cube it's about
+                let span = tracing::info_span!(
+                    "smartcube",
+                    device = dev.local_name(),
+                    driver = dev.driver_name(),
+                );
+                let task = async move {
+                    let events = dev.connect().await;
+                    let mut events = match idle_minutes {
+                        0 => events.boxed(),
+                        minutes => smartcube::watchdog(
+                            events,
+                            Duration::from_secs(60 * u64::from(minutes)),
+                        )
+                        .boxed(),
+                    };
                     while let Some(evt) = events.next().await {
-                        app_tx.send(Event::Smartcube(evt)).await.unwrap();
+                        let evt = match evt {
+                            SmartcubeEvent::Idle(_) => BluetoothEvent::DeviceIdle(dev.id()),
+                            evt => BluetoothEvent::Smartcube(evt),
+                        };
+                        app_tx.send(evt.into()).await.unwrap();
                     }
-                    panic!("Device event stream ended");
-                }));
+                    tracing::info!("Device's event stream ended");
+                };
+                *task_handle.borrow_mut() = Some(tokio().spawn(task.instrument(span)));
             } else {
                 if let Some(handle) = task_handle.borrow_mut().take() {
                     handle.abort();
@@ -147,38 +224,112 @@ impl Bluetooth {
 
     pub fn device_connected(&self, id: DeviceId) {
         let info = self.known_devices.get(&id).unwrap();
+        tracing::info!(
+            device = info.device.local_name(),
+            driver = info.device.driver_name(),
+            "Connected",
+        );
+        crate::crash::set_driver(Some(info.device.driver_name()));
         info.switch.set_active(true);
         info.switch.set_sensitive(true);
         info.spinner.set_spinning(false);
-        let toast = adw::Toast::new(&format!("Connected to {}", info.device.local_name()));
+        let toast = adw::Toast::new(&gettext_f(
+            "Connected to {device}",
+            &[("device", &info.device.local_name())],
+        ));
         self.toaster.add_toast(toast);
     }
 
     pub fn device_disconnected(&self, id: DeviceId) {
         let info = self.known_devices.get(&id).unwrap();
+        tracing::info!(
+            device = info.device.local_name(),
+            idle = info.idle.get(),
+            "Disconnected",
+        );
+        crate::crash::set_driver(None);
         info.switch.set_active(false);
         info.switch.set_sensitive(true);
         info.spinner.set_spinning(false);
-        let toast = adw::Toast::new(&format!("{} Disconnected", info.device.local_name()));
+        let message = if info.idle.take() {
+            gettext_noop!("Disconnected {device} to save its battery, since it wasn't being turned")
+        } else {
+            gettext_noop!("{device} Disconnected")
+        };
+        let toast = adw::Toast::new(&gettext_f(
+            message,
+            &[("device", &info.device.local_name())],
+        ));
         self.toaster.add_toast(toast);
     }
 
+    /// Disconnect a device that hasn't been turned for the time set in the preferences
+    pub fn device_idle(&self, id: DeviceId) {
+        let Some(info) = self.known_devices.get(&id) else {
+            return;
+        };
+        info.idle.set(true);
+        // the switch's handler does the disconnecting
+        info.switch.set_active(false);
+    }
+
     pub fn handle_smartcube_event(&self, evt: SmartcubeEvent) {
-        dbg!(evt);
+        tracing::debug!(?evt, "Smartcube event");
     }
 
     pub fn start_scan(&self) {
         if let Some(manager) = self.manager.clone() {
+            let mut tx = self.tx.clone();
             tokio().spawn(async move {
-                manager.start_scan().await;
+                if let Err(e) = manager.start_scan().await {
+                    tx.send(BluetoothEvent::Failed(e).into()).await.unwrap();
+                }
             });
         }
     }
 
     pub fn stop_scan(&self) {
-        let manager = self.manager.clone().unwrap();
-        tokio().spawn(async move {
-            manager.stop_scan().await;
-        });
+        if let Some(manager) = self.manager.clone() {
+            tokio().spawn(async move {
+                // fails if the adapter was turned off, in which case it isn't scanning anyway
+                let _ = manager.stop_scan().await;
+            });
+        }
+    }
+
+    /// Show why Bluetooth can't be used, with a button to retry, in place of the devices
+    pub fn show_error(&self, error: &BluetoothError) {
+        let (title, description) = match error {
+            BluetoothError::AdapterOff => (
+                gettext("Bluetooth Is Off"),
+                gettext("Turn on Bluetooth in the system settings, and make sure airplane mode is off"),
+            ),
+            BluetoothError::NoAdapter => (
+                gettext("No Bluetooth Adapter"),
+                gettext("Plug in a Bluetooth adapter to connect to smart cubes"),
+            ),
+            BluetoothError::Unavailable(_) => (
+                gettext("Bluetooth Unavailable"),
+                gettext("Make sure the system's Bluetooth service (BlueZ) is installed and running"),
+            ),
+            BluetoothError::PermissionDenied => (
+                gettext("No Permission to Use Bluetooth"),
+                gettext("Allow PuzzleTime to use Bluetooth, e.g. with Flatseal if it's installed as a Flatpak"),
+            ),
+            BluetoothError::Other(e) => (gettext("Bluetooth Error"), e.to_string()),
+        };
+        self.error_page.set_title(&title);
+        self.error_page.set_description(Some(&description));
+        self.stack.set_visible_child(&self.error_page);
+    }
+
+    /// Start over after an error, from initializing if that's what failed
+    pub fn retry(&mut self) {
+        self.stack.set_visible_child(&self.devices_page);
+        if self.manager.is_some() {
+            self.start_scan();
+        } else {
+            self.maybe_init();
+        }
     }
 }
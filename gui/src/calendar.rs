@@ -0,0 +1,217 @@
+//! A calendar of how many solves were done on each day of the past year, with a summary of
+//! any of the days
+use crate::prelude::*;
+use crate::stats::{render_average, Average};
+use crate::timer::render_single;
+use solvestats::session_mean_of;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Number of weeks shown, so that a whole year fits
+const WEEKS: i32 = 53;
+/// Size of each day's square in pixels
+const CELL: f64 = 12.0;
+/// Space between the squares in pixels
+const GAP: f64 = 3.0;
+/// Height of the month names above the squares in pixels
+const MONTHS_HEIGHT: f64 = 16.0;
+
+/// A day, as `(year, month, day)`
+type Day = (i32, i32, i32);
+
+#[derive(Debug, Clone)]
+struct DaySummary {
+    count: usize,
+    /// `None` if there were no solves, or they were all DNFs
+    best: Option<Duration>,
+    /// `None` if there were no solves
+    average: Option<Average>,
+}
+
+impl DaySummary {
+    fn of(times: &[Option<Duration>]) -> Self {
+        Self {
+            count: times.len(),
+            best: times.iter().flatten().min().copied(),
+            average: (!times.is_empty()).then(|| session_mean_of(times)),
+        }
+    }
+}
+
+/// Times of the solves done on each day
+fn times_by_day(
+    solves: &[(glib::DateTime, Option<Duration>)],
+) -> HashMap<Day, Vec<Option<Duration>>> {
+    let mut days: HashMap<Day, Vec<Option<Duration>>> = HashMap::new();
+    for (date, time) in solves {
+        days.entry(date.ymd()).or_default().push(*time);
+    }
+    days
+}
+
+/// A dialog with a heatmap of the number of `solves` on each day, and a summary of the day
+/// that's clicked on (today at first)
+pub fn calendar_dialog(solves: &[(glib::DateTime, Option<Duration>)]) -> adw::Dialog {
+    let builder =
+        gtk::Builder::from_resource("/io/github/flying_toast/PuzzleTime/calendar-dialog.ui");
+    let root: adw::Dialog = builder.object("root").unwrap();
+    let heatmap_group: adw::PreferencesGroup = builder.object("heatmap_group").unwrap();
+    let heatmap: gtk::DrawingArea = builder.object("heatmap").unwrap();
+    let day_group: adw::PreferencesGroup = builder.object("day_group").unwrap();
+    let count_row: adw::ActionRow = builder.object("count_row").unwrap();
+    let best_row: adw::ActionRow = builder.object("best_row").unwrap();
+    let average_row: adw::ActionRow = builder.object("average_row").unwrap();
+
+    let days = Rc::new(times_by_day(solves));
+    let now = glib::DateTime::now_local().unwrap();
+    let today =
+        glib::DateTime::from_local(now.year(), now.month(), now.day_of_month(), 0, 0, 0.0).unwrap();
+    // the squares are in columns of weeks starting on Monday, with this week last
+    let today_row = today.day_of_week() - 1;
+    let first = today.add_days(-((WEEKS - 1) * 7 + today_row)).unwrap();
+    let year_solves: usize = days
+        .iter()
+        .filter(|(&(year, month, day), _)| {
+            glib::DateTime::from_local(year, month, day, 0, 0, 0.0).is_ok_and(|date| date >= first)
+        })
+        .map(|(_, times)| times.len())
+        .sum();
+    heatmap_group.set_description(Some(&ngettext_f(
+        "{count} solve in the past year",
+        "{count} solves in the past year",
+        year_solves as u32,
+        &[("count", &year_solves.to_string())],
+    )));
+
+    // days before today of the selected square
+    let selected = Rc::new(Cell::new(0));
+    let date_of = {
+        let today = today.clone();
+        move |days_ago: i32| today.add_days(-days_ago).unwrap()
+    };
+    // days before today of the square at a column and row, if it isn't in the future
+    let square_days_ago = move |col: i32, row: i32| {
+        let days_ago = (WEEKS - 1 - col) * 7 + today_row - row;
+        (days_ago >= 0).then_some(days_ago)
+    };
+
+    let show_day = {
+        let days = days.clone();
+        let date_of = date_of.clone();
+        move |days_ago: i32| {
+            let date = date_of(days_ago);
+            let summary = DaySummary::of(days.get(&date.ymd()).map_or(&[][..], Vec::as_slice));
+            day_group.set_title(&date.format("%A, %x").unwrap());
+            count_row.set_subtitle(&summary.count.to_string());
+            best_row.set_subtitle(&match summary.best {
+                Some(best) => render_single(&best),
+                None if summary.count > 0 => "DNF".into(),
+                None => "-".into(),
+            });
+            average_row.set_subtitle(&summary.average.map_or("-".into(), render_average));
+        }
+    };
+    show_day(0);
+
+    heatmap.set_content_width((f64::from(WEEKS) * (CELL + GAP) - GAP) as i32);
+    heatmap.set_content_height((MONTHS_HEIGHT + 7.0 * (CELL + GAP) - GAP) as i32);
+    let days2 = days.clone();
+    let selected2 = selected.clone();
+    heatmap.set_draw_func(move |area, cr, _, _| {
+        let most = days2.values().map(Vec::len).max().unwrap_or(0).max(1);
+        let color = area.color();
+        let set_color = |alpha: f64| {
+            cr.set_source_rgba(
+                color.red().into(),
+                color.green().into(),
+                color.blue().into(),
+                f64::from(color.alpha()) * alpha,
+            );
+        };
+        cr.set_font_size(11.0);
+        for col in 0..WEEKS {
+            let x = f64::from(col) * (CELL + GAP);
+            // label the column that each month starts in
+            if let Some(monday) = square_days_ago(col, 0) {
+                let monday = date_of(monday);
+                if monday.day_of_month() <= 7 {
+                    set_color(0.6);
+                    cr.move_to(x, MONTHS_HEIGHT - 4.0);
+                    cr.show_text(&monday.format("%b").unwrap()).unwrap();
+                }
+            }
+            for row in 0..7 {
+                let Some(days_ago) = square_days_ago(col, row) else {
+                    continue;
+                };
+                let count = days2.get(&date_of(days_ago).ymd()).map_or(0, Vec::len);
+                let y = MONTHS_HEIGHT + f64::from(row) * (CELL + GAP);
+                set_color(if count == 0 {
+                    0.08
+                } else {
+                    0.25 + 0.75 * count as f64 / most as f64
+                });
+                cr.rectangle(x, y, CELL, CELL);
+                cr.fill().unwrap();
+                if days_ago == selected2.get() {
+                    set_color(1.0);
+                    cr.set_line_width(2.0);
+                    cr.rectangle(x - 1.0, y - 1.0, CELL + 2.0, CELL + 2.0);
+                    cr.stroke().unwrap();
+                }
+            }
+        }
+    });
+
+    let click = gtk::GestureClick::new();
+    let area = heatmap.clone();
+    click.connect_released(move |_, _, x, y| {
+        let col = (x / (CELL + GAP)).floor() as i32;
+        let row = ((y - MONTHS_HEIGHT) / (CELL + GAP)).floor() as i32;
+        if !(0..WEEKS).contains(&col) || !(0..7).contains(&row) {
+            return;
+        }
+        if let Some(days_ago) = square_days_ago(col, row) {
+            selected.set(days_ago);
+            show_day(days_ago);
+            area.queue_draw();
+        }
+    });
+    heatmap.add_controller(click);
+
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn daily_summaries() {
+        let at = |day, hour| glib::DateTime::from_local(2024, 3, day, hour, 0, 0.0).unwrap();
+        let secs = |secs| Some(Duration::from_secs(secs));
+        let days = times_by_day(&[
+            (at(1, 9), secs(12)),
+            (at(1, 23), None),
+            (at(1, 0), secs(10)),
+            (at(2, 12), None),
+        ]);
+        assert_eq!(days.len(), 2);
+
+        let first = DaySummary::of(&days[&(2024, 3, 1)]);
+        assert_eq!(first.count, 3);
+        assert_eq!(first.best, secs(10));
+        assert_eq!(first.average.unwrap().to_string(), "11.00");
+
+        let second = DaySummary::of(&days[&(2024, 3, 2)]);
+        assert_eq!(second.count, 1);
+        assert_eq!(second.best, None);
+        assert_eq!(second.average.unwrap().to_string(), "DNF");
+
+        let none = DaySummary::of(&[]);
+        assert_eq!(none.count, 0);
+        assert!(none.average.is_none());
+    }
+}
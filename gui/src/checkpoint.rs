@@ -0,0 +1,197 @@
+//! Checkpoints of what's tracked from the smart cube, saved every so often so that a restart
+//! in the middle of a session doesn't lose them: the cube's state, the turns of the running
+//! solve (which its reconstruction is made from) and the number of turns made today. Unlike
+//! the autosave, the file is kept when the app quits normally, since today's turns still
+//! count the next time it's opened.
+use crate::autosave::{parse_turns, render_turns, write_file};
+use crate::prelude::*;
+use crate::settings::APP_ID;
+use cubestruct::{CubieCube, Move};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// How often the checkpoint is saved (if anything changed)
+pub const INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct CubeCheckpoint {
+    /// Last known state of the smart cube
+    pub state: Option<CubieCube>,
+    pub solve: Option<SolveCheckpoint>,
+    pub turns_today: TurnCount,
+}
+
+/// A running smart cube solve, as far as it had got
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SolveCheckpoint {
+    /// When it started, as returned by [`crate::timer::Timer::started_at()`], to tell which
+    /// solve it is
+    pub started_at: i64,
+    /// State of the cube when it started
+    pub start_state: CubieCube,
+    /// Each turn so far, with when it was done as a time since the start
+    pub turns: Vec<(Move, Duration)>,
+}
+
+/// Turns of smart cubes made in a day
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct TurnCount {
+    /// As `YYYY-MM-DD`
+    day: String,
+    turns: u32,
+}
+
+impl TurnCount {
+    /// Count a turn made on `today`
+    pub fn count(&mut self, today: &str) {
+        if self.day != today {
+            self.day = today.to_owned();
+            self.turns = 0;
+        }
+        self.turns += 1;
+    }
+
+    /// Turns made on `today`
+    pub fn on(&self, today: &str) -> u32 {
+        if self.day == today {
+            self.turns
+        } else {
+            0
+        }
+    }
+}
+
+impl CubeCheckpoint {
+    pub fn serialize(&self) -> String {
+        let mut out = String::new();
+        if let Some(state) = &self.state {
+            out.push_str(&format!("state\t{}\n", state.to_facelet_string()));
+        }
+        if let Some(solve) = &self.solve {
+            out.push_str(&format!(
+                "solve\t{}\t{}\n",
+                solve.started_at,
+                solve.start_state.to_facelet_string()
+            ));
+            if !solve.turns.is_empty() {
+                out.push_str(&format!("turns\t{}\n", render_turns(&solve.turns)));
+            }
+        }
+        if self.turns_today.turns > 0 {
+            out.push_str(&format!(
+                "today\t{}\t{}\n",
+                self.turns_today.day, self.turns_today.turns
+            ));
+        }
+        out
+    }
+
+    /// Lines that can't be parsed are skipped
+    pub fn deserialize(s: &str) -> Self {
+        let mut checkpoint = Self::default();
+        for line in s.lines() {
+            let Some((kind, rest)) = line.split_once('\t') else {
+                continue;
+            };
+            match kind {
+                "state" => checkpoint.state = CubieCube::from_facelet_string(rest),
+                "solve" => {
+                    checkpoint.solve = rest.split_once('\t').and_then(|(started_at, state)| {
+                        Some(SolveCheckpoint {
+                            started_at: started_at.parse().ok()?,
+                            start_state: CubieCube::from_facelet_string(state)?,
+                            turns: Vec::new(),
+                        })
+                    });
+                }
+                "turns" => {
+                    if let Some(solve) = &mut checkpoint.solve {
+                        solve.turns = parse_turns(rest);
+                    }
+                }
+                "today" => {
+                    if let Some((day, turns)) = rest.split_once('\t') {
+                        if let Ok(turns) = turns.parse() {
+                            checkpoint.turns_today = TurnCount {
+                                day: day.to_owned(),
+                                turns,
+                            };
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        checkpoint
+    }
+}
+
+fn path() -> PathBuf {
+    glib::user_data_dir().join(APP_ID).join("cube-checkpoint")
+}
+
+/// The last checkpoint saved, or an empty one if there isn't one
+pub fn load() -> CubeCheckpoint {
+    std::fs::read_to_string(path())
+        .map(|contents| CubeCheckpoint::deserialize(&contents))
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Default)]
+pub struct Checkpoints {
+    /// Contents of the last successful write, to avoid rewriting the file when nothing
+    /// changed (a failed write is tried again next time)
+    last_written: Option<String>,
+}
+
+impl Checkpoints {
+    pub fn update(&mut self, checkpoint: &CubeCheckpoint) {
+        let contents = checkpoint.serialize();
+        if self.last_written.as_ref() == Some(&contents) {
+            return;
+        }
+
+        match write_file(&path(), &contents) {
+            Ok(()) => self.last_written = Some(contents),
+            Err(e) => tracing::warn!("Failed to write cube checkpoint: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut scrambled = CubieCube::SOLVED;
+        scrambled.apply_moves(&[Move::R, Move::U, Move::F2]);
+        let mut turns_today = TurnCount::default();
+        turns_today.count("2026-10-16");
+        turns_today.count("2026-10-17");
+        turns_today.count("2026-10-17");
+        assert_eq!(turns_today.on("2026-10-17"), 2);
+        assert_eq!(turns_today.on("2026-10-18"), 0);
+
+        let checkpoint = CubeCheckpoint {
+            state: Some(CubieCube::SOLVED),
+            solve: Some(SolveCheckpoint {
+                started_at: 1_760_700_000_000_000,
+                start_state: scrambled,
+                turns: vec![
+                    (Move::F2, Duration::from_millis(410)),
+                    (Move::Ui, Duration::from_millis(720)),
+                ],
+            }),
+            turns_today,
+        };
+        assert_eq!(
+            CubeCheckpoint::deserialize(&checkpoint.serialize()),
+            checkpoint
+        );
+        assert_eq!(
+            CubeCheckpoint::deserialize("state\tnot a cube\ntoday\t2026-10-17\tlots\n"),
+            CubeCheckpoint::default()
+        );
+    }
+}
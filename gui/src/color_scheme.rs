@@ -0,0 +1,111 @@
+//! The user's sticker colors, and the orientation they hold the cube in while scrambling
+use crate::prelude::*;
+use crate::settings::settings;
+use cubestruct::{Color, CubieCube, Rotation};
+
+/// Sides of the cube, named by their color in the standard scheme. In the same order as the
+/// `color-scheme` setting.
+pub const SIDES: [Color; 6] = [
+    Color::White,
+    Color::Yellow,
+    Color::Green,
+    Color::Blue,
+    Color::Red,
+    Color::Orange,
+];
+
+pub fn side_name(side: Color) -> String {
+    match side {
+        Color::White => gettext("White"),
+        Color::Yellow => gettext("Yellow"),
+        Color::Green => gettext("Green"),
+        Color::Blue => gettext("Blue"),
+        Color::Red => gettext("Red"),
+        Color::Orange => gettext("Orange"),
+    }
+}
+
+/// Value of a side in the `cube-top` and `cube-front` settings
+pub fn side_setting_value(side: Color) -> &'static str {
+    match side {
+        Color::White => "white",
+        Color::Yellow => "yellow",
+        Color::Green => "green",
+        Color::Blue => "blue",
+        Color::Red => "red",
+        Color::Orange => "orange",
+    }
+}
+
+fn side_from_setting(key: &str) -> Option<Color> {
+    let value = settings().string(key);
+    SIDES
+        .into_iter()
+        .find(|&side| side_setting_value(side) == value)
+}
+
+#[derive(Debug, Clone)]
+pub struct ColorScheme {
+    /// Indexed like [`SIDES`]
+    colors: [gdk::RGBA; 6],
+    /// Turn a cube from white top, green front to the user's orientation
+    rotations: Vec<Rotation>,
+    /// `held_sides[i]` is the side that's held where `SIDES[i]` is in the standard
+    /// orientation
+    held_sides: [Color; 6],
+}
+
+impl ColorScheme {
+    /// As set in the settings
+    pub fn current() -> Self {
+        let settings = settings();
+        let setting_colors = settings.strv("color-scheme");
+        let colors = std::array::from_fn(|i| {
+            setting_colors
+                .get(i)
+                .and_then(|color| gdk::RGBA::parse(color.as_str()).ok())
+                .unwrap_or(gdk::RGBA::BLACK)
+        });
+
+        let top = side_from_setting("cube-top").unwrap_or(Color::White);
+        let front = side_from_setting("cube-front").unwrap_or(Color::Green);
+        let rotations = Rotation::to_orientation(top, front).unwrap_or_default();
+        let mut facelets = CubieCube::SOLVED.to_facelet_cube();
+        for &rotation in &rotations {
+            facelets.rotate(rotation);
+        }
+        let held_sides = SIDES.map(|face| facelets.get_face(face)[4]);
+
+        Self {
+            colors,
+            rotations,
+            held_sides,
+        }
+    }
+
+    fn side_color(&self, side: Color) -> &gdk::RGBA {
+        &self.colors[SIDES.iter().position(|&s| s == side).unwrap()]
+    }
+
+    /// Color to draw the stickers that belong on `face` of a [`CubieCube`]. Since the cube
+    /// is held in the user's orientation, that's the color of the side held there.
+    pub fn sticker_rgb(&self, face: Color) -> (f64, f64, f64) {
+        let held = self.held_sides[SIDES.iter().position(|&s| s == face).unwrap()];
+        let color = self.side_color(held);
+        (
+            color.red().into(),
+            color.green().into(),
+            color.blue().into(),
+        )
+    }
+
+    /// Convert a state reported by a smart cube (which is always relative to its white
+    /// side on top and green in front) to how it's seen in the user's orientation
+    pub fn orient(&self, state: &CubieCube) -> CubieCube {
+        let mut oriented = *state;
+        for &rotation in &self.rotations {
+            oriented.rotate(rotation);
+        }
+        oriented
+    }
+}
@@ -0,0 +1,195 @@
+//! Crash reports, if they're turned on in the preferences. A panic saves a report to a file,
+//! and on the next start the app offers to open a new issue with it filled in. Nothing is
+//! sent anywhere without the user doing it.
+use crate::logging;
+use crate::prelude::*;
+use crate::settings::{settings, APP_ID};
+use std::backtrace::Backtrace;
+use std::panic::PanicHookInfo;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+const NEW_ISSUE_URL: &str = "https://github.com/Flying-Toast/cubeapp/issues/new";
+
+/// Reports are cut short to this many bytes in the issue, since all of it has to fit in the
+/// URL
+const MAX_ISSUE_BODY: usize = 6000;
+
+/// Whether the `crash-reports` setting is on, since the settings can't be read from the
+/// panic hook
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The driver of the connected smart cube, if one is connected
+static DRIVER: Mutex<Option<&'static str>> = Mutex::new(None);
+
+fn report_path() -> PathBuf {
+    glib::user_data_dir().join(APP_ID).join("crash-report.txt")
+}
+
+/// Where a report is moved to once it's been offered, so that it's only offered once
+fn offered_report_path() -> PathBuf {
+    glib::user_data_dir()
+        .join(APP_ID)
+        .join("last-crash-report.txt")
+}
+
+/// Set up writing crash reports, for as long as the setting is on
+pub fn install_hook() {
+    let settings = settings();
+    ENABLED.store(settings.boolean("crash-reports"), Ordering::Relaxed);
+    settings.connect_changed(Some("crash-reports"), |settings, key| {
+        ENABLED.store(settings.boolean(key), Ordering::Relaxed);
+    });
+
+    let path = report_path();
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if ENABLED.load(Ordering::Relaxed) {
+            let report = crash_report(info);
+            if let Some(dir) = path.parent() {
+                let _ = std::fs::create_dir_all(dir);
+            }
+            let _ = std::fs::write(&path, report);
+        }
+        default_hook(info);
+    }));
+}
+
+/// Keep track of which driver's cube is connected, to include in reports
+pub fn set_driver(driver: Option<&'static str>) {
+    *DRIVER.lock().unwrap() = driver;
+}
+
+fn crash_report(info: &PanicHookInfo) -> String {
+    let message = info
+        .payload()
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| info.payload().downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Box<dyn Any>");
+    let location = info.location().map(ToString::to_string).unwrap_or_default();
+    // not locking, in case the panic was while it was locked
+    let driver = DRIVER.try_lock().ok().and_then(|driver| *driver);
+    render_report(
+        message,
+        &location,
+        std::thread::current().name().unwrap_or("unnamed"),
+        driver,
+        &Backtrace::force_capture().to_string(),
+        &logging::recent(),
+    )
+}
+
+/// The report's first line is the issue's title
+fn render_report(
+    message: &str,
+    location: &str,
+    thread: &str,
+    driver: Option<&str>,
+    backtrace: &str,
+    log: &[String],
+) -> String {
+    let first_line = message.lines().next().unwrap_or_default();
+    format!(
+        "Crash: {first_line}\n\n\
+         Message: {message}\n\
+         Location: {location}\n\
+         Thread: {thread}\n\
+         Version: {version}\n\
+         OS: {os} ({arch})\n\
+         Smart cube driver: {driver}\n\n\
+         Backtrace:\n{backtrace}\n\n\
+         Recent log:\n{log}\n",
+        version = env!("CARGO_PKG_VERSION"),
+        os = std::env::consts::OS,
+        arch = std::env::consts::ARCH,
+        driver = driver.unwrap_or("none connected"),
+        log = log.join("\n"),
+    )
+}
+
+/// If the last run saved a crash report, offer to report it
+pub fn offer_report(toasts: &adw::ToastOverlay) {
+    let Ok(report) = std::fs::read_to_string(report_path()) else {
+        return;
+    };
+    if let Err(e) = std::fs::rename(report_path(), offered_report_path()) {
+        tracing::warn!("Failed to move crash report: {e}");
+    }
+
+    let toast = adw::Toast::builder()
+        .title(gettext(
+            "Puzzle Time crashed last time, and saved a crash report",
+        ))
+        .button_label(gettext("Report…"))
+        // it's easy to miss otherwise, since the window is just opening
+        .timeout(0)
+        .build();
+    let toasts2 = toasts.clone();
+    toast.connect_button_clicked(move |_| {
+        let uri = issue_url(&report);
+        let window = toasts2.root().and_downcast::<gtk::Window>();
+        glib::spawn_future_local(async move {
+            let launch = gtk::UriLauncher::new(&uri)
+                .launch_future(window.as_ref())
+                .await;
+            if let Err(e) = launch {
+                tracing::warn!("Failed to open the issue tracker: {e}");
+            }
+        });
+    });
+    toasts.add_toast(toast);
+}
+
+fn issue_url(report: &str) -> String {
+    let (title, body) = report.split_once("\n\n").unwrap_or((report, ""));
+    let body = if body.len() > MAX_ISSUE_BODY {
+        let mut end = MAX_ISSUE_BODY;
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!(
+            "{}\n\n(Cut short. The whole report is in {}.)",
+            &body[..end],
+            offered_report_path().display(),
+        )
+    } else {
+        body.to_owned()
+    };
+    let body = format!("What were you doing when it crashed?\n\n\n```\n{body}\n```\n");
+    let escape = |s: &str| glib::Uri::escape_string(s, None, false);
+    format!(
+        "{NEW_ISSUE_URL}?title={}&body={}",
+        escape(title),
+        escape(&body)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports() {
+        let report = render_report(
+            "index out of bounds\nmore detail",
+            "src/timer.rs:10:5",
+            "main",
+            Some("QiYi Smartcube"),
+            "0: puzzletime::main",
+            &["INFO connected".to_owned()],
+        );
+        assert!(report.starts_with("Crash: index out of bounds\n\n"));
+        assert!(report.contains("Smart cube driver: QiYi Smartcube\n"));
+
+        let url = issue_url(&report);
+        assert!(url.starts_with(&format!("{NEW_ISSUE_URL}?title=Crash%3A%20index%20out")));
+        assert!(!url.contains([' ', '\n', '#']));
+        // the only one is between the parameters
+        assert_eq!(url.matches('&').count(), 1);
+
+        let long = format!("Crash: x\n\n{}", "é".repeat(MAX_ISSUE_BODY));
+        assert!(issue_url(&long).contains("Cut%20short"));
+    }
+}
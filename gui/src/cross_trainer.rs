@@ -0,0 +1,117 @@
+use crate::i18n::format_decimal;
+use crate::prelude::*;
+use crate::timer::{render_average, render_time, Precision};
+use cubestruct::CubieCube;
+use std::time::Duration;
+
+/// A single cross-only attempt
+#[derive(Debug, Copy, Clone)]
+struct CrossResult {
+    time: Duration,
+    /// Number of state changes reported by the smart cube
+    moves: u32,
+}
+
+/// Cross-only practice: the timer stops as soon as the smart cube reports a solved
+/// cross (on any face), and the time and move count of each attempt is tracked.
+#[derive(Debug)]
+pub struct CrossTrainer {
+    root: gtk::Box,
+    last_label: gtk::Label,
+    mean_label: gtk::Label,
+    best_label: gtk::Label,
+    mean_moves_label: gtk::Label,
+    count_label: gtk::Label,
+    /// Moves done so far in the current attempt, or `None` if the timer isn't running
+    moves: Option<u32>,
+    /// Whether the cross has been solved in the current attempt
+    cross_solved: bool,
+    results: Vec<CrossResult>,
+}
+
+impl CrossTrainer {
+    pub fn new() -> Self {
+        let builder =
+            gtk::Builder::from_resource("/io/github/flying_toast/PuzzleTime/cross-trainer.ui");
+
+        Self {
+            root: builder.object("root").unwrap(),
+            last_label: builder.object("last_label").unwrap(),
+            mean_label: builder.object("mean_label").unwrap(),
+            best_label: builder.object("best_label").unwrap(),
+            mean_moves_label: builder.object("mean_moves_label").unwrap(),
+            count_label: builder.object("count_label").unwrap(),
+            moves: None,
+            cross_solved: false,
+            results: Vec::new(),
+        }
+    }
+
+    pub fn widget(&self) -> &gtk::Box {
+        &self.root
+    }
+
+    pub fn set_visible(&self, visible: bool) {
+        self.root.set_visible(visible);
+    }
+
+    /// Call when the timer starts
+    pub fn start(&mut self) {
+        self.moves = Some(0);
+        self.cross_solved = false;
+    }
+
+    /// Call for each new state from the smart cube.
+    /// Returns whether the cross was just solved (i.e. the timer should be stopped).
+    pub fn cube_moved(&mut self, state: &CubieCube) -> bool {
+        let Some(moves) = &mut self.moves else {
+            return false;
+        };
+        *moves += 1;
+        self.cross_solved = state.solved_cross().is_some();
+        self.cross_solved
+    }
+
+    /// Call when the timer stops. Returns `false` if the attempt was stopped before the
+    /// cross was solved, in which case it isn't recorded.
+    pub fn finish(&mut self, time: Duration) -> bool {
+        let Some(moves) = self.moves.take() else {
+            return false;
+        };
+        if !std::mem::take(&mut self.cross_solved) {
+            return false;
+        }
+
+        self.results.push(CrossResult { time, moves });
+        self.update_labels();
+        true
+    }
+
+    fn update_labels(&self) {
+        let Some(last) = self.results.last() else {
+            return;
+        };
+        let count = self.results.len() as u32;
+
+        self.last_label.set_label(&ngettext_f(
+            "{time} ({moves} move)",
+            "{time} ({moves} moves)",
+            last.moves,
+            &[
+                ("time", &render_time(&last.time, Precision::Hundredths)),
+                ("moves", &last.moves.to_string()),
+            ],
+        ));
+        let mean = self.results.iter().map(|r| r.time).sum::<Duration>() / count;
+        self.mean_label.set_label(&render_average(&mean));
+        let best = self.results.iter().map(|r| r.time).min().unwrap();
+        self.best_label
+            .set_label(&render_time(&best, Precision::Hundredths));
+        let total_moves: u32 = self.results.iter().map(|r| r.moves).sum();
+        self.mean_moves_label.set_label(&format_decimal(
+            f64::from(total_moves) / f64::from(count),
+            1,
+        ));
+        self.count_label.set_label(&count.to_string());
+    }
+}
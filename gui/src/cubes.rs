@@ -0,0 +1,83 @@
+//! The user's physical cubes, so that solves can be tagged with the cube they were done on
+//! and stats compared between cubes
+use crate::prelude::*;
+use crate::settings::settings;
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// Names of the user's cubes, in the order they were added
+pub fn cubes() -> Vec<String> {
+    settings()
+        .strv("cubes")
+        .iter()
+        .map(|name| name.as_str().to_owned())
+        .collect()
+}
+
+/// Add a cube to the list. Does nothing if there's already one with that name.
+pub fn add(name: &str) {
+    let name = name.trim();
+    let mut cubes = cubes();
+    if name.is_empty() || cubes.iter().any(|cube| cube == name) {
+        return;
+    }
+    cubes.push(name.to_owned());
+    settings().set_strv("cubes", cubes).unwrap();
+}
+
+/// Remove a cube from the list. Solves done on it stay tagged with it.
+pub fn remove(name: &str) {
+    let mut cubes = cubes();
+    cubes.retain(|cube| cube != name);
+    settings().set_strv("cubes", cubes).unwrap();
+    if settings().string("current-cube") == name {
+        settings().set_string("current-cube", "").unwrap();
+    }
+}
+
+/// The cube that new solves are done on
+pub fn current() -> Option<String> {
+    let name = settings().string("current-cube");
+    cubes().into_iter().find(|cube| *cube == name)
+}
+
+/// Strings for a dropdown of the cubes, with "No Cube" first (for solves that aren't tagged
+/// with a cube), kept up to date with the list. Use [`index_of`] and [`at_index`] to convert
+/// between cubes and positions in it.
+pub fn model() -> gtk::StringList {
+    let model = gtk::StringList::new(&[]);
+    let fill = |model: &gtk::StringList| {
+        let mut names = vec![gettext("No Cube")];
+        names.extend(cubes());
+        let names = names.iter().map(String::as_str).collect::<Vec<_>>();
+        model.splice(0, model.n_items(), &names);
+    };
+    fill(&model);
+    let model2 = model.downgrade();
+    let handler = Rc::new(Cell::new(None));
+    let handler2 = handler.clone();
+    handler.set(Some(settings().connect_changed(
+        Some("cubes"),
+        move |settings, _| match model2.upgrade() {
+            Some(model) => fill(&model),
+            // the dropdown it was for is gone
+            None => {
+                if let Some(handler) = handler2.take() {
+                    settings.disconnect(handler);
+                }
+            }
+        },
+    )));
+    model
+}
+
+/// Position of `cube` in [`model`]. Cubes that were removed from the list count as no cube.
+pub fn index_of(cube: Option<&str>) -> u32 {
+    cube.and_then(|cube| cubes().iter().position(|c| c == cube))
+        .map_or(0, |idx| idx as u32 + 1)
+}
+
+/// The cube at `index` in [`model`]
+pub fn at_index(index: u32) -> Option<String> {
+    cubes().into_iter().nth(index.checked_sub(1)? as usize)
+}
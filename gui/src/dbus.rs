@@ -0,0 +1,175 @@
+//! D-Bus interface for stream overlays and other external integrations. It's exported on
+//! the application's own bus name, at the application's object path.
+use crate::goals;
+use crate::prelude::*;
+use crate::stats::{render_average, Average};
+use cubestruct::CubieCube;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+const INTERFACE_NAME: &str = "io.github.flying_toast.PuzzleTime.Timer";
+
+const INTERFACE_XML: &str = r#"
+<node>
+  <interface name="io.github.flying_toast.PuzzleTime.Timer">
+    <!-- Elapsed time of the running solve (or the last one, if stopped), in seconds -->
+    <method name="GetCurrentTime">
+      <arg type="d" name="seconds" direction="out"/>
+      <arg type="b" name="running" direction="out"/>
+    </method>
+    <!-- The last recorded solve. `time` is empty if there isn't one yet. -->
+    <method name="GetLastSolve">
+      <arg type="s" name="time" direction="out"/>
+      <arg type="s" name="scramble" direction="out"/>
+    </method>
+    <!-- Stickers of the connected smart cube as 54 face letters, face by face in URFDLB
+         order. Empty if no cube is connected. -->
+    <method name="GetCubeState">
+      <arg type="s" name="facelets" direction="out"/>
+    </method>
+    <!-- Practice for desktop widgets: solves today (in any session), consecutive days with
+         a solve, and the session's current average of 5 (empty if there isn't one yet) -->
+    <method name="GetPractice">
+      <arg type="u" name="solves_today" direction="out"/>
+      <arg type="u" name="streak" direction="out"/>
+      <arg type="s" name="ao5" direction="out"/>
+    </method>
+    <signal name="TimerStarted"/>
+    <signal name="TimerStopped">
+      <arg type="d" name="seconds"/>
+    </signal>
+    <signal name="CubeStateChanged">
+      <arg type="s" name="facelets"/>
+    </signal>
+    <signal name="PracticeChanged">
+      <arg type="u" name="solves_today"/>
+      <arg type="u" name="streak"/>
+      <arg type="s" name="ao5"/>
+    </signal>
+  </interface>
+</node>
+"#;
+
+#[derive(Debug, Default)]
+struct State {
+    start_time: Option<Instant>,
+    last_elapsed: Duration,
+    last_solve: Option<(String, String)>,
+    cube_state: Option<String>,
+    /// As last sent in `PracticeChanged`
+    practice: Option<(u32, u32, String)>,
+    ao5: Option<String>,
+}
+
+/// Keeps the exported state up to date and emits signals
+#[derive(Debug)]
+pub struct DbusInterface {
+    connection: gio::DBusConnection,
+    object_path: String,
+    state: Rc<RefCell<State>>,
+}
+
+impl DbusInterface {
+    /// Returns `None` if the application isn't on the session bus
+    pub fn register(application: &adw::Application) -> Option<Self> {
+        let connection = application.dbus_connection()?;
+        let object_path = application.dbus_object_path()?.to_string();
+        let interface_info = gio::DBusNodeInfo::for_xml(INTERFACE_XML)
+            .unwrap()
+            .lookup_interface(INTERFACE_NAME)
+            .unwrap();
+        let state = Rc::new(RefCell::new(State::default()));
+
+        let state2 = state.clone();
+        let registration = connection.register_object(
+            &object_path,
+            &interface_info,
+            move |_, _, _, _, method, _, invocation| {
+                let state = state2.borrow();
+                let reply = match method {
+                    "GetCurrentTime" => {
+                        let elapsed = state
+                            .start_time
+                            .map_or(state.last_elapsed, |start| start.elapsed());
+                        (elapsed.as_secs_f64(), state.start_time.is_some()).to_variant()
+                    }
+                    "GetLastSolve" => state.last_solve.clone().unwrap_or_default().to_variant(),
+                    "GetCubeState" => (state.cube_state.clone().unwrap_or_default(),).to_variant(),
+                    "GetPractice" => {
+                        // read now, since the day might have changed since the last solve
+                        let (solves, streak) = goals::today();
+                        (solves, streak, state.ao5.clone().unwrap_or_default()).to_variant()
+                    }
+                    _ => unreachable!("Unknown method {method}"),
+                };
+                invocation.return_value(Some(&reply));
+            },
+            |_, _, _, _, _| unreachable!("No properties"),
+            |_, _, _, _, _, _| false,
+        );
+        if let Err(e) = registration {
+            tracing::warn!("Failed to register D-Bus interface: {e}");
+            return None;
+        }
+
+        Some(Self {
+            connection,
+            object_path,
+            state,
+        })
+    }
+
+    fn emit(&self, signal: &str, parameters: Option<&glib::Variant>) {
+        if let Err(e) =
+            self.connection
+                .emit_signal(None, &self.object_path, INTERFACE_NAME, signal, parameters)
+        {
+            tracing::warn!("Failed to emit D-Bus signal {signal}: {e}");
+        }
+    }
+
+    pub fn timer_started(&self) {
+        self.state.borrow_mut().start_time = Some(Instant::now());
+        self.emit("TimerStarted", None);
+    }
+
+    pub fn timer_stopped(&self, elapsed: Duration) {
+        let mut state = self.state.borrow_mut();
+        state.start_time = None;
+        state.last_elapsed = elapsed;
+        drop(state);
+        self.emit("TimerStopped", Some(&(elapsed.as_secs_f64(),).to_variant()));
+    }
+
+    /// `time` is as displayed in the stats, including any penalty
+    pub fn solve_recorded(&self, time: String, scramble: String) {
+        self.state.borrow_mut().last_solve = Some((time, scramble));
+    }
+
+    /// Emits `PracticeChanged` if today's practice or `ao5` (the session's current average
+    /// of 5) changed
+    pub fn practice_changed(&self, ao5: Option<Average>) {
+        let ao5 = ao5.map(render_average);
+        let (solves, streak) = goals::today();
+        let practice = (solves, streak, ao5.clone().unwrap_or_default());
+        let mut state = self.state.borrow_mut();
+        state.ao5 = ao5;
+        if state.practice.as_ref() == Some(&practice) {
+            return;
+        }
+        state.practice = Some(practice.clone());
+        drop(state);
+        self.emit("PracticeChanged", Some(&practice.to_variant()));
+    }
+
+    /// `None` if the cube disconnected
+    pub fn cube_state_changed(&self, cube: Option<&CubieCube>) {
+        let facelets = cube.map(CubieCube::to_facelet_string);
+        self.state.borrow_mut().cube_state = facelets.clone();
+        self.emit(
+            "CubeStateChanged",
+            Some(&(facelets.unwrap_or_default(),).to_variant()),
+        );
+    }
+}
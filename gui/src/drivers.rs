@@ -0,0 +1,70 @@
+//! The smart cube drivers: the ones built in, and any plugins (see [`smartcube::plugin`])
+//! listed in the drivers file, one path per line
+use crate::prelude::*;
+use crate::settings::APP_ID;
+use smartcube::plugin::{self, PluginError};
+use smartcube::Driver;
+use std::path::{Path, PathBuf};
+
+const BUILTIN: &[&dyn Driver] = &[
+    #[cfg(feature = "qiyi")]
+    &qiyi_smartcube::Driver,
+];
+
+pub fn config_path() -> PathBuf {
+    glib::user_config_dir().join(APP_ID).join("drivers")
+}
+
+/// The built-in drivers, followed by the ones from plugins that could be loaded. Plugins are
+/// only ever loaded once, so this should be too.
+pub fn load() -> (&'static [&'static dyn Driver], Vec<PluginError>) {
+    let mut drivers = BUILTIN.to_vec();
+    let mut errors = Vec::new();
+    let path = config_path();
+    // no file means no plugins
+    if let Ok(config) = std::fs::read_to_string(&path) {
+        for plugin in plugin_paths(&config, path.parent().unwrap()) {
+            // SAFETY: plugins are only loaded from the paths the user listed in the drivers
+            // file, so they're trusted to be plugins built for this build of the app
+            match unsafe { plugin::load_driver(&plugin) } {
+                Ok(driver) => drivers.push(driver),
+                Err(e) => errors.push(e),
+            }
+        }
+    }
+    (drivers.leak(), errors)
+}
+
+/// Paths from the drivers file, skipping blank lines and `#` comments. Relative paths are
+/// relative to the file's directory.
+fn plugin_paths(config: &str, dir: &Path) -> Vec<PathBuf> {
+    config
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| dir.join(line))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config() {
+        let config = "
+            # GAN cubes
+            /usr/lib/libgan_smartcube.so
+
+            plugins/libmoyu_smartcube.so
+        ";
+        let dir = Path::new("/home/cuber/.config/io.github.flying_toast.PuzzleTime");
+        assert_eq!(
+            plugin_paths(config, dir),
+            [
+                PathBuf::from("/usr/lib/libgan_smartcube.so"),
+                dir.join("plugins/libmoyu_smartcube.so"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,52 @@
+//! Saving and opening files chosen with a [`gtk::FileDialog`]. When sandboxed the dialog goes
+//! through the file chooser portal, and the chosen file doesn't necessarily have a local path,
+//! so files are read and written through GIO rather than `std::fs`.
+use crate::prelude::*;
+use crate::runtime::tokio;
+
+/// Ask where to save a file with `dialog`, then save what `render` returns there. `render`
+/// is given the chosen file's name, to pick the format by its extension, and runs on the
+/// Tokio runtime. Returns `None` if the dialog was cancelled.
+pub async fn save(
+    dialog: &gtk::FileDialog,
+    window: &(impl IsA<gtk::Window> + Clone + 'static),
+    render: impl FnOnce(&str) -> anyhow::Result<Vec<u8>> + Send + 'static,
+) -> Option<anyhow::Result<()>> {
+    let file = dialog.save_future(Some(window)).await.ok()?;
+    Some(write(&file, render).await)
+}
+
+/// Ask which file to open with `dialog`, then read it. Returns `None` if the dialog was
+/// cancelled.
+pub async fn open(
+    dialog: &gtk::FileDialog,
+    window: &(impl IsA<gtk::Window> + Clone + 'static),
+) -> Option<anyhow::Result<Vec<u8>>> {
+    let file = dialog.open_future(Some(window)).await.ok()?;
+    Some(
+        file.load_contents_future()
+            .await
+            .map(|(contents, _etag)| contents.to_vec())
+            .map_err(Into::into),
+    )
+}
+
+async fn write(
+    file: &gio::File,
+    render: impl FnOnce(&str) -> anyhow::Result<Vec<u8>> + Send + 'static,
+) -> anyhow::Result<()> {
+    let name = file
+        .basename()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let contents = tokio().spawn_blocking(move || render(&name)).await??;
+    file.replace_contents_future(
+        contents,
+        None,
+        false,
+        gio::FileCreateFlags::REPLACE_DESTINATION,
+    )
+    .await
+    .map_err(|(_, e)| e)?;
+    Ok(())
+}
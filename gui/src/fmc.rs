@@ -0,0 +1,202 @@
+use crate::prelude::*;
+use crate::timer::render_moveseq;
+use cubestruct::{FmcSolution, Niss};
+use std::time::{Duration, Instant};
+
+/// Time allowed for a fewest moves attempt
+const FMC_TIME_LIMIT: Duration = Duration::from_secs(60 * 60);
+
+/// Fewest moves practice: the solution is typed in and checked against the scramble
+#[derive(Debug)]
+pub struct Fmc {
+    tx: EventSender,
+    root: gtk::Box,
+    countdown_label: gtk::Label,
+    start_button: gtk::Button,
+    solution_entry: gtk::Entry,
+    submit_button: gtk::Button,
+    inverse_button: gtk::ToggleButton,
+    /// The position on the inverse scramble, while `inverse_button` is active
+    inverse_label: gtk::Label,
+    status_label: gtk::Label,
+    last_label: gtk::Label,
+    best_label: gtk::Label,
+    mo3_label: gtk::Label,
+    deadline: Option<Instant>,
+    tick_timeout: Option<glib::SourceId>,
+    /// The entered solution, if it solves the current scramble
+    solution: Option<FmcSolution>,
+    /// Move counts of the submitted solutions
+    results: Vec<usize>,
+}
+
+impl Fmc {
+    pub fn new(tx: EventSender) -> Self {
+        let builder = gtk::Builder::from_resource("/io/github/flying_toast/PuzzleTime/fmc.ui");
+        let start_button: gtk::Button = builder.object("start_button").unwrap();
+        let solution_entry: gtk::Entry = builder.object("solution_entry").unwrap();
+        let submit_button: gtk::Button = builder.object("submit_button").unwrap();
+        let inverse_button: gtk::ToggleButton = builder.object("inverse_button").unwrap();
+
+        let tx2 = tx.clone();
+        start_button.connect_clicked(move |_| send_evt(tx2.clone(), Event::FmcStart));
+        let tx2 = tx.clone();
+        solution_entry.connect_changed(move |_| send_evt(tx2.clone(), Event::FmcSolutionChanged));
+        let tx2 = tx.clone();
+        solution_entry.connect_activate(move |_| send_evt(tx2.clone(), Event::FmcSubmit));
+        let tx2 = tx.clone();
+        submit_button.connect_clicked(move |_| send_evt(tx2.clone(), Event::FmcSubmit));
+        let tx2 = tx.clone();
+        inverse_button.connect_toggled(move |_| send_evt(tx2.clone(), Event::FmcSolutionChanged));
+
+        Self {
+            tx,
+            root: builder.object("root").unwrap(),
+            countdown_label: builder.object("countdown_label").unwrap(),
+            start_button,
+            solution_entry,
+            submit_button,
+            inverse_button,
+            inverse_label: builder.object("inverse_label").unwrap(),
+            status_label: builder.object("status_label").unwrap(),
+            last_label: builder.object("last_label").unwrap(),
+            best_label: builder.object("best_label").unwrap(),
+            mo3_label: builder.object("mo3_label").unwrap(),
+            deadline: None,
+            tick_timeout: None,
+            solution: None,
+            results: Vec::new(),
+        }
+    }
+
+    pub fn widget(&self) -> &gtk::Box {
+        &self.root
+    }
+
+    pub fn set_visible(&self, visible: bool) {
+        self.root.set_visible(visible);
+    }
+
+    /// Start the 60 minute countdown
+    pub fn start(&mut self) {
+        self.stop_countdown();
+        self.deadline = Some(Instant::now() + FMC_TIME_LIMIT);
+        self.start_button.set_label(&gettext("Restart"));
+        let tx = self.tx.clone();
+        self.tick_timeout = Some(glib::timeout_add(Duration::from_secs(1), move || {
+            send_evt(tx.clone(), Event::FmcTick);
+            glib::ControlFlow::Continue
+        }));
+        self.tick();
+    }
+
+    fn stop_countdown(&mut self) {
+        if let Some(timeout) = self.tick_timeout.take() {
+            timeout.remove();
+        }
+        self.deadline = None;
+        self.start_button.set_label(&gettext("Start"));
+    }
+
+    /// Update the countdown. Returns `true` if time just ran out.
+    pub fn tick(&mut self) -> bool {
+        let Some(deadline) = self.deadline else {
+            return false;
+        };
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let secs = remaining.as_secs_f64().ceil() as u64;
+        self.countdown_label
+            .set_label(&format!("{}:{:02}", secs / 60, secs % 60));
+
+        if remaining.is_zero() {
+            self.stop_countdown();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Check the entered solution against the scramble
+    pub fn validate(&mut self, scramble: &[cubestruct::Move]) {
+        self.solution = None;
+        self.status_label.set_css_classes(&["dim-label"]);
+        let text = self.solution_entry.text();
+        // the moves so far, unless they can't be read
+        let mut niss = None;
+
+        if text.trim().is_empty() {
+            self.status_label.set_label("");
+            niss = Some(Niss::new(scramble));
+        } else {
+            let parsed = text.parse::<FmcSolution>();
+            niss = parsed.as_ref().ok().map(|solution| solution.niss(scramble));
+            match parsed {
+                Err(e) => {
+                    self.status_label.set_label(&e.to_string());
+                    self.status_label.set_css_classes(&["error"]);
+                }
+                Ok(solution) if solution.solves(scramble) => {
+                    self.status_label.set_label(&ngettext_f(
+                        "Solved in {count} move",
+                        "Solved in {count} moves",
+                        solution.move_count() as u32,
+                        &[("count", &solution.move_count().to_string())],
+                    ));
+                    self.status_label.set_css_classes(&["success"]);
+                    self.solution = Some(solution);
+                }
+                Ok(solution) => {
+                    self.status_label.set_label(&ngettext_f(
+                        "{count} move, doesn't solve the scramble yet",
+                        "{count} moves, doesn't solve the scramble yet",
+                        solution.move_count() as u32,
+                        &[("count", &solution.move_count().to_string())],
+                    ));
+                }
+            }
+        }
+
+        self.submit_button.set_sensitive(self.solution.is_some());
+        self.show_inverse(niss);
+    }
+
+    /// Show where the moves so far leave the inverse scramble, if asked to
+    fn show_inverse(&self, niss: Option<Niss>) {
+        let setup = niss
+            .filter(|_| self.inverse_button.is_active())
+            .map(|mut niss| {
+                niss.switch();
+                niss.setup()
+            })
+            .filter(|setup| !setup.is_empty());
+        match setup {
+            Some(setup) => {
+                self.inverse_label.set_label(&gettext_f(
+                    "Inverse scramble: {moves}",
+                    &[("moves", &render_moveseq(&setup, false))],
+                ));
+                self.inverse_label.set_visible(true);
+            }
+            None => self.inverse_label.set_visible(false),
+        }
+    }
+
+    /// Record the entered solution if it's valid. Returns its move count.
+    pub fn submit(&mut self) -> Option<usize> {
+        let move_count = self.solution.take()?.move_count();
+        self.results.push(move_count);
+        self.stop_countdown();
+        self.countdown_label.set_label("60:00");
+        self.solution_entry.set_text("");
+
+        self.last_label.set_label(&move_count.to_string());
+        self.best_label
+            .set_label(&self.results.iter().min().unwrap().to_string());
+        if let [.., a, b, c] = self.results[..] {
+            self.mo3_label
+                .set_label(&format!("{:.2}", (a + b + c) as f64 / 3.0));
+        }
+
+        Some(move_count)
+    }
+}
@@ -0,0 +1,261 @@
+//! Daily goals and practice streaks. Progress is kept in the settings so that it carries
+//! over between sessions.
+use crate::prelude::*;
+use crate::settings::settings;
+use crate::stats::{render_average, Average, NextSolve};
+use crate::timer::{render_time, Precision};
+use std::time::Duration;
+
+/// Practice progress, as stored in the settings
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct Practice {
+    /// Last day with a solve, as `YYYY-MM-DD`
+    day: String,
+    /// Number of solves on `day`
+    solves: u32,
+    /// Consecutive days with a solve, up to and including `day`
+    streak: u32,
+    best_streak: u32,
+}
+
+impl Practice {
+    fn load() -> Self {
+        let settings = settings();
+        Self {
+            day: settings.string("practice-day").into(),
+            solves: settings.uint("practice-day-solves"),
+            streak: settings.uint("practice-streak"),
+            best_streak: settings.uint("best-practice-streak"),
+        }
+    }
+
+    fn save(&self) {
+        let settings = settings();
+        settings.set_string("practice-day", &self.day).unwrap();
+        settings
+            .set_uint("practice-day-solves", self.solves)
+            .unwrap();
+        settings.set_uint("practice-streak", self.streak).unwrap();
+        settings
+            .set_uint("best-practice-streak", self.best_streak)
+            .unwrap();
+    }
+
+    fn record_solve(&mut self, today: &str, yesterday: &str) {
+        if self.day == today {
+            self.solves += 1;
+            return;
+        }
+        self.streak = if self.day == yesterday {
+            self.streak + 1
+        } else {
+            1
+        };
+        self.best_streak = self.best_streak.max(self.streak);
+        self.day = today.to_string();
+        self.solves = 1;
+    }
+
+    /// Returns `(solves today, current streak)`. The streak is 0 if it's been broken.
+    fn current(&self, today: &str, yesterday: &str) -> (u32, u32) {
+        let solves = if self.day == today { self.solves } else { 0 };
+        let streak = if self.day == today || self.day == yesterday {
+            self.streak
+        } else {
+            0
+        };
+        (solves, streak)
+    }
+}
+
+/// Today and yesterday, as `YYYY-MM-DD`
+pub fn today_and_yesterday() -> (String, String) {
+    let now = glib::DateTime::now_local().unwrap();
+    let day = |date: &glib::DateTime| date.format("%F").unwrap().to_string();
+    (day(&now), day(&now.add_days(-1).unwrap()))
+}
+
+/// Returns `(solves today, current practice streak)`
+pub fn today() -> (u32, u32) {
+    let (today, yesterday) = today_and_yesterday();
+    Practice::load().current(&today, &yesterday)
+}
+
+/// Progress towards the daily solve count and target average, shown in the stats sidebar
+#[derive(Debug)]
+pub struct Goals {
+    root: gtk::Box,
+    solves_box: gtk::Box,
+    solves_label: gtk::Label,
+    solves_bar: gtk::LevelBar,
+    average_label: gtk::Label,
+    /// What the next solve needs to be for the current ao5 and ao12 to reach the goal
+    next_label: gtk::Label,
+    streak_label: gtk::Label,
+    /// Smart cube turns made today
+    turns_label: gtk::Label,
+}
+
+impl Goals {
+    pub fn new(tx: EventSender) -> Self {
+        let builder = gtk::Builder::from_resource("/io/github/flying_toast/PuzzleTime/goals.ui");
+
+        for key in ["goal-solves", "goal-average"] {
+            let tx = tx.clone();
+            settings().connect_changed(Some(key), move |_, _| {
+                send_evt(tx.clone(), StatsEvent::Changed);
+            });
+        }
+
+        Self {
+            root: builder.object("root").unwrap(),
+            solves_box: builder.object("solves_box").unwrap(),
+            solves_label: builder.object("solves_label").unwrap(),
+            solves_bar: builder.object("solves_bar").unwrap(),
+            average_label: builder.object("average_label").unwrap(),
+            next_label: builder.object("next_label").unwrap(),
+            streak_label: builder.object("streak_label").unwrap(),
+            turns_label: builder.object("turns_label").unwrap(),
+        }
+    }
+
+    pub fn widget(&self) -> &gtk::Box {
+        &self.root
+    }
+
+    /// Count a new solve towards today's goal and the streak
+    pub fn record_solve(&self) {
+        let (today, yesterday) = today_and_yesterday();
+        let mut practice = Practice::load();
+        practice.record_solve(&today, &yesterday);
+        practice.save();
+    }
+
+    /// `average` is the session's current aggregate (if there are enough results), and
+    /// its title. `next_solves` is what the next solve needs to be for averages of each
+    /// size to reach the goal average.
+    pub fn update(&self, average: Option<Average>, title: &str, next_solves: &[(u32, NextSolve)]) {
+        let settings = settings();
+        let (today, yesterday) = today_and_yesterday();
+        let practice = Practice::load();
+        let (solves, streak) = practice.current(&today, &yesterday);
+
+        let solves_goal = settings.uint("goal-solves");
+        self.solves_box.set_visible(solves_goal > 0);
+        if solves_goal > 0 {
+            self.solves_label.set_label(&ngettext_f(
+                "{solves}/{goal} solve today",
+                "{solves}/{goal} solves today",
+                solves_goal,
+                &[
+                    ("solves", &solves.to_string()),
+                    ("goal", &solves_goal.to_string()),
+                ],
+            ));
+            self.solves_bar.set_max_value(solves_goal.into());
+            self.solves_bar.set_value(solves.min(solves_goal).into());
+        }
+
+        let average_goal = settings.double("goal-average");
+        self.average_label.set_visible(average_goal > 0.0);
+        if average_goal > 0.0 {
+            let goal = Duration::from_secs_f64(average_goal);
+            let current = average.map_or("-".into(), render_average);
+            self.average_label.set_label(&gettext_f(
+                "{title}: {current} (goal {goal})",
+                &[
+                    ("title", title),
+                    ("current", &current),
+                    ("goal", &render_time(&goal, Precision::Hundredths)),
+                ],
+            ));
+            if matches!(average, Some(Average::Some(time)) if time <= goal) {
+                self.average_label.add_css_class("success");
+            } else {
+                self.average_label.remove_css_class("success");
+            }
+        }
+
+        let next_lines = next_solves
+            .iter()
+            .map(|&(n, next)| {
+                let n = n.to_string();
+                match next {
+                    NextSolve::Any => {
+                        gettext_f("ao{n}: goal reached even with a DNF", &[("n", &n)])
+                    }
+                    NextSolve::Under(time) => gettext_f(
+                        "ao{n}: next solve needs {time} or better",
+                        &[
+                            ("n", &n),
+                            ("time", &render_time(&time, Precision::Hundredths)),
+                        ],
+                    ),
+                    NextSolve::Impossible => {
+                        gettext_f("ao{n}: goal out of reach on the next solve", &[("n", &n)])
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+        self.next_label.set_visible(!next_lines.is_empty());
+        self.next_label.set_label(&next_lines.join("\n"));
+
+        let current = match streak {
+            0 => gettext("No practice streak"),
+            _ => ngettext_f(
+                "{streak} day streak",
+                "{streak} day streak",
+                streak,
+                &[("streak", &streak.to_string())],
+            ),
+        };
+        self.streak_label.set_label(&ngettext_f(
+            "{current} (best {best} day)",
+            "{current} (best {best} days)",
+            practice.best_streak,
+            &[
+                ("current", &current),
+                ("best", &practice.best_streak.to_string()),
+            ],
+        ));
+    }
+
+    /// Show how many turns of smart cubes have been made today, if any
+    pub fn set_turns_today(&self, turns: u32) {
+        self.turns_label.set_visible(turns > 0);
+        self.turns_label.set_label(&ngettext_f(
+            "{turns} turn today",
+            "{turns} turns today",
+            turns,
+            &[("turns", &turns.to_string())],
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streaks() {
+        let mut practice = Practice {
+            day: String::new(),
+            solves: 0,
+            streak: 0,
+            best_streak: 0,
+        };
+        practice.record_solve("2024-07-01", "2024-06-30");
+        practice.record_solve("2024-07-01", "2024-06-30");
+        assert_eq!(practice.current("2024-07-01", "2024-06-30"), (2, 1));
+
+        practice.record_solve("2024-07-02", "2024-07-01");
+        assert_eq!(practice.current("2024-07-02", "2024-07-01"), (1, 2));
+        // the streak isn't broken until a whole day is missed
+        assert_eq!(practice.current("2024-07-03", "2024-07-02"), (0, 2));
+        assert_eq!(practice.current("2024-07-04", "2024-07-03"), (0, 0));
+
+        practice.record_solve("2024-07-04", "2024-07-03");
+        assert_eq!(practice.current("2024-07-04", "2024-07-03"), (1, 1));
+        assert_eq!(practice.best_streak, 2);
+    }
+}
@@ -0,0 +1,170 @@
+//! Working out a hold time (how long the start key has to be held before letting go starts
+//! the timer) that suits the user, from a few of their holds. A hold time that's longer than
+//! someone's natural hold means false starts; a much shorter one means accidental starts.
+use crate::keybindings::{event_instant, is_timer_key};
+use crate::prelude::*;
+use crate::settings::settings;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Number of holds measured
+const HOLDS: usize = 5;
+
+/// Holds shorter than this are taken as slips rather than attempts, and not counted
+const MIN_HOLD: Duration = Duration::from_millis(60);
+
+/// Suggestions are rounded down to a multiple of this
+const SUGGESTION_STEP: Duration = Duration::from_millis(50);
+
+const MIN_SUGGESTION: Duration = Duration::from_millis(100);
+const MAX_SUGGESTION: Duration = Duration::from_millis(1000);
+
+/// Shows a dialog that measures the user's holds of the start key (or of a touch), and
+/// offers to set the hold time from them
+pub fn show(parent: &impl IsA<gtk::Widget>) {
+    let builder = gtk::Builder::from_resource(
+        "/io/github/flying_toast/PuzzleTime/hold-calibration-dialog.ui",
+    );
+    let root: adw::Dialog = builder.object("root").unwrap();
+    let stack: gtk::Stack = builder.object("stack").unwrap();
+    let measure_page: adw::StatusPage = builder.object("measure_page").unwrap();
+    let result_page: adw::StatusPage = builder.object("result_page").unwrap();
+    let pad: gtk::Widget = builder.object("pad").unwrap();
+    let use_suggested: gtk::Button = builder.object("use_suggested").unwrap();
+    let try_again: gtk::Button = builder.object("try_again").unwrap();
+
+    let holds = Rc::new(RefCell::new(Vec::new()));
+    // when the current hold started
+    let held_since = Rc::new(Cell::<Option<Instant>>::None);
+    let suggested = Rc::new(Cell::new(Duration::ZERO));
+
+    measure_page.set_description(Some(&progress_text(0)));
+
+    // returns whether the hold is being measured, which it isn't once all of them are done
+    let hold_started = {
+        let (holds, held_since) = (holds.clone(), held_since.clone());
+        move |at: Instant| {
+            if holds.borrow().len() >= HOLDS {
+                return false;
+            }
+            // key repeats while held
+            if held_since.get().is_none() {
+                held_since.set(Some(at));
+            }
+            true
+        }
+    };
+    let hold_ended = {
+        let (stack, measure_page, result_page) =
+            (stack.clone(), measure_page.clone(), result_page.clone());
+        let (holds, suggested) = (holds.clone(), suggested.clone());
+        move |at: Instant| {
+            let Some(since) = held_since.take() else {
+                return;
+            };
+            let hold = at.saturating_duration_since(since);
+            if hold < MIN_HOLD {
+                return;
+            }
+            let mut holds = holds.borrow_mut();
+            holds.push(hold);
+            measure_page.set_description(Some(&progress_text(holds.len())));
+            if holds.len() < HOLDS {
+                return;
+            }
+            suggested.set(suggestion(&holds));
+            let ms = |duration: Duration| duration.as_millis().to_string();
+            result_page.set_title(&gettext_f(
+                "Suggested Hold Time: {ms} ms",
+                &[("ms", &ms(suggested.get()))],
+            ));
+            result_page.set_description(Some(&gettext_f(
+                "Your holds were {shortest}–{longest} ms. A hold time a little under your shortest hold won't cut off your starts, but a quick tap still won't start the timer.",
+                &[
+                    ("shortest", &ms(*holds.iter().min().unwrap())),
+                    ("longest", &ms(*holds.iter().max().unwrap())),
+                ],
+            )));
+            stack.set_visible_child(&result_page);
+        }
+    };
+
+    let keys = gtk::EventControllerKey::new();
+    keys.set_propagation_phase(gtk::PropagationPhase::Capture);
+    let hold_started2 = hold_started.clone();
+    keys.connect_key_pressed(move |controller, key, _, _| {
+        if is_timer_key(key) && hold_started2(event_instant(controller)) {
+            glib::Propagation::Stop
+        } else {
+            glib::Propagation::Proceed
+        }
+    });
+    let hold_ended2 = hold_ended.clone();
+    keys.connect_key_released(move |controller, key, _, _| {
+        if is_timer_key(key) {
+            hold_ended2(event_instant(controller));
+        }
+    });
+    root.add_controller(keys);
+
+    let touch = gtk::GestureDrag::new();
+    touch.connect_drag_begin(move |gesture, _, _| {
+        gesture.set_state(gtk::EventSequenceState::Claimed);
+        hold_started(event_instant(gesture));
+    });
+    touch.connect_drag_end(move |gesture, _, _| hold_ended(event_instant(gesture)));
+    pad.add_controller(touch);
+
+    let root2 = root.clone();
+    let suggested2 = suggested.clone();
+    use_suggested.connect_clicked(move |_| {
+        let ms = suggested2.get().as_millis() as u32;
+        settings().set_uint("hold-duration", ms).unwrap();
+        root2.close();
+    });
+    try_again.connect_clicked(move |_| {
+        holds.borrow_mut().clear();
+        measure_page.set_description(Some(&progress_text(0)));
+        stack.set_visible_child(&measure_page);
+    });
+
+    root.present(parent);
+}
+
+fn progress_text(done: usize) -> String {
+    gettext_f(
+        "Hold the timer key or the area below as you would to start a solve, then let go. {done} of {total} done.",
+        &[("done", &done.to_string()), ("total", &HOLDS.to_string())],
+    )
+}
+
+/// A hold time three quarters of the shortest of `holds`, rounded down to a
+/// [`SUGGESTION_STEP`], within [`MIN_SUGGESTION`] and [`MAX_SUGGESTION`]
+fn suggestion(holds: &[Duration]) -> Duration {
+    let shortest = holds.iter().min().copied().unwrap_or(MAX_SUGGESTION);
+    let steps = (shortest * 3 / 4).as_millis() / SUGGESTION_STEP.as_millis();
+    (SUGGESTION_STEP * steps as u32).clamp(MIN_SUGGESTION, MAX_SUGGESTION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suggestions() {
+        let ms = |ms: &[u64]| {
+            ms.iter()
+                .map(|&ms| Duration::from_millis(ms))
+                .collect::<Vec<_>>()
+        };
+        // 3/4 of 380 is 285
+        assert_eq!(
+            suggestion(&ms(&[420, 380, 510, 455, 400])),
+            Duration::from_millis(250)
+        );
+        // tappers and deliberate holders stay within bounds
+        assert_eq!(suggestion(&ms(&[90, 110, 95])), MIN_SUGGESTION);
+        assert_eq!(suggestion(&ms(&[1_800, 2_100])), MAX_SUGGESTION);
+    }
+}
@@ -0,0 +1,59 @@
+//! HTTP requests through libsoup, which runs them on the main loop
+use crate::prelude::*;
+use anyhow::Context;
+use glib::translate::IntoGlib;
+
+pub struct Response {
+    pub status: u32,
+    pub etag: Option<String>,
+    pub body: Vec<u8>,
+}
+
+thread_local! {
+    /// Shared so that connections to the same server are reused
+    static SESSION: soup::Session = {
+        let session = soup::Session::new();
+        session.set_user_agent("PuzzleTime");
+        session
+    };
+}
+
+/// Make an HTTP request and wait for the whole response
+pub async fn request(
+    url: &str,
+    method: &str,
+    headers: &[(&str, String)],
+    body: &[u8],
+) -> anyhow::Result<Response> {
+    let uri = glib::Uri::parse(url, glib::UriFlags::NONE).context(gettext("invalid URL"))?;
+    let message = soup::Message::from_uri(method, &uri);
+    if let Some(request_headers) = message.request_headers() {
+        for (name, value) in headers {
+            request_headers.append(name, value);
+        }
+    }
+    if !body.is_empty() {
+        message.set_request_body_from_bytes(None, Some(&glib::Bytes::from(body)));
+    }
+
+    let session = SESSION.with(Clone::clone);
+    let body = session
+        .send_and_read_future(&message, glib::Priority::DEFAULT)
+        .await
+        .with_context(|| {
+            gettext_f(
+                "couldn't connect to {host}",
+                &[("host", &uri.host().unwrap_or_default())],
+            )
+        })?;
+    let etag = message
+        .response_headers()
+        .and_then(|headers| headers.one("ETag"))
+        .map(Into::into);
+
+    Ok(Response {
+        status: message.status().into_glib() as u32,
+        etag,
+        body: body.to_vec(),
+    })
+}
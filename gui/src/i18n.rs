@@ -0,0 +1,93 @@
+//! Translations of the user-visible strings, and formatting numbers for the user's locale.
+//! Strings in the `.blp` files are marked with `_()`, and the builder translates them with
+//! the text domain set up here.
+pub use gettextrs::{gettext, ngettext};
+use std::ffi::CStr;
+use std::sync::OnceLock;
+
+pub const GETTEXT_PACKAGE: &str = "puzzletime";
+
+/// Where the compiled translations are installed. Can be overridden at build time for
+/// installs outside of the Flatpak.
+const LOCALEDIR: &str = match option_env!("PUZZLETIME_LOCALEDIR") {
+    Some(dir) => dir,
+    None => "/app/share/locale",
+};
+
+/// Set up gettext to use the app's translations. The locale itself is set by GTK when it's
+/// initialized.
+pub fn init() {
+    if let Err(e) = gettextrs::bindtextdomain(GETTEXT_PACKAGE, LOCALEDIR)
+        .and_then(|_| gettextrs::bind_textdomain_codeset(GETTEXT_PACKAGE, "UTF-8"))
+        .and_then(|_| gettextrs::textdomain(GETTEXT_PACKAGE))
+    {
+        tracing::warn!("Failed to set up translations: {e}");
+    }
+}
+
+/// Mark a string that has to be `'static` for translation, so that it's extracted along with
+/// the others. It's translated with [`gettext`] where it's shown.
+macro_rules! gettext_noop {
+    ($msgid:literal) => {
+        $msgid
+    };
+}
+pub(crate) use gettext_noop;
+
+/// Replace each `{name}` in `translated` with its value in `args`. The placeholders are named
+/// so that translations can put them in a different order.
+fn fill(mut translated: String, args: &[(&str, &str)]) -> String {
+    for (name, value) in args {
+        translated = translated.replace(&format!("{{{name}}}"), value);
+    }
+    translated
+}
+
+/// [`gettext`] with `{name}` placeholders, filled in from `args`
+pub fn gettext_f(msgid: &str, args: &[(&str, &str)]) -> String {
+    fill(gettext(msgid), args)
+}
+
+/// [`ngettext`] with `{name}` placeholders, filled in from `args`
+pub fn ngettext_f(msgid: &str, msgid_plural: &str, n: u32, args: &[(&str, &str)]) -> String {
+    fill(ngettext(msgid, msgid_plural, n), args)
+}
+
+/// The locale's decimal separator, e.g. `,` in German
+pub fn decimal_separator() -> &'static str {
+    static SEPARATOR: OnceLock<String> = OnceLock::new();
+    SEPARATOR.get_or_init(|| {
+        // SAFETY: `localeconv` returns a valid struct, and the locale isn't changed after
+        // GTK sets it up
+        let separator = unsafe { CStr::from_ptr((*libc::localeconv()).decimal_point) };
+        match separator.to_str() {
+            Ok(separator) if !separator.is_empty() => separator.into(),
+            _ => ".".into(),
+        }
+    })
+}
+
+/// `value` with `digits` digits after the decimal separator
+pub fn format_decimal(value: f64, digits: usize) -> String {
+    format!("{value:.digits$}").replace('.', decimal_separator())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders() {
+        assert_eq!(
+            fill(
+                "{count} of {total} solved".into(),
+                &[("total", "5"), ("count", "3")]
+            ),
+            "3 of 5 solved"
+        );
+        assert_eq!(
+            fill("No placeholders".into(), &[("x", "1")]),
+            "No placeholders"
+        );
+    }
+}
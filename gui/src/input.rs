@@ -0,0 +1,233 @@
+//! Where the timer's start/stop input comes from. Each kind of device is an [`InputSource`]
+//! that sends [`TimerEvent::Trigger`]s, and [`InputArbiter`] decides which of them count when
+//! more than one is in use, so supporting a new device only needs a source of its own.
+use crate::keybindings;
+use crate::prelude::*;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// The kinds of device that can start and stop the timer
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum InputKind {
+    Keyboard,
+    Touch,
+    /// Stops solves in modes that know when the cube is done, e.g. the cross trainer
+    Smartcube,
+}
+
+impl InputKind {
+    /// A solve can only be stopped or split by input of at least the priority of the one
+    /// that started it. A device that times solves itself (like a Stackmat) should go above
+    /// these, so that a stray key can't stop it.
+    fn priority(self) -> u8 {
+        match self {
+            InputKind::Keyboard | InputKind::Touch => 0,
+            InputKind::Smartcube => 1,
+        }
+    }
+}
+
+/// What an input source reports, with when it happened (see
+/// [`keybindings::event_instant()`])
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Trigger {
+    /// Pressed, like the start key going down (or repeating while it's held)
+    Down(Instant),
+    /// Let go
+    Up(Instant),
+    /// Stop the running solve, e.g. another key was pressed or the cube was solved
+    Stop(Instant),
+}
+
+/// A device that can start and stop the timer
+pub trait InputSource {
+    fn kind(&self) -> InputKind;
+
+    /// Start sending the device's input as [`TimerEvent::Trigger`]s with `tx`
+    fn connect(&self, tx: EventSender);
+}
+
+/// The configured timer key (see [`keybindings::TriggerMode`])
+#[derive(Debug)]
+pub struct KeyboardInput {
+    window: adw::ApplicationWindow,
+}
+
+impl KeyboardInput {
+    pub fn new(window: &adw::ApplicationWindow) -> Self {
+        Self {
+            window: window.clone(),
+        }
+    }
+}
+
+impl InputSource for KeyboardInput {
+    fn kind(&self) -> InputKind {
+        InputKind::Keyboard
+    }
+
+    fn connect(&self, tx: EventSender) {
+        self.window
+            .add_controller(keybindings::timer_key_controller(tx, self.kind()));
+    }
+}
+
+/// Touching and holding anywhere on `widget`, which works like holding the timer key
+#[derive(Debug)]
+pub struct TouchInput {
+    widget: gtk::Widget,
+    /// Set on the first touch, so that touch controls can be shown
+    used: Rc<Cell<bool>>,
+}
+
+impl TouchInput {
+    pub fn new(widget: &impl IsA<gtk::Widget>, used: Rc<Cell<bool>>) -> Self {
+        Self {
+            widget: widget.clone().upcast(),
+            used,
+        }
+    }
+}
+
+impl InputSource for TouchInput {
+    fn kind(&self) -> InputKind {
+        InputKind::Touch
+    }
+
+    fn connect(&self, tx: EventSender) {
+        let kind = self.kind();
+        let gesture = gtk::GestureDrag::new();
+        gesture.set_touch_only(true);
+        let tx2 = tx.clone();
+        let used = self.used.clone();
+        gesture.connect_drag_begin(move |gesture, _, _| {
+            // so the touch isn't also taken as e.g. a swipe
+            gesture.set_state(gtk::EventSequenceState::Claimed);
+            used.set(true);
+            let at = keybindings::event_instant(gesture);
+            send_evt(tx2.clone(), TimerEvent::Trigger(kind, Trigger::Down(at)));
+        });
+        // also emitted if the touch is cancelled, so the timer is never left armed
+        gesture.connect_drag_end(move |gesture, _, _| {
+            let at = keybindings::event_instant(gesture);
+            send_evt(tx.clone(), TimerEvent::Trigger(kind, Trigger::Up(at)));
+        });
+        self.widget.add_controller(gesture);
+    }
+}
+
+/// A press that's held for longer than this no longer keeps other sources out, in case
+/// its release was missed (e.g. the window lost focus while a key was down)
+const STALE_HOLD: Duration = Duration::from_secs(30);
+
+/// Decides which input counts when several sources are used:
+/// - While one source is pressed, presses and releases of the others are ignored, so e.g. a
+///   touch can't release a hold of the keyboard.
+/// - A running solve can only be stopped or split by a source of at least the priority of
+///   the one that started it (see [`InputKind::priority`]).
+#[derive(Debug, Default)]
+pub struct InputArbiter {
+    /// The source that's pressed, and when it was first pressed
+    holding: Option<(InputKind, Instant)>,
+    /// The source of the last input that was accepted
+    last: Option<InputKind>,
+    /// The source that started the running solve
+    started_by: Option<InputKind>,
+}
+
+impl InputArbiter {
+    /// Whether `trigger` from `kind` should be acted on
+    pub fn accepts(&mut self, kind: InputKind, trigger: Trigger) -> bool {
+        let accepted = match trigger {
+            Trigger::Down(at) => match self.holding {
+                Some((holder, since))
+                    if holder != kind && at.saturating_duration_since(since) < STALE_HOLD =>
+                {
+                    false
+                }
+                // key repeats
+                Some((holder, _)) if holder == kind => true,
+                _ if !self.can_interrupt(kind) => false,
+                _ => {
+                    self.holding = Some((kind, at));
+                    true
+                }
+            },
+            Trigger::Up(_) => {
+                let holding = self.holding.is_some_and(|(holder, _)| holder == kind);
+                if holding {
+                    self.holding = None;
+                }
+                holding
+            }
+            Trigger::Stop(_) => self.can_interrupt(kind),
+        };
+        if accepted {
+            self.last = Some(kind);
+        }
+        accepted
+    }
+
+    fn can_interrupt(&self, kind: InputKind) -> bool {
+        self.started_by
+            .map_or(true, |starter| kind.priority() >= starter.priority())
+    }
+
+    /// Call when a solve starts, which is from the last input that was accepted
+    pub fn solve_started(&mut self) {
+        self.started_by = self.last;
+    }
+
+    pub fn solve_stopped(&mut self) {
+        self.started_by = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn holds_keep_other_sources_out() {
+        let at = Instant::now();
+        let mut arbiter = InputArbiter::default();
+        assert!(arbiter.accepts(InputKind::Keyboard, Trigger::Down(at)));
+        assert!(!arbiter.accepts(InputKind::Touch, Trigger::Down(at)));
+        assert!(!arbiter.accepts(InputKind::Touch, Trigger::Up(at)));
+        // key repeats
+        assert!(arbiter.accepts(InputKind::Keyboard, Trigger::Down(at)));
+        assert!(arbiter.accepts(InputKind::Keyboard, Trigger::Up(at)));
+        arbiter.solve_started();
+
+        // a touch can stop what the keyboard started
+        assert!(arbiter.accepts(InputKind::Touch, Trigger::Down(at)));
+        arbiter.solve_stopped();
+        assert!(!arbiter.accepts(InputKind::Keyboard, Trigger::Up(at)));
+        assert!(arbiter.accepts(InputKind::Touch, Trigger::Up(at)));
+
+        // a release that never came stops blocking eventually
+        assert!(arbiter.accepts(InputKind::Keyboard, Trigger::Down(at)));
+        assert!(!arbiter.accepts(InputKind::Touch, Trigger::Down(at + STALE_HOLD / 2)));
+        assert!(arbiter.accepts(InputKind::Touch, Trigger::Down(at + STALE_HOLD)));
+    }
+
+    #[test]
+    fn priorities() {
+        let at = Instant::now();
+        let mut arbiter = InputArbiter::default();
+        arbiter.accepts(InputKind::Keyboard, Trigger::Down(at));
+        arbiter.accepts(InputKind::Keyboard, Trigger::Up(at));
+        arbiter.solve_started();
+        assert!(arbiter.accepts(InputKind::Smartcube, Trigger::Stop(at)));
+        arbiter.solve_stopped();
+
+        // nothing lower than what started a solve can stop it
+        arbiter.started_by = Some(InputKind::Smartcube);
+        assert!(!arbiter.accepts(InputKind::Keyboard, Trigger::Stop(at)));
+        assert!(!arbiter.accepts(InputKind::Touch, Trigger::Down(at)));
+        assert!(arbiter.accepts(InputKind::Smartcube, Trigger::Stop(at)));
+        arbiter.solve_stopped();
+        assert!(arbiter.accepts(InputKind::Keyboard, Trigger::Stop(at)));
+    }
+}
@@ -0,0 +1,380 @@
+use crate::i18n::gettext_noop;
+use crate::input::{InputKind, Trigger};
+use crate::prelude::*;
+use crate::settings::settings;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Event times further in the past than this are assumed to be from a different clock (e.g.
+/// an X server's), so aren't used
+const MAX_EVENT_AGE: Duration = Duration::from_secs(1);
+
+/// How the timer is started/stopped from the keyboard
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TriggerMode {
+    /// A single key (spacebar by default)
+    Key,
+    /// Holding both shift keys at the same time, like the pads of a stackmat
+    BothShift,
+    /// Any key that isn't a modifier or part of a shortcut
+    AnyKey,
+}
+
+impl TriggerMode {
+    /// In the same order as the `trigger_row` dropdown
+    const ALL: [Self; 3] = [Self::Key, Self::BothShift, Self::AnyKey];
+
+    pub fn current() -> Self {
+        match settings().string("timer-trigger").as_str() {
+            "both-shift" => Self::BothShift,
+            "any-key" => Self::AnyKey,
+            _ => Self::Key,
+        }
+    }
+
+    fn setting_value(self) -> &'static str {
+        match self {
+            Self::Key => "key",
+            Self::BothShift => "both-shift",
+            Self::AnyKey => "any-key",
+        }
+    }
+}
+
+/// An app action whose accelerator is user-configurable
+#[derive(Debug)]
+pub struct Shortcut {
+    pub action: &'static str,
+    pub settings_key: &'static str,
+    pub title: &'static str,
+}
+
+pub static SHORTCUTS: [Shortcut; 6] = [
+    Shortcut {
+        action: "app.plus2-last",
+        settings_key: "shortcut-plus2",
+        title: gettext_noop!("Toggle +2 on Last Result"),
+    },
+    Shortcut {
+        action: "app.dnf-last",
+        settings_key: "shortcut-dnf",
+        title: gettext_noop!("Toggle DNF on Last Result"),
+    },
+    Shortcut {
+        action: "app.delete-last",
+        settings_key: "shortcut-delete-last",
+        title: gettext_noop!("Delete Last Result"),
+    },
+    Shortcut {
+        action: "app.new-scramble",
+        settings_key: "shortcut-new-scramble",
+        title: gettext_noop!("Next Scramble"),
+    },
+    Shortcut {
+        action: "app.previous-scramble",
+        settings_key: "shortcut-previous-scramble",
+        title: gettext_noop!("Previous Scramble"),
+    },
+    Shortcut {
+        action: "app.focus-mode",
+        settings_key: "shortcut-focus-mode",
+        title: gettext_noop!("Toggle Focus Mode"),
+    },
+];
+
+/// Set the accels of all the [`SHORTCUTS`], and keep them in sync with the settings
+pub fn install_accels(app: &adw::Application) {
+    let settings = settings();
+    for shortcut in &SHORTCUTS {
+        set_accel(app, shortcut);
+        let app = app.clone();
+        settings.connect_changed(Some(shortcut.settings_key), move |_, _| {
+            set_accel(&app, shortcut);
+        });
+    }
+}
+
+/// Human-readable label of the accel currently assigned to `action`, if any
+pub fn accel_label(action: &str) -> Option<String> {
+    let shortcut = SHORTCUTS.iter().find(|s| s.action == action)?;
+    let (key, mods) = gtk::accelerator_parse(settings().string(shortcut.settings_key))?;
+    Some(gtk::accelerator_get_label(key, mods).to_string())
+}
+
+fn set_accel(app: &adw::Application, shortcut: &Shortcut) {
+    let accel = settings().string(shortcut.settings_key);
+    if accel.is_empty() {
+        app.set_accels_for_action(shortcut.action, &[]);
+    } else {
+        app.set_accels_for_action(shortcut.action, &[accel.as_str()]);
+    }
+}
+
+fn is_modifier(key: gdk::Key) -> bool {
+    matches!(
+        key,
+        gdk::Key::Shift_L
+            | gdk::Key::Shift_R
+            | gdk::Key::Control_L
+            | gdk::Key::Control_R
+            | gdk::Key::Alt_L
+            | gdk::Key::Alt_R
+            | gdk::Key::Super_L
+            | gdk::Key::Super_R
+            | gdk::Key::Meta_L
+            | gdk::Key::Meta_R
+            | gdk::Key::ISO_Level3_Shift
+    )
+}
+
+pub fn is_timer_key(key: gdk::Key) -> bool {
+    gdk::Key::from_name(settings().string("timer-key"))
+        .is_some_and(|timer_key| timer_key.to_lower() == key.to_lower())
+}
+
+/// Creates the controller that turns presses and releases of the timer key into
+/// [`TimerEvent::Trigger`]s from `kind`, according to the configured [`TriggerMode`]
+pub fn timer_key_controller(tx: EventSender, kind: InputKind) -> gtk::EventControllerKey {
+    let key_controller = gtk::EventControllerKey::new();
+    key_controller.set_propagation_phase(gtk::PropagationPhase::Capture);
+
+    // (left, right)
+    let shifts_held = Rc::new(Cell::new((false, false)));
+
+    let tx2 = tx.clone();
+    let shifts_held2 = shifts_held.clone();
+    key_controller.connect_key_pressed(move |controller, key, _, modifiers| {
+        if should_ignore_keys(controller) {
+            return glib::Propagation::Proceed;
+        }
+
+        let is_trigger = match TriggerMode::current() {
+            TriggerMode::Key => is_timer_key(key),
+            TriggerMode::BothShift => {
+                let (left, right) = shifts_held2.get();
+                let held = match key {
+                    gdk::Key::Shift_L => (true, right),
+                    gdk::Key::Shift_R => (left, true),
+                    _ => (left, right),
+                };
+                shifts_held2.set(held);
+                held == (true, true)
+            }
+            TriggerMode::AnyKey => {
+                !is_modifier(key)
+                    && !modifiers.intersects(
+                        gdk::ModifierType::CONTROL_MASK
+                            | gdk::ModifierType::ALT_MASK
+                            | gdk::ModifierType::SUPER_MASK,
+                    )
+            }
+        };
+
+        let at = event_instant(controller);
+        if is_trigger {
+            send_evt(tx2.clone(), TimerEvent::Trigger(kind, Trigger::Down(at)));
+            glib::Propagation::Stop
+        } else {
+            send_evt(tx2.clone(), TimerEvent::NonSpacebarKeyDown(at));
+            glib::Propagation::Proceed
+        }
+    });
+
+    key_controller.connect_key_released(move |controller, key, _, _| {
+        let is_trigger = match TriggerMode::current() {
+            TriggerMode::Key => is_timer_key(key),
+            TriggerMode::BothShift => {
+                let (left, right) = shifts_held.get();
+                let held = match key {
+                    gdk::Key::Shift_L => (false, right),
+                    gdk::Key::Shift_R => (left, false),
+                    _ => (left, right),
+                };
+                shifts_held.set(held);
+                (left, right) == (true, true) && held != (true, true)
+            }
+            TriggerMode::AnyKey => !is_modifier(key),
+        };
+
+        if is_trigger {
+            let at = event_instant(controller);
+            send_evt(tx.clone(), TimerEvent::Trigger(kind, Trigger::Up(at)));
+        }
+    });
+
+    key_controller
+}
+
+/// When the event that `controller` is handling happened, so that the timer isn't thrown
+/// off by however long it took to get through the main loop
+pub fn event_instant(controller: &impl IsA<gtk::EventController>) -> Instant {
+    let now = Instant::now();
+    // GDK's event times are the monotonic clock in milliseconds, truncated to 32 bits
+    let now_millis = (glib::monotonic_time() / 1000) as u32;
+    event_age(controller.current_event_time(), now_millis)
+        .and_then(|age| now.checked_sub(age))
+        .unwrap_or(now)
+}
+
+/// How long ago an event at `event_time` was, if it looks like it's from the same clock as
+/// `now_millis`
+fn event_age(event_time: u32, now_millis: u32) -> Option<Duration> {
+    // 0 means the event has no time
+    if event_time == 0 {
+        return None;
+    }
+    let age = Duration::from_millis(now_millis.wrapping_sub(event_time).into());
+    (age <= MAX_EVENT_AGE).then_some(age)
+}
+
+/// Don't steal keypresses from dialogs (e.g. while recording a shortcut) or from text
+/// fields (e.g. the FMC solution entry)
+fn should_ignore_keys(controller: &gtk::EventControllerKey) -> bool {
+    let Some(window) = controller.widget().and_downcast::<adw::ApplicationWindow>() else {
+        return false;
+    };
+
+    window.visible_dialog().is_some()
+        || gtk::prelude::GtkWindowExt::focus(&window)
+            .is_some_and(|focus| focus.is::<gtk::Text>() || focus.is::<gtk::SpinButton>())
+}
+
+/// The shortcut (or timer key) currently waiting for a keypress in the shortcuts dialog
+#[derive(Debug)]
+struct Recording {
+    settings_key: &'static str,
+    label: gtk::ShortcutLabel,
+    /// The timer key is a bare key, without any modifiers
+    is_timer_key: bool,
+}
+
+pub fn shortcuts_dialog() -> adw::PreferencesDialog {
+    let builder =
+        gtk::Builder::from_resource("/io/github/flying_toast/PuzzleTime/shortcuts-dialog.ui");
+    let root: adw::PreferencesDialog = builder.object("root").unwrap();
+    let trigger_row: adw::ComboRow = builder.object("trigger_row").unwrap();
+    let timer_key_row: adw::ActionRow = builder.object("timer_key_row").unwrap();
+    let timer_key_label: gtk::ShortcutLabel = builder.object("timer_key_label").unwrap();
+    let shortcuts_group: adw::PreferencesGroup = builder.object("shortcuts_group").unwrap();
+
+    let recording: Rc<RefCell<Option<Recording>>> = Rc::new(RefCell::new(None));
+
+    trigger_row.set_selected(
+        TriggerMode::ALL
+            .iter()
+            .position(|&m| m == TriggerMode::current())
+            .unwrap() as u32,
+    );
+    timer_key_row.set_visible(TriggerMode::current() == TriggerMode::Key);
+    trigger_row.connect_selected_notify(move |row| {
+        let mode = TriggerMode::ALL[row.selected() as usize];
+        settings()
+            .set_string("timer-trigger", mode.setting_value())
+            .unwrap();
+        timer_key_row.set_visible(mode == TriggerMode::Key);
+    });
+
+    show_setting(&timer_key_label, "timer-key");
+    let recording2 = recording.clone();
+    let edit_timer_key: gtk::Button = builder.object("edit_timer_key").unwrap();
+    edit_timer_key.connect_clicked(move |_| {
+        start_recording(&recording2, "timer-key", &timer_key_label, true);
+    });
+
+    for shortcut in &SHORTCUTS {
+        let label = gtk::ShortcutLabel::new("");
+        show_setting(&label, shortcut.settings_key);
+        label.set_valign(gtk::Align::Center);
+        let edit_button = gtk::Button::from_icon_name("document-edit-symbolic");
+        edit_button.set_valign(gtk::Align::Center);
+        edit_button.add_css_class("flat");
+        let row = adw::ActionRow::builder()
+            .title(gettext(shortcut.title))
+            .activatable_widget(&edit_button)
+            .build();
+        row.add_suffix(&label);
+        row.add_suffix(&edit_button);
+
+        let recording = recording.clone();
+        edit_button.connect_clicked(move |_| {
+            start_recording(&recording, shortcut.settings_key, &label, false);
+        });
+        shortcuts_group.add(&row);
+    }
+
+    let key_controller = gtk::EventControllerKey::new();
+    key_controller.set_propagation_phase(gtk::PropagationPhase::Capture);
+    key_controller.connect_key_pressed(move |_, key, _, modifiers| {
+        if is_modifier(key) {
+            return glib::Propagation::Proceed;
+        }
+        let Some(rec) = recording.borrow_mut().take() else {
+            return glib::Propagation::Proceed;
+        };
+        let modifiers = modifiers & gtk::accelerator_get_default_mod_mask();
+
+        let new_value = if key == gdk::Key::Escape && modifiers.is_empty() {
+            // cancel
+            None
+        } else if rec.is_timer_key {
+            key.to_lower().name().map(|name| name.to_string())
+        } else if key == gdk::Key::BackSpace && modifiers.is_empty() {
+            // disable the shortcut
+            Some(String::new())
+        } else {
+            Some(gtk::accelerator_name(key.to_lower(), modifiers).to_string())
+        };
+
+        if let Some(value) = new_value {
+            settings().set_string(rec.settings_key, &value).unwrap();
+        }
+        show_setting(&rec.label, rec.settings_key);
+        glib::Propagation::Stop
+    });
+    root.add_controller(key_controller);
+
+    root
+}
+
+fn start_recording(
+    recording: &RefCell<Option<Recording>>,
+    settings_key: &'static str,
+    label: &gtk::ShortcutLabel,
+    is_timer_key: bool,
+) {
+    // put back the label of whatever we were recording before
+    if let Some(prev) = recording.borrow_mut().take() {
+        show_setting(&prev.label, prev.settings_key);
+    }
+    label.set_accelerator("");
+    label.set_disabled_text(&gettext("Press a key…"));
+    *recording.borrow_mut() = Some(Recording {
+        settings_key,
+        label: label.clone(),
+        is_timer_key,
+    });
+}
+
+fn show_setting(label: &gtk::ShortcutLabel, settings_key: &str) {
+    label.set_accelerator(&settings().string(settings_key));
+    label.set_disabled_text(&gettext("Disabled"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_ages() {
+        let millis = Duration::from_millis;
+        assert_eq!(event_age(9_950, 10_000), Some(millis(50)));
+        assert_eq!(event_age(10_000, 10_000), Some(Duration::ZERO));
+        // the clock wrapped around since the event
+        assert_eq!(event_age(u32::MAX - 9, 20), Some(millis(30)));
+        assert_eq!(event_age(0, 10_000), None);
+        // from another clock, in the past or the future
+        assert_eq!(event_age(1_000, 500_000), None);
+        assert_eq!(event_age(10_010, 10_000), None);
+    }
+}
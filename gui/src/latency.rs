@@ -0,0 +1,202 @@
+//! A diagnostic for how much the time turns of a smart cube take to reach the app varies,
+//! since that limits how accurately the cube can start and stop the timer.
+//!
+//! This isn't the whole latency. Turns are timed by the cube's clock, which
+//! [`smartcube::CubeClock`] maps so that the least delayed recent message had no delay, so
+//! what's measured is how much later than that each turn arrives. A delay that every
+//! message has (which can't be told from the messages themselves) isn't included.
+use crate::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Number of turns that are plotted and summarized
+const MAX_SAMPLES: usize = 100;
+
+/// The plot's vertical axis goes up to a multiple of this
+const PLOT_STEP: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Default)]
+pub struct LatencyMeter {
+    /// While the dialog is open
+    ui: Option<Ui>,
+}
+
+#[derive(Debug)]
+struct Ui {
+    stack: gtk::Stack,
+    results_page: gtk::Box,
+    plot: gtk::DrawingArea,
+    summary: gtk::Label,
+    /// How much later than the least delayed message each turn arrived, oldest first
+    samples: Rc<RefCell<Vec<Duration>>>,
+    closed: Rc<Cell<bool>>,
+}
+
+impl LatencyMeter {
+    pub fn show(&mut self, parent: &impl IsA<gtk::Widget>) {
+        let builder =
+            gtk::Builder::from_resource("/io/github/flying_toast/PuzzleTime/latency-dialog.ui");
+        let root: adw::Dialog = builder.object("root").unwrap();
+        let stack: gtk::Stack = builder.object("stack").unwrap();
+        let waiting_page: adw::StatusPage = builder.object("waiting_page").unwrap();
+        let plot: gtk::DrawingArea = builder.object("plot").unwrap();
+        let reset: gtk::Button = builder.object("reset").unwrap();
+
+        let samples = Rc::new(RefCell::new(Vec::new()));
+        let samples2 = samples.clone();
+        plot.set_draw_func(move |area, cr, width, height| {
+            draw_plot(area, cr, &samples2.borrow(), width.into(), height.into());
+        });
+        let samples2 = samples.clone();
+        reset.connect_clicked(move |_| {
+            samples2.borrow_mut().clear();
+            stack.set_visible_child(&waiting_page);
+        });
+        let closed = Rc::new(Cell::new(false));
+        let closed2 = closed.clone();
+        root.connect_closed(move |_| closed2.set(true));
+
+        root.present(parent);
+        self.ui = Some(Ui {
+            stack: builder.object("stack").unwrap(),
+            results_page: builder.object("results_page").unwrap(),
+            plot,
+            summary: builder.object("summary").unwrap(),
+            samples,
+            closed,
+        });
+    }
+
+    /// Record a turn that the cube says happened at `at` (as mapped by
+    /// [`smartcube::CubeClock`]), if the dialog is open
+    pub fn record(&mut self, at: Instant) {
+        if self.ui.as_ref().is_some_and(|ui| ui.closed.get()) {
+            self.ui = None;
+        }
+        let Some(ui) = &self.ui else {
+            return;
+        };
+
+        let mut samples = ui.samples.borrow_mut();
+        samples.push(Instant::now().saturating_duration_since(at));
+        if samples.len() > MAX_SAMPLES {
+            samples.remove(0);
+        }
+        if let Some(summary) = Summary::of(&samples) {
+            ui.summary.set_label(&gettext_f(
+                "Median +{median} ms · 95% under +{p95} ms · Max +{max} ms",
+                &[
+                    ("median", &summary.median.as_millis().to_string()),
+                    ("p95", &summary.p95.as_millis().to_string()),
+                    ("max", &summary.max.as_millis().to_string()),
+                ],
+            ));
+        }
+        ui.stack.set_visible_child(&ui.results_page);
+        ui.plot.queue_draw();
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct Summary {
+    median: Duration,
+    /// 95th percentile
+    p95: Duration,
+    max: Duration,
+}
+
+impl Summary {
+    fn of(samples: &[Duration]) -> Option<Self> {
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+        let max = *sorted.last()?;
+        // nearest rank
+        let percentile = |p: usize| sorted[(sorted.len() * p).div_ceil(100).max(1) - 1];
+        Some(Self {
+            median: percentile(50),
+            p95: percentile(95),
+            max,
+        })
+    }
+}
+
+/// A bar for each turn, oldest on the left, with a line at each [`PLOT_STEP`]
+fn draw_plot(
+    area: &gtk::DrawingArea,
+    cr: &gtk::cairo::Context,
+    samples: &[Duration],
+    width: f64,
+    height: f64,
+) {
+    let color = area.color();
+    let set_color = |alpha: f32| {
+        cr.set_source_rgba(
+            color.red().into(),
+            color.green().into(),
+            color.blue().into(),
+            (color.alpha() * alpha).into(),
+        );
+    };
+    let steps = samples.iter().max().map_or(1, |max| {
+        max.as_millis().div_ceil(PLOT_STEP.as_millis()).max(1)
+    }) as u32;
+    let top = PLOT_STEP * steps;
+    let y = |delay: Duration| height * (1.0 - delay.as_secs_f64() / top.as_secs_f64());
+
+    set_color(0.15);
+    cr.set_line_width(1.0);
+    cr.set_font_size(11.0);
+    for step in 1..=steps {
+        let line_y = y(PLOT_STEP * step).round() + 0.5;
+        cr.move_to(0.0, line_y);
+        cr.line_to(width, line_y);
+        cr.stroke().unwrap();
+    }
+    set_color(0.6);
+    cr.move_to(2.0, 12.0);
+    cr.show_text(&gettext_f(
+        "{ms} ms",
+        &[("ms", &top.as_millis().to_string())],
+    ))
+    .unwrap();
+
+    let bar_width = width / MAX_SAMPLES as f64;
+    set_color(0.8);
+    for (i, &delay) in samples.iter().enumerate() {
+        let bar_y = y(delay);
+        cr.rectangle(
+            i as f64 * bar_width,
+            bar_y,
+            (bar_width - 1.0).max(1.0),
+            height - bar_y,
+        );
+    }
+    cr.fill().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary() {
+        let ms = |ms: &[u64]| {
+            ms.iter()
+                .map(|&ms| Duration::from_millis(ms))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(Summary::of(&[]), None);
+        assert_eq!(
+            Summary::of(&ms(&[30, 10, 20])),
+            Some(Summary {
+                median: Duration::from_millis(20),
+                p95: Duration::from_millis(30),
+                max: Duration::from_millis(30),
+            })
+        );
+        let summary = Summary::of(&ms(&(1..=100).collect::<Vec<_>>())).unwrap();
+        assert_eq!(summary.median, Duration::from_millis(50));
+        assert_eq!(summary.p95, Duration::from_millis(95));
+    }
+}
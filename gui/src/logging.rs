@@ -0,0 +1,193 @@
+//! Logging, to stderr and to a log file that's rotated so it never gets big. The most recent
+//! lines are also kept in memory, for the debug logs dialog to show. `RUST_LOG` sets what's
+//! logged, like `RUST_LOG=qiyi_smartcube=debug` for everything a QiYi cube sends.
+use crate::prelude::*;
+use crate::settings::APP_ID;
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError, TryLockError};
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{fmt, EnvFilter};
+
+/// The log file is rotated once it would get bigger than this
+const MAX_LOG_SIZE: u64 = 512 * 1024;
+
+/// Number of rotated log files kept, as `puzzletime.log.1` (the newest), `puzzletime.log.2`...
+const OLD_LOGS: u32 = 2;
+
+/// Number of lines kept in memory
+const RECENT_LINES: usize = 1000;
+
+static RECENT: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+pub fn log_path() -> PathBuf {
+    glib::user_data_dir()
+        .join(APP_ID)
+        .join("logs")
+        .join("puzzletime.log")
+}
+
+/// Start logging. Until this is called, nothing is logged.
+pub fn init() {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let file = RotatingFile::open(log_path());
+    let (file, file_error) = match file {
+        Ok(file) => (Some(file), None),
+        Err(e) => (None, Some(e)),
+    };
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(io::stderr))
+        .with(file.map(|file| fmt::layer().with_ansi(false).with_writer(Mutex::new(file))))
+        .with(fmt::layer().with_ansi(false).with_writer(|| RecentLines))
+        .init();
+    if let Some(e) = file_error {
+        tracing::warn!("Failed to open log file: {e}");
+    }
+}
+
+/// The lines logged most recently, oldest first. For crash reports, so it doesn't wait for
+/// the lock (the panic might have been while this thread held it), and is empty if it's
+/// held.
+pub fn recent() -> Vec<String> {
+    let recent = match RECENT.try_lock() {
+        Ok(recent) => recent,
+        // still usable after a panic elsewhere
+        Err(TryLockError::Poisoned(e)) => e.into_inner(),
+        Err(TryLockError::WouldBlock) => return Vec::new(),
+    };
+    recent.iter().cloned().collect()
+}
+
+/// Takes each event as a line of [`RECENT`]. The fmt layer writes each event all at once.
+struct RecentLines;
+
+impl Write for RecentLines {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let line = String::from_utf8_lossy(buf).trim_end().to_owned();
+        // a panic while it was locked doesn't stop logging
+        let mut recent = RECENT.lock().unwrap_or_else(PoisonError::into_inner);
+        push_recent(&mut recent, line);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn push_recent(recent: &mut VecDeque<String>, line: String) {
+    if recent.len() == RECENT_LINES {
+        recent.pop_front();
+    }
+    recent.push_back(line);
+}
+
+/// A log file that's moved aside when it gets to [`MAX_LOG_SIZE`]
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf) -> io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { path, file, size })
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        for n in (1..OLD_LOGS).rev() {
+            // the older ones might not exist yet
+            let _ = fs::rename(rotated_path(&self.path, n), rotated_path(&self.path, n + 1));
+        }
+        fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > MAX_LOG_SIZE {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn rotated_path(path: &Path, n: u32) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(format!(".{n}"));
+    path.into()
+}
+
+/// Shows the recent lines of the log, to copy into a bug report
+pub fn show_dialog(parent: &impl IsA<gtk::Widget>) {
+    let builder = gtk::Builder::from_resource("/io/github/flying_toast/PuzzleTime/logs-dialog.ui");
+    let root: adw::Dialog = builder.object("root").unwrap();
+    let text: gtk::TextView = builder.object("text").unwrap();
+    let copy: gtk::Button = builder.object("copy").unwrap();
+    let open_folder: gtk::Button = builder.object("open_folder").unwrap();
+    let toasts: adw::ToastOverlay = builder.object("toasts").unwrap();
+
+    let mut logs = recent().join("\n");
+    logs.push('\n');
+    text.buffer().set_text(&logs);
+    copy.connect_clicked(move |button| {
+        button.clipboard().set_text(&logs);
+        toasts.add_toast(adw::Toast::new(&gettext("Copied to clipboard")));
+    });
+    let root2 = root.clone();
+    open_folder.connect_clicked(move |_| {
+        let file = gio::File::for_path(log_path());
+        let window = root2.root().and_downcast::<gtk::Window>();
+        gtk::FileLauncher::new(Some(&file)).open_containing_folder(
+            window.as_ref(),
+            gio::Cancellable::NONE,
+            |result| {
+                if let Err(e) = result {
+                    tracing::warn!("Failed to open log folder: {e}");
+                }
+            },
+        );
+    });
+
+    root.present(parent);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotation() {
+        let mut recent = VecDeque::new();
+        for i in 0..RECENT_LINES + 2 {
+            push_recent(&mut recent, i.to_string());
+        }
+        assert_eq!(recent.len(), RECENT_LINES);
+        assert_eq!(recent.front().unwrap(), "2");
+
+        assert_eq!(
+            rotated_path(Path::new("/logs/puzzletime.log"), 2),
+            Path::new("/logs/puzzletime.log.2")
+        );
+    }
+}
@@ -1,50 +1,283 @@
+mod alg_trainer;
+mod animation;
+mod autosave;
 mod bluetooth;
+mod calendar;
+mod checkpoint;
+mod color_scheme;
+mod crash;
+mod cross_trainer;
+mod cubes;
+mod dbus;
+mod drivers;
+mod files;
+mod fmc;
+mod goals;
+mod hold_calibration;
+mod http;
+mod i18n;
+mod input;
+mod keybindings;
+mod latency;
+mod logging;
+mod mode;
+mod multi_blind;
+mod overlay;
+mod preferences;
 mod prelude;
+mod puzzle_diagram;
+mod race;
+mod report;
+mod robot;
+mod runtime;
+mod scramble_sheet;
+mod search_provider;
+mod settings;
+mod signing;
+mod solve_state;
+mod sounds;
+mod spectator;
 mod stat_object;
+mod stat_row;
 mod stats;
+mod summary;
+mod sync;
 mod timer;
+mod wca;
 
 use crate::prelude::*;
 use futures::{channel::mpsc, stream::StreamExt};
-use stats::SolveStat;
-use std::time::Duration;
+use input::{InputArbiter, InputKind, InputSource, Trigger};
+use mode::Mode;
+use solve_state::{SolveAction, SolveInput, SolveState};
+use stats::{Penalty, PenaltySource, SolveStat};
+use std::time::{Duration, Instant};
 
 #[derive(Debug)]
 pub enum Event {
-    SpacebarDown,
-    SpacebarUp,
-    /// A key other than spacebar was pressed
-    NonSpacebarKeyDown,
+    Timer(TimerEvent),
+    Stats(StatsEvent),
+    Bluetooth(BluetoothEvent),
+    Quit,
+    ShowRaceDialog,
+    /// Join a race (see [`race`]), leaving any other one
+    JoinRace {
+        /// Address of the relay server
+        server: String,
+        room: String,
+        name: String,
+    },
+    LeaveRace,
+    /// A message from the relay server of the race connection with the given ID
+    RaceMessage {
+        id: u64,
+        message: race::Message,
+    },
+    /// The race connection with the given ID ended, because of `error` if there was one
+    RaceEnded {
+        id: u64,
+        error: Option<String>,
+    },
+    /// The alg trainer sets, or which one is chosen, changed
+    TrainerSetChanged,
+    /// Fraction of the solver's tables that have been built
+    SolverProgress(f32),
+    /// All of the solver's tables have been built
+    SolverReady(cubestruct::SolverContext),
+    ShowShortcutsDialog,
+    /// Show the recent log, for bug reports
+    ShowDebugLogs,
+    ShowPreferences,
+    ToggleFocusMode,
+    SetMode(Mode),
+    /// The number of solved cubes was entered for a multi-blind attempt
+    MultiBlindResult(multi_blind::MultiBlindResult),
+    FmcStart,
+    /// Update the FMC countdown
+    FmcTick,
+    /// Save a checkpoint of the smart cube's state and turns
+    Checkpoint,
+    FmcSolutionChanged,
+    FmcSubmit,
+    ExportSummary,
+    ExportReport,
+    /// Ask for a file of scrambles to use instead of generated ones
+    ImportScrambles,
+    ExportScrambleSheet,
+    ExportSolveRecords,
+    /// Have the scrambling robot scramble the cube with the current scramble
+    SendToRobot,
+    /// A scramble was sent to the robot, whose name is returned
+    RobotScrambled(Result<String, String>),
+    /// Sync the solve history with the server
+    Sync,
+    /// Sent when a sync started by [`sync::HistorySync::start()`] is done
+    SyncFinished {
+        /// The history that was synced, as it was when the sync started
+        local: Vec<autosave::SavedSolve>,
+        /// The merged history
+        result: Result<Vec<autosave::SavedSolve>, String>,
+    },
+    /// Official records were fetched for the WCA ID in the preferences
+    WcaRecords {
+        /// `None` if no valid WCA ID is set
+        wca_id: Option<String>,
+        result: Result<wca::OfficialRecords, String>,
+    },
+}
+
+/// Events about the timer and its scrambles (see [`CubeApp::handle_timer_event`])
+#[derive(Debug)]
+pub enum TimerEvent {
+    /// Input from one of the [`input::InputSource`]s, like the start/stop key (spacebar by
+    /// default) being pressed
+    Trigger(InputKind, Trigger),
+    /// A key other than the start/stop key was pressed
+    NonSpacebarKeyDown(Instant),
     /// Update timer's displayed time
     UpdateDisplayTime,
-    /// Send when it's time to turn on the timer's green light
-    GreenLightReady,
-    Quit,
+    /// Send when it's time to turn on the timer's green light, for the hold of the start/stop
+    /// key that began at this time
+    GreenLightReady(Instant),
+    /// Deal with a solve that was running when the app crashed, by resuming it or (if
+    /// `resume` is false) recording it as a DNF
+    RecoverSolve {
+        /// As returned by [`timer::Timer::started_at()`]
+        started_at: i64,
+        resume: bool,
+    },
+    NewScramble,
+    PreviousScramble,
+    /// Scrambles were generated for [`timer::Timer`]
+    ScramblesReady(Vec<Vec<cubestruct::Move>>),
+    /// Seed the session's scrambles with the given text, or make them random if `None`
+    SetScrambleSeed(Option<String>),
+    /// The solver's solution to a scramble was found to be `moves` long
+    ScrambleDifficulty {
+        scramble: Vec<cubestruct::Move>,
+        moves: u8,
+    },
+    /// Use the scramble at the given index of the scramble history again
+    ReuseScramble(usize),
+    /// Use a scramble that takes a solved cube to the smart cube's current state
+    ScrambleFromCube,
+    /// Use a scramble from outside the app, e.g. a search result
+    UseScramble(Vec<cubestruct::Move>),
+    /// Scrambles were loaded from a file, to use in order
+    ScramblesImported(Vec<Vec<cubestruct::Move>>),
+}
+
+/// Events about the results (see [`CubeApp::handle_stats_event`])
+#[derive(Debug)]
+pub enum StatsEvent {
+    /// A result was added, removed or changed
+    Changed,
     /// Show the stat at the given index
     ShowStat(u32),
+    /// Move the stat at the given index to the trash, asking first if it's part of a best
+    /// average
     DeleteStat(u32),
-    RestoreDeletedStat,
-    StatsChanged,
-    ShowBluetoothPopup,
-    StopBluetoothScan,
-    BluetoothInitialized(smartcube::BluetoothManager),
-    BluetoothDeviceDiscoverd(smartcube::Device),
-    BluetoothDeviceConnected(smartcube::DeviceId),
-    BluetoothDeviceDisconnected(smartcube::DeviceId),
+    /// Move the stat at the given index to the trash without asking
+    TrashStat(u32),
+    DeleteLastStat,
+    RestoreTrashedStat(stats::TrashId),
+    /// Put the stats that were moved to the trash for a new session back
+    RestoreSession(Vec<stats::TrashId>),
+    DeleteTrashedStat(stats::TrashId),
+    /// Toggle the given penalty on the most recent stat
+    TogglePenaltyLast(Penalty),
+    ShowTrash,
+    ShowCalendar,
+    /// Copy the latest result in the session's format, for sharing
+    CopyResult,
+    /// Copy the latest average of this many solves as Markdown
+    CopyMarkdown(u32),
+}
+
+/// Events about bluetooth devices, including smart cubes (see
+/// [`CubeApp::handle_bluetooth_event`])
+#[derive(Debug)]
+pub enum BluetoothEvent {
+    ShowPopup,
+    ShowLatencyMeter,
+    StopScan,
+    Initialized(Result<smartcube::BluetoothManager, smartcube::BluetoothError>),
+    /// Scanning for devices failed, e.g. because the adapter is off
+    Failed(smartcube::BluetoothError),
+    Retry,
+    DeviceDiscovered(smartcube::Device),
+    DeviceConnected(smartcube::DeviceId),
+    DeviceDisconnected(smartcube::DeviceId),
+    /// A connected smart cube hasn't been turned for the idle time in the preferences
+    DeviceIdle(smartcube::DeviceId),
     Smartcube(smartcube::SmartcubeEvent),
 }
 
+impl From<TimerEvent> for Event {
+    fn from(evt: TimerEvent) -> Self {
+        Self::Timer(evt)
+    }
+}
+
+impl From<StatsEvent> for Event {
+    fn from(evt: StatsEvent) -> Self {
+        Self::Stats(evt)
+    }
+}
+
+impl From<BluetoothEvent> for Event {
+    fn from(evt: BluetoothEvent) -> Self {
+        Self::Bluetooth(evt)
+    }
+}
+
 #[derive(Debug)]
 struct CubeApp {
     application: adw::Application,
     bluetooth: bluetooth::Bluetooth,
+    latency: latency::LatencyMeter,
     window: adw::ApplicationWindow,
     toasts: adw::ToastOverlay,
     timer: timer::Timer,
     stats: stats::Stats,
-    spacebar_being_held: bool,
-    greenlight_timeout: Option<glib::SourceId>,
-    timer_ready: bool,
+    cross_trainer: cross_trainer::CrossTrainer,
+    alg_trainer: alg_trainer::AlgTrainer,
+    multi_blind: multi_blind::MultiBlind,
+    fmc: fmc::Fmc,
+    race: race::Race,
+    mode: Mode,
+    /// `None` if the app isn't on the session bus
+    dbus: Option<dbus::DbusInterface>,
+    overlay: overlay::Overlay,
+    spectators: spectator::Spectators,
+    recorder: signing::Recorder,
+    autosave: autosave::Autosave,
+    sync: sync::HistorySync,
+    /// Last state reported by the connected smart cube
+    cube_state: Option<cubestruct::CubieCube>,
+    /// When the connected smart cube was last turned
+    last_turn: Option<Instant>,
+    /// State of the connected smart cube when the running (or last) solve started
+    solve_start_state: Option<cubestruct::CubieCube>,
+    checkpoints: checkpoint::Checkpoints,
+    /// State of the smart cube in the checkpoint left by the last run, until a cube reports
+    /// its state
+    checkpointed_state: Option<cubestruct::CubieCube>,
+    /// The running solve in the checkpoint left by the last run, for if it's resumed
+    checkpointed_solve: Option<checkpoint::SolveCheckpoint>,
+    turns_today: checkpoint::TurnCount,
+    stats_split: adw::OverlaySplitView,
+    timer_tbview: adw::ToolbarView,
+    /// Holds the results instead of the sidebar on narrow windows
+    stats_sheet: gtk::Revealer,
+    /// Shown while the solver's tables are being built
+    solver_banner: adw::Banner,
+    focus_mode: bool,
+    /// Whether the sidebar was visible before entering focus mode
+    sidebar_was_shown: bool,
+    solve_state: SolveState,
+    /// Which timer input counts, when more than one source is used
+    inputs: InputArbiter,
     tx: EventSender,
 }
 
@@ -54,6 +287,19 @@ impl CubeApp {
             gtk::Builder::from_resource("/io/github/flying_toast/PuzzleTime/main-window.ui");
         let window: adw::ApplicationWindow = builder.object("window").unwrap();
         window.set_application(Some(&app));
+        let stats_split: adw::OverlaySplitView = builder.object("stats_split").unwrap();
+        restore_window_state(&window, &stats_split);
+        let split = stats_split.clone();
+        // hidden windows keep the application running, and with it the D-Bus interface
+        window.connect_close_request(move |window| {
+            save_window_state(window, &split);
+            if settings::settings().boolean("run-in-background") {
+                window.set_visible(false);
+                glib::Propagation::Stop
+            } else {
+                glib::Propagation::Proceed
+            }
+        });
 
         let quit_act = gio::SimpleAction::new("quit", None);
         let tx2 = tx.clone();
@@ -61,81 +307,1331 @@ impl CubeApp {
         app.set_accels_for_action("app.quit", &["<Primary>Q"]);
         app.add_action(&quit_act);
 
-        let remove_undo = gio::SimpleAction::new("undo-remove-stat", None);
-        let tx2 = tx.clone();
-        remove_undo.connect_activate(move |_, _| send_evt(tx2.clone(), Event::RestoreDeletedStat));
-        app.add_action(&remove_undo);
-
         let bluetooth_popup_act = gio::SimpleAction::new("bluetooth-popup", None);
         let tx2 = tx.clone();
         bluetooth_popup_act
-            .connect_activate(move |_, _| send_evt(tx2.clone(), Event::ShowBluetoothPopup));
+            .connect_activate(move |_, _| send_evt(tx2.clone(), BluetoothEvent::ShowPopup));
         app.add_action(&bluetooth_popup_act);
 
-        let timer = timer::Timer::new(tx.clone());
+        let sync_act = gio::SimpleAction::new("sync", None);
+        let tx2 = tx.clone();
+        sync_act.connect_activate(move |_, _| send_evt(tx2.clone(), Event::Sync));
+        settings::settings()
+            .bind("sync-enabled", &sync_act, "enabled")
+            .get()
+            .build();
+        app.add_action(&sync_act);
+        sync::connect_enabled(tx.clone());
+        wca::watch(tx.clone());
 
-        let key_controller = gtk::EventControllerKey::new();
-        key_controller.set_propagation_phase(gtk::PropagationPhase::Capture);
+        let simple_actions: [(&str, fn() -> Event); 23] = [
+            ("shortcuts", || Event::ShowShortcutsDialog),
+            ("debug-logs", || Event::ShowDebugLogs),
+            ("preferences", || Event::ShowPreferences),
+            ("focus-mode", || Event::ToggleFocusMode),
+            ("plus2-last", || {
+                StatsEvent::TogglePenaltyLast(Penalty::Plus2).into()
+            }),
+            ("dnf-last", || {
+                StatsEvent::TogglePenaltyLast(Penalty::Dnf).into()
+            }),
+            ("delete-last", || StatsEvent::DeleteLastStat.into()),
+            ("new-scramble", || TimerEvent::NewScramble.into()),
+            ("previous-scramble", || TimerEvent::PreviousScramble.into()),
+            ("scramble-from-cube", || TimerEvent::ScrambleFromCube.into()),
+            ("export-summary", || Event::ExportSummary),
+            ("export-report", || Event::ExportReport),
+            ("export-scramble-sheet", || Event::ExportScrambleSheet),
+            ("export-solve-records", || Event::ExportSolveRecords),
+            ("import-scrambles", || Event::ImportScrambles),
+            ("trash", || StatsEvent::ShowTrash.into()),
+            ("calendar", || StatsEvent::ShowCalendar.into()),
+            ("copy-result", || StatsEvent::CopyResult.into()),
+            ("copy-ao5-markdown", || StatsEvent::CopyMarkdown(5).into()),
+            ("copy-ao12-markdown", || StatsEvent::CopyMarkdown(12).into()),
+            ("latency-meter", || BluetoothEvent::ShowLatencyMeter.into()),
+            ("race", || Event::ShowRaceDialog),
+            ("send-to-robot", || Event::SendToRobot),
+        ];
+        for (name, make_evt) in simple_actions {
+            let act = gio::SimpleAction::new(name, None);
+            let tx2 = tx.clone();
+            act.connect_activate(move |_, _| send_evt(tx2.clone(), make_evt()));
+            app.add_action(&act);
+        }
+        // needs the solver, so it's enabled once its tables are built
+        set_action_enabled(&app, "scramble-from-cube", false);
+        let mode_act = gio::SimpleAction::new_stateful(
+            "mode",
+            Some(glib::VariantTy::STRING),
+            &Mode::default().action_target().to_variant(),
+        );
         let tx2 = tx.clone();
-        key_controller.connect_key_pressed(move |_, key, _, _| {
-            if key == gdk::Key::space {
-                send_evt(tx2.clone(), Event::SpacebarDown);
-                glib::Propagation::Stop
-            } else {
-                send_evt(tx2.clone(), Event::NonSpacebarKeyDown);
-                glib::Propagation::Proceed
+        mode_act.connect_activate(move |_, param| {
+            if let Some(mode) = param
+                .and_then(|p| p.str())
+                .and_then(Mode::from_action_target)
+            {
+                send_evt(tx2.clone(), Event::SetMode(mode));
             }
         });
+        app.add_action(&mode_act);
+        let use_scramble_act =
+            gio::SimpleAction::new("use-scramble", Some(glib::VariantTy::STRING));
         let tx2 = tx.clone();
-        key_controller.connect_key_released(move |_, key, _, _| {
-            if key == gdk::Key::space {
-                send_evt(tx2.clone(), Event::SpacebarUp);
+        use_scramble_act.connect_activate(move |_, param| {
+            if let Some(Ok(scramble)) = param.and_then(|p| p.str()).map(cubestruct::parse_moves) {
+                send_evt(tx2.clone(), TimerEvent::UseScramble(scramble));
             }
         });
-        window.add_controller(key_controller);
+        app.add_action(&use_scramble_act);
+
+        app.set_accels_for_action("app.shortcuts", &["<Primary>question"]);
+        app.set_accels_for_action("app.preferences", &["<Primary>comma"]);
+        app.set_accels_for_action("app.copy-result", &["<Primary><Shift>c"]);
+        keybindings::install_accels(&app);
+
+        let toasts: adw::ToastOverlay = builder.object("toasts").unwrap();
+        let timer = timer::Timer::new(tx.clone(), toasts.clone());
+
+        let inputs: [Box<dyn InputSource>; 2] = [
+            Box::new(input::KeyboardInput::new(&window)),
+            Box::new(timer.touch_input()),
+        ];
+        for input in inputs {
+            input.connect(tx.clone());
+        }
 
         let stats = stats::Stats::new(tx.clone());
         let timer_tbview: adw::ToolbarView = builder.object("timer_tbview").unwrap();
         timer_tbview.set_content(Some(timer.widget()));
-        let stats_split: adw::OverlaySplitView = builder.object("stats_split").unwrap();
+        let cross_trainer = cross_trainer::CrossTrainer::new();
+        timer_tbview.add_bottom_bar(cross_trainer.widget());
+        let alg_trainer = alg_trainer::AlgTrainer::new(tx.clone());
+        timer_tbview.add_bottom_bar(alg_trainer.widget());
+        let multi_blind = multi_blind::MultiBlind::new();
+        timer_tbview.add_bottom_bar(multi_blind.widget());
+        let fmc = fmc::Fmc::new(tx.clone());
+        timer_tbview.add_bottom_bar(fmc.widget());
+        let race = race::Race::new(tx.clone(), toasts.clone());
+        timer_tbview.add_bottom_bar(race.widget());
+        let timer_header: adw::HeaderBar = builder.object("timer_header").unwrap();
+        timer_header.pack_start(timer.header_widget());
         stats_split.set_sidebar(Some(stats.widget()));
+        let stats_sheet: gtk::Revealer = builder.object("stats_sheet").unwrap();
+        timer_tbview.add_bottom_bar(&stats_sheet);
+        Self::move_stats_on_narrow(&builder, &stats_split, stats.widget().as_ref());
+        let solver_banner: adw::Banner = builder.object("solver_banner").unwrap();
+        build_solver_tables(tx.clone());
+        let checkpointed = checkpoint::load();
+        let (today, _) = goals::today_and_yesterday();
+        stats.set_turns_today(checkpointed.turns_today.on(&today));
 
         window.present();
 
-        let toasts: adw::ToastOverlay = builder.object("toasts").unwrap();
         Self {
+            dbus: dbus::DbusInterface::register(&app),
+            overlay: overlay::Overlay::default(),
+            spectators: spectator::Spectators::new(),
+            recorder: signing::Recorder::default(),
+            autosave: autosave::Autosave::default(),
+            sync: sync::HistorySync::default(),
+            cube_state: None,
+            last_turn: None,
+            solve_start_state: None,
+            checkpoints: checkpoint::Checkpoints::default(),
+            checkpointed_state: checkpointed.state,
+            checkpointed_solve: checkpointed.solve,
+            turns_today: checkpointed.turns_today,
             application: app,
             bluetooth: bluetooth::Bluetooth::new(tx.clone(), toasts.clone()),
+            latency: latency::LatencyMeter::default(),
             tx,
             timer,
             stats,
-            spacebar_being_held: false,
+            cross_trainer,
+            alg_trainer,
+            multi_blind,
+            fmc,
+            race,
+            mode: Mode::default(),
+            stats_split,
+            timer_tbview,
+            stats_sheet,
+            solver_banner,
+            focus_mode: false,
+            sidebar_was_shown: true,
+            solve_state: SolveState::default(),
+            inputs: InputArbiter::default(),
             window,
-            timer_ready: false,
             toasts,
-            greenlight_timeout: None,
         }
     }
 
-    fn stop_timer(&mut self) {
+    /// Move the results from the sidebar to the bottom sheet while the window is narrow,
+    /// where a sidebar wouldn't leave enough room for the timer
+    fn move_stats_on_narrow(
+        builder: &gtk::Builder,
+        stats_split: &adw::OverlaySplitView,
+        stats_widget: &gtk::Widget,
+    ) {
+        let narrow: adw::Breakpoint = builder.object("narrow_breakpoint").unwrap();
+        let stats_sheet: gtk::Revealer = builder.object("stats_sheet").unwrap();
+        let sheet_bin: adw::Bin = builder.object("stats_sheet_bin").unwrap();
+
+        let split = stats_split.clone();
+        let bin = sheet_bin.clone();
+        let widget = stats_widget.clone();
+        narrow.connect_apply(move |_| {
+            split.set_sidebar(None::<&gtk::Widget>);
+            bin.set_child(Some(&widget));
+        });
+        let split = stats_split.clone();
+        let widget = stats_widget.clone();
+        narrow.connect_unapply(move |_| {
+            stats_sheet.set_reveal_child(false);
+            sheet_bin.set_child(None::<&gtk::Widget>);
+            split.set_sidebar(Some(&widget));
+        });
+    }
+
+    /// Whether the running solve started too recently to be stopped, so a stop now is
+    /// probably accidental (see the `min-solve-duration` setting)
+    fn stop_too_soon(&self) -> bool {
+        let min = settings::settings().double("min-solve-duration");
+        self.timer
+            .elapsed()
+            .is_some_and(|elapsed| elapsed.as_secs_f64() < min)
+    }
+
+    /// Whether the connected smart cube is being turned, so other keys are probably being
+    /// hit by accident
+    fn cube_turning(&self) -> bool {
+        self.cube_state.is_some()
+            && self
+                .last_turn
+                .is_some_and(|at| at.elapsed() < CUBE_TURNING_PERIOD)
+    }
+
+    /// Act on input from a source, if the [`InputArbiter`] lets it through
+    fn trigger(&mut self, kind: InputKind, trigger: Trigger) {
+        // there's nothing to solve while a seeded scramble is still being generated
+        if matches!(trigger, Trigger::Down(_))
+            && self.solve_state == SolveState::Idle
+            && self.timer.current_scramble().is_empty()
+        {
+            return;
+        }
+        if !self.inputs.accepts(kind, trigger) {
+            return;
+        }
+        match trigger {
+            Trigger::Down(at) => {
+                let split = match self.mode {
+                    Mode::Normal => {
+                        let splits = settings::settings().uint("manual-splits") as usize;
+                        self.timer.splits().len() < splits
+                    }
+                    // the first press after the start ends memo
+                    Mode::Blind => self.timer.memo_time().is_none(),
+                    _ => false,
+                };
+                self.solve_input(SolveInput::KeyDown {
+                    at,
+                    inspection: self.mode.has_inspection()
+                        && settings::settings().boolean("inspection"),
+                    split,
+                    too_soon: self.stop_too_soon(),
+                });
+            }
+            Trigger::Up(at) => self.solve_input(SolveInput::KeyUp { at }),
+            Trigger::Stop(at) => self.solve_input(SolveInput::Stop { at }),
+        }
+    }
+
+    /// Move the solve along (see [`SolveState::transition`]), and do whatever that calls for
+    fn solve_input(&mut self, input: SolveInput) {
+        let (state, action) = self.solve_state.transition(input);
+        self.solve_state = state;
+        match action {
+            None => {}
+            Some(SolveAction::StartInspection) => self.timer.start_inspection(),
+            Some(SolveAction::RedLight { since }) => {
+                self.timer.red_light_on();
+                let tx = self.tx.clone();
+                let hold = Duration::from_millis(settings::settings().uint("hold-duration").into());
+                // from when the key went down, not when the event got here
+                glib::timeout_add(hold.saturating_sub(since.elapsed()), move || {
+                    send_evt(tx.clone(), TimerEvent::GreenLightReady(since));
+                    glib::ControlFlow::Break
+                });
+            }
+            Some(SolveAction::GreenLight) => self.timer.both_lights_on(),
+            Some(SolveAction::LightsOff) => self.timer.lights_off(),
+            Some(SolveAction::Start { at }) => {
+                self.timer.start(at);
+                self.inputs.solve_started();
+                self.solve_start_state = self.cube_state;
+                self.recorder.start(self.cube_state.as_ref());
+                self.checkpoint();
+                if let Some(dbus) = &self.dbus {
+                    dbus.timer_started();
+                }
+                self.spectators.timer_changed(true, Duration::ZERO);
+                if self.mode == Mode::CrossTrainer {
+                    self.cross_trainer.start();
+                }
+            }
+            Some(SolveAction::Split { at }) if self.mode == Mode::Blind => {
+                self.timer.split_memo(at);
+            }
+            Some(SolveAction::Split { at }) => self.timer.split(at),
+            Some(SolveAction::Stop { at }) => self.stop_timer(at),
+        }
+    }
+
+    fn stop_timer(&mut self, at: Instant) {
         self.timer.lights_off();
-        let elapsed_time = self.timer.stop();
-        let stat = SolveStat::new(self.tx.clone(), elapsed_time, self.timer.take_scramble());
-        self.stats.append_stat(&stat);
+        let elapsed_time = self.timer.stop(at);
+        self.inputs.solve_stopped();
+        if let Some(dbus) = &self.dbus {
+            dbus.timer_stopped(elapsed_time);
+        }
+        self.spectators.timer_changed(false, elapsed_time);
+        let difficulty = self.timer.difficulty(self.timer.current_scramble());
+        let scramble = self.timer.take_scramble();
+        match self.mode {
+            Mode::CrossTrainer => {
+                if !self.cross_trainer.finish(elapsed_time) {
+                    self.toasts.add_toast(adw::Toast::new(&gettext(
+                        "Cross wasn't solved, attempt not recorded",
+                    )));
+                }
+            }
+            Mode::AlgTrainer => {
+                if !self.alg_trainer.finish(&scramble, elapsed_time) {
+                    self.toasts.add_toast(adw::Toast::new(&gettext(
+                        "Scramble isn't a case from the set, attempt not recorded",
+                    )));
+                }
+            }
+            Mode::MultiBlind => {
+                self.multi_blind
+                    .finish(self.tx.clone(), &self.window, elapsed_time);
+            }
+            Mode::Normal | Mode::Blind | Mode::Fmc => {
+                let stat = SolveStat::new(self.tx.clone(), elapsed_time, scramble);
+                stat.set_scramble_difficulty(difficulty);
+                if let Some(memo_time) = self.timer.memo_time() {
+                    stat.set_memo_time(memo_time);
+                }
+                stat.set_splits(self.timer.splits().to_vec());
+                stat.set_stages(self.timer.stages().to_vec());
+                stat.set_turns(self.timer.turns().to_vec());
+                let penalty = self.timer.inspection_penalty();
+                if penalty != Penalty::None {
+                    stat.apply_penalty(penalty, PenaltySource::InspectionOverrun);
+                }
+                self.check_misscramble(&stat);
+                if let Some(result) =
+                    self.recorder
+                        .finish(stat.scramble(), elapsed_time, stat.penalty())
+                {
+                    self.solve_signed(result);
+                }
+                if let Some(dbus) = &self.dbus {
+                    dbus.solve_recorded(
+                        stat.property("time-string"),
+                        timer::render_moveseq(stat.scramble(), false),
+                    );
+                }
+                // unless the seed was changed since joining the race
+                let racing = self.race.room().is_some_and(|room| {
+                    self.timer
+                        .scramble_seed()
+                        .is_some_and(|seed| seed.text == room)
+                });
+                if let Some(round) = self.timer.seed_index(stat.scramble()).filter(|_| racing) {
+                    self.race.submit(round, stat.time());
+                }
+                self.maybe_start_new_session(&stat.date());
+                let previous_best = self.stats.best_single();
+                self.stats.append_stat(&stat);
+                if stat
+                    .time()
+                    .zip(previous_best)
+                    .is_some_and(|(time, best)| time < best)
+                {
+                    sounds::play(sounds::Sound::PersonalBest);
+                }
+            }
+        }
+        // attempts in the trainers and multi-blind aren't recorded
+        self.recorder.cancel();
+        self.update_overlay();
+    }
+
+    /// Start a new session before a solve finished at `now`, if it's due (see the
+    /// `auto-new-session` setting)
+    fn maybe_start_new_session(&mut self, now: &glib::DateTime) {
+        if !self
+            .stats
+            .due_new_session(stats::SessionSplit::from_settings(), now)
+        {
+            return;
+        }
+        let ids = self.stats.start_new_session();
+        let toast = adw::Toast::new(&gettext("Started a new session"));
+        toast.set_button_label(Some(&gettext("Undo")));
+        let tx = self.tx.clone();
+        toast.connect_button_clicked(move |_| {
+            send_evt(tx.clone(), StatsEvent::RestoreSession(ids.clone()));
+        });
+        self.toasts.add_toast(toast);
+    }
+
+    /// Show the hash of a solve's record, to submit along with the result
+    fn solve_signed(&self, result: anyhow::Result<String>) {
+        let hash = match result {
+            Ok(hash) => hash,
+            Err(e) => {
+                self.toasts.add_toast(adw::Toast::new(&gettext_f(
+                    "Couldn't record solve: {error}",
+                    &[("error", &e.to_string())],
+                )));
+                return;
+            }
+        };
+        let toast = adw::Toast::new(&gettext_f(
+            "Solve recorded with hash {hash}…",
+            &[("hash", &hash[..12])],
+        ));
+        toast.set_button_label(Some(&gettext("Copy Hash")));
+        toast.connect_button_clicked(move |_| {
+            gdk::Display::default().unwrap().clipboard().set_text(&hash);
+        });
+        self.toasts.add_toast(toast);
+    }
+
+    /// Save the log of solve records, for an online competition to verify
+    fn export_solve_records(&self) {
+        let window = self.window.clone();
+        let toasts = self.toasts.clone();
+        let dialog = gtk::FileDialog::builder()
+            .title(gettext("Export Solve Records"))
+            .initial_name("solve-records.txt")
+            .build();
+
+        glib::spawn_future_local(async move {
+            let Some(result) = files::save(&dialog, &window, |_| {
+                Ok(signing::log_contents()?.into_bytes())
+            })
+            .await
+            else {
+                // cancelled
+                return;
+            };
+            let msg = match result {
+                Ok(()) => gettext("Solve records exported"),
+                Err(e) => gettext_f(
+                    "Couldn't export solve records: {error}",
+                    &[("error", &e.to_string())],
+                ),
+            };
+            toasts.add_toast(adw::Toast::new(&msg));
+        });
+    }
+
+    fn update_overlay(&mut self) {
+        self.overlay.update(&overlay::OverlayData {
+            time: self.timer.displayed_time(),
+            running: self.timer.running(),
+            ao5: self.stats.current_average(5),
+            ao12: self.stats.current_average(12),
+        });
+    }
+
+    fn saved_solves(&self) -> Vec<autosave::SavedSolve> {
+        (0..self.stats.length())
+            .map(|idx| self.stats.get_stat(idx).unwrap().to_saved())
+            .collect()
+    }
+
+    fn autosave(&mut self) {
+        let solves = self.saved_solves();
+        self.sync.save_history(&solves);
+        self.autosave.update(&autosave::SessionState {
+            solves,
+            scramble: self.timer.current_scramble().to_vec(),
+            solve_started: self.timer.started_at(),
+            scramble_seed: self.timer.scramble_seed().cloned(),
+        });
+    }
+
+    fn checkpoint(&mut self) {
+        let solve = match (
+            self.timer.started_at(),
+            self.solve_start_state,
+            self.timer.known_turns(),
+        ) {
+            (Some(started_at), Some(start_state), Some(turns)) => {
+                Some(checkpoint::SolveCheckpoint {
+                    started_at,
+                    start_state,
+                    turns: turns.to_vec(),
+                })
+            }
+            _ => None,
+        };
+        self.checkpoints.update(&checkpoint::CubeCheckpoint {
+            state: self.cube_state.or(self.checkpointed_state),
+            solve,
+            turns_today: self.turns_today.clone(),
+        });
+        // in case the day has changed since the last turn
+        let (today, _) = goals::today_and_yesterday();
+        self.stats.set_turns_today(self.turns_today.on(&today));
+    }
+
+    fn count_turn(&mut self) {
+        let (today, _) = goals::today_and_yesterday();
+        self.turns_today.count(&today);
+        self.stats.set_turns_today(self.turns_today.on(&today));
+    }
+
+    /// Start the session with the local copy of the synced history
+    fn load_synced_history(&mut self) {
+        for saved in &sync::load_history() {
+            self.stats
+                .append_restored(&SolveStat::from_saved(self.tx.clone(), saved));
+        }
+    }
+
+    /// Bring the session up to date with the result of a sync. `synced` is the history as
+    /// it was when the sync started, since solves could have been changed while syncing.
+    fn apply_synced(&mut self, synced: &[autosave::SavedSolve], merged: &[autosave::SavedSolve]) {
+        let mut current = self.saved_solves();
+
+        // removed on another machine, so put them in the trash in case that was a mistake
+        for old in synced {
+            if merged.iter().any(|new| sync::same_solve(new, old)) {
+                continue;
+            }
+            if let Some(idx) = current.iter().position(|solve| solve == old) {
+                self.stats.remove(idx as u32);
+                current.remove(idx);
+            }
+        }
+
+        for new in merged {
+            match synced.iter().find(|old| sync::same_solve(old, new)) {
+                Some(old) if old != new => {
+                    // unless it's been changed here since, in which case that change wins
+                    let Some(idx) = current.iter().position(|solve| solve == old) else {
+                        continue;
+                    };
+                    let stat = self.stats.get_stat(idx as u32).unwrap();
+                    stat.set_penalties(new.penalties.clone(), PenaltySource::Synced);
+                    stat.set_hand(new.hand);
+                    stat.set_comment(new.comment.as_str());
+                    stat.set_attachment(new.attachment.as_deref());
+                    if let Some(memo_time) = new.memo_time {
+                        stat.set_memo_time(memo_time);
+                    }
+                    stat.set_splits(new.splits.clone());
+                    current[idx] = new.clone();
+                }
+                Some(_) => {}
+                None => {
+                    // added on another machine
+                    let idx = current.partition_point(|solve| solve.date <= new.date);
+                    self.stats
+                        .insert_synced(idx as u32, &SolveStat::from_saved(self.tx.clone(), new));
+                    current.insert(idx, new.clone());
+                }
+            }
+        }
+    }
+
+    /// Pick the session back up from an autosave left by a crash, asking what to do with
+    /// the solve that was running (if any)
+    fn recover_session(&mut self, state: autosave::SessionState) {
+        for saved in &state.solves {
+            self.stats
+                .append_restored(&SolveStat::from_saved(self.tx.clone(), saved));
+        }
+        if let Some(seed) = state.scramble_seed {
+            self.stats.set_seed_text(&seed.text);
+            self.timer.set_scramble_seed(Some(seed));
+        }
+        if !state.scramble.is_empty() {
+            self.timer.use_scramble(state.scramble);
+            self.fmc.validate(self.timer.current_scramble());
+        }
+
+        let Some(started_at) = state.solve_started else {
+            self.toasts
+                .add_toast(adw::Toast::new(&gettext("Recovered the previous session")));
+            return;
+        };
+        let dialog = adw::AlertDialog::new(
+            Some(&gettext("Recover Interrupted Solve?")),
+            Some(&gettext(
+                "PuzzleTime closed unexpectedly while a solve was running. It can be recorded \
+                 as a DNF, or resumed as if the timer had kept running.",
+            )),
+        );
+        dialog.add_responses(&[
+            ("discard", &gettext("Discard")),
+            ("dnf", &gettext("Record DNF")),
+            ("resume", &gettext("Resume")),
+        ]);
+        dialog.set_response_appearance("discard", adw::ResponseAppearance::Destructive);
+        dialog.set_response_appearance("resume", adw::ResponseAppearance::Suggested);
+        dialog.set_default_response(Some("resume"));
+        // closing without choosing shouldn't lose the solve
+        dialog.set_close_response("dnf");
+
+        let window = self.window.clone();
+        let tx = self.tx.clone();
+        glib::spawn_future_local(async move {
+            let resume = match dialog.choose_future(&window).await.as_str() {
+                "resume" => true,
+                "dnf" => false,
+                _ => return,
+            };
+            send_evt(tx, TimerEvent::RecoverSolve { started_at, resume });
+        });
+    }
+
+    fn set_mode(&mut self, mode: Mode) {
+        if self.timer.running() || self.timer.inspecting() {
+            return;
+        }
+        self.mode = mode;
+        self.cross_trainer.set_visible(mode == Mode::CrossTrainer);
+        self.alg_trainer.set_visible(mode == Mode::AlgTrainer);
+        self.timer.set_case_algs(if mode == Mode::AlgTrainer {
+            self.alg_trainer.case_algs()
+        } else {
+            Vec::new()
+        });
+        self.multi_blind.set_visible(mode == Mode::MultiBlind);
+        self.fmc.set_visible(mode == Mode::Fmc);
+        self.timer.set_blind(mode == Mode::Blind);
+        self.stats.set_blind(mode == Mode::Blind);
+        settings::settings()
+            .set_string("mode", mode.action_target())
+            .unwrap();
+        if let Some(act) = self
+            .application
+            .lookup_action("mode")
+            .and_downcast::<gio::SimpleAction>()
+        {
+            act.set_state(&mode.action_target().to_variant());
+        }
+    }
+
+    fn set_focus_mode(&mut self, focus_mode: bool) {
+        if focus_mode == self.focus_mode {
+            return;
+        }
+        self.focus_mode = focus_mode;
+        self.timer.set_focus_mode(focus_mode);
+        self.timer_tbview.set_reveal_top_bars(!focus_mode);
+        if focus_mode {
+            self.sidebar_was_shown = self.stats_split.shows_sidebar();
+            self.stats_split.set_show_sidebar(false);
+            self.stats_sheet.set_reveal_child(false);
+            self.window.fullscreen();
+            if let Some(accel) = keybindings::accel_label("app.focus-mode") {
+                self.toasts.add_toast(adw::Toast::new(&gettext_f(
+                    "Press {accel} to exit focus mode",
+                    &[("accel", &accel)],
+                )));
+            }
+        } else {
+            self.stats_split.set_show_sidebar(self.sidebar_was_shown);
+            self.window.unfullscreen();
+        }
+    }
+
+    /// Flag `stat` if a smart cube showed it wasn't started from the scrambled state
+    fn check_misscramble(&self, stat: &SolveStat) {
+        let Some(state) = self.solve_start_state else {
+            return;
+        };
+        if state == stat.scramble_state() {
+            return;
+        }
+        stat.set_misscrambled_state(state);
+        let penalty = match settings::settings().string("misscramble-action").as_str() {
+            "plus2" => Penalty::Plus2,
+            "dnf" => Penalty::Dnf,
+            _ => Penalty::None,
+        };
+        // a +2 goes on top of any inspection penalty, and there's nothing more to do to
+        // a DNF
+        let applied = penalty != Penalty::None && stat.penalty() != Penalty::Dnf;
+        if applied {
+            stat.apply_penalty(penalty, PenaltySource::Misscramble);
+        }
+        self.toasts
+            .add_toast(adw::Toast::new(&match (applied, penalty) {
+                (true, Penalty::Plus2) => {
+                    gettext("The cube wasn't in the scrambled state, +2 applied")
+                }
+                (true, _) => gettext("The cube wasn't in the scrambled state, result is a DNF"),
+                (false, _) => gettext("The cube wasn't in the scrambled state"),
+            }));
+    }
+
+    /// Treat the smart cube's current state as the scramble, so that it can be solved
+    /// from wherever it is
+    fn scramble_from_cube(&mut self) {
+        // the action is only enabled once the solver is ready
+        let Some(solver) = self.timer.solver().cloned() else {
+            return;
+        };
+        let msg = match &self.cube_state {
+            None => gettext("Connect a smart cube first"),
+            Some(state) if *state == cubestruct::CubieCube::SOLVED => {
+                gettext("The cube is already solved")
+            }
+            Some(state) => match solver.scramble_to_state(state) {
+                Some(scramble) => {
+                    self.timer.use_scramble(scramble);
+                    self.fmc.validate(self.timer.current_scramble());
+                    return;
+                }
+                None => gettext("The cube is in an impossible state"),
+            },
+        };
+        self.toasts.add_toast(adw::Toast::new(&msg));
+    }
+
+    fn solver_progress(&self, fraction: f32) {
+        // a late update after the tables are done
+        if self.timer.solver().is_some() {
+            return;
+        }
+        self.solver_banner.set_title(&gettext_f(
+            "Preparing random-state scrambles… {percent}%",
+            &[("percent", &format!("{:.0}", fraction * 100.0))],
+        ));
+        self.solver_banner.set_revealed(true);
+    }
+
+    fn solver_ready(&mut self, solver: cubestruct::SolverContext) {
+        self.solver_banner.set_revealed(false);
+        set_action_enabled(&self.application, "scramble-from-cube", true);
+        self.timer.solver_ready(solver);
+    }
+
+    /// Ask where to save an image of the session summary, then write it there
+    fn export_summary(&self) {
+        let mut summary = self.stats.summary();
+        summary.scramble_seed = self.timer.scramble_seed().map(|seed| seed.text.clone());
+        let window = self.window.clone();
+        let toasts = self.toasts.clone();
+
+        let filters = gio::ListStore::new::<gtk::FileFilter>();
+        for (name, mime) in [
+            (gettext("PNG Image"), "image/png"),
+            (gettext("SVG Image"), "image/svg+xml"),
+        ] {
+            let filter = gtk::FileFilter::new();
+            filter.set_name(Some(&name));
+            filter.add_mime_type(mime);
+            filters.append(&filter);
+        }
+        let dialog = gtk::FileDialog::builder()
+            .title(gettext("Export Session Summary"))
+            .initial_name("session-summary.png")
+            .filters(&filters)
+            .build();
+
+        glib::spawn_future_local(async move {
+            let Some(result) =
+                files::save(&dialog, &window, move |name| summary.render(name)).await
+            else {
+                // cancelled
+                return;
+            };
+            let msg = match result {
+                Ok(()) => gettext("Session summary exported"),
+                Err(e) => gettext_f(
+                    "Couldn't export session summary: {error}",
+                    &[("error", &e.to_string())],
+                ),
+            };
+            toasts.add_toast(adw::Toast::new(&msg));
+        });
+    }
+
+    /// Ask where to save an HTML report of the session, then write it there
+    fn export_report(&self) {
+        let mut report = self.stats.report();
+        report.summary.scramble_seed = self.timer.scramble_seed().map(|seed| seed.text.clone());
+        let window = self.window.clone();
+        let toasts = self.toasts.clone();
+
+        let filters = gio::ListStore::new::<gtk::FileFilter>();
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some(&gettext("HTML Document")));
+        filter.add_mime_type("text/html");
+        filters.append(&filter);
+        let dialog = gtk::FileDialog::builder()
+            .title(gettext("Export Session Report"))
+            .initial_name("session-report.html")
+            .filters(&filters)
+            .build();
+
+        glib::spawn_future_local(async move {
+            let Some(result) =
+                files::save(&dialog, &window, move |_| Ok(report.render().into_bytes())).await
+            else {
+                // cancelled
+                return;
+            };
+            let msg = match result {
+                Ok(()) => gettext("Session report exported"),
+                Err(e) => gettext_f(
+                    "Couldn't export session report: {error}",
+                    &[("error", &e.to_string())],
+                ),
+            };
+            toasts.add_toast(adw::Toast::new(&msg));
+        });
+    }
+
+    /// Ask for a text file with a scramble on each line, then use its scrambles
+    fn import_scrambles(&self) {
+        let window = self.window.clone();
+        let toasts = self.toasts.clone();
+        let tx = self.tx.clone();
+
+        let filters = gio::ListStore::new::<gtk::FileFilter>();
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some(&gettext("Text Files")));
+        filter.add_mime_type("text/plain");
+        filters.append(&filter);
+        let dialog = gtk::FileDialog::builder()
+            .title(gettext("Import Scrambles"))
+            .filters(&filters)
+            .build();
+
+        glib::spawn_future_local(async move {
+            let Some(contents) = files::open(&dialog, &window).await else {
+                // cancelled
+                return;
+            };
+            let scrambles = contents.and_then(|contents| {
+                timer::parse_scramble_list(&String::from_utf8_lossy(&contents))
+            });
+            match scrambles {
+                Ok(scrambles) => send_evt(tx, TimerEvent::ScramblesImported(scrambles)),
+                Err(e) => toasts.add_toast(adw::Toast::new(&gettext_f(
+                    "Couldn't import scrambles: {error}",
+                    &[("error", &e.to_string())],
+                ))),
+            }
+        });
+    }
+
+    /// Ask how many groups of scrambles to generate and where to save them, then write a
+    /// printable PDF
+    fn export_scramble_sheet(&self) {
+        let window = self.window.clone();
+        let toasts = self.toasts.clone();
+
+        let dialog = adw::AlertDialog::new(
+            Some(&gettext("Export Scramble Sheet")),
+            Some(&gettext("Each group has 5 scrambles and 2 extras")),
+        );
+        let groups_spin = gtk::SpinButton::with_range(1.0, 26.0, 1.0);
+        groups_spin.set_value(1.0);
+        groups_spin.set_halign(gtk::Align::Center);
+        dialog.set_extra_child(Some(&groups_spin));
+        dialog.add_responses(&[
+            ("cancel", &gettext("Cancel")),
+            ("export", &gettext("Export…")),
+        ]);
+        dialog.set_response_appearance("export", adw::ResponseAppearance::Suggested);
+        dialog.set_default_response(Some("export"));
+        dialog.set_close_response("cancel");
+
+        let filters = gio::ListStore::new::<gtk::FileFilter>();
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some(&gettext("PDF Document")));
+        filter.add_mime_type("application/pdf");
+        filters.append(&filter);
+        let file_dialog = gtk::FileDialog::builder()
+            .title(gettext("Export Scramble Sheet"))
+            .initial_name("scrambles.pdf")
+            .filters(&filters)
+            .build();
+
+        glib::spawn_future_local(async move {
+            if dialog.choose_future(&window).await != "export" {
+                return;
+            }
+            let sheet = scramble_sheet::ScrambleSheet::generate(groups_spin.value_as_int() as u32);
+            let Some(result) = files::save(&file_dialog, &window, move |_| sheet.render()).await
+            else {
+                // cancelled
+                return;
+            };
+            let msg = match result {
+                Ok(()) => gettext("Scramble sheet exported"),
+                Err(e) => gettext_f(
+                    "Couldn't export scramble sheet: {error}",
+                    &[("error", &e.to_string())],
+                ),
+            };
+            toasts.add_toast(adw::Toast::new(&msg));
+        });
+    }
+
+    /// Handle an event about the timer or its scrambles
+    fn handle_timer_event(&mut self, evt: TimerEvent) {
+        match evt {
+            TimerEvent::Trigger(kind, trigger) => self.trigger(kind, trigger),
+            TimerEvent::NonSpacebarKeyDown(at) => {
+                if settings::settings().boolean("ignore-keys-while-turning") && self.cube_turning()
+                {
+                    return;
+                }
+                if self.timer.running() {
+                    if self.mode == Mode::Blind && self.timer.memo_time().is_none() {
+                        self.timer.split_memo(at);
+                    } else if !self.stop_too_soon() {
+                        self.trigger(InputKind::Keyboard, Trigger::Stop(at));
+                    }
+                }
+            }
+            TimerEvent::UpdateDisplayTime => {
+                self.timer.update_displayed_time();
+                self.update_overlay();
+            }
+            TimerEvent::GreenLightReady(since) => {
+                self.solve_input(SolveInput::HoldElapsed { since });
+            }
+            TimerEvent::NewScramble => {
+                if !self.timer.running() {
+                    self.timer.next_scramble();
+                    self.fmc.validate(self.timer.current_scramble());
+                }
+            }
+            TimerEvent::ScramblesReady(scrambles) => {
+                if self.timer.scrambles_ready(scrambles) {
+                    self.fmc.validate(self.timer.current_scramble());
+                }
+            }
+            TimerEvent::SetScrambleSeed(text) => {
+                if !self.timer.running() {
+                    self.timer
+                        .set_scramble_seed(text.map(timer::ScrambleSeed::new));
+                    self.fmc.validate(self.timer.current_scramble());
+                }
+            }
+            TimerEvent::ScrambleDifficulty { scramble, moves } => {
+                self.timer.difficulty_ready(scramble, moves);
+            }
+            TimerEvent::PreviousScramble => {
+                if !self.timer.running() {
+                    self.timer.previous_scramble();
+                    self.fmc.validate(self.timer.current_scramble());
+                }
+            }
+            TimerEvent::ReuseScramble(idx) => {
+                if !self.timer.running() {
+                    self.timer.reuse_scramble(idx);
+                    self.fmc.validate(self.timer.current_scramble());
+                }
+            }
+            TimerEvent::ScrambleFromCube => {
+                if !self.timer.running() {
+                    self.scramble_from_cube();
+                }
+            }
+            TimerEvent::UseScramble(scramble) => {
+                if !self.timer.running() {
+                    self.timer.use_scramble(scramble);
+                    self.fmc.validate(self.timer.current_scramble());
+                }
+            }
+            TimerEvent::ScramblesImported(scrambles) => {
+                if self.timer.running() {
+                    return;
+                }
+                self.toasts.add_toast(adw::Toast::new(&ngettext_f(
+                    "Imported {count} scramble",
+                    "Imported {count} scrambles",
+                    scrambles.len() as u32,
+                    &[("count", &scrambles.len().to_string())],
+                )));
+                self.timer.import_scrambles(scrambles);
+                self.fmc.validate(self.timer.current_scramble());
+            }
+            TimerEvent::RecoverSolve { started_at, resume } => {
+                if self.timer.running() || self.timer.inspecting() {
+                    return;
+                }
+                if resume {
+                    self.timer.resume(started_at);
+                    self.solve_input(SolveInput::Resume);
+                    // the turns so far, if they were checkpointed, so its reconstruction
+                    // isn't lost
+                    if let Some(solve) = self
+                        .checkpointed_solve
+                        .take()
+                        .filter(|solve| solve.started_at == started_at)
+                    {
+                        self.solve_start_state = Some(solve.start_state);
+                        self.timer.restore_turns(solve.turns);
+                    }
+                    if let Some(dbus) = &self.dbus {
+                        dbus.timer_started();
+                    }
+                    self.spectators
+                        .timer_changed(true, self.timer.elapsed().unwrap_or_default());
+                } else {
+                    let elapsed =
+                        Duration::from_micros((glib::real_time() - started_at).max(0) as u64);
+                    let stat = SolveStat::new(self.tx.clone(), elapsed, self.timer.take_scramble());
+                    stat.apply_penalty(Penalty::Dnf, PenaltySource::Interrupted);
+                    self.stats.append_stat(&stat);
+                }
+            }
+        }
+    }
+
+    /// Handle an event about the results
+    fn handle_stats_event(&mut self, evt: StatsEvent) {
+        match evt {
+            StatsEvent::ShowStat(idx) => {
+                stats::stat_info_dialog(self.tx.clone(), &self.stats.get_stat(idx).unwrap(), idx)
+                    .present(&self.window);
+            }
+            StatsEvent::DeleteStat(idx) => {
+                let changes = self.stats.record_changes(idx);
+                if changes.is_empty() {
+                    self.trash_stat(idx);
+                } else {
+                    self.confirm_delete(idx, &changes);
+                }
+            }
+            StatsEvent::TrashStat(idx) => {
+                self.trash_stat(idx);
+            }
+            StatsEvent::RestoreTrashedStat(id) => {
+                if self.stats.restore(id).is_none() {
+                    self.toasts
+                        .add_toast(adw::Toast::new(&gettext("Result was permanently deleted")));
+                }
+            }
+            StatsEvent::RestoreSession(ids) => {
+                for id in ids {
+                    self.stats.restore(id);
+                }
+            }
+            StatsEvent::DeleteTrashedStat(id) => {
+                self.stats.delete_permanently(id);
+            }
+            StatsEvent::ShowTrash => {
+                stats::trash_dialog(self.tx.clone(), &self.stats.trash()).present(&self.window);
+            }
+            StatsEvent::ShowCalendar => {
+                calendar::calendar_dialog(&self.stats.dated_times()).present(&self.window);
+            }
+            StatsEvent::CopyResult => match self.stats.share_latest() {
+                Some(text) => {
+                    gdk::Display::default().unwrap().clipboard().set_text(&text);
+                    self.toasts
+                        .add_toast(adw::Toast::new(&gettext("Copied to clipboard")));
+                }
+                None => {
+                    self.toasts
+                        .add_toast(adw::Toast::new(&gettext("Not enough results to copy yet")));
+                }
+            },
+            StatsEvent::CopyMarkdown(n) => match self.stats.share_latest_markdown(n) {
+                Some(text) => {
+                    gdk::Display::default().unwrap().clipboard().set_text(&text);
+                    self.toasts
+                        .add_toast(adw::Toast::new(&gettext("Copied to clipboard")));
+                }
+                None => {
+                    self.toasts
+                        .add_toast(adw::Toast::new(&gettext("Not enough results to copy yet")));
+                }
+            },
+            StatsEvent::DeleteLastStat => {
+                if self.stats.length() > 0 {
+                    send_evt(
+                        self.tx.clone(),
+                        StatsEvent::DeleteStat(self.stats.length() - 1),
+                    );
+                }
+            }
+            StatsEvent::TogglePenaltyLast(penalty) => {
+                if let Some(stat) = self.stats.last_stat() {
+                    stat.toggle_penalty(penalty);
+                }
+            }
+            StatsEvent::Changed => {
+                self.stats.update_stats();
+                self.update_overlay();
+                if let Some(dbus) = &self.dbus {
+                    dbus.practice_changed(self.stats.current_average(5));
+                }
+                self.sync.schedule(self.tx.clone());
+            }
+        }
+    }
+
+    fn trash_stat(&mut self, idx: u32) {
+        self.stats.remove(idx);
+        let id = self.stats.last_trashed().unwrap();
+
+        let toast = adw::Toast::new(&gettext_f(
+            "Result {number} Moved to Trash",
+            &[("number", &(idx + 1).to_string())],
+        ));
+        toast.set_button_label(Some(&gettext("Undo")));
+        let tx = self.tx.clone();
+        toast.connect_button_clicked(move |_| {
+            send_evt(tx.clone(), StatsEvent::RestoreTrashedStat(id));
+        });
+        self.toasts.add_toast(toast);
+    }
+
+    /// Ask before deleting the stat at `idx`, listing the best averages that would change
+    fn confirm_delete(&self, idx: u32, changes: &[stats::RecordChange]) {
+        let lines = changes
+            .iter()
+            .map(|change| {
+                gettext_f(
+                    "Best ao{n}: {before} → {after}",
+                    &[
+                        ("n", &change.n.to_string()),
+                        ("before", &stats::render_average(change.before)),
+                        (
+                            "after",
+                            &change
+                                .after
+                                .map_or_else(|| gettext("none"), stats::render_average),
+                        ),
+                    ],
+                )
+            })
+            .collect::<Vec<_>>();
+        let dialog = adw::AlertDialog::new(
+            Some(&gettext("Delete a Record Solve?")),
+            Some(&format!(
+                "{}\n\n{}",
+                gettext_f(
+                    "Result {number} is part of a best average, which will change:",
+                    &[("number", &(idx + 1).to_string())],
+                ),
+                lines.join("\n"),
+            )),
+        );
+        dialog.add_responses(&[
+            ("cancel", &gettext("Cancel")),
+            ("delete", &gettext("Delete")),
+        ]);
+        dialog.set_response_appearance("delete", adw::ResponseAppearance::Destructive);
+        dialog.set_default_response(Some("cancel"));
+        dialog.set_close_response("cancel");
+
+        let tx = self.tx.clone();
+        dialog.connect_response(Some("delete"), move |_, _| {
+            send_evt(tx.clone(), StatsEvent::TrashStat(idx));
+        });
+        dialog.present(&self.window);
     }
+
+    /// Handle an event about bluetooth devices, including smart cubes
+    fn handle_bluetooth_event(&mut self, evt: BluetoothEvent) {
+        match evt {
+            BluetoothEvent::ShowPopup => {
+                self.bluetooth.maybe_init();
+                self.bluetooth.start_scan();
+                self.bluetooth.dialog().present(&self.window);
+            }
+            BluetoothEvent::ShowLatencyMeter => {
+                self.latency.show(&self.window);
+            }
+            BluetoothEvent::Initialized(manager) => {
+                self.bluetooth.manager_ready(manager);
+            }
+            BluetoothEvent::Failed(e) => {
+                self.bluetooth.show_error(&e);
+            }
+            BluetoothEvent::Retry => {
+                self.bluetooth.retry();
+            }
+            BluetoothEvent::DeviceDiscovered(dev) => {
+                self.bluetooth.add_discovered_device(dev);
+            }
+            BluetoothEvent::DeviceConnected(id) => {
+                self.bluetooth.device_connected(id);
+            }
+            BluetoothEvent::DeviceDisconnected(id) => {
+                self.bluetooth.device_disconnected(id);
+                self.cube_state = None;
+                self.last_turn = None;
+                if let Some(dbus) = &self.dbus {
+                    dbus.cube_state_changed(None);
+                }
+                self.spectators.cube_changed(None);
+                self.recorder.cancel();
+            }
+            BluetoothEvent::DeviceIdle(id) => {
+                self.bluetooth.device_idle(id);
+            }
+            BluetoothEvent::Smartcube(evt) => {
+                if let smartcube::SmartcubeEvent::StateChange(state, instant) = &evt {
+                    self.latency.record(*instant);
+                    self.last_turn = Some(*instant);
+                    let state = &color_scheme::ColorScheme::current().orient(state);
+                    // after a restart, the cube carries on from the checkpointed state
+                    let last = self.cube_state.or(self.checkpointed_state.take());
+                    let moov = last.and_then(|last| last.move_to(state));
+                    self.cube_state = Some(*state);
+                    // cubes report their state again when they reconnect, which isn't a turn
+                    let turned = last != Some(*state);
+                    if last.is_some() && turned {
+                        self.count_turn();
+                    }
+                    if let Some(dbus) = &self.dbus {
+                        dbus.cube_state_changed(Some(state));
+                    }
+                    self.spectators.cube_changed(Some(state));
+                    if self.timer.running() && turned {
+                        self.recorder.turn(state, *instant);
+                        match self.mode {
+                            Mode::Normal => {
+                                let pb_stages = settings::settings()
+                                    .boolean("show-pb-pace")
+                                    .then(|| self.stats.pb_stages())
+                                    .flatten();
+                                self.timer.cube_turned(
+                                    moov,
+                                    *instant,
+                                    state.cfop_stage(),
+                                    pb_stages.as_deref(),
+                                );
+                            }
+                            Mode::AlgTrainer | Mode::MultiBlind | Mode::Fmc => {}
+                            Mode::CrossTrainer => {
+                                if self.cross_trainer.cube_moved(state) {
+                                    self.trigger(InputKind::Smartcube, Trigger::Stop(*instant));
+                                }
+                            }
+                            // the first turn ends memo
+                            Mode::Blind => self.timer.split_memo(*instant),
+                        }
+                    }
+                }
+                self.bluetooth.handle_smartcube_event(evt);
+            }
+            BluetoothEvent::StopScan => {
+                self.bluetooth.stop_scan();
+            }
+        }
+    }
+}
+
+/// A smart cube counts as being turned until this long after its last turn
+const CUBE_TURNING_PERIOD: Duration = Duration::from_millis(500);
+
+/// Build the solver's tables on a worker thread, so the first random-state scramble doesn't
+/// hold up the UI. Until they're ready, scrambles are random-move ones. Most of the tables
+/// are saved after the first run, so later ones only have to load them.
+fn build_solver_tables(tx: EventSender) {
+    send_evt(tx.clone(), Event::SolverProgress(0.0));
+    let path = glib::user_cache_dir()
+        .join(settings::APP_ID)
+        .join("solver-tables");
+    std::thread::spawn(move || {
+        let loaded = match std::fs::read(&path) {
+            Ok(bytes) => cubestruct::read_solver_tables(&bytes)
+                .inspect_err(|e| tracing::warn!("Not using saved solver tables: {e}"))
+                .is_ok(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+            Err(e) => {
+                tracing::warn!("Failed to read saved solver tables: {e}");
+                false
+            }
+        };
+        let solver = cubestruct::SolverContext::new(|fraction| {
+            send_evt(tx.clone(), Event::SolverProgress(fraction));
+        });
+        send_evt(tx, Event::SolverReady(solver));
+
+        if !loaded {
+            let mut contents = Vec::new();
+            cubestruct::write_solver_tables(&mut contents).unwrap();
+            let written = std::fs::create_dir_all(path.parent().unwrap())
+                .map_err(|e| e.to_string())
+                .and_then(|()| {
+                    glib::file_set_contents(&path, &contents).map_err(|e| e.to_string())
+                });
+            if let Err(e) = written {
+                tracing::warn!("Failed to save solver tables: {e}");
+            }
+        }
+    });
 }
 
-const TIMER_IDLE_HOLD_PERIOD: Duration = Duration::from_millis(500);
+/// Size the window and show the sidebar as they were when the window was last closed
+fn restore_window_state(window: &adw::ApplicationWindow, stats_split: &adw::OverlaySplitView) {
+    let settings = settings::settings();
+    window.set_default_size(settings.int("window-width"), settings.int("window-height"));
+    window.set_maximized(settings.boolean("window-maximized"));
+    stats_split.set_show_sidebar(settings.boolean("show-sidebar"));
+}
+
+fn save_window_state(window: &adw::ApplicationWindow, stats_split: &adw::OverlaySplitView) {
+    let settings = settings::settings();
+    if !window.is_maximized() && !window.is_fullscreen() {
+        let (width, height) = window.default_size();
+        settings.set_int("window-width", width).unwrap();
+        settings.set_int("window-height", height).unwrap();
+    }
+    settings
+        .set_boolean("window-maximized", window.is_maximized())
+        .unwrap();
+    // in focus mode (which is fullscreen) or a narrow window, the sidebar is hidden whatever
+    // the user chose
+    if !window.is_fullscreen() && !stats_split.is_collapsed() {
+        settings
+            .set_boolean("show-sidebar", stats_split.shows_sidebar())
+            .unwrap();
+    }
+}
+
+fn set_action_enabled(app: &adw::Application, name: &str, enabled: bool) {
+    if let Some(act) = app.lookup_action(name).and_downcast::<gio::SimpleAction>() {
+        act.set_enabled(enabled);
+    }
+}
 
 fn main() {
+    logging::init();
     gtk::init().unwrap();
     adw::init().unwrap();
+    i18n::init();
+    crash::install_hook();
 
     let application = adw::Application::builder()
-        .application_id("io.github.flying_toast.PuzzleTime")
+        .application_id(settings::APP_ID)
         .build();
 
-    application.connect_startup(|_| {
+    application.connect_startup(|application| {
         gio::resources_register_include!("PuzzleTime.gresource").unwrap();
         gtk::IconTheme::for_display(&gdk::Display::default().unwrap())
             .add_resource_path("/io/github/flying_toast/PuzzleTime/icons");
@@ -148,123 +1644,203 @@ fn main() {
             &provider,
             gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
         );
+
+        search_provider::register(application);
     });
 
+    // quitting normally, so there's nothing to recover next time
+    application.connect_shutdown(|_| autosave::clear());
+
     let application2 = application.clone();
 
     application2.clone().connect_activate(move |_| {
+        // launched again while running, possibly in the background
+        if let Some(window) = application.active_window() {
+            window.present();
+            return;
+        }
         let (tx, mut rx) = mpsc::unbounded();
         let tx2 = tx.clone();
         let mut app = CubeApp::new(application.clone(), tx);
+        if let Some(mode) = Mode::from_action_target(&settings::settings().string("mode")) {
+            app.set_mode(mode);
+        }
+        if let Some(state) = autosave::load() {
+            app.recover_session(state);
+        } else if sync::enabled() {
+            app.load_synced_history();
+        }
+        crash::offer_report(&app.toasts);
+        if sync::enabled() {
+            send_evt(tx2.clone(), Event::Sync);
+        }
+        let tx3 = tx2.clone();
+        glib::timeout_add_local(checkpoint::INTERVAL, move || {
+            send_evt(tx3.clone(), Event::Checkpoint);
+            glib::ControlFlow::Continue
+        });
 
         glib::spawn_future_local(async move {
             while let Some(evt) = rx.next().await {
+                // these happen many times a second and don't change anything that's saved
+                let autosave = !matches!(
+                    evt,
+                    Event::Timer(TimerEvent::UpdateDisplayTime)
+                        | Event::FmcTick
+                        | Event::Checkpoint
+                );
                 match evt {
+                    Event::Timer(evt) => app.handle_timer_event(evt),
+                    Event::Stats(evt) => app.handle_stats_event(evt),
+                    Event::Bluetooth(evt) => app.handle_bluetooth_event(evt),
                     Event::Quit => {
+                        save_window_state(&app.window, &app.stats_split);
+                        app.checkpoint();
                         app.application.quit();
                     }
-                    Event::SpacebarDown => {
-                        if app.spacebar_being_held {
-                            continue;
-                        }
-                        app.spacebar_being_held = true;
-                        app.timer_ready = false;
-
-                        if app.timer.running() {
-                            app.stop_timer();
-                        } else {
-                            app.timer.red_light_on();
-                            let tx2 = tx2.clone();
-                            app.greenlight_timeout =
-                                Some(glib::timeout_add(TIMER_IDLE_HOLD_PERIOD, move || {
-                                    send_evt(tx2.clone(), Event::GreenLightReady);
-                                    glib::ControlFlow::Break
-                                }));
-                        }
+                    Event::ShowRaceDialog => {
+                        race::join_dialog(tx2.clone(), &app.window);
                     }
-                    Event::SpacebarUp => {
-                        if !app.spacebar_being_held {
-                            continue;
-                        }
-                        app.spacebar_being_held = false;
-
-                        if !app.timer_ready {
-                            if let Some(srcid) = app.greenlight_timeout.take() {
-                                srcid.remove();
-                            }
-                            app.timer.lights_off();
-                            continue;
-                        }
-
-                        app.timer_ready = false;
-                        app.timer.start();
+                    Event::JoinRace { server, room, name } => {
+                        app.race.join(&server, room.clone(), name);
+                        // everyone in the room races on the scrambles seeded by its name
+                        app.stats.set_seed_text(&room);
+                        app.timer
+                            .set_scramble_seed(Some(timer::ScrambleSeed::new(room)));
+                        app.fmc.validate(app.timer.current_scramble());
+                    }
+                    Event::LeaveRace => {
+                        app.race.leave();
                     }
-                    Event::NonSpacebarKeyDown => {
-                        if app.timer.running() {
-                            app.stop_timer();
+                    Event::RaceMessage { id, message } => {
+                        app.race.message(id, message);
+                    }
+                    Event::RaceEnded { id, error } => {
+                        app.race.ended(id, error);
+                    }
+                    Event::TrainerSetChanged => {
+                        app.alg_trainer.load_set();
+                        if app.mode == Mode::AlgTrainer {
+                            app.timer.set_case_algs(app.alg_trainer.case_algs());
                         }
                     }
-                    Event::UpdateDisplayTime => {
-                        app.timer.update_displayed_time();
+                    Event::SolverProgress(fraction) => {
+                        app.solver_progress(fraction);
                     }
-                    Event::GreenLightReady => {
-                        app.greenlight_timeout = None;
-                        app.timer_ready = true;
-                        app.timer.both_lights_on();
+                    Event::SolverReady(solver) => {
+                        app.solver_ready(solver);
                     }
-                    Event::ShowStat(idx) => {
-                        stats::stat_info_dialog(
-                            tx2.clone(),
-                            &app.stats.get_stat(idx).unwrap(),
-                            idx,
-                        )
-                        .present(&app.window);
-                    }
-                    Event::DeleteStat(idx) => {
-                        let backup_stat = app.stats.get_stat(idx).unwrap();
-                        app.stats.set_backup((idx, backup_stat));
-                        app.stats.remove(idx);
-
-                        let toast = adw::Toast::new(&format!("Result {} Deleted", idx + 1));
-                        toast.set_button_label(Some("Undo"));
-                        toast.set_action_name(Some("app.undo-remove-stat"));
-                        app.toasts.add_toast(toast);
-                    }
-                    Event::RestoreDeletedStat => {
-                        if let Some((idx, stat)) = app.stats.take_backup() {
-                            app.stats.insert_stat(idx, &stat);
-                        } else {
+                    Event::ShowPreferences => {
+                        preferences::preferences_dialog().present(&app.window);
+                    }
+                    Event::ToggleFocusMode => {
+                        app.set_focus_mode(!app.focus_mode);
+                    }
+                    Event::SetMode(mode) => {
+                        app.set_mode(mode);
+                    }
+                    Event::MultiBlindResult(result) => {
+                        app.multi_blind.record(result);
+                    }
+                    Event::FmcStart => {
+                        app.fmc.start();
+                    }
+                    Event::Checkpoint => app.checkpoint(),
+                    Event::FmcTick => {
+                        if app.fmc.tick() {
                             app.toasts
-                                .add_toast(adw::Toast::new("Failed to Undo Deletion"));
+                                .add_toast(adw::Toast::new(&gettext("Time's up!")));
                         }
                     }
-                    Event::StatsChanged => {
-                        app.stats.update_stats();
+                    Event::FmcSolutionChanged => {
+                        app.fmc.validate(app.timer.current_scramble());
                     }
-                    Event::ShowBluetoothPopup => {
-                        app.bluetooth.maybe_init();
-                        app.bluetooth.start_scan();
-                        app.bluetooth.dialog().present(&app.window);
+                    Event::FmcSubmit => {
+                        if let Some(move_count) = app.fmc.submit() {
+                            app.timer.next_scramble();
+                            app.toasts.add_toast(adw::Toast::new(&ngettext_f(
+                                "Recorded a {count} move solution",
+                                "Recorded a {count} move solution",
+                                move_count as u32,
+                                &[("count", &move_count.to_string())],
+                            )));
+                        }
+                    }
+                    Event::ExportReport => {
+                        app.export_report();
+                    }
+                    Event::ImportScrambles => {
+                        app.import_scrambles();
+                    }
+                    Event::ExportSummary => {
+                        app.export_summary();
+                    }
+                    Event::ExportScrambleSheet => {
+                        app.export_scramble_sheet();
+                    }
+                    Event::ExportSolveRecords => {
+                        app.export_solve_records();
                     }
-                    Event::BluetoothInitialized(manager) => {
-                        app.bluetooth.manager_ready(manager);
+                    Event::SendToRobot => {
+                        // GAN robots are found among the devices seen while scanning
+                        app.bluetooth.maybe_init();
+                        robot::send(
+                            tx2.clone(),
+                            app.bluetooth.manager().cloned(),
+                            app.timer.current_scramble().to_vec(),
+                        );
                     }
-                    Event::BluetoothDeviceDiscoverd(dev) => {
-                        app.bluetooth.add_discovered_device(dev);
+                    Event::RobotScrambled(result) => {
+                        let msg = match result {
+                            Ok(robot) => {
+                                gettext_f("Scramble sent to {robot}", &[("robot", &robot)])
+                            }
+                            Err(e) => gettext_f(
+                                "Couldn't send scramble to robot: {error}",
+                                &[("error", &e)],
+                            ),
+                        };
+                        app.toasts.add_toast(adw::Toast::new(&msg));
                     }
-                    Event::BluetoothDeviceConnected(id) => {
-                        app.bluetooth.device_connected(id);
+                    Event::ShowShortcutsDialog => {
+                        keybindings::shortcuts_dialog().present(&app.window);
                     }
-                    Event::BluetoothDeviceDisconnected(id) => {
-                        app.bluetooth.device_disconnected(id);
+                    Event::ShowDebugLogs => logging::show_dialog(&app.window),
+                    Event::Sync => {
+                        let local = app.saved_solves();
+                        app.sync.start(app.tx.clone(), local);
                     }
-                    Event::Smartcube(evt) => {
-                        app.bluetooth.handle_smartcube_event(evt);
+                    Event::SyncFinished { local, result } => {
+                        if let Some(e) = app.sync.finish(app.tx.clone(), &result) {
+                            app.toasts.add_toast(adw::Toast::new(&gettext_f(
+                                "Couldn't sync: {error}",
+                                &[("error", &e.to_string())],
+                            )));
+                        }
+                        if let Ok(merged) = result {
+                            app.apply_synced(&local, &merged);
+                        }
                     }
-                    Event::StopBluetoothScan => {
-                        app.bluetooth.stop_scan();
+                    Event::WcaRecords { wca_id, result } => {
+                        // the ID could have been changed while fetching
+                        if wca_id != wca::wca_id() {
+                            continue;
+                        }
+                        match result {
+                            Ok(records) => app.stats.set_official_records(wca_id.map(|_| records)),
+                            Err(e) => {
+                                tracing::warn!("Failed to fetch WCA results: {e}");
+                                app.toasts.add_toast(adw::Toast::new(&gettext_f(
+                                    "Couldn't get official results: {error}",
+                                    &[("error", &e.to_string())],
+                                )));
+                            }
+                        }
                     }
                 }
+                if autosave {
+                    app.autosave();
+                }
             }
         });
     });
@@ -0,0 +1,50 @@
+/// What the timer is being used to practice
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum Mode {
+    #[default]
+    Normal,
+    /// Stop the timer once a cross is solved (see [`crate::cross_trainer`])
+    CrossTrainer,
+    /// Scrambles set up cases from a set of algs (see [`crate::alg_trainer`])
+    AlgTrainer,
+    /// Blindfolded, with separate memo and execution times
+    Blind,
+    /// Several cubes blindfolded in one attempt (see [`crate::multi_blind`])
+    MultiBlind,
+    /// Fewest moves, with the solution typed in (see [`crate::fmc`])
+    Fmc,
+}
+
+impl Mode {
+    const ALL: [Self; 6] = [
+        Self::Normal,
+        Self::CrossTrainer,
+        Self::AlgTrainer,
+        Self::Blind,
+        Self::MultiBlind,
+        Self::Fmc,
+    ];
+
+    /// Target of the `app.mode` action for this mode
+    pub fn action_target(self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::CrossTrainer => "cross-trainer",
+            Self::AlgTrainer => "alg-trainer",
+            Self::Blind => "blind",
+            Self::MultiBlind => "multi-blind",
+            Self::Fmc => "fmc",
+        }
+    }
+
+    /// Whether solves in this mode start with WCA inspection (if enabled in the settings)
+    pub fn has_inspection(self) -> bool {
+        matches!(self, Self::Normal | Self::CrossTrainer)
+    }
+
+    pub fn from_action_target(target: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|mode| mode.action_target() == target)
+    }
+}
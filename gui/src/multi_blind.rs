@@ -0,0 +1,218 @@
+use crate::prelude::*;
+use crate::timer::{render_single, render_time, Precision};
+use std::cmp::Ordering;
+use std::fmt;
+use std::time::Duration;
+
+/// One multi-blind attempt. Unlike other events, a single result covers every cube in
+/// the attempt.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct MultiBlindResult {
+    pub attempted: u32,
+    pub solved: u32,
+    pub time: Duration,
+}
+
+impl MultiBlindResult {
+    /// Solved cubes minus unsolved cubes
+    pub fn points(&self) -> i64 {
+        i64::from(self.solved) - i64::from(self.attempted - self.solved)
+    }
+
+    /// Attempts that score negative points or solve fewer than 2 cubes are DNFs
+    pub fn is_dnf(&self) -> bool {
+        self.points() < 0 || self.solved < 2
+    }
+
+    /// Compare by WCA ranking: more points is better, then less time, then fewer unsolved
+    /// cubes. DNFs are worse than everything else.
+    pub fn cmp_rank(&self, other: &Self) -> Ordering {
+        match (self.is_dnf(), other.is_dnf()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => other
+                .points()
+                .cmp(&self.points())
+                .then(self.time.cmp(&other.time))
+                .then((self.attempted - self.solved).cmp(&(other.attempted - other.solved))),
+        }
+    }
+}
+
+impl fmt::Display for MultiBlindResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_dnf() {
+            write!(f, "DNF ({}/{})", self.solved, self.attempted)
+        } else {
+            write!(
+                f,
+                "{}/{} {}",
+                self.solved,
+                self.attempted,
+                render_time(&self.time, Precision::Tenths)
+            )
+        }
+    }
+}
+
+/// WCA time limit for attempting `cubes` cubes: 10 minutes per cube, up to an hour
+pub fn time_limit(cubes: u32) -> Duration {
+    Duration::from_secs(60 * 10 * u64::from(cubes.min(6)))
+}
+
+#[derive(Debug)]
+pub struct MultiBlind {
+    root: gtk::Box,
+    cubes_spin: gtk::SpinButton,
+    last_label: gtk::Label,
+    best_label: gtk::Label,
+    count_label: gtk::Label,
+    results: Vec<MultiBlindResult>,
+}
+
+impl MultiBlind {
+    pub fn new() -> Self {
+        let builder =
+            gtk::Builder::from_resource("/io/github/flying_toast/PuzzleTime/multi-blind.ui");
+        let cubes_spin: gtk::SpinButton = builder.object("cubes_spin").unwrap();
+        let limit_label: gtk::Label = builder.object("limit_label").unwrap();
+
+        let show_limit = move |spin: &gtk::SpinButton| {
+            let limit = time_limit(spin.value_as_int() as u32);
+            limit_label.set_label(&format!("{}:00", limit.as_secs() / 60));
+        };
+        show_limit(&cubes_spin);
+        cubes_spin.connect_value_changed(show_limit);
+
+        Self {
+            root: builder.object("root").unwrap(),
+            cubes_spin,
+            last_label: builder.object("last_label").unwrap(),
+            best_label: builder.object("best_label").unwrap(),
+            count_label: builder.object("count_label").unwrap(),
+            results: Vec::new(),
+        }
+    }
+
+    pub fn widget(&self) -> &gtk::Box {
+        &self.root
+    }
+
+    pub fn set_visible(&self, visible: bool) {
+        self.root.set_visible(visible);
+    }
+
+    /// Number of cubes in the next attempt
+    pub fn attempted(&self) -> u32 {
+        self.cubes_spin.value_as_int() as u32
+    }
+
+    /// Call when the timer stops: asks how many cubes were solved, then sends
+    /// [`Event::MultiBlindResult`]
+    pub fn finish(&self, tx: EventSender, window: &adw::ApplicationWindow, time: Duration) {
+        let attempted = self.attempted();
+        let dialog = adw::AlertDialog::new(
+            Some(&gettext("How Many Cubes Were Solved?")),
+            Some(&ngettext_f(
+                "{attempted} cube attempted in {time}",
+                "{attempted} cubes attempted in {time}",
+                attempted,
+                &[
+                    ("attempted", &attempted.to_string()),
+                    ("time", &render_single(&time)),
+                ],
+            )),
+        );
+        let solved_spin = gtk::SpinButton::with_range(0.0, attempted.into(), 1.0);
+        solved_spin.set_value(attempted.into());
+        solved_spin.set_halign(gtk::Align::Center);
+        dialog.set_extra_child(Some(&solved_spin));
+        dialog.add_responses(&[("discard", &gettext("Discard")), ("save", &gettext("Save"))]);
+        dialog.set_response_appearance("discard", adw::ResponseAppearance::Destructive);
+        dialog.set_response_appearance("save", adw::ResponseAppearance::Suggested);
+        dialog.set_default_response(Some("save"));
+        dialog.set_close_response("discard");
+
+        let window = window.clone();
+        glib::spawn_future_local(async move {
+            if dialog.choose_future(&window).await == "save" {
+                let result = MultiBlindResult {
+                    attempted,
+                    solved: solved_spin.value_as_int() as u32,
+                    time,
+                };
+                send_evt(tx, Event::MultiBlindResult(result));
+            }
+        });
+    }
+
+    pub fn record(&mut self, result: MultiBlindResult) {
+        self.results.push(result);
+
+        let mut last = result.to_string();
+        if !result.is_dnf() {
+            last = gettext_f(
+                "{result} ({points} pts)",
+                &[("result", &last), ("points", &result.points().to_string())],
+            );
+        }
+        self.last_label.set_label(&last);
+        let best = self.results.iter().min_by(|a, b| a.cmp_rank(b)).unwrap();
+        self.best_label.set_label(&best.to_string());
+        self.count_label.set_label(&self.results.len().to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(solved: u32, attempted: u32, secs: u64) -> MultiBlindResult {
+        MultiBlindResult {
+            attempted,
+            solved,
+            time: Duration::from_secs(secs),
+        }
+    }
+
+    #[test]
+    fn points() {
+        assert_eq!(result(5, 6, 0).points(), 4);
+        assert_eq!(result(2, 2, 0).points(), 2);
+        assert_eq!(result(2, 4, 0).points(), 0);
+        assert!(!result(2, 4, 0).is_dnf());
+        assert!(result(1, 2, 0).is_dnf());
+        assert!(result(2, 5, 0).is_dnf());
+    }
+
+    #[test]
+    fn ranking() {
+        // more points wins, even if slower
+        assert_eq!(
+            result(5, 6, 3000).cmp_rank(&result(3, 3, 1000)),
+            Ordering::Less
+        );
+        // same points, faster wins
+        assert_eq!(
+            result(3, 3, 900).cmp_rank(&result(4, 5, 1000)),
+            Ordering::Less
+        );
+        // same points and time, fewer missed wins
+        assert_eq!(
+            result(3, 3, 1000).cmp_rank(&result(4, 5, 1000)),
+            Ordering::Less
+        );
+        assert_eq!(
+            result(1, 2, 10).cmp_rank(&result(2, 4, 3000)),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn time_limits() {
+        assert_eq!(time_limit(2), Duration::from_secs(20 * 60));
+        assert_eq!(time_limit(6), Duration::from_secs(60 * 60));
+        assert_eq!(time_limit(20), Duration::from_secs(60 * 60));
+    }
+}
@@ -0,0 +1,100 @@
+//! Writes the current time and averages to a file, for streamers to use as a text source
+//! in OBS. The file is JSON if its extension is `.json`, otherwise plain text.
+use crate::settings::settings;
+use crate::stats::{render_average, Average};
+use std::path::PathBuf;
+
+/// What's shown on the overlay
+#[derive(Debug, Clone)]
+pub struct OverlayData {
+    /// As displayed by the timer
+    pub time: String,
+    pub running: bool,
+    pub ao5: Option<Average>,
+    pub ao12: Option<Average>,
+}
+
+impl OverlayData {
+    fn to_json(&self) -> String {
+        let average = |avg: Option<Average>| match avg {
+            Some(avg) => format!("\"{}\"", render_average(avg)),
+            None => "null".into(),
+        };
+        format!(
+            "{{\"time\": \"{}\", \"running\": {}, \"ao5\": {}, \"ao12\": {}}}\n",
+            self.time.replace('\\', "\\\\").replace('"', "\\\""),
+            self.running,
+            average(self.ao5),
+            average(self.ao12),
+        )
+    }
+
+    fn to_text(&self) -> String {
+        let average = |avg: Option<Average>| avg.map_or("-".into(), render_average);
+        format!(
+            "{}\nao5: {}\nao12: {}\n",
+            self.time,
+            average(self.ao5),
+            average(self.ao12)
+        )
+    }
+}
+
+/// Keeps the file set in the `overlay-file` setting up to date
+#[derive(Debug, Default)]
+pub struct Overlay {
+    /// Path and contents of the last write, to avoid rewriting the file on every tick
+    last_written: Option<(PathBuf, String)>,
+}
+
+impl Overlay {
+    pub fn update(&mut self, data: &OverlayData) {
+        let path = PathBuf::from(settings().string("overlay-file").as_str());
+        if path.as_os_str().is_empty() {
+            self.last_written = None;
+            return;
+        }
+        let contents = if path.extension().is_some_and(|ext| ext == "json") {
+            data.to_json()
+        } else {
+            data.to_text()
+        };
+        if self
+            .last_written
+            .as_ref()
+            .is_some_and(|(p, c)| *p == path && *c == contents)
+        {
+            return;
+        }
+
+        // write then rename, so that the file is never read half-written
+        let tmp_path = path.with_extension("tmp");
+        let result =
+            std::fs::write(&tmp_path, &contents).and_then(|()| std::fs::rename(&tmp_path, &path));
+        if let Err(e) = result {
+            tracing::warn!("Failed to write overlay file: {e}");
+        }
+        self.last_written = Some((path, contents));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn formats() {
+        let data = OverlayData {
+            time: "12.34".into(),
+            running: false,
+            ao5: Some(Average::Some(Duration::from_millis(11500))),
+            ao12: None,
+        };
+        assert_eq!(
+            data.to_json(),
+            "{\"time\": \"12.34\", \"running\": false, \"ao5\": \"11.50\", \"ao12\": null}\n"
+        );
+        assert_eq!(data.to_text(), "12.34\nao5: 11.50\nao12: -\n");
+    }
+}
@@ -0,0 +1,371 @@
+use crate::color_scheme::{side_name, side_setting_value, SIDES};
+use crate::cubes;
+use crate::prelude::*;
+use crate::settings::settings;
+use crate::stats::StatTile;
+use crate::sync;
+use cubestruct::Rotation;
+use std::cell::RefCell;
+
+pub fn preferences_dialog() -> adw::PreferencesDialog {
+    let builder =
+        gtk::Builder::from_resource("/io/github/flying_toast/PuzzleTime/preferences-dialog.ui");
+    let root: adw::PreferencesDialog = builder.object("root").unwrap();
+    let settings = settings();
+
+    let switches = [
+        ("smooth_timer", "smooth-timer"),
+        ("show_milliseconds", "show-milliseconds"),
+        ("show_scramble_diagram", "show-scramble-diagram"),
+        ("random_orientation", "random-orientation"),
+        ("dim_scramble", "dim-scramble-while-solving"),
+        ("inspection", "inspection"),
+        ("ignore_keys_while_turning", "ignore-keys-while-turning"),
+        ("show_pb_pace", "show-pb-pace"),
+        ("sound_timer", "sound-timer"),
+        ("sound_inspection", "sound-inspection"),
+        ("sound_pb", "sound-pb"),
+        ("sync_enabled", "sync-enabled"),
+        ("run_in_background", "run-in-background"),
+        ("spectator_enabled", "spectator-enabled"),
+        ("spectator_lan", "spectator-lan"),
+        ("record_solves", "record-solves"),
+        ("crash_reports", "crash-reports"),
+    ];
+    for (id, key) in switches {
+        let switch: adw::SwitchRow = builder.object(id).unwrap();
+        settings.bind(key, &switch, "active").build();
+    }
+
+    // values in the same order as each row's model
+    let choices: [(&str, &str, &'static [&str]); 4] = [
+        (
+            "running_display",
+            "running-time-display",
+            &["tenths", "hundredths", "seconds", "hidden"],
+        ),
+        (
+            "misscramble_action",
+            "misscramble-action",
+            &["warn", "plus2", "dnf"],
+        ),
+        ("robot", "robot", &["gan", "serial"]),
+        (
+            "auto_new_session",
+            "auto-new-session",
+            &["never", "midnight", "inactivity"],
+        ),
+    ];
+    for (id, key, values) in choices {
+        let row: adw::ComboRow = builder.object(id).unwrap();
+        settings
+            .bind(key, &row, "selected")
+            .mapping(move |variant, _| {
+                let value = variant.str()?;
+                let idx = values.iter().position(|&v| v == value)?;
+                Some((idx as u32).to_value())
+            })
+            .set_mapping(move |value, _| {
+                let idx = value.get::<u32>().ok()?;
+                Some(values.get(idx as usize)?.to_variant())
+            })
+            .build();
+    }
+
+    // in the same order as the `cube_top` and `cube_front` rows' models
+    for (id, key) in [("cube_top", "cube-top"), ("cube_front", "cube-front")] {
+        let row: adw::ComboRow = builder.object(id).unwrap();
+        settings
+            .bind(key, &row, "selected")
+            .mapping(|variant, _| {
+                let value = variant.str()?;
+                let idx = SIDES
+                    .iter()
+                    .position(|&side| side_setting_value(side) == value)?;
+                Some((idx as u32).to_value())
+            })
+            .set_mapping(|value, _| {
+                let idx = value.get::<u32>().ok()?;
+                Some(side_setting_value(*SIDES.get(idx as usize)?).to_variant())
+            })
+            .build();
+    }
+    let cube_top: adw::ComboRow = builder.object("cube_top").unwrap();
+    let cube_front: adw::ComboRow = builder.object("cube_front").unwrap();
+    let cube_front2 = cube_front.clone();
+    cube_top.connect_selected_notify(move |cube_top| {
+        // keep the front next to the top
+        let top = SIDES[cube_top.selected() as usize];
+        let front = SIDES[cube_front2.selected() as usize];
+        if Rotation::to_orientation(top, front).is_none() {
+            let adjacent = SIDES
+                .iter()
+                .position(|&side| Rotation::to_orientation(top, side).is_some())
+                .unwrap();
+            cube_front2.set_selected(adjacent as u32);
+        }
+        flag_invalid_front(cube_top, &cube_front2);
+    });
+    let cube_top2 = cube_top.clone();
+    cube_front.connect_selected_notify(move |cube_front| {
+        flag_invalid_front(&cube_top2, cube_front);
+    });
+    // the settings could have been changed outside the app
+    flag_invalid_front(&cube_top, &cube_front);
+
+    let color_scheme: adw::ExpanderRow = builder.object("color_scheme").unwrap();
+    let colors = settings.strv("color-scheme");
+    for (i, side) in SIDES.into_iter().enumerate() {
+        let dialog = gtk::ColorDialog::new();
+        dialog.set_with_alpha(false);
+        let button = gtk::ColorDialogButton::new(Some(dialog));
+        button.set_valign(gtk::Align::Center);
+        if let Some(rgba) = colors
+            .get(i)
+            .and_then(|color| gdk::RGBA::parse(color.as_str()).ok())
+        {
+            button.set_rgba(&rgba);
+        }
+        button.connect_rgba_notify(move |button| {
+            let settings = settings();
+            let mut colors = settings
+                .strv("color-scheme")
+                .iter()
+                .map(|color| color.as_str().to_owned())
+                .collect::<Vec<_>>();
+            colors.resize(SIDES.len(), String::new());
+            colors[i] = button.rgba().to_str().to_string();
+            settings.set_strv("color-scheme", colors).unwrap();
+        });
+        let row = adw::ActionRow::builder().title(side_name(side)).build();
+        row.add_suffix(&button);
+        color_scheme.add_row(&row);
+    }
+
+    let cubes_group: adw::PreferencesGroup = builder.object("cubes_group").unwrap();
+    let new_cube: adw::EntryRow = builder.object("new_cube").unwrap();
+    new_cube.connect_apply(|row| {
+        cubes::add(&row.text());
+        row.set_text("");
+    });
+    let cube_rows = RefCell::new(Vec::new());
+    fill_cubes(&cubes_group, &cube_rows);
+    let handler = std::cell::Cell::new(Some(
+        settings.connect_changed(Some("cubes"), move |_, _| {
+            fill_cubes(&cubes_group, &cube_rows)
+        }),
+    ));
+    let settings2 = settings.clone();
+    root.connect_closed(move |_| {
+        if let Some(handler) = handler.take() {
+            settings2.disconnect(handler);
+        }
+    });
+
+    let stat_tiles: gtk::ListBox = builder.object("stat_tiles").unwrap();
+    fill_stat_tiles(&stat_tiles);
+
+    for (id, key) in [
+        ("goal_solves", "goal-solves"),
+        ("goal_average", "goal-average"),
+        ("sub_x_threshold", "sub-x-threshold"),
+        ("manual_splits", "manual-splits"),
+        ("hold_duration", "hold-duration"),
+        ("min_solve_duration", "min-solve-duration"),
+        ("new_session_inactivity", "new-session-inactivity"),
+        ("idle_disconnect", "idle-disconnect"),
+        ("spectator_port", "spectator-port"),
+    ] {
+        let row: adw::SpinRow = builder.object(id).unwrap();
+        settings.bind(key, &row, "value").build();
+    }
+
+    for (id, key) in [
+        ("wca_id", "wca-id"),
+        ("sync_url", "sync-url"),
+        ("sync_username", "sync-username"),
+        ("robot_serial_port", "robot-serial-port"),
+    ] {
+        let row: adw::EntryRow = builder.object(id).unwrap();
+        settings.bind(key, &row, "text").build();
+    }
+
+    // kept in the keyring rather than the settings
+    let sync_password: adw::PasswordEntryRow = builder.object("sync_password").unwrap();
+    let sync_password2 = sync_password.clone();
+    glib::spawn_future_local(async move {
+        match sync::password().await {
+            Ok(password) => sync_password2.set_text(&password),
+            Err(e) => tracing::warn!("Failed to read the sync password: {e:#}"),
+        }
+    });
+    sync_password.connect_apply(|row| {
+        let password = row.text();
+        glib::spawn_future_local(async move {
+            if let Err(e) = sync::set_password(&password).await {
+                tracing::warn!("Failed to save the sync password: {e:#}");
+            }
+        });
+    });
+
+    let calibrate_hold: gtk::Button = builder.object("calibrate_hold").unwrap();
+    let root2 = root.clone();
+    calibrate_hold.connect_clicked(move |_| crate::hold_calibration::show(&root2));
+
+    let volume: gtk::Adjustment = builder.object("volume_adjustment").unwrap();
+    settings.bind("sound-volume", &volume, "value").build();
+
+    let overlay_file_row: adw::ActionRow = builder.object("overlay_file_row").unwrap();
+    let choose_overlay_file: gtk::Button = builder.object("choose_overlay_file").unwrap();
+    let clear_overlay_file: gtk::Button = builder.object("clear_overlay_file").unwrap();
+    let default_subtitle = overlay_file_row.subtitle().unwrap_or_default();
+    let clear_overlay_file2 = clear_overlay_file.clone();
+    let show_overlay_file = move |settings: &gio::Settings| {
+        let path = settings.string("overlay-file");
+        if path.is_empty() {
+            overlay_file_row.set_subtitle(&default_subtitle);
+        } else {
+            overlay_file_row.set_subtitle(&path);
+        }
+        clear_overlay_file2.set_sensitive(!path.is_empty());
+    };
+    show_overlay_file(&settings);
+    let handler = std::cell::Cell::new(Some(settings.connect_changed(
+        Some("overlay-file"),
+        move |settings, _| {
+            show_overlay_file(settings);
+        },
+    )));
+    let settings2 = settings.clone();
+    root.connect_closed(move |_| {
+        if let Some(handler) = handler.take() {
+            settings2.disconnect(handler);
+        }
+    });
+
+    let root2 = root.clone();
+    choose_overlay_file.connect_clicked(move |_| {
+        let dialog = gtk::FileDialog::builder()
+            .title(gettext("Overlay File"))
+            .initial_name("puzzletime-overlay.txt")
+            .build();
+        let window = root2.root().and_downcast::<gtk::Window>();
+        glib::spawn_future_local(async move {
+            let Ok(file) = dialog.save_future(window.as_ref()).await else {
+                // cancelled
+                return;
+            };
+            if let Some(path) = file.path() {
+                settings()
+                    .set_string("overlay-file", &path.to_string_lossy())
+                    .unwrap();
+            }
+        });
+    });
+    clear_overlay_file.connect_clicked(|_| settings().set_string("overlay-file", "").unwrap());
+
+    root
+}
+
+/// Replace the rows in `group` with one for each cube, with a button to remove it
+fn fill_cubes(group: &adw::PreferencesGroup, rows: &RefCell<Vec<adw::ActionRow>>) {
+    for row in rows.borrow_mut().drain(..) {
+        group.remove(&row);
+    }
+    for name in cubes::cubes() {
+        let row = adw::ActionRow::builder()
+            .title(&name)
+            .use_markup(false)
+            .build();
+        let button = gtk::Button::from_icon_name("user-trash-symbolic");
+        button.set_tooltip_text(Some(&gettext("Remove")));
+        button.set_valign(gtk::Align::Center);
+        button.add_css_class("flat");
+        // rebuilding happens in the settings' signal, so wait until this one is done
+        button.connect_clicked(move |_| {
+            let name = name.clone();
+            glib::idle_add_local_once(move || cubes::remove(&name));
+        });
+        row.add_suffix(&button);
+        group.add(&row);
+        rows.borrow_mut().push(row);
+    }
+}
+
+/// Add a row to `list` for every [`StatTile`], shown ones first, with a switch to show or
+/// hide it and buttons to move it up or down
+fn fill_stat_tiles(list: &gtk::ListBox) {
+    list.remove_all();
+    let shown = StatTile::shown();
+    let hidden = StatTile::ALL
+        .into_iter()
+        .filter(|tile| !shown.contains(tile));
+    for (i, tile) in shown.iter().copied().chain(hidden).enumerate() {
+        let is_shown = i < shown.len();
+        let row = adw::ActionRow::builder().title(tile.name()).build();
+
+        for (icon, tooltip, offset) in [
+            ("go-up-symbolic", gettext("Move Up"), -1),
+            ("go-down-symbolic", gettext("Move Down"), 1),
+        ] {
+            let button = gtk::Button::from_icon_name(icon);
+            button.set_tooltip_text(Some(&tooltip));
+            button.set_valign(gtk::Align::Center);
+            button.add_css_class("flat");
+            let target = i.checked_add_signed(offset).filter(|&t| t < shown.len());
+            button.set_sensitive(is_shown && target.is_some());
+            let (list, shown) = (list.clone(), shown.clone());
+            button.connect_clicked(move |_| {
+                if let Some(target) = target {
+                    let mut shown = shown.clone();
+                    shown.swap(i, target);
+                    set_stat_tiles(&list, &shown);
+                }
+            });
+            row.add_suffix(&button);
+        }
+
+        let switch = gtk::Switch::builder()
+            .active(is_shown)
+            .valign(gtk::Align::Center)
+            .build();
+        let (list, shown) = (list.clone(), shown.clone());
+        switch.connect_active_notify(move |switch| {
+            let mut shown = shown.clone();
+            if switch.is_active() {
+                shown.push(tile);
+            } else {
+                shown.retain(|&t| t != tile);
+            }
+            set_stat_tiles(&list, &shown);
+        });
+        row.add_suffix(&switch);
+        row.set_activatable_widget(Some(&switch));
+        list.append(&row);
+    }
+}
+
+fn set_stat_tiles(list: &gtk::ListBox, shown: &[StatTile]) {
+    let values = shown
+        .iter()
+        .map(|tile| tile.setting_value().to_string())
+        .collect::<Vec<_>>();
+    settings().set_strv("stat-tiles", values).unwrap();
+    // rebuild once the signal that called this is done with its row
+    let list = list.clone();
+    glib::idle_add_local_once(move || fill_stat_tiles(&list));
+}
+
+/// Mark the front side row if its side isn't next to the top side, in which case the cube is
+/// shown in the default orientation
+fn flag_invalid_front(cube_top: &adw::ComboRow, cube_front: &adw::ComboRow) {
+    let top = SIDES[cube_top.selected() as usize];
+    let front = SIDES[cube_front.selected() as usize];
+    if Rotation::to_orientation(top, front).is_some() {
+        cube_front.set_subtitle(&gettext("While scrambling"));
+        cube_front.remove_css_class("error");
+    } else {
+        cube_front.set_subtitle(&gettext("Has to be next to the top side"));
+        cube_front.add_css_class("error");
+    }
+}
@@ -1,4 +1,5 @@
-pub use crate::Event;
+pub use crate::i18n::{gettext, gettext_f, ngettext, ngettext_f};
+pub use crate::{BluetoothEvent, Event, StatsEvent, TimerEvent};
 pub use adw::prelude::*;
 pub use futures::prelude::*;
 pub use gtk::subclass::prelude::*;
@@ -6,7 +7,8 @@ pub use gtk::{gdk, gio, glib};
 
 pub type EventSender = futures::channel::mpsc::UnboundedSender<Event>;
 
-pub fn send_evt(mut tx: EventSender, evt: Event) {
+pub fn send_evt(mut tx: EventSender, evt: impl Into<Event>) {
+    let evt = evt.into();
     glib::spawn_future(async move {
         tx.send(evt).await.unwrap();
     });
@@ -0,0 +1,97 @@
+//! Diagrams of scrambled puzzles. Each puzzle's state type implements [`PuzzleDiagram`], so
+//! that the places that show a scramble (the timer view, the result details and the scramble
+//! sheets) don't need to know which puzzle it's for.
+use crate::color_scheme::ColorScheme;
+use crate::prelude::*;
+use cubestruct::Puzzle;
+use gtk::cairo;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+pub trait PuzzleDiagram: std::fmt::Debug {
+    /// Width of the diagram divided by its height
+    fn aspect_ratio(&self) -> f64;
+
+    /// Draw the diagram as large as it fits, centered in a `width`x`height` area
+    fn draw(&self, cr: &cairo::Context, width: f64, height: f64);
+}
+
+/// Gap between stickers, as a fraction of the sticker size
+const STICKER_GAP: f64 = 0.08;
+
+/// Draws the puzzle's net in the user's color scheme
+impl<P: Puzzle> PuzzleDiagram for P {
+    fn aspect_ratio(&self) -> f64 {
+        let net = self.net();
+        net.width / net.height
+    }
+
+    fn draw(&self, cr: &cairo::Context, width: f64, height: f64) {
+        let scheme = ColorScheme::current();
+        let net = self.net();
+        let scale = f64::min(width / net.width, height / net.height);
+        let x0 = (width - net.width * scale) / 2.0;
+        let y0 = (height - net.height * scale) / 2.0;
+
+        for sticker in net.stickers {
+            // shrink the sticker towards its center to leave a gap around it
+            let n = sticker.outline.len() as f64;
+            let cx = sticker.outline.iter().map(|p| p.0).sum::<f64>() / n;
+            let cy = sticker.outline.iter().map(|p| p.1).sum::<f64>() / n;
+            for &(x, y) in &sticker.outline {
+                let x = cx + (x - cx) * (1.0 - STICKER_GAP);
+                let y = cy + (y - cy) * (1.0 - STICKER_GAP);
+                cr.line_to(x0 + x * scale, y0 + y * scale);
+            }
+            cr.close_path();
+            let (r, g, b) = scheme.sticker_rgb(sticker.color);
+            cr.set_source_rgb(r, g, b);
+            cr.fill().unwrap();
+        }
+    }
+}
+
+/// Height of diagram widgets, which are as wide as their diagram's aspect ratio needs
+const WIDGET_HEIGHT: i32 = 180;
+
+/// A widget that shows `diagram`
+pub fn widget(diagram: impl PuzzleDiagram + 'static) -> gtk::DrawingArea {
+    let area = gtk::DrawingArea::new();
+    area.set_content_height(WIDGET_HEIGHT);
+    area.set_content_width((f64::from(WIDGET_HEIGHT) * diagram.aspect_ratio()) as i32);
+    area.set_draw_func(move |_, cr, width, height| {
+        diagram.draw(cr, width.into(), height.into());
+    });
+    area
+}
+
+/// Shows a diagram that can be changed, or nothing
+#[derive(Debug, Clone)]
+pub struct DiagramView {
+    area: gtk::DrawingArea,
+    diagram: Rc<RefCell<Option<Box<dyn PuzzleDiagram>>>>,
+}
+
+impl DiagramView {
+    /// Draws in `area`, keeping its height and setting its width for the diagram
+    pub fn new(area: gtk::DrawingArea) -> Self {
+        let diagram: Rc<RefCell<Option<Box<dyn PuzzleDiagram>>>> = Rc::default();
+        let diagram2 = diagram.clone();
+        area.set_draw_func(move |_, cr, width, height| {
+            if let Some(diagram) = &*diagram2.borrow() {
+                diagram.draw(cr, width.into(), height.into());
+            }
+        });
+        Self { area, diagram }
+    }
+
+    pub fn set_diagram(&self, diagram: Option<Box<dyn PuzzleDiagram>>) {
+        if let Some(diagram) = &diagram {
+            let height = f64::from(self.area.content_height());
+            self.area
+                .set_content_width((height * diagram.aspect_ratio()) as i32);
+        }
+        self.diagram.replace(diagram);
+        self.area.queue_draw();
+    }
+}
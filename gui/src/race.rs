@@ -0,0 +1,408 @@
+//! Racing other people online. Everyone in a race connects to a relay server, which passes
+//! messages between the people in the same room. The room's name is used as the scramble
+//! seed (see [`ScrambleSeed`]), so everyone gets the same scrambles in the same order, and
+//! round `n` is the `n`th scramble of the seeded stream.
+//!
+//! # Protocol
+//!
+//! Messages are lines of tab-separated fields, over a plain TCP connection. The client
+//! sends `join <room> <name>` first, then `result <round> <time>` after each solve, where
+//! `round` starts at 0 and `time` is in microseconds, or `dnf`. The server sends:
+//!
+//! - `joined <name>` when someone joins the room, including for everyone who was already in
+//!   it when the client joined
+//! - `left <name>` when someone leaves
+//! - `result <name> <round> <time>` when someone else sends a result
+//!
+//! Lines the client doesn't understand are ignored, so the server can send more.
+use crate::prelude::*;
+use crate::settings::settings;
+use crate::timer::render_single;
+use anyhow::Context;
+use futures::channel::mpsc;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Port of the relay server if the address doesn't have one
+const DEFAULT_PORT: u16 = 7532;
+
+/// How many of the latest rounds are shown
+const ROUNDS_SHOWN: usize = 5;
+
+/// A message from the relay server
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Message {
+    Joined(String),
+    Left(String),
+    Result {
+        name: String,
+        round: u64,
+        /// `None` if DNF
+        time: Option<Duration>,
+    },
+}
+
+impl Message {
+    fn parse(line: &str) -> Option<Self> {
+        let fields = line.split('\t').collect::<Vec<_>>();
+        match fields.as_slice() {
+            ["joined", name] => Some(Self::Joined(name.to_string())),
+            ["left", name] => Some(Self::Left(name.to_string())),
+            ["result", name, round, time] => Some(Self::Result {
+                name: name.to_string(),
+                round: round.parse().ok()?,
+                time: parse_time(time)?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Returns `None` if `time` isn't valid, or `Some(None)` if it's a DNF
+fn parse_time(time: &str) -> Option<Option<Duration>> {
+    if time == "dnf" {
+        Some(None)
+    } else {
+        time.parse()
+            .ok()
+            .map(|micros| Some(Duration::from_micros(micros)))
+    }
+}
+
+fn render_result(time: Option<Duration>) -> String {
+    time.map_or_else(|| "DNF".into(), |t| render_single(&t))
+}
+
+/// Ask where and as who to race, then send [`Event::JoinRace`]
+pub fn join_dialog(tx: EventSender, window: &adw::ApplicationWindow) {
+    let dialog = adw::AlertDialog::new(
+        Some(&gettext("Join Race")),
+        Some(&gettext(
+            "Everyone in the same room gets the same scrambles, and sees each other's times \
+             as they go",
+        )),
+    );
+    let list = gtk::ListBox::new();
+    list.set_selection_mode(gtk::SelectionMode::None);
+    list.add_css_class("boxed-list");
+    let entry_row = |title: String, text: &str| {
+        let row = adw::EntryRow::builder().title(title).text(text).build();
+        list.append(&row);
+        row
+    };
+    let server_row = entry_row(gettext("Server"), &settings().string("race-server"));
+    let room_row = entry_row(gettext("Room"), "");
+    let mut name = settings().string("race-name").to_string();
+    if name.is_empty() {
+        name = glib::real_name().to_string_lossy().into_owned();
+    }
+    let name_row = entry_row(gettext("Your Name"), &name);
+    dialog.set_extra_child(Some(&list));
+    dialog.add_responses(&[("cancel", &gettext("Cancel")), ("join", &gettext("Join"))]);
+    dialog.set_response_appearance("join", adw::ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("join"));
+    dialog.set_close_response("cancel");
+
+    let window = window.clone();
+    glib::spawn_future_local(async move {
+        if dialog.choose_future(&window).await != "join" {
+            return;
+        }
+        // tabs and newlines would break the protocol
+        let field = |row: &adw::EntryRow| row.text().trim().replace(['\t', '\n'], " ");
+        let (server, room, name) = (field(&server_row), field(&room_row), field(&name_row));
+        if server.is_empty() || room.is_empty() || name.is_empty() {
+            return;
+        }
+        settings().set_string("race-server", &server).unwrap();
+        settings().set_string("race-name", &name).unwrap();
+        send_evt(tx, Event::JoinRace { server, room, name });
+    });
+}
+
+/// The race that's being taken part in, shown in a bar under the timer
+#[derive(Debug)]
+pub struct Race {
+    tx: EventSender,
+    toaster: adw::ToastOverlay,
+    root: gtk::Box,
+    title_label: gtk::Label,
+    results_grid: gtk::Grid,
+    /// `None` when not in a race
+    session: Option<Session>,
+    /// Identifies connections, so that events from ones that were left can be ignored
+    next_id: u64,
+}
+
+#[derive(Debug)]
+struct Session {
+    id: u64,
+    room: String,
+    name: String,
+    /// Lines for the connection to send
+    outgoing: mpsc::UnboundedSender<String>,
+    connection: glib::JoinHandle<()>,
+    /// Everyone else in the room, in the order they joined
+    opponents: Vec<String>,
+    /// Everyone's results (including ours), by round and name
+    results: BTreeMap<u64, HashMap<String, Option<Duration>>>,
+}
+
+impl Race {
+    pub fn new(tx: EventSender, toaster: adw::ToastOverlay) -> Self {
+        let builder = gtk::Builder::from_resource("/io/github/flying_toast/PuzzleTime/race.ui");
+        let leave_button: gtk::Button = builder.object("leave_button").unwrap();
+        let tx2 = tx.clone();
+        leave_button.connect_clicked(move |_| send_evt(tx2.clone(), Event::LeaveRace));
+
+        Self {
+            tx,
+            toaster,
+            root: builder.object("root").unwrap(),
+            title_label: builder.object("title_label").unwrap(),
+            results_grid: builder.object("results_grid").unwrap(),
+            session: None,
+            next_id: 0,
+        }
+    }
+
+    pub fn widget(&self) -> &gtk::Box {
+        &self.root
+    }
+
+    /// Room of the race being taken part in, which is also the scramble seed
+    pub fn room(&self) -> Option<&str> {
+        self.session.as_ref().map(|session| session.room.as_str())
+    }
+
+    /// Connect to the relay `server` and join `room` as `name`, leaving any other race.
+    /// [`Event::RaceMessage`] is sent for each message from the server, and
+    /// [`Event::RaceEnded`] when the connection ends.
+    pub fn join(&mut self, server: &str, room: String, name: String) {
+        self.leave();
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let (outgoing, lines) = mpsc::unbounded();
+        outgoing
+            .unbounded_send(format!("join\t{room}\t{name}\n"))
+            .unwrap();
+        let server = server.to_string();
+        let tx = self.tx.clone();
+        let connection = glib::spawn_future_local(async move {
+            let error = match run_connection(&server, lines, id, tx.clone()).await {
+                Ok(()) => None,
+                Err(e) => Some(format!("{e:#}")),
+            };
+            send_evt(tx, Event::RaceEnded { id, error });
+        });
+
+        self.session = Some(Session {
+            id,
+            room,
+            name,
+            outgoing,
+            connection,
+            opponents: Vec::new(),
+            results: BTreeMap::new(),
+        });
+        self.update();
+    }
+
+    pub fn leave(&mut self) {
+        if let Some(session) = self.session.take() {
+            session.connection.abort();
+        }
+        self.update();
+    }
+
+    /// Share our result for `round` with the room, if in a race
+    pub fn submit(&mut self, round: u64, time: Option<Duration>) {
+        let Some(session) = &mut self.session else {
+            return;
+        };
+        let time_field = time.map_or_else(|| "dnf".into(), |t| t.as_micros().to_string());
+        // if the connection has ended, `RaceEnded` will say so
+        let _ = session
+            .outgoing
+            .unbounded_send(format!("result\t{round}\t{time_field}\n"));
+        session
+            .results
+            .entry(round)
+            .or_default()
+            .insert(session.name.clone(), time);
+        self.update();
+    }
+
+    /// Handle a message from [`Event::RaceMessage`]
+    pub fn message(&mut self, id: u64, message: Message) {
+        let Some(session) = self.session.as_mut().filter(|session| session.id == id) else {
+            return;
+        };
+        match message {
+            Message::Joined(name) => {
+                self.toaster.add_toast(adw::Toast::new(&gettext_f(
+                    "{name} joined the race",
+                    &[("name", &name)],
+                )));
+                session.opponents.push(name);
+            }
+            Message::Left(name) => {
+                self.toaster.add_toast(adw::Toast::new(&gettext_f(
+                    "{name} left the race",
+                    &[("name", &name)],
+                )));
+                session.opponents.retain(|opponent| *opponent != name);
+            }
+            Message::Result { name, round, time } => {
+                session.results.entry(round).or_default().insert(name, time);
+            }
+        }
+        self.update();
+    }
+
+    /// Handle [`Event::RaceEnded`]
+    pub fn ended(&mut self, id: u64, error: Option<String>) {
+        if self.session.as_ref().is_none_or(|session| session.id != id) {
+            return;
+        }
+        self.session = None;
+        self.update();
+        let msg = match error {
+            Some(error) => gettext_f("Disconnected from the race: {error}", &[("error", &error)]),
+            None => gettext("The race server closed the connection"),
+        };
+        self.toaster.add_toast(adw::Toast::new(&msg));
+    }
+
+    /// Show the latest rounds, with a column for each racer
+    fn update(&self) {
+        self.root.set_visible(self.session.is_some());
+        while let Some(child) = self.results_grid.first_child() {
+            self.results_grid.remove(&child);
+        }
+        let Some(session) = &self.session else {
+            return;
+        };
+        self.title_label
+            .set_label(&gettext_f("Racing in “{room}”", &[("room", &session.room)]));
+
+        let label = |text: &str, classes: &[&str]| {
+            let label = gtk::Label::new(Some(text));
+            for class in classes {
+                label.add_css_class(class);
+            }
+            label
+        };
+        let racers = std::iter::once(&session.name)
+            .chain(&session.opponents)
+            .collect::<Vec<_>>();
+        self.results_grid
+            .attach(&label(&gettext("Round"), &["heading"]), 0, 0, 1, 1);
+        for (column, racer) in racers.iter().enumerate() {
+            let title = if column == 0 {
+                gettext("You")
+            } else {
+                racer.to_string()
+            };
+            self.results_grid
+                .attach(&label(&title, &["heading"]), column as i32 + 1, 0, 1, 1);
+        }
+        for (row, (round, results)) in session.results.iter().rev().take(ROUNDS_SHOWN).enumerate() {
+            let row = row as i32 + 1;
+            self.results_grid.attach(
+                &label(&(round + 1).to_string(), &["numeric", "dim-label"]),
+                0,
+                row,
+                1,
+                1,
+            );
+            let fastest = results.values().flatten().min();
+            for (column, racer) in racers.iter().enumerate() {
+                let (text, classes): (_, &[&str]) = match results.get(*racer) {
+                    Some(time) if time.is_some() && time.as_ref() == fastest => {
+                        (render_result(*time), &["numeric", "success"])
+                    }
+                    Some(time) => (render_result(*time), &["numeric"]),
+                    None => ("-".into(), &["dim-label"]),
+                };
+                self.results_grid
+                    .attach(&label(&text, classes), column as i32 + 1, row, 1, 1);
+            }
+        }
+    }
+}
+
+/// Send `lines` to the relay server, and the messages it sends back as events, until
+/// either side closes the connection
+async fn run_connection(
+    server: &str,
+    mut lines: mpsc::UnboundedReceiver<String>,
+    id: u64,
+    tx: EventSender,
+) -> anyhow::Result<()> {
+    let connection = gio::SocketClient::new()
+        .connect_to_host_future(server, DEFAULT_PORT)
+        .await
+        .with_context(|| gettext_f("couldn't connect to {server}", &[("server", server)]))?;
+    let output = connection.output_stream();
+    let input = gio::DataInputStream::new(&connection.input_stream());
+
+    let send = async {
+        while let Some(line) = lines.next().await {
+            output
+                .write_all_future(line.into_bytes(), glib::Priority::DEFAULT)
+                .await
+                .map_err(|(_, e)| e)?;
+        }
+        anyhow::Ok(())
+    };
+    let receive = async {
+        while let Some(line) = input.read_line_utf8_future(glib::Priority::DEFAULT).await? {
+            if let Some(message) = Message::parse(line.trim_end_matches('\r')) {
+                send_evt(tx.clone(), Event::RaceMessage { id, message });
+            }
+        }
+        anyhow::Ok(())
+    };
+    futures::pin_mut!(send, receive);
+    match future::select(send, receive).await {
+        future::Either::Left((result, _)) | future::Either::Right((result, _)) => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_messages() {
+        assert_eq!(
+            Message::parse("joined\tAlex"),
+            Some(Message::Joined("Alex".into()))
+        );
+        assert_eq!(
+            Message::parse("left\tAlex"),
+            Some(Message::Left("Alex".into()))
+        );
+        assert_eq!(
+            Message::parse("result\tAlex\t3\t9870000"),
+            Some(Message::Result {
+                name: "Alex".into(),
+                round: 3,
+                time: Some(Duration::from_millis(9870)),
+            })
+        );
+        assert_eq!(
+            Message::parse("result\tAlex\t0\tdnf"),
+            Some(Message::Result {
+                name: "Alex".into(),
+                round: 0,
+                time: None,
+            })
+        );
+        assert_eq!(Message::parse("result\tAlex\t0\tfast"), None);
+        assert_eq!(Message::parse("chat\tAlex\thi"), None);
+    }
+}
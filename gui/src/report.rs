@@ -0,0 +1,346 @@
+//! Standalone HTML report of a session: the stats, a graph of the times and every solve with
+//! a link to reconstruct it online, in a single file that opens in any browser
+use crate::prelude::*;
+use crate::stats::{Average, Penalties, Penalty};
+use crate::summary::SessionSummary;
+use crate::timer::{render_single, render_time, Precision};
+use cubestruct::{Move, MoveSequence};
+use std::fmt::Write;
+use std::time::Duration;
+
+const GRAPH_WIDTH: f64 = 800.0;
+const GRAPH_HEIGHT: f64 = 240.0;
+const GRAPH_PADDING: f64 = 16.0;
+
+const STYLE: &str = "
+body { margin: 0 auto; max-width: 880px; padding: 24px; font-family: Cantarell, sans-serif;
+  background: #242428; color: #fff; }
+h1 { font-size: 28px; margin-bottom: 4px; }
+a { color: #78aeed; }
+.seed { opacity: 0.6; }
+.tiles { display: flex; gap: 12px; margin: 24px 0; }
+.tile { flex: 1; padding: 12px; border-radius: 8px; background: rgba(255, 255, 255, 0.08); }
+.tile .value { font-size: 24px; font-weight: bold; }
+.tile .title { opacity: 0.6; }
+svg { width: 100%; border-radius: 8px; background: rgba(255, 255, 255, 0.08); }
+.times { fill: none; stroke: #3584e4; stroke-width: 3; stroke-linejoin: round; }
+.averages { fill: none; stroke: #f6d32d; stroke-width: 2; stroke-dasharray: 6 4; }
+circle { fill: #3584e4; cursor: pointer; }
+circle.highlighted { fill: #fff; }
+.axis { fill: rgba(255, 255, 255, 0.6); font-size: 12px; }
+table { width: 100%; margin-top: 24px; border-collapse: collapse; }
+th, td { padding: 6px 8px; text-align: left; vertical-align: top; }
+th { opacity: 0.6; font-weight: normal; }
+tr.highlighted td { background: rgba(255, 255, 255, 0.12); }
+td.scramble { font-family: monospace; }
+";
+
+/// Clicking a point on the graph scrolls to its solve, and hovering over a solve picks out
+/// its point
+const SCRIPT: &str = "
+function highlight(idx, on) {
+  for (const el of document.querySelectorAll('[data-solve=\"' + idx + '\"]')) {
+    el.classList.toggle('highlighted', on);
+  }
+}
+for (const el of document.querySelectorAll('[data-solve]')) {
+  const idx = el.dataset.solve;
+  el.addEventListener('mouseenter', () => highlight(idx, true));
+  el.addEventListener('mouseleave', () => highlight(idx, false));
+  if (el.tagName === 'circle') {
+    el.addEventListener('click', () =>
+      document.getElementById('solve-' + idx).scrollIntoView({ behavior: 'smooth', block: 'center' }));
+  }
+}
+";
+
+/// A solve, as it's shown in a [`SessionReport`]
+#[derive(Debug, Clone)]
+pub struct ReportSolve {
+    /// As it's shared, e.g. `12.34+` for a time including a +2
+    pub result: String,
+    /// `None` if DNF
+    pub time: Option<Duration>,
+    pub penalties: Penalties,
+    /// Average of 5 ending with this solve, if there have been 5 solves
+    pub ao5: Option<Average>,
+    pub scramble: Vec<Move>,
+    /// When the solve was finished, formatted for display
+    pub date: String,
+    pub comment: String,
+}
+
+/// Snapshot of a session, for rendering as an HTML report
+#[derive(Debug, Clone)]
+pub struct SessionReport {
+    pub summary: SessionSummary,
+    /// Oldest first
+    pub solves: Vec<ReportSolve>,
+}
+
+impl SessionReport {
+    pub fn render(&self) -> String {
+        let title = gettext("Session Report");
+        let mut html = String::new();
+        // writing to a `String` can't fail
+        let _ = write!(
+            html,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+             <title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n",
+            title = escape(&title),
+        );
+        if let Some(seed) = &self.summary.scramble_seed {
+            let text = gettext_f("Scramble seed: {seed}", &[("seed", seed)]);
+            let _ = writeln!(html, "<div class=\"seed\">{}</div>", escape(&text));
+        }
+
+        html.push_str("<div class=\"tiles\">\n");
+        let tiles = [
+            (gettext("Solves"), self.solves.len().to_string()),
+            (
+                gettext("Best Single"),
+                self.summary
+                    .best_single
+                    .map(|t| render_single(&t))
+                    .unwrap_or_else(|| "-".into()),
+            ),
+            (gettext("Best Ao5"), render_average(self.summary.best_ao5)),
+            (gettext("Best Ao12"), render_average(self.summary.best_ao12)),
+            (gettext("Mean"), render_average(self.summary.mean)),
+        ];
+        for (title, value) in tiles {
+            let _ = writeln!(
+                html,
+                "<div class=\"tile\"><div class=\"value\">{}</div>\
+                 <div class=\"title\">{}</div></div>",
+                escape(&value),
+                escape(&title),
+            );
+        }
+        html.push_str("</div>\n");
+
+        self.write_graph(&mut html);
+        self.write_solves(&mut html);
+
+        let _ = write!(html, "<script>{SCRIPT}</script>\n</body>\n</html>\n");
+        html
+    }
+
+    /// SVG line graph of the non-DNF times and the averages of 5
+    fn write_graph(&self, html: &mut String) {
+        let times = self
+            .solves
+            .iter()
+            .enumerate()
+            .filter_map(|(i, solve)| solve.time.map(|t| (i, t.as_secs_f64())))
+            .collect::<Vec<_>>();
+        let averages = self
+            .solves
+            .iter()
+            .enumerate()
+            .filter_map(|(i, solve)| solve.ao5.and_then(average_secs).map(|secs| (i, secs)))
+            .collect::<Vec<_>>();
+        if self.solves.len() < 2 || times.is_empty() {
+            return;
+        }
+
+        // averages are never outside the range of the times
+        let min = times.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+        let max = times.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+        let range = if max > min { max - min } else { 1.0 };
+        let (inner_width, inner_height) = (
+            GRAPH_WIDTH - 2.0 * GRAPH_PADDING,
+            GRAPH_HEIGHT - 2.0 * GRAPH_PADDING,
+        );
+        let to_xy = |i: usize, secs: f64| {
+            (
+                GRAPH_PADDING + inner_width * i as f64 / (self.solves.len() - 1) as f64,
+                GRAPH_PADDING + inner_height * (1.0 - (secs - min) / range),
+            )
+        };
+        let points = |values: Vec<(usize, f64)>| {
+            values
+                .into_iter()
+                .map(|(i, secs)| {
+                    let (x, y) = to_xy(i, secs);
+                    format!("{x:.1},{y:.1}")
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        let _ = writeln!(
+            html,
+            "<svg viewBox=\"0 0 {GRAPH_WIDTH} {GRAPH_HEIGHT}\" role=\"img\" aria-label=\"{}\">",
+            escape(&gettext("Times")),
+        );
+        let _ = writeln!(
+            html,
+            "<polyline class=\"averages\" points=\"{}\"/>",
+            points(averages),
+        );
+        let _ = writeln!(
+            html,
+            "<polyline class=\"times\" points=\"{}\"/>",
+            points(times.clone()),
+        );
+        for (i, secs) in times {
+            let (x, y) = to_xy(i, secs);
+            let _ = writeln!(
+                html,
+                "<circle cx=\"{x:.1}\" cy=\"{y:.1}\" r=\"4\" data-solve=\"{number}\">\
+                 <title>{number}. {}</title></circle>",
+                escape(&self.solves[i].result),
+                number = i + 1,
+            );
+        }
+        for (secs, y) in [(max, GRAPH_PADDING - 2.0), (min, GRAPH_HEIGHT - 4.0)] {
+            let _ = writeln!(
+                html,
+                "<text class=\"axis\" x=\"4\" y=\"{y}\">{}</text>",
+                render_time(&Duration::from_secs_f64(secs), Precision::Hundredths),
+            );
+        }
+        html.push_str("</svg>\n");
+    }
+
+    /// Table of every solve, newest first like the stats list
+    fn write_solves(&self, html: &mut String) {
+        let _ = writeln!(
+            html,
+            "<table>\n<tr><th>#</th><th>{}</th><th>{}</th><th>{}</th><th>{}</th><th></th></tr>",
+            escape(&gettext("Time")),
+            escape(&gettext("Ao5")),
+            escape(&gettext("Scramble")),
+            escape(&gettext("Date")),
+        );
+        for (i, solve) in self.solves.iter().enumerate().rev() {
+            let number = i + 1;
+            let title = gettext_f("Solve {number}", &[("number", &number.to_string())]);
+            // the penalties in full, e.g. `+2+2` rather than just a `+`
+            let penalties = if solve.penalties.kind() == Penalty::None {
+                String::new()
+            } else {
+                format!(
+                    "<br><small>{}</small>",
+                    escape(&solve.penalties.to_string())
+                )
+            };
+            let comment = if solve.comment.is_empty() {
+                String::new()
+            } else {
+                format!("<br><small>{}</small>", escape(&solve.comment))
+            };
+            let _ = writeln!(
+                html,
+                "<tr id=\"solve-{number}\" data-solve=\"{number}\"><td>{number}</td>\
+                 <td>{}{penalties}</td><td>{}</td><td class=\"scramble\">{}{comment}</td><td>{}</td>\
+                 <td><a href=\"{}\" target=\"_blank\">{}</a></td></tr>",
+                escape(&solve.result),
+                escape(&render_average(solve.ao5)),
+                escape(&MoveSequence::new(&solve.scramble).to_string()),
+                escape(&solve.date),
+                escape(&reconstruction_url(&solve.scramble, &title)),
+                escape(&gettext("Reconstruct")),
+            );
+        }
+        html.push_str("</table>\n");
+    }
+}
+
+/// Link to alg.cubing.net with the cube set up in the scrambled state, ready for the solve
+/// to be typed in
+fn reconstruction_url(scramble: &[Move], title: &str) -> String {
+    // alg.cubing.net's own URL style, which keeps the moves readable
+    let setup = MoveSequence::new(scramble)
+        .to_string()
+        .replace(' ', "_")
+        .replace('\'', "-");
+    format!(
+        "https://alg.cubing.net/?setup={setup}&title={}",
+        glib::Uri::escape_string(title, None, true),
+    )
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn render_average(avg: Option<Average>) -> String {
+    avg.map(crate::stats::render_average)
+        .unwrap_or_else(|| "-".into())
+}
+
+fn average_secs(avg: Average) -> Option<f64> {
+    match avg {
+        Average::Dnf => None,
+        Average::Some(time) => Some(time.as_secs_f64()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cubestruct::parse_moves;
+
+    #[test]
+    fn escaping() {
+        assert_eq!(
+            escape("<b>\"R U R'\" & more</b>"),
+            "&lt;b&gt;&quot;R U R&#39;&quot; &amp; more&lt;/b&gt;"
+        );
+        assert_eq!(escape("12.34+"), "12.34+");
+    }
+
+    #[test]
+    fn reconstruction_urls() {
+        let scramble = parse_moves("R U' F2").unwrap();
+        assert_eq!(
+            reconstruction_url(&scramble, "Solve 1"),
+            "https://alg.cubing.net/?setup=R_U-_F2&title=Solve%201"
+        );
+    }
+
+    #[test]
+    fn report_contents() {
+        let solve = |secs: f64, comment: &str| ReportSolve {
+            result: render_time(&Duration::from_secs_f64(secs), Precision::Hundredths),
+            time: Some(Duration::from_secs_f64(secs)),
+            penalties: Penalties::default(),
+            ao5: None,
+            scramble: parse_moves("R U").unwrap(),
+            date: String::new(),
+            comment: comment.into(),
+        };
+        let report = SessionReport {
+            summary: SessionSummary {
+                best_single: Some(Duration::from_secs(10)),
+                best_ao5: None,
+                best_ao12: None,
+                mean: None,
+                times: vec![Some(Duration::from_secs(10)); 2],
+                scramble_seed: None,
+            },
+            solves: vec![solve(10.0, "<script>"), solve(12.0, "")],
+        };
+        let html = report.render();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert_eq!(html.matches("<circle").count(), 2);
+        assert_eq!(html.matches("<tr id=").count(), 2);
+        // the newest solve is listed first
+        assert!(html.find("id=\"solve-2\"").unwrap() < html.find("id=\"solve-1\"").unwrap());
+        assert!(html.contains("&lt;script&gt;"));
+    }
+}
@@ -0,0 +1,34 @@
+//! Sending scrambles to a scrambling robot, of the kind chosen in the preferences (see
+//! [`smartcube::Robot`])
+use crate::prelude::*;
+use crate::runtime::tokio;
+use crate::settings::settings;
+use cubestruct::Move;
+use smartcube::{BluetoothManager, GanRobot, Robot, RobotError, SerialRobot};
+
+/// Have the robot scramble the cube, then send [`Event::RobotScrambled`]. GAN robots are
+/// found over Bluetooth, so they need `manager`.
+pub fn send(mut tx: EventSender, manager: Option<BluetoothManager>, scramble: Vec<Move>) {
+    let serial_port = (settings().string("robot") == "serial")
+        .then(|| settings().string("robot-serial-port").to_string());
+    tokio().spawn(async move {
+        let result = scramble_with(serial_port, manager, &scramble)
+            .await
+            .map_err(|e| e.to_string());
+        tx.send(Event::RobotScrambled(result)).await.unwrap();
+    });
+}
+
+/// Returns the robot's name
+async fn scramble_with(
+    serial_port: Option<String>,
+    manager: Option<BluetoothManager>,
+    scramble: &[Move],
+) -> Result<String, RobotError> {
+    let robot: Box<dyn Robot> = match serial_port {
+        Some(path) => Box::new(SerialRobot::new(path)),
+        None => Box::new(GanRobot::find(&manager.ok_or(RobotError::NotFound)?).await?),
+    };
+    robot.scramble(scramble).await?;
+    Ok(robot.name())
+}
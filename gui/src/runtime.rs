@@ -0,0 +1,9 @@
+//! Tokio runtime for work that mustn't hold up the UI, like talking to Bluetooth devices and
+//! rendering files to save
+use std::sync::OnceLock;
+use tokio::runtime::Runtime;
+
+pub fn tokio() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| Runtime::new().unwrap())
+}
@@ -0,0 +1,144 @@
+//! Printable scramble sheets, laid out like the ones used at competitions
+use crate::prelude::*;
+use crate::puzzle_diagram::PuzzleDiagram;
+use cubestruct::{CubieCube, Move, MoveSequence};
+use gtk::cairo;
+
+/// Scrambles per group, not including extras
+const GROUP_SCRAMBLES: usize = 5;
+const GROUP_EXTRAS: usize = 2;
+/// A4, in points
+const PAGE_WIDTH: f64 = 595.0;
+const PAGE_HEIGHT: f64 = 842.0;
+const MARGIN: f64 = 40.0;
+const HEADER_HEIGHT: f64 = 60.0;
+/// Characters per line of scramble text
+const LINE_WIDTH: usize = 40;
+const FONT: &str = "Cantarell";
+const MONOSPACE_FONT: &str = "monospace";
+
+/// Scrambles for one event, split into groups
+#[derive(Debug, Clone)]
+pub struct ScrambleSheet {
+    event: String,
+    /// Each group has `GROUP_SCRAMBLES` scrambles followed by `GROUP_EXTRAS` extras
+    groups: Vec<Vec<Vec<Move>>>,
+}
+
+impl ScrambleSheet {
+    /// Generate `num_groups` groups of 3x3 scrambles
+    pub fn generate(num_groups: u32) -> Self {
+        let groups = (0..num_groups)
+            .map(|_| {
+                (0..GROUP_SCRAMBLES + GROUP_EXTRAS)
+                    .map(|_| cubestruct::random_move_scramble())
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            event: gettext("3x3x3 Cube"),
+            groups,
+        }
+    }
+
+    /// Render as a PDF with one page per group
+    pub fn render(&self) -> anyhow::Result<Vec<u8>> {
+        let surface = cairo::PdfSurface::for_stream(PAGE_WIDTH, PAGE_HEIGHT, Vec::new())?;
+        let cr = cairo::Context::new(&surface)?;
+        for (i, group) in self.groups.iter().enumerate() {
+            self.draw_group(&cr, i, group)?;
+            cr.show_page()?;
+        }
+        let pdf = surface
+            .finish_output_stream()
+            .map_err(std::io::Error::from)?;
+        Ok(*pdf.downcast::<Vec<u8>>().unwrap())
+    }
+
+    fn draw_group(
+        &self,
+        cr: &cairo::Context,
+        group_idx: usize,
+        group: &[Vec<Move>],
+    ) -> Result<(), cairo::Error> {
+        cr.set_source_rgb(0.0, 0.0, 0.0);
+        cr.select_font_face(FONT, cairo::FontSlant::Normal, cairo::FontWeight::Bold);
+        cr.set_font_size(20.0);
+        cr.move_to(MARGIN, MARGIN + 20.0);
+        cr.show_text(&gettext_f(
+            "{event} - Group {group}",
+            &[("event", &self.event), ("group", &group_name(group_idx))],
+        ))?;
+
+        let row_height = (PAGE_HEIGHT - 2.0 * MARGIN - HEADER_HEIGHT) / group.len() as f64;
+        let net_width = row_height * 4.0 / 3.0;
+        for (i, scramble) in group.iter().enumerate() {
+            let y = MARGIN + HEADER_HEIGHT + i as f64 * row_height;
+            let label = if i < GROUP_SCRAMBLES {
+                (i + 1).to_string()
+            } else {
+                format!("E{}", i - GROUP_SCRAMBLES + 1)
+            };
+
+            cr.set_source_rgb(0.6, 0.6, 0.6);
+            cr.set_line_width(0.5);
+            cr.move_to(MARGIN, y);
+            cr.line_to(PAGE_WIDTH - MARGIN, y);
+            cr.stroke()?;
+
+            cr.set_source_rgb(0.0, 0.0, 0.0);
+            cr.select_font_face(FONT, cairo::FontSlant::Normal, cairo::FontWeight::Bold);
+            cr.set_font_size(14.0);
+            cr.move_to(MARGIN, y + row_height / 2.0);
+            cr.show_text(&label)?;
+
+            cr.select_font_face(
+                MONOSPACE_FONT,
+                cairo::FontSlant::Normal,
+                cairo::FontWeight::Normal,
+            );
+            cr.set_font_size(12.0);
+            let text = MoveSequence::new(scramble).wrapped(LINE_WIDTH).to_string();
+            let lines = text.lines().collect::<Vec<_>>();
+            let text_top = y + row_height / 2.0 - (lines.len() as f64 - 1.0) * 9.0;
+            for (line_idx, line) in lines.iter().enumerate() {
+                cr.move_to(MARGIN + 36.0, text_top + line_idx as f64 * 18.0);
+                cr.show_text(line)?;
+            }
+
+            let mut state = CubieCube::SOLVED;
+            state.apply_moves(scramble);
+            cr.save()?;
+            cr.translate(PAGE_WIDTH - MARGIN - net_width, y + 4.0);
+            state.draw(cr, net_width, row_height - 8.0);
+            cr.restore()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// `A`, `B`, ..., `Z`, `AA`, `AB`, ...
+fn group_name(idx: usize) -> String {
+    let letter = char::from(b'A' + (idx % 26) as u8);
+    if idx < 26 {
+        letter.to_string()
+    } else {
+        format!("{}{letter}", group_name(idx / 26 - 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn group_names() {
+        assert_eq!(group_name(0), "A");
+        assert_eq!(group_name(25), "Z");
+        assert_eq!(group_name(26), "AA");
+        assert_eq!(group_name(27), "AB");
+        assert_eq!(group_name(52), "BA");
+    }
+}
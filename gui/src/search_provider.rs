@@ -0,0 +1,170 @@
+//! GNOME Shell search provider, so that searching for "scramble" shows a fresh scramble that
+//! opens the app with it as the current scramble. It's registered when the application starts
+//! up, so that it also works when the app is only started by D-Bus activation to search.
+use crate::prelude::*;
+use crate::timer::render_moveseq;
+use std::collections::HashMap;
+use std::time::Duration;
+
+const INTERFACE_NAME: &str = "org.gnome.Shell.SearchProvider2";
+
+const INTERFACE_XML: &str = r#"
+<node>
+  <interface name="org.gnome.Shell.SearchProvider2">
+    <method name="GetInitialResultSet">
+      <arg type="as" name="terms" direction="in"/>
+      <arg type="as" name="results" direction="out"/>
+    </method>
+    <method name="GetSubsearchResultSet">
+      <arg type="as" name="previous_results" direction="in"/>
+      <arg type="as" name="terms" direction="in"/>
+      <arg type="as" name="results" direction="out"/>
+    </method>
+    <method name="GetResultMetas">
+      <arg type="as" name="identifiers" direction="in"/>
+      <arg type="aa{sv}" name="metas" direction="out"/>
+    </method>
+    <method name="ActivateResult">
+      <arg type="s" name="identifier" direction="in"/>
+      <arg type="as" name="terms" direction="in"/>
+      <arg type="u" name="timestamp" direction="in"/>
+    </method>
+    <method name="LaunchSearch">
+      <arg type="as" name="terms" direction="in"/>
+      <arg type="u" name="timestamp" direction="in"/>
+    </method>
+  </interface>
+</node>
+"#;
+
+/// Object path of the search provider, under the application's own path. The search provider
+/// file that tells GNOME Shell about it has to match.
+const OBJECT_PATH_SUFFIX: &str = "/SearchProvider";
+
+/// How long the app stays running after a search, when it was only started for searching
+const SERVICE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Shortest term that counts as searching for a scramble, so one or two letters don't match
+const MIN_TERM_LEN: usize = 3;
+
+/// Whether the search `terms` are looking for a scramble
+fn wants_scramble(terms: &[String]) -> bool {
+    terms
+        .iter()
+        .any(|term| term.len() >= MIN_TERM_LEN && "scramble".starts_with(&term.to_lowercase()))
+}
+
+/// Result identifiers for the search `terms`: a new scramble, as its notation, if they're
+/// looking for one
+fn results_for(terms: &[String]) -> Vec<String> {
+    if !wants_scramble(terms) {
+        return Vec::new();
+    }
+    // random-state scrambles need the solver's tables, which aren't built if the app was
+    // only started for searching
+    let scramble = match cubestruct::SolverContext::get() {
+        Some(solver) => solver.random_state_scramble(),
+        None => cubestruct::random_move_scramble(),
+    };
+    vec![render_moveseq(&scramble, false)]
+}
+
+fn result_meta(identifier: &str) -> HashMap<String, glib::Variant> {
+    HashMap::from([
+        ("id".into(), identifier.to_variant()),
+        ("name".into(), gettext("Scramble").to_variant()),
+        ("description".into(), identifier.to_variant()),
+    ])
+}
+
+/// Export the search provider on the application's connection. Does nothing if the
+/// application isn't on the session bus.
+pub fn register(application: &adw::Application) {
+    let (Some(connection), Some(app_path)) = (
+        application.dbus_connection(),
+        application.dbus_object_path(),
+    ) else {
+        return;
+    };
+    // started by D-Bus activation, not by the user
+    if application
+        .flags()
+        .contains(gio::ApplicationFlags::IS_SERVICE)
+    {
+        application.set_inactivity_timeout(SERVICE_TIMEOUT.as_millis() as u32);
+    }
+    let interface_info = gio::DBusNodeInfo::for_xml(INTERFACE_XML)
+        .unwrap()
+        .lookup_interface(INTERFACE_NAME)
+        .unwrap();
+
+    let application = application.clone();
+    let registration = connection.register_object(
+        &format!("{app_path}{OBJECT_PATH_SUFFIX}"),
+        &interface_info,
+        move |_, _, _, _, method, parameters, invocation| {
+            // restarts the inactivity timeout once the call is done
+            let _hold = application.hold();
+            let reply = match method {
+                "GetInitialResultSet" => {
+                    let (terms,) = parameters.get::<(Vec<String>,)>().unwrap();
+                    Some((results_for(&terms),).to_variant())
+                }
+                "GetSubsearchResultSet" => {
+                    let (_, terms) = parameters.get::<(Vec<String>, Vec<String>)>().unwrap();
+                    Some((results_for(&terms),).to_variant())
+                }
+                "GetResultMetas" => {
+                    let (identifiers,) = parameters.get::<(Vec<String>,)>().unwrap();
+                    let metas = identifiers
+                        .iter()
+                        .map(|id| result_meta(id))
+                        .collect::<Vec<_>>();
+                    Some((metas,).to_variant())
+                }
+                "ActivateResult" => {
+                    let (identifier, _, _) =
+                        parameters.get::<(String, Vec<String>, u32)>().unwrap();
+                    show_window(&application);
+                    application.activate_action("use-scramble", Some(&identifier.to_variant()));
+                    None
+                }
+                "LaunchSearch" => {
+                    show_window(&application);
+                    None
+                }
+                _ => unreachable!("Unknown method {method}"),
+            };
+            invocation.return_value(reply.as_ref());
+        },
+        |_, _, _, _, _| unreachable!("No properties"),
+        |_, _, _, _, _, _| false,
+    );
+    if let Err(e) = registration {
+        tracing::warn!("Failed to register search provider: {e}");
+    }
+}
+
+/// Bring the window to the front, opening it if the app was only running for searching
+fn show_window(application: &adw::Application) {
+    match application.active_window() {
+        Some(window) => window.present(),
+        None => application.activate(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scramble_terms() {
+        let terms = |terms: &[&str]| terms.iter().map(|&t| t.into()).collect::<Vec<String>>();
+        assert!(wants_scramble(&terms(&["scramble"])));
+        assert!(wants_scramble(&terms(&["Scram"])));
+        assert!(wants_scramble(&terms(&["new", "scr"])));
+        assert!(!wants_scramble(&terms(&["sc"])));
+        assert!(!wants_scramble(&terms(&["scrambles"])));
+        assert!(!wants_scramble(&terms(&["timer"])));
+    }
+}
@@ -0,0 +1,32 @@
+use crate::prelude::*;
+
+pub const APP_ID: &str = "io.github.flying_toast.PuzzleTime";
+
+thread_local! {
+    static SETTINGS: gio::Settings = load_settings();
+}
+
+/// Get the app's GSettings
+pub fn settings() -> gio::Settings {
+    SETTINGS.with(Clone::clone)
+}
+
+fn load_settings() -> gio::Settings {
+    let default_source = gio::SettingsSchemaSource::default();
+    let schema = default_source
+        .as_ref()
+        .and_then(|src| src.lookup(APP_ID, true))
+        .unwrap_or_else(|| {
+            // not installed (e.g. `cargo run`), so use the schema compiled by build.rs
+            gio::SettingsSchemaSource::from_directory(
+                env!("OUT_DIR"),
+                default_source.as_ref(),
+                false,
+            )
+            .unwrap()
+            .lookup(APP_ID, false)
+            .expect("Can't find GSettings schema")
+        });
+
+    gio::Settings::new_full(&schema, None::<&gio::SettingsBackend>, None)
+}
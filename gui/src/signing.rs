@@ -0,0 +1,264 @@
+//! Tamper-evident records of smart cube solves, for unofficial online competitions. While
+//! enabled in the preferences, each solve done on a smart cube is added to a log with its
+//! scramble, the state the cube started in, and every turn with when it happened.
+//!
+//! Each record has the SHA-256 hash of the one before it and a hash of itself, so the log
+//! is a hash chain: changing, removing or reordering a record changes the hash of every
+//! record after it. A competition can ask for the record's hash along with each submitted
+//! result, and later check the exported log against the hashes it was sent with
+//! [`verify()`].
+//!
+//! # Format
+//!
+//! One record per line, with tab-separated fields:
+//!
+//! 1. Hash of the previous record ([`FIRST_PREV_HASH`] for the first one)
+//! 2. When the solve started, in microseconds since the Unix epoch
+//! 3. The time in microseconds, without any penalty
+//! 4. The penalty when the solve was recorded: `none`, `plus2` or `dnf`
+//! 5. The scramble
+//! 6. The cube's state when the solve started, in the format of
+//!    [`CubieCube::to_facelet_string()`]
+//! 7. The turns, separated by spaces, each as `<move>@<milliseconds since the start>`.
+//!    The move is `?` if the cube reported a change that isn't a single turn.
+//! 8. Hash of the first 7 fields joined with tabs
+//!
+//! Hashes are lowercase hex.
+use crate::autosave::{penalty_name, write_file};
+use crate::prelude::*;
+use crate::settings::{settings, APP_ID};
+use crate::stats::Penalty;
+use crate::timer::render_moveseq;
+use cubestruct::{CubieCube, Move};
+use std::fmt::Write;
+use std::io::Write as _;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// What the first record has instead of the previous record's hash
+pub const FIRST_PREV_HASH: &str =
+    "0000000000000000000000000000000000000000000000000000000000000000";
+
+fn path() -> PathBuf {
+    glib::user_data_dir().join(APP_ID).join("solve-records")
+}
+
+/// The whole log, to be exported
+pub fn log_contents() -> std::io::Result<String> {
+    match std::fs::read_to_string(path()) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        result => result,
+    }
+}
+
+fn sha256(data: &str) -> String {
+    let mut checksum = glib::Checksum::new(glib::ChecksumType::Sha256).unwrap();
+    checksum.update(data.as_bytes());
+    checksum.string().unwrap()
+}
+
+/// Records the running solve, if it's done on a smart cube and recording is enabled
+#[derive(Debug, Default)]
+pub struct Recorder {
+    current: Option<Recording>,
+}
+
+#[derive(Debug)]
+struct Recording {
+    started: Instant,
+    /// In microseconds since the Unix epoch
+    started_at: i64,
+    start_state: CubieCube,
+    last_state: CubieCube,
+    turns: Vec<(Option<Move>, Duration)>,
+}
+
+impl Recorder {
+    /// Call when the timer starts, with the state of the connected smart cube (if any)
+    pub fn start(&mut self, cube: Option<&CubieCube>) {
+        self.current = cube
+            .filter(|_| settings().boolean("record-solves"))
+            .map(|&state| Recording {
+                started: Instant::now(),
+                started_at: glib::real_time(),
+                start_state: state,
+                last_state: state,
+                turns: Vec::new(),
+            });
+    }
+
+    /// Call with each new state of the smart cube, which it reported at `at`
+    pub fn turn(&mut self, state: &CubieCube, at: Instant) {
+        if let Some(recording) = &mut self.current {
+            let moov = recording.last_state.move_to(state);
+            recording.last_state = *state;
+            recording
+                .turns
+                .push((moov, at.saturating_duration_since(recording.started)));
+        }
+    }
+
+    /// Stop recording without saving, e.g. if the cube disconnected so some turns would be
+    /// missing
+    pub fn cancel(&mut self) {
+        self.current = None;
+    }
+
+    /// Call when the timer stops. Adds the solve to the log if it was recorded, and returns
+    /// its record's hash.
+    pub fn finish(
+        &mut self,
+        scramble: &[Move],
+        time: Duration,
+        penalty: Penalty,
+    ) -> Option<anyhow::Result<String>> {
+        let recording = self.current.take()?;
+        Some(append(&recording, scramble, time, penalty))
+    }
+}
+
+/// Add a record of the solve to the end of the log, returning its hash
+fn append(
+    recording: &Recording,
+    scramble: &[Move],
+    time: Duration,
+    penalty: Penalty,
+) -> anyhow::Result<String> {
+    let log = log_contents()?;
+    // a new record would only extend a chain that can't be verified
+    let hashes = verify(&log)?;
+    let prev_hash = hashes.last().map_or(FIRST_PREV_HASH, String::as_str);
+    let (line, hash) = record_line(prev_hash, recording, scramble, time, penalty);
+    if log.is_empty() {
+        // creates the directory too
+        write_file(&path(), &line).map_err(anyhow::Error::msg)?;
+    } else {
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(path())?
+            .write_all(line.as_bytes())?;
+    }
+    Ok(hash)
+}
+
+/// Returns the line (with its newline) and the record's hash
+fn record_line(
+    prev_hash: &str,
+    recording: &Recording,
+    scramble: &[Move],
+    time: Duration,
+    penalty: Penalty,
+) -> (String, String) {
+    let mut turns = String::new();
+    for (moov, at) in &recording.turns {
+        if !turns.is_empty() {
+            turns.push(' ');
+        }
+        let moov = moov.map_or_else(|| "?".into(), |moov| moov.to_string());
+        write!(turns, "{moov}@{}", at.as_millis()).unwrap();
+    }
+    let fields = [
+        prev_hash.to_string(),
+        recording.started_at.to_string(),
+        time.as_micros().to_string(),
+        penalty_name(penalty).to_string(),
+        render_moveseq(scramble, false),
+        recording.start_state.to_facelet_string(),
+        turns,
+    ]
+    .join("\t");
+    let hash = sha256(&fields);
+    (format!("{fields}\t{hash}\n"), hash)
+}
+
+/// Why a log failed [`verify()`], with the number of the first bad record (from 1)
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum VerifyError {
+    Malformed(usize),
+    BrokenChain(usize),
+    WrongHash(usize),
+}
+
+impl std::fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Malformed(n) => write!(f, "Record {n} is malformed"),
+            Self::BrokenChain(n) => write!(f, "Record {n} doesn't follow the one before it"),
+            Self::WrongHash(n) => write!(f, "Record {n} has been changed"),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+/// Check that a log hasn't been tampered with, except for records being removed from its
+/// end. Returns the hash of each record, to be compared with the ones submitted with
+/// results.
+pub fn verify(log: &str) -> Result<Vec<String>, VerifyError> {
+    let mut hashes: Vec<String> = Vec::new();
+    for (i, line) in log.lines().enumerate() {
+        let number = i + 1;
+        let Some((fields, hash)) = line.rsplit_once('\t') else {
+            return Err(VerifyError::Malformed(number));
+        };
+        if fields.split('\t').count() != 7 {
+            return Err(VerifyError::Malformed(number));
+        }
+        let prev_hash = fields.split('\t').next().unwrap();
+        let expected_prev = hashes.last().map_or(FIRST_PREV_HASH, String::as_str);
+        if prev_hash != expected_prev {
+            return Err(VerifyError::BrokenChain(number));
+        }
+        if sha256(fields) != hash {
+            return Err(VerifyError::WrongHash(number));
+        }
+        hashes.push(hash.to_string());
+    }
+    Ok(hashes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recording() -> Recording {
+        let mut state = CubieCube::SOLVED;
+        state.apply_move(Move::Ri);
+        let started = Instant::now();
+        Recording {
+            started,
+            started_at: 1_700_000_000_000_000,
+            start_state: state,
+            last_state: CubieCube::SOLVED,
+            turns: vec![
+                (Some(Move::R), Duration::from_millis(350)),
+                (None, Duration::from_millis(900)),
+            ],
+        }
+    }
+
+    #[test]
+    fn chain() {
+        let time = Duration::from_millis(1234);
+        let (first, first_hash) = record_line(
+            FIRST_PREV_HASH,
+            &recording(),
+            &[Move::R],
+            time,
+            Penalty::None,
+        );
+        assert!(first.ends_with(&format!("\tR@350 ?@900\t{first_hash}\n")));
+        let (second, second_hash) =
+            record_line(&first_hash, &recording(), &[Move::R], time, Penalty::Plus2);
+        let log = format!("{first}{second}");
+        assert_eq!(verify(&log), Ok(vec![first_hash, second_hash]));
+
+        assert_eq!(verify(&second), Err(VerifyError::BrokenChain(1)));
+        assert_eq!(
+            verify(&log.replacen("1234000", "1134000", 1)),
+            Err(VerifyError::WrongHash(1))
+        );
+        assert_eq!(verify("nonsense\n"), Err(VerifyError::Malformed(1)));
+        assert_eq!(verify(""), Ok(Vec::new()));
+    }
+}
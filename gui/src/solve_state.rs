@@ -0,0 +1,278 @@
+//! Where the timer is in a solve, and how the start/stop key and other inputs move it along.
+//! [`SolveState::transition`] only decides what should happen; doing it (starting the timer,
+//! turning lights on etc.) is up to the caller.
+use std::time::Instant;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum SolveState {
+    /// Waiting for the next solve
+    #[default]
+    Idle,
+    /// Counting down WCA inspection. `key_down` is whether the key that started it is still
+    /// down, so that its key repeats don't start holding for the solve.
+    Inspecting { key_down: bool },
+    /// The start/stop key is down, but hasn't been held long enough to start a solve (the red
+    /// light). `inspecting` is whether inspection is running underneath, to go back to if the
+    /// key is let go early.
+    Holding { inspecting: bool, since: Instant },
+    /// Held long enough, so the solve starts when the key is let go (the green light)
+    Ready,
+    /// A solve is running. `key_down` is whether the key that took a split is still down, so
+    /// that its key repeats don't take more.
+    Solving { key_down: bool },
+    /// The solve was just stopped, and the key that stopped it is still down
+    Stopped,
+}
+
+/// Something that can move a solve along
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SolveInput {
+    /// The start/stop key went down (or repeated while held) at `at`
+    KeyDown {
+        at: Instant,
+        /// Whether a solve starts with inspection in the current mode and settings
+        inspection: bool,
+        /// Whether the running solve should take a split instead of stopping
+        split: bool,
+        /// Whether the running solve started too recently to stop
+        too_soon: bool,
+    },
+    /// The start/stop key was let go at `at`
+    KeyUp { at: Instant },
+    /// The key has been held long enough to start, if it's still the hold that began at
+    /// `since`
+    HoldElapsed { since: Instant },
+    /// Something other than the start/stop key stopped the solve at `at`, e.g. another key
+    /// or the smart cube
+    Stop { at: Instant },
+    /// A solve interrupted by a crash was picked back up
+    Resume,
+}
+
+/// What the caller should do for a transition. Starts, splits and stops are at the time of
+/// the input that caused them, rather than when it was handled.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SolveAction {
+    StartInspection,
+    /// Turn the red light on, and send [`SolveInput::HoldElapsed`] with this hold's start
+    /// once it's been held long enough
+    RedLight {
+        since: Instant,
+    },
+    GreenLight,
+    LightsOff,
+    Start {
+        at: Instant,
+    },
+    Split {
+        at: Instant,
+    },
+    Stop {
+        at: Instant,
+    },
+}
+
+impl SolveState {
+    /// The state after `input`, and what to do about it. Inputs that don't apply to the
+    /// current state leave it as it is.
+    pub fn transition(self, input: SolveInput) -> (Self, Option<SolveAction>) {
+        use SolveAction as A;
+        use SolveInput as I;
+        use SolveState as S;
+
+        match (self, input) {
+            (
+                S::Idle,
+                I::KeyDown {
+                    inspection: true, ..
+                },
+            ) => (S::Inspecting { key_down: true }, Some(A::StartInspection)),
+            (S::Idle, I::KeyDown { at, .. }) => (
+                S::Holding {
+                    inspecting: false,
+                    since: at,
+                },
+                Some(A::RedLight { since: at }),
+            ),
+            (S::Idle, I::Resume) => (S::Solving { key_down: false }, None),
+
+            (S::Inspecting { key_down: false }, I::KeyDown { at, .. }) => (
+                S::Holding {
+                    inspecting: true,
+                    since: at,
+                },
+                Some(A::RedLight { since: at }),
+            ),
+            (S::Inspecting { key_down: true }, I::KeyUp { .. }) => {
+                (S::Inspecting { key_down: false }, None)
+            }
+
+            (S::Holding { inspecting, .. }, I::KeyUp { .. }) => (
+                if inspecting {
+                    S::Inspecting { key_down: false }
+                } else {
+                    S::Idle
+                },
+                Some(A::LightsOff),
+            ),
+            (
+                S::Holding { since, .. },
+                I::HoldElapsed {
+                    since: elapsed_since,
+                },
+            ) if since == elapsed_since => (S::Ready, Some(A::GreenLight)),
+
+            (S::Ready, I::KeyUp { at }) => (S::Solving { key_down: false }, Some(A::Start { at })),
+
+            (S::Solving { key_down: false }, I::KeyDown { too_soon: true, .. }) => (self, None),
+            (
+                S::Solving { key_down: false },
+                I::KeyDown {
+                    at, split: true, ..
+                },
+            ) => (S::Solving { key_down: true }, Some(A::Split { at })),
+            (S::Solving { key_down: false }, I::KeyDown { at, .. }) => {
+                (S::Stopped, Some(A::Stop { at }))
+            }
+            (S::Solving { key_down: true }, I::KeyUp { .. }) => {
+                (S::Solving { key_down: false }, None)
+            }
+            (S::Solving { key_down }, I::Stop { at }) => (
+                if key_down { S::Stopped } else { S::Idle },
+                Some(A::Stop { at }),
+            ),
+
+            (S::Stopped, I::KeyUp { .. }) => (S::Idle, None),
+            (S::Stopped, I::Resume) => (S::Solving { key_down: true }, None),
+
+            _ => (self, None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_down(at: Instant, inspection: bool, split: bool) -> SolveInput {
+        SolveInput::KeyDown {
+            at,
+            inspection,
+            split,
+            too_soon: false,
+        }
+    }
+
+    /// Apply `inputs` in order from `state`, returning the final state and every action
+    fn run(state: SolveState, inputs: &[SolveInput]) -> (SolveState, Vec<SolveAction>) {
+        inputs
+            .iter()
+            .fold((state, Vec::new()), |(state, mut actions), &input| {
+                let (state, action) = state.transition(input);
+                actions.extend(action);
+                (state, actions)
+            })
+    }
+
+    #[test]
+    fn hold_start_and_stop() {
+        let at = Instant::now();
+        let (state, actions) = run(
+            SolveState::Idle,
+            &[
+                key_down(at, false, false),
+                // key repeats while held do nothing
+                key_down(at, false, false),
+                SolveInput::HoldElapsed { since: at },
+                SolveInput::KeyUp { at },
+                key_down(at, false, false),
+                key_down(at, false, false),
+            ],
+        );
+        assert_eq!(state, SolveState::Stopped);
+        assert_eq!(
+            actions,
+            [
+                SolveAction::RedLight { since: at },
+                SolveAction::GreenLight,
+                SolveAction::Start { at },
+                SolveAction::Stop { at },
+            ]
+        );
+        assert_eq!(
+            state.transition(SolveInput::KeyUp { at }).0,
+            SolveState::Idle
+        );
+    }
+
+    #[test]
+    fn letting_go_too_early() {
+        let first = Instant::now();
+        let second = first + std::time::Duration::from_secs(1);
+        let (state, actions) = run(
+            SolveState::Idle,
+            &[
+                key_down(first, false, false),
+                SolveInput::KeyUp { at: first },
+                key_down(second, false, false),
+                // the first hold's timeout doesn't count for the second
+                SolveInput::HoldElapsed { since: first },
+                SolveInput::KeyUp { at: second },
+            ],
+        );
+        assert_eq!(state, SolveState::Idle);
+        assert!(!actions.contains(&SolveAction::GreenLight));
+        assert!(!actions
+            .iter()
+            .any(|action| matches!(action, SolveAction::Start { .. })));
+    }
+
+    #[test]
+    fn inspection_and_splits() {
+        let at = Instant::now();
+        let (state, actions) = run(
+            SolveState::Idle,
+            &[
+                key_down(at, true, false),
+                key_down(at, true, false),
+                SolveInput::KeyUp { at },
+                key_down(at, true, false),
+                // let go early, back to inspecting
+                SolveInput::KeyUp { at },
+                key_down(at, true, false),
+                SolveInput::HoldElapsed { since: at },
+                SolveInput::KeyUp { at },
+                key_down(at, true, true),
+                key_down(at, true, true),
+                SolveInput::KeyUp { at },
+                SolveInput::Stop { at },
+            ],
+        );
+        assert_eq!(state, SolveState::Idle);
+        assert_eq!(
+            actions,
+            [
+                SolveAction::StartInspection,
+                SolveAction::RedLight { since: at },
+                SolveAction::LightsOff,
+                SolveAction::RedLight { since: at },
+                SolveAction::GreenLight,
+                SolveAction::Start { at },
+                SolveAction::Split { at },
+                SolveAction::Stop { at },
+            ]
+        );
+    }
+
+    #[test]
+    fn too_soon_to_stop() {
+        let state = SolveState::Solving { key_down: false };
+        let input = SolveInput::KeyDown {
+            at: Instant::now(),
+            inspection: false,
+            split: false,
+            too_soon: true,
+        };
+        assert_eq!(state.transition(input), (state, None));
+    }
+}
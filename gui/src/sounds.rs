@@ -0,0 +1,117 @@
+//! Audio cues, synthesized as short tones and played through GTK's media backend
+use crate::prelude::*;
+use crate::settings::settings;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+const SAMPLE_RATE: u32 = 44100;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Sound {
+    Start,
+    Stop,
+    /// 8 seconds of inspection have passed
+    Inspection8,
+    /// 12 seconds of inspection have passed
+    Inspection12,
+    /// New best single
+    PersonalBest,
+}
+
+impl Sound {
+    /// The boolean setting that turns this sound on
+    fn settings_key(self) -> &'static str {
+        match self {
+            Self::Start | Self::Stop => "sound-timer",
+            Self::Inspection8 | Self::Inspection12 => "sound-inspection",
+            Self::PersonalBest => "sound-pb",
+        }
+    }
+
+    /// `(frequency in Hz, duration in ms)` of each note. A frequency of 0 is a rest.
+    fn notes(self) -> &'static [(f32, u32)] {
+        match self {
+            Self::Start => &[(1320.0, 80)],
+            Self::Stop => &[(880.0, 80)],
+            Self::Inspection8 => &[(660.0, 150)],
+            Self::Inspection12 => &[(660.0, 120), (0.0, 80), (660.0, 120)],
+            Self::PersonalBest => &[(523.0, 100), (659.0, 100), (784.0, 100), (1047.0, 250)],
+        }
+    }
+}
+
+thread_local! {
+    static PLAYERS: RefCell<HashMap<Sound, gtk::MediaFile>> = RefCell::new(HashMap::new());
+}
+
+/// Play `sound`, if it's turned on in the settings
+pub fn play(sound: Sound) {
+    let settings = settings();
+    if !settings.boolean(sound.settings_key()) {
+        return;
+    }
+
+    PLAYERS.with_borrow_mut(|players| {
+        let player = players.entry(sound).or_insert_with(|| {
+            let wav = glib::Bytes::from_owned(render_wav(sound.notes()));
+            gtk::MediaFile::for_input_stream(&gio::MemoryInputStream::from_bytes(&wav))
+        });
+        player.set_volume(settings.double("sound-volume"));
+        player.seek(0);
+        player.play();
+    });
+}
+
+/// Render `notes` as a mono 16-bit PCM WAV file
+fn render_wav(notes: &[(f32, u32)]) -> Vec<u8> {
+    let mut samples = Vec::new();
+    for &(freq, ms) in notes {
+        let len = SAMPLE_RATE * ms / 1000;
+        // fade in and out over 5ms to avoid clicks
+        let fade = (SAMPLE_RATE / 200).min(len / 2);
+        for i in 0..len {
+            let envelope = (i.min(len - 1 - i) as f32 / fade as f32).min(1.0);
+            let t = i as f32 / SAMPLE_RATE as f32;
+            let sample = (t * freq * std::f32::consts::TAU).sin() * envelope * 0.5;
+            samples.push((sample * f32::from(i16::MAX)) as i16);
+        }
+    }
+
+    let data_len = samples.len() as u32 * 2;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    // PCM, mono
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    // byte rate, block align, bits per sample
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+    wav
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wav_length() {
+        let wav = render_wav(&[(440.0, 100), (0.0, 50)]);
+        let samples = SAMPLE_RATE as usize * 150 / 1000;
+        assert_eq!(wav.len(), 44 + samples * 2);
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(
+            u32::from_le_bytes(wav[4..8].try_into().unwrap()) as usize,
+            wav.len() - 8
+        );
+    }
+}
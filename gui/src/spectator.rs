@@ -0,0 +1,212 @@
+//! Broadcasts the connected smart cube's turns and the timer over a WebSocket, so that another
+//! machine or a browser page can show them to an audience. Enabled in the preferences, and
+//! listens on the `spectator-port`, only on this machine unless `spectator-lan` is set.
+//! Browser pages can only connect if they're served from this machine or their origin is
+//! one of the `spectator-origins`. Pages opened from a file have the origin `null`, which
+//! has to be listed there too, since sandboxed frames on any website send it as well.
+//!
+//! # Messages
+//!
+//! Each message is a JSON object in a text frame, with a `type` of:
+//!
+//! - `state`, sent first: `facelets` is the cube's state (`null` if no cube is connected),
+//!   `running` is whether the timer is running, and `elapsed` is the timer's time in
+//!   milliseconds
+//! - `turn`, when the cube is turned: `move` is the turn (`null` if the state changed by
+//!   more than one turn, e.g. after a dropped packet), `facelets` is the new state, and
+//!   `ms` is when it happened, in milliseconds since broadcasting started
+//! - `cube`, when a cube connects or disconnects: `facelets` as in `state`
+//! - `timer`, when the timer starts or stops: `running` and `elapsed` as in `state`
+//!
+//! Facelets are in the same format as the D-Bus interface's `CubeState` (see
+//! [`crate::dbus`]). Anything spectators send is ignored.
+use crate::prelude::*;
+use crate::settings::settings;
+use cubestruct::CubieCube;
+use glib::translate::IntoGlib;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Serves spectators while enabled in the settings
+#[derive(Debug, Clone)]
+pub struct Spectators {
+    inner: Rc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    server: RefCell<Option<soup::Server>>,
+    clients: RefCell<Vec<soup::WebsocketConnection>>,
+    cube: RefCell<Option<CubieCube>>,
+    timer: RefCell<(bool, Duration)>,
+    started: Instant,
+}
+
+impl Spectators {
+    pub fn new() -> Self {
+        let this = Self {
+            inner: Rc::new(Inner {
+                server: RefCell::new(None),
+                clients: RefCell::new(Vec::new()),
+                cube: RefCell::new(None),
+                timer: RefCell::new((false, Duration::ZERO)),
+                started: Instant::now(),
+            }),
+        };
+        for key in ["spectator-enabled", "spectator-port", "spectator-lan"] {
+            let this2 = this.clone();
+            settings().connect_changed(Some(key), move |_, _| this2.restart());
+        }
+        this.restart();
+        this
+    }
+
+    /// Start or stop listening, according to the settings
+    fn restart(&self) {
+        if let Some(server) = self.inner.server.take() {
+            server.disconnect();
+        }
+        // spectators of the old server are disconnected
+        for client in self.inner.clients.take() {
+            client.close(soup::WebsocketCloseCode::GoingAway.into_glib() as u16, None);
+        }
+        if !settings().boolean("spectator-enabled") {
+            return;
+        }
+
+        let server = glib::Object::new::<soup::Server>();
+        let port = settings().uint("spectator-port");
+        let listening = if settings().boolean("spectator-lan") {
+            server.listen_all(port, soup::ServerListenOptions::empty())
+        } else {
+            server.listen_local(port, soup::ServerListenOptions::empty())
+        };
+        if let Err(e) = listening {
+            tracing::warn!("Failed to listen for spectators on port {port}: {e}");
+            return;
+        }
+        let this = self.clone();
+        server.add_websocket_handler(None, None, &[], move |_, message, _, connection| {
+            let origin = message
+                .request_headers()
+                .and_then(|headers| headers.one("Origin"));
+            let allowed = settings().strv("spectator-origins");
+            let allowed: Vec<_> = allowed.iter().map(|origin| origin.as_str()).collect();
+            if !origin_allowed(origin.as_deref(), &allowed) {
+                tracing::warn!("Refused a spectator from {}", origin.unwrap());
+                connection.close(
+                    soup::WebsocketCloseCode::PolicyViolation.into_glib() as u16,
+                    None,
+                );
+                return;
+            }
+            connection.send_text(&this.state_message());
+            this.inner.clients.borrow_mut().push(connection.clone());
+        });
+        self.inner.server.replace(Some(server));
+    }
+
+    /// Call with each new state of the smart cube, or `None` when it disconnects
+    pub fn cube_changed(&self, state: Option<&CubieCube>) {
+        let previous = self.inner.cube.replace(state.copied());
+        let message = match (previous, state) {
+            (Some(previous), Some(state)) => {
+                let moov = previous.move_to(state);
+                format!(
+                    "{{\"type\": \"turn\", \"move\": {}, \"facelets\": {}, \"ms\": {}}}",
+                    json_string(moov.map(|moov| moov.to_string())),
+                    json_string(Some(state.to_facelet_string())),
+                    self.inner.started.elapsed().as_millis(),
+                )
+            }
+            _ => format!(
+                "{{\"type\": \"cube\", \"facelets\": {}}}",
+                json_string(state.map(CubieCube::to_facelet_string)),
+            ),
+        };
+        self.broadcast(message);
+    }
+
+    /// Call when the timer starts (with how long it's already been running, if it's being
+    /// resumed) or stops
+    pub fn timer_changed(&self, running: bool, elapsed: Duration) {
+        self.inner.timer.replace((running, elapsed));
+        self.broadcast(format!(
+            "{{\"type\": \"timer\", \"running\": {running}, \"elapsed\": {}}}",
+            elapsed.as_millis(),
+        ));
+    }
+
+    /// Everything a new spectator needs to know
+    fn state_message(&self) -> String {
+        let (running, elapsed) = *self.inner.timer.borrow();
+        format!(
+            "{{\"type\": \"state\", \"facelets\": {}, \"running\": {running}, \"elapsed\": {}}}",
+            json_string(
+                self.inner
+                    .cube
+                    .borrow()
+                    .as_ref()
+                    .map(CubieCube::to_facelet_string)
+            ),
+            elapsed.as_millis(),
+        )
+    }
+
+    fn broadcast(&self, message: String) {
+        let mut clients = self.inner.clients.borrow_mut();
+        clients.retain(|client| client.state() == soup::WebsocketState::Open);
+        for client in clients.iter() {
+            client.send_text(&message);
+        }
+    }
+}
+
+/// `s` as a JSON string, or `null`
+fn json_string(s: Option<String>) -> String {
+    match s {
+        Some(s) => format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"")),
+        None => "null".into(),
+    }
+}
+
+/// Whether a spectator connecting from a page at `origin` (the `Origin` header, which only
+/// browsers send) is let in. Otherwise any web page open in a browser on the network could
+/// connect. `null` (local files, but also sandboxed frames and `data:` URLs) is only let in
+/// if it's listed in `allowed`, like any other origin.
+fn origin_allowed(origin: Option<&str>, allowed: &[&str]) -> bool {
+    let Some(origin) = origin else {
+        return true;
+    };
+    if allowed.contains(&origin) {
+        return true;
+    }
+    glib::Uri::parse(origin, glib::UriFlags::NONE).is_ok_and(|uri| {
+        uri.host()
+            .is_some_and(|host| ["localhost", "127.0.0.1", "::1"].contains(&host.as_str()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn origins() {
+        let allowed = ["https://overlay.example"];
+        assert!(origin_allowed(None, &allowed));
+        assert!(!origin_allowed(Some("null"), &allowed));
+        assert!(origin_allowed(Some("null"), &["null"]));
+        assert!(origin_allowed(Some("http://localhost:8000"), &allowed));
+        assert!(origin_allowed(Some("http://127.0.0.1"), &allowed));
+        assert!(origin_allowed(Some("http://[::1]:8080"), &allowed));
+        assert!(origin_allowed(Some("https://overlay.example"), &allowed));
+        assert!(!origin_allowed(Some("https://evil.example"), &allowed));
+        assert!(!origin_allowed(
+            Some("https://localhost.evil.example"),
+            &allowed
+        ));
+        assert!(!origin_allowed(Some("not a url"), &allowed));
+    }
+}
@@ -1,6 +1,8 @@
+use crate::autosave::SavedSolve;
 use crate::prelude::*;
-use crate::stats::Penalty;
-use std::cell::{Cell, OnceCell};
+use crate::stats::{Hand, Penalties, Penalty, PenaltyChange, PenaltySource, Trim};
+use crate::timer::render_single;
+use std::cell::{Cell, OnceCell, RefCell};
 use std::time::Duration;
 
 glib::wrapper! {
@@ -9,47 +11,256 @@ glib::wrapper! {
 
 impl SolveStat {
     pub fn new(tx: EventSender, time: Duration, scramble: Vec<cubestruct::Move>) -> Self {
+        Self::new_at(tx, time, scramble, glib::DateTime::now_local().unwrap())
+    }
+
+    /// A solve that was finished at `date`
+    fn new_at(
+        tx: EventSender,
+        time: Duration,
+        scramble: Vec<cubestruct::Move>,
+        date: glib::DateTime,
+    ) -> Self {
         let this: Self = glib::Object::builder().build();
         let imp = this.imp();
 
         imp.time.set(time);
+        imp.date.set(date).unwrap();
         imp.tx.set(Some(tx));
+        let mut scramble_state = cubestruct::CubieCube::SOLVED;
+        scramble_state.apply_moves(&scramble);
+        imp.scramble_state.set(scramble_state).unwrap();
         imp.scramble.set(scramble).unwrap();
 
         let tx2 = this.get_tx();
-        this.connect_notify(None, move |_, _| send_evt(tx2.clone(), Event::StatsChanged));
+        this.connect_notify(None, move |_, pspec| {
+            // worked out from the stats, rather than changing them
+            if pspec.name() != "trim" {
+                send_evt(tx2.clone(), StatsEvent::Changed);
+            }
+        });
+
+        this
+    }
 
+    /// Restore a solve from an autosave
+    pub fn from_saved(tx: EventSender, saved: &SavedSolve) -> Self {
+        let date = glib::DateTime::from_unix_local(saved.date)
+            .unwrap_or_else(|_| glib::DateTime::now_local().unwrap());
+        let this = Self::new_at(tx, saved.time, saved.scramble.clone(), date);
+        let imp = this.imp();
+        imp.memo_time.set(saved.memo_time);
+        imp.splits.replace(saved.splits.clone());
+        imp.stages.replace(saved.stages.clone());
+        imp.turns.replace(saved.turns.clone());
+        // the penalty history isn't saved, so don't record this as a change
+        imp.penalties.replace(saved.penalties.clone());
+        imp.hand.set(saved.hand);
+        imp.comment.replace(saved.comment.clone());
+        imp.attachment.replace(saved.attachment.clone());
+        imp.cube.replace(saved.cube.clone());
+        imp.scramble_difficulty.set(saved.scramble_difficulty);
         this
     }
 
+    pub fn to_saved(&self) -> SavedSolve {
+        SavedSolve {
+            time: self.imp().time.get(),
+            date: self.date().to_unix(),
+            penalties: self.penalties(),
+            memo_time: self.memo_time(),
+            splits: self.splits(),
+            stages: self.stages(),
+            turns: self.turns(),
+            hand: self.hand(),
+            scramble: self.scramble().to_vec(),
+            comment: self.comment(),
+            attachment: self.attachment(),
+            cube: self.cube(),
+            scramble_difficulty: self.scramble_difficulty(),
+        }
+    }
+
     fn get_tx(&self) -> EventSender {
         let tx = self.imp().tx.take().unwrap();
         self.imp().tx.set(Some(tx.clone()));
         tx
     }
 
-    /// Returns `None` if DNF
+    /// Including any time penalties. Returns `None` if DNF.
     pub fn time(&self) -> Option<Duration> {
-        match self.penalty() {
-            Penalty::None => Some(self.imp().time.get()),
-            Penalty::Plus2 => Some(self.imp().time.get() + Duration::from_secs(2)),
-            Penalty::Dnf => None,
+        let penalties = self.imp().penalties.borrow();
+        (!penalties.dnf).then(|| self.imp().time.get() + penalties.added_time())
+    }
+
+    /// For working out statistics (see [`solvestats`])
+    pub fn to_solve(&self) -> solvestats::Solve {
+        let penalties = self.imp().penalties.borrow();
+        solvestats::Solve {
+            time: self.imp().time.get(),
+            dnf: penalties.dnf,
+            added: penalties.added.clone(),
         }
     }
 
+    pub fn penalties(&self) -> Penalties {
+        self.imp().penalties.borrow().clone()
+    }
+
+    /// Turn the given penalty on, or off if it's already applied. A DNF keeps any seconds
+    /// added to the time underneath it, so they're back if it's turned off again; turning +2
+    /// on clears a DNF, and turning it off clears every added second.
+    pub fn toggle_penalty(&self, penalty: Penalty) {
+        let prop = match penalty {
+            Penalty::None => return,
+            Penalty::Dnf => "is-dnf",
+            Penalty::Plus2 => "is-plus2",
+        };
+        self.set_property(prop, !self.property::<bool>(prop));
+    }
+
+    /// Give the penalty for a reason other than the user toggling it, on top of any the solve
+    /// already has (see [`Penalties::with`])
+    pub fn apply_penalty(&self, penalty: Penalty, source: PenaltySource) {
+        let penalties = self.penalties().with(penalty);
+        self.imp().change_penalties(penalties, source);
+    }
+
+    /// Add a time penalty of `seconds`, on top of any the solve already has
+    pub fn add_time_penalty(&self, seconds: u32, source: PenaltySource) {
+        let mut penalties = self.penalties();
+        penalties.added.push(seconds);
+        self.imp().change_penalties(penalties, source);
+    }
+
+    /// Replace all the solve's penalties, e.g. with ones synced from elsewhere
+    pub fn set_penalties(&self, penalties: Penalties, source: PenaltySource) {
+        self.imp().change_penalties(penalties, source);
+    }
+
+    /// Every change to the penalty, oldest first
+    pub fn penalty_history(&self) -> Vec<PenaltyChange> {
+        self.imp().penalty_history.borrow().clone()
+    }
+
     pub fn scramble(&self) -> &[cubestruct::Move] {
         self.imp().scramble.get().unwrap()
     }
+
+    /// When the solve was finished
+    pub fn date(&self) -> glib::DateTime {
+        self.imp().date.get().unwrap().clone()
+    }
+
+    /// For blindfolded solves, how long was spent memorizing
+    pub fn memo_time(&self) -> Option<Duration> {
+        self.imp().memo_time.get()
+    }
+
+    pub fn set_memo_time(&self, memo_time: Duration) {
+        self.imp().memo_time.set(Some(memo_time));
+    }
+
+    /// Manual splits, as times since the start
+    pub fn splits(&self) -> Vec<Duration> {
+        self.imp().splits.borrow().clone()
+    }
+
+    pub fn set_splits(&self, splits: Vec<Duration>) {
+        self.imp().splits.replace(splits);
+    }
+
+    /// When each of [`crate::timer::PACE_STAGES`] was reached, for solves on a smart cube
+    pub fn stages(&self) -> Vec<Duration> {
+        self.imp().stages.borrow().clone()
+    }
+
+    pub fn set_stages(&self, stages: Vec<Duration>) {
+        self.imp().stages.replace(stages);
+    }
+
+    /// Each turn of a solve on a smart cube, with when it was done as a time since the
+    /// start. Empty if the cube reported a change that isn't a single turn.
+    pub fn turns(&self) -> Vec<(cubestruct::Move, Duration)> {
+        self.imp().turns.borrow().clone()
+    }
+
+    pub fn set_turns(&self, turns: Vec<(cubestruct::Move, Duration)>) {
+        self.imp().turns.replace(turns);
+    }
+
+    /// Tags in the comment (see [`tags_in`])
+    pub fn tags(&self) -> Vec<String> {
+        tags_in(&self.comment())
+    }
+
+    /// The state the cube was in after applying the scramble
+    pub fn scramble_state(&self) -> cubestruct::CubieCube {
+        *self.imp().scramble_state.get().unwrap()
+    }
+
+    /// If the solve was started from a different state than [`Self::scramble_state`]
+    /// (according to a smart cube), the state it was actually started from
+    pub fn misscrambled_state(&self) -> Option<cubestruct::CubieCube> {
+        self.imp().misscrambled_state.get()
+    }
+
+    /// Length of the shortest solution to the scramble the solver found, if it was worked
+    /// out before the solve (see [`cubestruct::CubieCube::solution_length_bound`])
+    pub fn scramble_difficulty(&self) -> Option<u8> {
+        self.imp().scramble_difficulty.get()
+    }
+
+    pub fn set_scramble_difficulty(&self, moves: Option<u8>) {
+        self.imp().scramble_difficulty.set(moves);
+    }
+
+    pub fn set_misscrambled_state(&self, state: cubestruct::CubieCube) {
+        self.imp().misscrambled_state.set(Some(state));
+    }
+}
+
+/// Words in `comment` starting with `#`, lowercased and without the `#`
+pub fn tags_in(comment: &str) -> Vec<String> {
+    comment
+        .split_whitespace()
+        .filter_map(|word| word.strip_prefix('#'))
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_lowercase)
+        .collect()
 }
 
 #[derive(Default, glib::Properties)]
 #[properties(wrapper_type = SolveStat)]
 pub struct SolveStatImp {
     time: Cell<Duration>,
-    #[property(get, set, builder(Penalty::None))]
-    penalty: Cell<Penalty>,
+    date: OnceCell<glib::DateTime>,
+    memo_time: Cell<Option<Duration>>,
+    splits: RefCell<Vec<Duration>>,
+    stages: RefCell<Vec<Duration>>,
+    turns: RefCell<Vec<(cubestruct::Move, Duration)>>,
+    /// The property is the kind of penalty, for binding to
+    #[property(name = "penalty", get = Self::penalty_kind, type = Penalty, builder(Penalty::None))]
+    penalties: RefCell<Penalties>,
+    penalty_history: RefCell<Vec<PenaltyChange>>,
+    #[property(get, set, builder(Hand::Both))]
+    hand: Cell<Hand>,
+    #[property(get, set)]
+    comment: RefCell<String>,
+    /// URL or file path of e.g. a video of the solve
+    #[property(get, set, nullable)]
+    attachment: RefCell<Option<String>>,
+    /// Name of the cube the solve was done on (see [`crate::cubes`])
+    #[property(get, set, nullable)]
+    cube: RefCell<Option<String>>,
     tx: Cell<Option<EventSender>>,
     scramble: OnceCell<Vec<cubestruct::Move>>,
+    scramble_state: OnceCell<cubestruct::CubieCube>,
+    misscrambled_state: Cell<Option<cubestruct::CubieCube>>,
+    scramble_difficulty: Cell<Option<u8>>,
+    /// Whether it's dropped from the current averages (see [`crate::stats::Stats`])
+    #[property(get, set, builder(Trim::None))]
+    trim: Cell<Trim>,
 }
 
 #[glib::object_subclass]
@@ -93,7 +304,7 @@ impl ObjectImpl for SolveStatImp {
         match pspec.name() {
             "time-string" => {
                 if let Some(dur) = self.obj().time() {
-                    crate::timer::render_time(&dur, true).to_value()
+                    render_single(&dur).to_value()
                 } else {
                     "DNF".to_value()
                 }
@@ -105,46 +316,72 @@ impl ObjectImpl for SolveStatImp {
     }
 
     fn set_property(&self, id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        let mut penalties = self.penalties.borrow().clone();
         match pspec.name() {
-            "is-plus2" => {
-                let value = value.get::<bool>().unwrap();
-                match (value, self.penalty.get()) {
-                    (true, Penalty::Dnf) => {
-                        self.penalty.set(Penalty::Plus2);
-                        self.obj().notify_penalty();
-                        self.obj().notify("is-dnf");
-                    }
-                    (true, Penalty::None) => {
-                        self.penalty.set(Penalty::Plus2);
-                        self.obj().notify_penalty();
+            // turning +2 on undoes a DNF, and adds a +2 unless there's already a time penalty
+            "is-plus2" => match (value.get::<bool>().unwrap(), penalties.kind()) {
+                (true, Penalty::Plus2) => {}
+                (true, _) => {
+                    penalties.dnf = false;
+                    if penalties.added.is_empty() {
+                        penalties.added.push(2);
                     }
-                    (false, Penalty::Plus2) => {
-                        self.penalty.set(Penalty::None);
-                        self.obj().notify_penalty();
-                    }
-                    _ => {}
+                    self.change_penalties(penalties, PenaltySource::Manual);
                 }
-            }
-            "is-dnf" => {
-                let value = value.get::<bool>().unwrap();
-                match (value, self.penalty.get()) {
-                    (true, Penalty::Plus2) => {
-                        self.penalty.set(Penalty::Dnf);
-                        self.obj().notify_penalty();
-                        self.obj().notify("is-plus2");
-                    }
-                    (true, Penalty::None) => {
-                        self.penalty.set(Penalty::Dnf);
-                        self.obj().notify_penalty();
-                    }
-                    (false, Penalty::Dnf) => {
-                        self.penalty.set(Penalty::None);
-                        self.obj().notify_penalty();
-                    }
-                    _ => {}
+                (false, Penalty::Plus2) => {
+                    penalties.added.clear();
+                    self.change_penalties(penalties, PenaltySource::Manual);
                 }
+                _ => {}
+            },
+            "is-dnf" => {
+                penalties.dnf = value.get::<bool>().unwrap();
+                self.change_penalties(penalties, PenaltySource::Manual);
             }
             _ => self.derived_set_property(id, value, pspec),
         }
     }
 }
+
+impl SolveStatImp {
+    fn penalty_kind(&self) -> Penalty {
+        self.penalties.borrow().kind()
+    }
+
+    /// Set the penalties, recording the change and notifying the affected properties
+    fn change_penalties(&self, penalties: Penalties, source: PenaltySource) {
+        let old = self.penalties.replace(penalties.clone());
+        if old == penalties {
+            return;
+        }
+        let (old_kind, kind) = (old.kind(), penalties.kind());
+        self.penalty_history.borrow_mut().push(PenaltyChange {
+            penalties,
+            source,
+            at: glib::DateTime::now_local().unwrap(),
+        });
+
+        // even if the kind is the same, the time may have changed
+        self.obj().notify_penalty();
+        if old_kind == Penalty::Dnf || kind == Penalty::Dnf {
+            self.obj().notify("is-dnf");
+        }
+        if old_kind == Penalty::Plus2 || kind == Penalty::Plus2 {
+            self.obj().notify("is-plus2");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags() {
+        assert_eq!(
+            tags_in("#OLL-skip lucky # #pb#2 not#tag"),
+            ["oll-skip", "pb#2"]
+        );
+        assert!(tags_in("").is_empty());
+    }
+}
@@ -0,0 +1,143 @@
+//! The row that shows a [`SolveStat`] in a list, with its number, time and penalty buttons.
+//! Hovering it shows the solve's scramble and date. Times dropped from the current averages
+//! are highlighted.
+use crate::prelude::*;
+use crate::stats::{Penalty, SolveStat, Trim};
+use std::cell::RefCell;
+
+glib::wrapper! {
+    pub struct StatRow(ObjectSubclass<StatRowImp>)
+        @extends gtk::Box, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Orientable;
+}
+
+impl Default for StatRow {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
+
+impl StatRow {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Show `stat` as result number `index + 1`, calling `on_click` with it when the row is
+    /// clicked. A row can be bound again after [`Self::unbind()`], so list views can reuse it.
+    pub fn bind(&self, stat: &SolveStat, index: u32, on_click: impl Fn(&SolveStat) + 'static) {
+        let imp = self.imp();
+        self.unbind();
+
+        let stat2 = stat.clone();
+        imp.click_handler.replace(Some(
+            imp.gestureclick
+                .connect_released(move |_, _, _, _| on_click(&stat2)),
+        ));
+
+        imp.index_label.set_label(&format!("{}.", index + 1));
+        self.set_tooltip_markup(Some(&tooltip_markup(stat)));
+
+        imp.bindings.replace(vec![
+            stat.bind_property("is-dnf", &*imp.dnf_btn, "active")
+                .bidirectional()
+                .sync_create()
+                .build(),
+            stat.bind_property("is-plus2", &*imp.plus2_btn, "active")
+                .bidirectional()
+                .sync_create()
+                .build(),
+            stat.bind_property("penalty", &*imp.dnf_btn, "css-classes")
+                .transform_to(|_, penalty: Penalty| {
+                    Some(if penalty == Penalty::Dnf {
+                        ["error"].as_slice()
+                    } else {
+                        ["dim-label"].as_slice()
+                    })
+                })
+                .sync_create()
+                .build(),
+            stat.bind_property("penalty", &*imp.plus2_btn, "css-classes")
+                .transform_to(|_, penalty: Penalty| {
+                    Some(if penalty == Penalty::Plus2 {
+                        ["warning"].as_slice()
+                    } else {
+                        ["dim-label"].as_slice()
+                    })
+                })
+                .sync_create()
+                .build(),
+            stat.bind_property("time-string", &*imp.time_label, "label")
+                .sync_create()
+                .build(),
+            stat.bind_property("trim", &*imp.time_label, "css-classes")
+                .transform_to(|_, trim: Trim| {
+                    Some(match trim {
+                        Trim::None => [].as_slice(),
+                        Trim::Best => ["best-time"].as_slice(),
+                        Trim::Worst => ["worst-time"].as_slice(),
+                    })
+                })
+                .sync_create()
+                .build(),
+        ]);
+    }
+
+    /// Stop showing the stat from [`Self::bind()`], if any
+    pub fn unbind(&self) {
+        let imp = self.imp();
+        imp.bindings.take().iter().for_each(glib::Binding::unbind);
+        if let Some(handler) = imp.click_handler.take() {
+            imp.gestureclick.disconnect(handler);
+        }
+    }
+}
+
+/// The solve's scramble and when it was done, so they can be seen without opening the stat's
+/// dialog
+fn tooltip_markup(stat: &SolveStat) -> String {
+    let scramble = cubestruct::MoveSequence::new(stat.scramble()).wrapped(SCRAMBLE_WIDTH);
+    format!(
+        "<tt>{}</tt>\n<small>{}</small>",
+        glib::markup_escape_text(&scramble.to_string()),
+        glib::markup_escape_text(&stat.date().format("%c").unwrap()),
+    )
+}
+
+/// Characters per line of the scramble in the tooltip
+const SCRAMBLE_WIDTH: usize = 36;
+
+#[derive(Default, gtk::CompositeTemplate)]
+#[template(resource = "/io/github/flying_toast/PuzzleTime/stat-item.ui")]
+pub struct StatRowImp {
+    #[template_child]
+    gestureclick: TemplateChild<gtk::GestureClick>,
+    #[template_child]
+    index_label: TemplateChild<gtk::Label>,
+    #[template_child]
+    time_label: TemplateChild<gtk::Label>,
+    #[template_child]
+    plus2_btn: TemplateChild<gtk::ToggleButton>,
+    #[template_child]
+    dnf_btn: TemplateChild<gtk::ToggleButton>,
+    bindings: RefCell<Vec<glib::Binding>>,
+    click_handler: RefCell<Option<glib::SignalHandlerId>>,
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for StatRowImp {
+    const NAME: &'static str = "PuzzleTimeStatRow";
+    type Type = StatRow;
+    type ParentType = gtk::Box;
+
+    fn class_init(klass: &mut Self::Class) {
+        klass.bind_template();
+    }
+
+    fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+        obj.init_template();
+    }
+}
+
+impl ObjectImpl for StatRowImp {}
+impl WidgetImpl for StatRowImp {}
+impl BoxImpl for StatRowImp {}
@@ -1,8 +1,23 @@
+use crate::animation::SolveAnimation;
+use crate::cubes;
+use crate::files;
+use crate::i18n::decimal_separator;
 use crate::prelude::*;
+use crate::settings::settings;
 pub use crate::stat_object::SolveStat;
+use crate::stat_row::StatRow;
+use crate::timer::{render_moveseq, render_single, render_time, Precision};
+use crate::wca::OfficialRecords;
+use solvestats::{compare_times, round_average, round_single, session_mean_of, Session, TrimRule};
+pub use solvestats::{Average, NextSolve, RecordChange};
+use std::cell::{Cell, RefCell};
 use std::cmp::Ordering;
+use std::fmt::Write;
+use std::rc::Rc;
 use std::time::Duration;
 
+/// The kind of penalty a solve has, for toggling, filtering and sorting. A solve's time
+/// penalties can add more than 2 seconds (see [`Penalties`]); it's `Plus2` if it has any.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Default, glib::Enum)]
 #[enum_type(name = "PuzzleTimePenalty")]
 pub enum Penalty {
@@ -12,14 +27,588 @@ pub enum Penalty {
     Plus2,
 }
 
+impl std::fmt::Display for Penalty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Penalty::None => write!(f, "{}", gettext("No penalty")),
+            Penalty::Dnf => write!(f, "DNF"),
+            Penalty::Plus2 => write!(f, "+2"),
+        }
+    }
+}
+
+/// All of a solve's penalties. Time penalties add up, as the WCA regulations have them do for
+/// separate infractions (e.g. +2 for inspection and +2 for a misaligned face), and can be any
+/// number of seconds for rules other than the WCA's.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Penalties {
+    pub dnf: bool,
+    /// Seconds added by each time penalty, in the order they were given. They're kept when
+    /// the solve is a DNF, so that they're back if the DNF is removed.
+    pub added: Vec<u32>,
+}
+
+impl Penalties {
+    /// Just `penalty`, as a single +2 if it's [`Penalty::Plus2`]
+    pub fn of(penalty: Penalty) -> Self {
+        Self {
+            dnf: penalty == Penalty::Dnf,
+            added: match penalty {
+                Penalty::Plus2 => vec![2],
+                _ => Vec::new(),
+            },
+        }
+    }
+
+    pub fn kind(&self) -> Penalty {
+        if self.dnf {
+            Penalty::Dnf
+        } else if self.added.is_empty() {
+            Penalty::None
+        } else {
+            Penalty::Plus2
+        }
+    }
+
+    /// Time added by all the time penalties
+    pub fn added_time(&self) -> Duration {
+        Duration::from_secs(self.added.iter().copied().map(u64::from).sum())
+    }
+
+    /// `penalty` given on top of these: `None` removes them all, `Dnf` makes it a DNF and
+    /// `Plus2` adds a +2
+    pub fn with(mut self, penalty: Penalty) -> Self {
+        match penalty {
+            Penalty::None => return Self::default(),
+            Penalty::Dnf => self.dnf = true,
+            Penalty::Plus2 => self.added.push(2),
+        }
+        self
+    }
+
+    /// The time penalties, e.g. `+2+2`
+    fn render_added(&self) -> String {
+        self.added.iter().map(|secs| format!("+{secs}")).collect()
+    }
+}
+
+impl std::fmt::Display for Penalties {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.dnf, self.added.is_empty()) {
+            (false, true) => write!(f, "{}", Penalty::None),
+            (false, false) => write!(f, "{}", self.render_added()),
+            (true, true) => write!(f, "DNF"),
+            (true, false) => write!(f, "DNF ({})", self.render_added()),
+        }
+    }
+}
+
+/// Which hand(s) a solve was done with
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default, glib::Enum)]
+#[enum_type(name = "PuzzleTimeHand")]
+pub enum Hand {
+    #[default]
+    Both,
+    /// One-handed with the left hand
+    Left,
+    /// One-handed with the right hand
+    Right,
+}
+
+impl Hand {
+    /// In declaration order, which is also the order of the `hand_dropdown` and of the
+    /// stat dialog's `hand_row`
+    pub const ALL: [Self; 3] = [Self::Both, Self::Left, Self::Right];
+}
+
+/// Why a penalty was changed
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PenaltySource {
+    /// Toggled by the user
+    Manual,
+    /// Inspection ran over 15 seconds (+2) or 17 seconds (DNF)
+    InspectionOverrun,
+    /// The smartcube wasn't in the scrambled state when the solve started
+    Misscramble,
+    /// The app closed while the solve was running
+    Interrupted,
+    /// Changed on another machine, and synced from there
+    Synced,
+}
+
+impl std::fmt::Display for PenaltySource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PenaltySource::Manual => write!(f, "{}", gettext("manually")),
+            PenaltySource::InspectionOverrun => write!(f, "{}", gettext("inspection overrun")),
+            PenaltySource::Misscramble => write!(f, "{}", gettext("misscramble")),
+            PenaltySource::Interrupted => write!(f, "{}", gettext("interrupted")),
+            PenaltySource::Synced => write!(f, "{}", gettext("on another device")),
+        }
+    }
+}
+
+/// One entry in a solve's penalty history
+#[derive(Debug, Clone)]
+pub struct PenaltyChange {
+    /// The penalties after the change
+    pub penalties: Penalties,
+    pub source: PenaltySource,
+    pub at: glib::DateTime,
+}
+
+/// Which solves are shown in the results list
+#[derive(Debug, Clone, Default, PartialEq)]
+struct SolveFilter {
+    penalty: Option<Penalty>,
+    /// Only solves finished at or after this
+    since: Option<glib::DateTime>,
+    /// Only solves faster than this
+    faster_than: Option<Duration>,
+    /// Only solves slower than this
+    slower_than: Option<Duration>,
+    /// Tags that solves must have
+    tags: Vec<String>,
+    /// Lowercase text that must be in the comment or scramble
+    text: String,
+}
+
+impl SolveFilter {
+    /// `search` is text to search for in comments and scrambles, where words starting
+    /// with `#` are tags the solve must have instead
+    fn new(
+        penalty: Option<Penalty>,
+        since: Option<glib::DateTime>,
+        faster_than: Option<Duration>,
+        slower_than: Option<Duration>,
+        search: &str,
+    ) -> Self {
+        let text = search
+            .split_whitespace()
+            .filter(|word| !word.starts_with('#'))
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase();
+        Self {
+            penalty,
+            since,
+            faster_than,
+            slower_than,
+            tags: crate::stat_object::tags_in(search),
+            text,
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        *self != Self::default()
+    }
+
+    fn matches(&self, stat: &SolveStat) -> bool {
+        if self
+            .penalty
+            .is_some_and(|penalty| penalty != stat.penalty())
+        {
+            return false;
+        }
+        if self
+            .since
+            .as_ref()
+            .is_some_and(|since| stat.date() < *since)
+        {
+            return false;
+        }
+        if self.faster_than.is_some() || self.slower_than.is_some() {
+            let Some(time) = stat.time() else {
+                return false;
+            };
+            if self.faster_than.is_some_and(|max| time >= max)
+                || self.slower_than.is_some_and(|min| time <= min)
+            {
+                return false;
+            }
+        }
+        let tags = stat.tags();
+        if !self.tags.iter().all(|tag| tags.contains(tag)) {
+            return false;
+        }
+        self.text.is_empty()
+            || stat.comment().to_lowercase().contains(&self.text)
+            || render_moveseq(stat.scramble(), false)
+                .to_lowercase()
+                .contains(&self.text)
+    }
+}
+
+/// Order of the results list
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+enum SortOrder {
+    /// The order the solves were done in
+    #[default]
+    Oldest,
+    Newest,
+    Fastest,
+    Slowest,
+    /// DNFs, then +2s, then solves without a penalty
+    Penalties,
+}
+
+impl SortOrder {
+    /// In declaration order, which is also the order of the `sort_dropdown`
+    const ALL: [Self; 5] = [
+        Self::Oldest,
+        Self::Newest,
+        Self::Fastest,
+        Self::Slowest,
+        Self::Penalties,
+    ];
+
+    /// Solves that compare equal stay in the order they were done in
+    fn compare(self, a: &SolveStat, b: &SolveStat) -> Ordering {
+        let penalty_rank = |stat: &SolveStat| match stat.penalty() {
+            Penalty::Dnf => 0,
+            Penalty::Plus2 => 1,
+            Penalty::None => 2,
+        };
+        match self {
+            Self::Oldest => Ordering::Equal,
+            Self::Newest => b.date().cmp(&a.date()),
+            Self::Fastest => compare_times(&a.time(), &b.time()),
+            Self::Slowest => compare_times(&b.time(), &a.time()),
+            Self::Penalties => penalty_rank(a).cmp(&penalty_rank(b)),
+        }
+    }
+}
+
+/// The widgets for sorting and filtering the results list
+#[derive(Debug, Clone)]
+struct ListControls {
+    /// In the same order as [`SortOrder::ALL`]
+    sort: gtk::DropDown,
+    /// In the same order as [`ListControls::PENALTIES`]
+    penalty: gtk::DropDown,
+    /// Today, past week or past month
+    date: gtk::DropDown,
+    faster_than: gtk::SpinButton,
+    slower_than: gtk::SpinButton,
+    search: gtk::SearchEntry,
+    button: gtk::MenuButton,
+    filter: gtk::CustomFilter,
+    solve_filter: Rc<RefCell<SolveFilter>>,
+    sorter: gtk::CustomSorter,
+    sort_order: Rc<Cell<SortOrder>>,
+}
+
+impl ListControls {
+    /// Options of the `penalty_filter` dropdown
+    const PENALTIES: [Option<Penalty>; 4] = [
+        None,
+        Some(Penalty::None),
+        Some(Penalty::Plus2),
+        Some(Penalty::Dnf),
+    ];
+
+    /// Sort and filter the list by what's set in the controls
+    fn apply(&self) {
+        // 0 means no limit
+        let seconds = |spin: &gtk::SpinButton| {
+            (spin.value() > 0.0).then(|| Duration::from_secs_f64(spin.value()))
+        };
+        let now = glib::DateTime::now_local().unwrap();
+        let since = match self.date.selected() {
+            1 => Some(start_of_day(&now)),
+            2 => now.add_days(-7).ok(),
+            3 => now.add_months(-1).ok(),
+            _ => None,
+        };
+        let solve_filter = SolveFilter::new(
+            Self::PENALTIES[self.penalty.selected() as usize],
+            since,
+            seconds(&self.faster_than),
+            seconds(&self.slower_than),
+            &self.search.text(),
+        );
+        let sort_order = SortOrder::ALL[self.sort.selected() as usize];
+        // highlight the button if anything in its popover is set
+        if sort_order != SortOrder::default()
+            || solve_filter.penalty.is_some()
+            || solve_filter.since.is_some()
+            || solve_filter.faster_than.is_some()
+            || solve_filter.slower_than.is_some()
+        {
+            self.button.add_css_class("accent");
+        } else {
+            self.button.remove_css_class("accent");
+        }
+        *self.solve_filter.borrow_mut() = solve_filter;
+        self.filter.changed(gtk::FilterChange::Different);
+        if self.sort_order.replace(sort_order) != sort_order {
+            self.sorter.changed(gtk::SorterChange::Different);
+        }
+    }
+}
+
+/// When a new session starts by itself, moving the previous session's solves to the trash
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SessionSplit {
+    Never,
+    /// On the first solve of a new day
+    Midnight,
+    /// On the first solve after this long without one
+    Inactivity(Duration),
+}
+
+impl SessionSplit {
+    /// As set in the `auto-new-session` and `new-session-inactivity` settings
+    pub fn from_settings() -> Self {
+        let settings = settings();
+        match settings.string("auto-new-session").as_str() {
+            "midnight" => Self::Midnight,
+            "inactivity" => Self::Inactivity(Duration::from_secs(
+                u64::from(settings.uint("new-session-inactivity")) * 60 * 60,
+            )),
+            _ => Self::Never,
+        }
+    }
+
+    /// Whether a solve finished at `now` starts a new session, if the last one was finished
+    /// at `last`
+    fn starts_session(self, last: &glib::DateTime, now: &glib::DateTime) -> bool {
+        match self {
+            Self::Never => false,
+            Self::Midnight => start_of_day(last) != start_of_day(now),
+            Self::Inactivity(period) => {
+                now.difference(last).as_microseconds() >= period.as_micros() as i64
+            }
+        }
+    }
+}
+
+/// Midnight at the start of `date`'s day
+fn start_of_day(date: &glib::DateTime) -> glib::DateTime {
+    let (year, month, day) = date.ymd();
+    glib::DateTime::from_local(year, month, day, 0, 0, 0.0).unwrap()
+}
+
+/// Index of `stat` in `store`
+fn index_of(store: &gio::ListStore, stat: &SolveStat) -> Option<u32> {
+    (0..store.n_items()).find(|&idx| store.item(idx).as_ref() == Some(stat.upcast_ref()))
+}
+
+/// How results are aggregated in a session
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum ResultFormat {
+    #[default]
+    Ao5,
+    Mo3,
+    Bo1,
+    /// 2x2, 3x3 and 4x4 solved back to back, with one time for all three.
+    /// Scrambles are still only generated for 3x3.
+    Relay234,
+}
+
+impl ResultFormat {
+    /// In declaration order, which is also the order of the `format_dropdown`
+    const ALL: [Self; 4] = [Self::Ao5, Self::Mo3, Self::Bo1, Self::Relay234];
+
+    /// Number of consecutive results that are aggregated, and how they're aggregated
+    fn window(self) -> (usize, TrimRule) {
+        match self {
+            Self::Ao5 => (5, TrimRule::Wca),
+            Self::Mo3 | Self::Relay234 => (3, TrimRule::Mean),
+            Self::Bo1 => (1, TrimRule::Mean),
+        }
+    }
+
+    /// Value in the `result-format` setting
+    fn setting_value(self) -> &'static str {
+        match self {
+            Self::Ao5 => "ao5",
+            Self::Mo3 => "mo3",
+            Self::Bo1 => "bo1",
+            Self::Relay234 => "relay-234",
+        }
+    }
+
+    /// Short name used when sharing a result, like `ao5`
+    fn short_name(self) -> &'static str {
+        match self {
+            Self::Ao5 => "ao5",
+            Self::Mo3 => "mo3",
+            Self::Bo1 => "single",
+            Self::Relay234 => "mo3 relay",
+        }
+    }
+
+    /// Titles of the current and best aggregate
+    fn titles(self) -> (String, String) {
+        match self {
+            Self::Ao5 => (gettext("Average of 5"), gettext("Best Average of 5")),
+            Self::Mo3 => (gettext("Mean of 3"), gettext("Best Mean of 3")),
+            Self::Bo1 => (gettext("Last Single"), gettext("Best Single")),
+            Self::Relay234 => (
+                gettext("Mean of 3 Relays"),
+                gettext("Best Mean of 3 Relays"),
+            ),
+        }
+    }
+}
+
+/// Number of latest solves that the sub-X rate tile shows the trend of
+const SUB_X_TREND: usize = 50;
+
+/// A statistic that can be shown in a tile at the top of the sidebar
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum StatTile {
+    /// Mean of the non-DNF solves, or the success rate in blindfolded mode
+    SessionAverage,
+    /// Latest result in the session's [`ResultFormat`]
+    Aggregate,
+    BestAggregate,
+    Ao5,
+    BestAo5,
+    Ao12,
+    BestAo12,
+    BestSingle,
+    Count,
+    /// Number of solves finished today
+    SolvesToday,
+    /// Standard deviation of the non-DNF solves
+    Deviation,
+    /// Interquartile range of the non-DNF solves
+    Iqr,
+    /// See [`consistency_of`]
+    Consistency,
+    /// Share of solves under the `sub-x-threshold` setting, and how the latest
+    /// [`SUB_X_TREND`] solves compare
+    SubX,
+}
+
+impl StatTile {
+    pub const ALL: [Self; 14] = [
+        Self::SessionAverage,
+        Self::Aggregate,
+        Self::BestAggregate,
+        Self::Ao5,
+        Self::BestAo5,
+        Self::Ao12,
+        Self::BestAo12,
+        Self::BestSingle,
+        Self::Count,
+        Self::SolvesToday,
+        Self::Deviation,
+        Self::Iqr,
+        Self::Consistency,
+        Self::SubX,
+    ];
+
+    /// Value in the `stat-tiles` setting
+    pub fn setting_value(self) -> &'static str {
+        match self {
+            Self::SessionAverage => "session-average",
+            Self::Aggregate => "aggregate",
+            Self::BestAggregate => "best-aggregate",
+            Self::Ao5 => "ao5",
+            Self::BestAo5 => "best-ao5",
+            Self::Ao12 => "ao12",
+            Self::BestAo12 => "best-ao12",
+            Self::BestSingle => "best-single",
+            Self::Count => "count",
+            Self::SolvesToday => "solves-today",
+            Self::Deviation => "deviation",
+            Self::Iqr => "iqr",
+            Self::Consistency => "consistency",
+            Self::SubX => "sub-x",
+        }
+    }
+
+    /// Name to show in the preferences
+    pub fn name(self) -> String {
+        match self {
+            Self::SessionAverage => gettext("Session Average"),
+            Self::Aggregate => gettext("Current Result"),
+            Self::BestAggregate => gettext("Best Result"),
+            Self::Ao5 => gettext("Average of 5"),
+            Self::BestAo5 => gettext("Best Average of 5"),
+            Self::Ao12 => gettext("Average of 12"),
+            Self::BestAo12 => gettext("Best Average of 12"),
+            Self::BestSingle => gettext("Best Single"),
+            Self::Count => gettext("Number of Solves"),
+            Self::SolvesToday => gettext("Solves Today"),
+            Self::Deviation => gettext("Standard Deviation"),
+            Self::Iqr => gettext("Interquartile Range"),
+            Self::Consistency => gettext("Consistency"),
+            Self::SubX => gettext("Sub-X Rate"),
+        }
+    }
+
+    /// Tiles to show, in order, as set in the settings
+    pub fn shown() -> Vec<Self> {
+        let mut shown = Vec::new();
+        for value in settings().strv("stat-tiles") {
+            if let Some(tile) = Self::ALL
+                .into_iter()
+                .find(|tile| tile.setting_value() == value.as_str())
+            {
+                if !shown.contains(&tile) {
+                    shown.push(tile);
+                }
+            }
+        }
+        shown
+    }
+}
+
+/// Whether a solve is one of the best or worst times that are dropped from the current ao5 or
+/// ao12, which are shown in parentheses when shared
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default, glib::Enum)]
+#[enum_type(name = "PuzzleTimeTrim")]
+pub enum Trim {
+    #[default]
+    None,
+    Best,
+    Worst,
+}
+
+/// Identifies a stat in the trash. Events refer to trashed stats by this instead of
+/// holding the [`SolveStat`], since events have to be `Send`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct TrashId(u64);
+
 #[derive(Debug)]
 pub struct Stats {
     root: gtk::Box,
     store: gio::ListStore,
-    backup: Option<(u32, SolveStat)>,
-    ao5_label: gtk::Label,
-    best_ao5: gtk::Label,
-    session_average_label: gtk::Label,
+    /// Deleted stats and the index they were at, most recently deleted last
+    trash: Vec<(TrashId, u32, SolveStat)>,
+    next_trash_id: u64,
+    format_dropdown: gtk::DropDown,
+    /// Hand that new solves are done with
+    hand_dropdown: gtk::DropDown,
+    /// Seed of the session's scrambles, empty if they're random
+    seed_entry: gtk::Entry,
+    /// Stats of one-handed solves, split by hand
+    hand_split: gtk::Box,
+    left_mean: gtk::Label,
+    left_best: gtk::Label,
+    right_mean: gtk::Label,
+    right_best: gtk::Label,
+    /// Stats of the solves done on each cube, if any are tagged with a cube
+    cube_split: gtk::Grid,
+    /// Comparison with the official results from the user's WCA profile
+    official: gtk::Box,
+    official_single: gtk::Label,
+    practice_single: gtk::Label,
+    official_average: gtk::Label,
+    practice_average: gtk::Label,
+    /// `None` if there's no WCA ID set
+    official_records: Option<OfficialRecords>,
+    list_controls: ListControls,
+    tiles_grid: gtk::Grid,
+    /// Tiles currently in `tiles_grid`, with their value and title labels
+    tiles: Vec<(StatTile, gtk::Label, gtk::Label)>,
+    /// Show the success rate instead of the session average
+    blind: bool,
+    goals: crate::goals::Goals,
 }
 
 impl Stats {
@@ -30,81 +619,58 @@ impl Stats {
             builder.object("listview_factory").unwrap();
         listview_factory.connect_setup(|_factory, list_item| {
             let list_item = list_item.downcast_ref::<gtk::ListItem>().unwrap();
-            let ui = StatItemUi::new();
-            list_item.set_child(Some(&ui.root));
-            unsafe {
-                list_item.set_data("PuzzleTimeUiStruct", ui);
-            }
+            list_item.set_child(Some(&StatRow::new()));
         });
+        let store = gio::ListStore::new::<SolveStat>();
         let tx2 = tx.clone();
+        let store2 = store.clone();
         listview_factory.connect_bind(move |_factory, list_item| {
             let list_item = list_item.downcast_ref::<gtk::ListItem>().unwrap();
-            let mut ui: StatItemUi = unsafe { list_item.steal_data("PuzzleTimeUiStruct") }.unwrap();
-            let item = list_item.item().unwrap().downcast::<SolveStat>().unwrap();
-            let my_index = list_item.position();
-
-            let tx2 = tx2.clone();
-            ui.click_handler = Some(ui.gestureclick.connect_released(move |_, _, _, _| {
-                send_evt(tx2.clone(), Event::ShowStat(my_index));
-            }));
-
-            ui.index_label.set_label(&format!("{}.", my_index + 1));
-
-            ui.bindings = vec![
-                item.bind_property("is-dnf", &ui.dnf_button, "active")
-                    .bidirectional()
-                    .sync_create()
-                    .build(),
-                item.bind_property("is-plus2", &ui.plus2_button, "active")
-                    .bidirectional()
-                    .sync_create()
-                    .build(),
-                item.bind_property("penalty", &ui.dnf_button, "css-classes")
-                    .transform_to(|_, penalty: Penalty| {
-                        Some(if penalty == Penalty::Dnf {
-                            ["error"].as_slice()
-                        } else {
-                            ["dim-label"].as_slice()
-                        })
-                    })
-                    .sync_create()
-                    .build(),
-                item.bind_property("penalty", &ui.plus2_button, "css-classes")
-                    .transform_to(|_, penalty: Penalty| {
-                        Some(if penalty == Penalty::Plus2 {
-                            ["warning"].as_slice()
-                        } else {
-                            ["dim-label"].as_slice()
-                        })
-                    })
-                    .sync_create()
-                    .build(),
-                item.bind_property("time-string", &ui.time_label, "label")
-                    .sync_create()
-                    .build(),
-            ];
-
-            unsafe {
-                list_item.set_data("PuzzleTimeUiStruct", ui);
-            }
+            let row = list_item.child().and_downcast::<StatRow>().unwrap();
+            let item = list_item.item().and_downcast::<SolveStat>().unwrap();
+            // the list may be filtered, so the position in it isn't the index in the store
+            let my_index = index_of(&store2, &item).unwrap();
+
+            let (tx, store) = (tx2.clone(), store2.clone());
+            row.bind(&item, my_index, move |stat| {
+                if let Some(index) = index_of(&store, stat) {
+                    send_evt(tx.clone(), StatsEvent::ShowStat(index));
+                }
+            });
         });
-        listview_factory.connect_unbind(move |_factory, list_item| {
+        listview_factory.connect_unbind(|_factory, list_item| {
             let list_item = list_item.downcast_ref::<gtk::ListItem>().unwrap();
-            let mut ui: StatItemUi = unsafe { list_item.steal_data("PuzzleTimeUiStruct") }.unwrap();
-
-            std::mem::take(&mut ui.bindings)
-                .iter()
-                .for_each(glib::Binding::unbind);
-            ui.gestureclick.disconnect(ui.click_handler.take().unwrap());
-
-            unsafe {
-                list_item.set_data("PuzzleTimeUiStruct", ui);
-            }
+            list_item
+                .child()
+                .and_downcast::<StatRow>()
+                .unwrap()
+                .unbind();
         });
-        let store = gio::ListStore::new::<SolveStat>();
-        let listview_model: gtk::NoSelection = gtk::NoSelection::new(Some(store.clone()));
+        let solve_filter = Rc::new(RefCell::new(SolveFilter::default()));
+        let solve_filter2 = solve_filter.clone();
+        let filter = gtk::CustomFilter::new(move |item| {
+            solve_filter2
+                .borrow()
+                .matches(item.downcast_ref::<SolveStat>().unwrap())
+        });
+        let filter_model = gtk::FilterListModel::new(Some(store.clone()), Some(filter.clone()));
+        let sort_order = Rc::new(Cell::new(SortOrder::default()));
+        let sort_order2 = sort_order.clone();
+        let sorter = gtk::CustomSorter::new(move |a, b| {
+            sort_order2
+                .get()
+                .compare(
+                    a.downcast_ref::<SolveStat>().unwrap(),
+                    b.downcast_ref::<SolveStat>().unwrap(),
+                )
+                .into()
+        });
+        // deleting and undoing use the index in `store`, so they aren't affected by the
+        // sorting or filtering
+        let sort_model = gtk::SortListModel::new(Some(filter_model), Some(sorter.clone()));
+        let listview_model: gtk::NoSelection = gtk::NoSelection::new(Some(sort_model));
         let listview: gtk::ListView = builder.object("listview").unwrap();
-        listview_model
+        store
             .bind_property("n-items", &statuspage, "visible")
             .transform_to(|_, n: u32| Some(n == 0))
             .sync_create()
@@ -114,178 +680,793 @@ impl Stats {
 
         let tx2 = tx.clone();
         store.connect_notify(Some("n-items"), move |_, _| {
-            send_evt(tx2.clone(), Event::StatsChanged);
+            send_evt(tx2.clone(), StatsEvent::Changed);
         });
 
-        Self {
+        let list_controls = ListControls {
+            sort: builder.object("sort_dropdown").unwrap(),
+            penalty: builder.object("penalty_filter").unwrap(),
+            date: builder.object("date_filter").unwrap(),
+            faster_than: builder.object("faster_than_filter").unwrap(),
+            slower_than: builder.object("slower_than_filter").unwrap(),
+            search: builder.object("search_entry").unwrap(),
+            button: builder.object("filter_button").unwrap(),
+            filter,
+            solve_filter,
+            sorter,
+            sort_order,
+        };
+        let controls = list_controls.clone();
+        list_controls
+            .sort
+            .connect_selected_notify(move |_| controls.apply());
+        let controls = list_controls.clone();
+        list_controls
+            .penalty
+            .connect_selected_notify(move |_| controls.apply());
+        let controls = list_controls.clone();
+        list_controls
+            .date
+            .connect_selected_notify(move |_| controls.apply());
+        for spin in [&list_controls.faster_than, &list_controls.slower_than] {
+            let controls = list_controls.clone();
+            spin.connect_value_changed(move |_| controls.apply());
+        }
+        let controls = list_controls.clone();
+        list_controls
+            .search
+            .connect_search_changed(move |_| controls.apply());
+
+        let format_dropdown: gtk::DropDown = builder.object("format_dropdown").unwrap();
+        let saved_format = settings().string("result-format");
+        if let Some(format) = ResultFormat::ALL
+            .into_iter()
+            .find(|format| format.setting_value() == saved_format.as_str())
+        {
+            format_dropdown.set_selected(format as u32);
+        }
+        let tx2 = tx.clone();
+        format_dropdown.connect_selected_notify(move |dropdown| {
+            let format = ResultFormat::ALL[dropdown.selected() as usize];
+            settings()
+                .set_string("result-format", format.setting_value())
+                .unwrap();
+            send_evt(tx2.clone(), StatsEvent::Changed);
+        });
+
+        let seed_entry: gtk::Entry = builder.object("seed_entry").unwrap();
+        let tx2 = tx.clone();
+        seed_entry.connect_activate(move |entry| {
+            let text = entry.text().trim().to_string();
+            send_evt(
+                tx2.clone(),
+                TimerEvent::SetScrambleSeed((!text.is_empty()).then_some(text)),
+            );
+        });
+
+        for key in ["stat-tiles", "sub-x-threshold"] {
+            let tx = tx.clone();
+            settings().connect_changed(Some(key), move |_, _| {
+                send_evt(tx.clone(), StatsEvent::Changed);
+            });
+        }
+
+        let cube_box: gtk::Box = builder.object("cube_box").unwrap();
+        let cube_dropdown: gtk::DropDown = builder.object("cube_dropdown").unwrap();
+        let cubes_model = cubes::model();
+        cube_dropdown.set_model(Some(&cubes_model));
+        let select_current = move |dropdown: &gtk::DropDown| {
+            dropdown.set_selected(cubes::index_of(cubes::current().as_deref()));
+        };
+        select_current(&cube_dropdown);
+        cube_box.set_visible(cubes_model.n_items() > 1);
+        let dropdown = cube_dropdown.clone();
+        cubes_model.connect_items_changed(move |model, _, _, _| {
+            cube_box.set_visible(model.n_items() > 1);
+            select_current(&dropdown);
+        });
+        let dropdown = cube_dropdown.clone();
+        settings().connect_changed(Some("current-cube"), move |_, _| select_current(&dropdown));
+        cube_dropdown.connect_selected_notify(|dropdown| {
+            let name = cubes::at_index(dropdown.selected()).unwrap_or_default();
+            if settings().string("current-cube") != name {
+                settings().set_string("current-cube", &name).unwrap();
+            }
+        });
+
+        let goals = crate::goals::Goals::new(tx.clone());
+        let goals_bin: adw::Bin = builder.object("goals_bin").unwrap();
+        goals_bin.set_child(Some(goals.widget()));
+
+        let mut this = Self {
             root: builder.object("root").unwrap(),
             store,
-            backup: None,
-            session_average_label: builder.object("session_average_label").unwrap(),
-            ao5_label: builder.object("ao5_label").unwrap(),
-            best_ao5: builder.object("best_ao5").unwrap(),
-        }
+            trash: Vec::new(),
+            next_trash_id: 0,
+            format_dropdown,
+            hand_dropdown: builder.object("hand_dropdown").unwrap(),
+            seed_entry,
+            hand_split: builder.object("hand_split").unwrap(),
+            left_mean: builder.object("left_mean").unwrap(),
+            left_best: builder.object("left_best").unwrap(),
+            right_mean: builder.object("right_mean").unwrap(),
+            right_best: builder.object("right_best").unwrap(),
+            cube_split: builder.object("cube_split").unwrap(),
+            official: builder.object("official").unwrap(),
+            official_single: builder.object("official_single").unwrap(),
+            practice_single: builder.object("practice_single").unwrap(),
+            official_average: builder.object("official_average").unwrap(),
+            practice_average: builder.object("practice_average").unwrap(),
+            official_records: None,
+            list_controls,
+            tiles_grid: builder.object("tiles_grid").unwrap(),
+            tiles: Vec::new(),
+            blind: false,
+            goals,
+        };
+        this.build_tiles(&StatTile::shown());
+        this
     }
 
     pub fn widget(&self) -> &impl IsA<gtk::Widget> {
         &self.root
     }
 
+    /// Show the seed of the session's scrambles, e.g. after recovering the session
+    pub fn set_seed_text(&self, text: &str) {
+        self.seed_entry.set_text(text);
+    }
+
+    /// Add a new solve to the end of the session, done with the session's current hand and
+    /// cube
     pub fn append_stat(&mut self, stat: &SolveStat) {
-        self.backup = None;
-        self.store.append(stat)
+        stat.set_hand(Hand::ALL[self.hand_dropdown.selected() as usize]);
+        stat.set_cube(cubes::current());
+        self.store.append(stat);
+        self.goals.record_solve();
     }
 
-    pub fn insert_stat(&mut self, idx: u32, stat: &SolveStat) {
-        self.backup = None;
-        self.store.insert(idx, stat)
+    /// Show how many turns of smart cubes have been made today
+    pub fn set_turns_today(&self, turns: u32) {
+        self.goals.set_turns_today(turns);
+    }
+
+    /// Add a solve recovered from an autosave, keeping its hand and cube and not counting it
+    /// towards the goals again
+    pub fn append_restored(&mut self, stat: &SolveStat) {
+        self.store.append(stat);
+    }
+
+    /// Add a solve done on another machine, at `index`
+    pub fn insert_synced(&mut self, index: u32, stat: &SolveStat) {
+        self.store.insert(index, stat);
     }
 
     pub fn get_stat(&self, index: u32) -> Option<SolveStat> {
         self.store.item(index).and_downcast::<SolveStat>()
     }
 
-    pub fn remove(&self, index: u32) {
+    pub fn last_stat(&self) -> Option<SolveStat> {
+        self.length()
+            .checked_sub(1)
+            .and_then(|idx| self.get_stat(idx))
+    }
+
+    /// Move the stat at `index` to the trash
+    pub fn remove(&mut self, index: u32) {
+        let stat = self.get_stat(index).unwrap();
         self.store.remove(index);
+        let id = TrashId(self.next_trash_id);
+        self.next_trash_id += 1;
+        self.trash.push((id, index, stat));
+    }
+
+    /// Stats in the trash, most recently deleted first
+    pub fn trash(&self) -> Vec<(TrashId, SolveStat)> {
+        self.trash
+            .iter()
+            .rev()
+            .map(|(id, _, stat)| (*id, stat.clone()))
+            .collect()
     }
 
-    pub fn set_backup(&mut self, backup: (u32, SolveStat)) {
-        self.backup = Some(backup);
+    /// Put a stat from the trash back where it was (or at the end, if the session has
+    /// gotten shorter since). Returns the index it was restored to.
+    pub fn restore(&mut self, id: TrashId) -> Option<u32> {
+        let pos = self.trash.iter().position(|(i, _, _)| *i == id)?;
+        let (_, index, stat) = self.trash.remove(pos);
+        let index = index.min(self.length());
+        self.store.insert(index, &stat);
+        Some(index)
     }
 
-    pub fn take_backup(&mut self) -> Option<(u32, SolveStat)> {
-        self.backup.take()
+    /// Whether a solve finished at `now` should start a new session, by `split`
+    pub fn due_new_session(&self, split: SessionSplit, now: &glib::DateTime) -> bool {
+        self.last_stat()
+            .is_some_and(|last| split.starts_session(&last.date(), now))
+    }
+
+    /// Move all of the session's stats to the trash, so that the stats start over. Returns
+    /// their trash IDs, to restore in order.
+    pub fn start_new_session(&mut self) -> Vec<TrashId> {
+        let mut ids = Vec::new();
+        // from the end, so the indices of the ones before don't change
+        for index in (0..self.length()).rev() {
+            self.remove(index);
+            ids.push(self.last_trashed().unwrap());
+        }
+        ids.reverse();
+        ids
+    }
+
+    /// The most recently deleted stat
+    pub fn last_trashed(&self) -> Option<TrashId> {
+        self.trash.last().map(|(id, _, _)| *id)
+    }
+
+    pub fn delete_permanently(&mut self, id: TrashId) {
+        self.trash.retain(|(i, _, _)| *i != id);
     }
 
     /// Returns the number of stats that are in the store
-    fn length(&self) -> u32 {
+    pub fn length(&self) -> u32 {
         self.store.n_items()
     }
 
-    pub fn update_stats(&self) {
-        if self.length() < 5 {
-            self.ao5_label.set_label("-");
-            self.best_ao5.set_label("-");
+    pub fn format(&self) -> ResultFormat {
+        ResultFormat::ALL[self.format_dropdown.selected() as usize]
+    }
+
+    pub fn update_stats(&mut self) {
+        let shown = StatTile::shown();
+        if !self
+            .tiles
+            .iter()
+            .map(|(tile, _, _)| *tile)
+            .eq(shown.iter().copied())
+        {
+            self.build_tiles(&shown);
+        }
+
+        let format = self.format();
+        let (n, rule) = format.window();
+        let session = self.session();
+        let current = session.current(n, rule);
+        let goal = settings().double("goal-average");
+        let next_solves = if goal > 0.0 && !self.blind {
+            [5, 12]
+                .into_iter()
+                .filter_map(|n| {
+                    let next = session.next_solve_needed(n, Duration::from_secs_f64(goal))?;
+                    Some((n as u32, next))
+                })
+                .collect()
         } else {
-            self.ao5_label
-                .set_label(&format!("{}", self.ao5_at(self.length() - 5)));
-            self.best_ao5.set_label(&format!("{}", self.best_ao5()));
+            Vec::new()
+        };
+        self.goals.update(current, &format.titles().0, &next_solves);
+        for (tile, value, title) in &self.tiles {
+            let (tile_title, tile_value) = self.tile_contents(*tile);
+            title.set_label(&tile_title);
+            value.set_label(&tile_value);
         }
-        if self.length() > 0 {
-            self.session_average_label
-                .set_label(&format!("{}", self.session_average()));
+        self.update_hand_split();
+        self.update_cube_split();
+        self.update_official();
+        self.update_trims();
+        // a change to a solve could change whether it matches, or where it's sorted
+        if self.list_controls.solve_filter.borrow().is_active() {
+            self.list_controls
+                .filter
+                .changed(gtk::FilterChange::Different);
+        }
+        if self.list_controls.sort_order.get() != SortOrder::default() {
+            self.list_controls
+                .sorter
+                .changed(gtk::SorterChange::Different);
         }
     }
 
-    fn ao5_at(&self, start_idx: u32) -> Average {
-        let mut times = (start_idx..start_idx + 5)
-            .map(|idx| self.get_stat(idx).unwrap().time())
-            .collect::<Vec<_>>();
+    /// Mark the solves dropped from the current averages, for the list to highlight
+    fn update_trims(&self) {
+        let trims = current_trims(self.session().results());
+        for (idx, trim) in (0..).zip(trims) {
+            let stat = self.get_stat(idx).unwrap();
+            // setting it notifies even if it's the same, so only set it when it changes
+            if stat.trim() != trim {
+                stat.set_trim(trim);
+            }
+        }
+    }
+
+    /// Replace the tiles in the grid. With an odd number of tiles, the first one gets a
+    /// row to itself.
+    fn build_tiles(&mut self, shown: &[StatTile]) {
+        while let Some(child) = self.tiles_grid.first_child() {
+            self.tiles_grid.remove(&child);
+        }
+        self.tiles_grid.set_visible(!shown.is_empty());
+
+        let offset = shown.len() % 2;
+        self.tiles = shown
+            .iter()
+            .enumerate()
+            .map(|(i, &tile)| {
+                let value = gtk::Label::new(None);
+                value.add_css_class("title-3");
+                let title = gtk::Label::new(None);
+                title.add_css_class("dim-label");
+                let content = gtk::Box::builder()
+                    .orientation(gtk::Orientation::Vertical)
+                    .margin_top(4)
+                    .margin_bottom(4)
+                    .build();
+                content.append(&value);
+                content.append(&title);
+                let card = gtk::Box::builder()
+                    .orientation(gtk::Orientation::Vertical)
+                    .hexpand(true)
+                    .build();
+                card.add_css_class("card");
+                card.append(&content);
 
-        let num_dnfs = times.iter().filter(|x| x.is_none()).count();
+                if i < offset {
+                    self.tiles_grid.attach(&card, 0, 0, 2, 1);
+                } else {
+                    let pos = (i + offset) as i32;
+                    self.tiles_grid.attach(&card, pos % 2, pos / 2, 1, 1);
+                }
+                (tile, value, title)
+            })
+            .collect();
+    }
 
-        if num_dnfs > 1 {
-            return Average::Dnf;
+    /// Title and value of a tile
+    fn tile_contents(&self, tile: StatTile) -> (String, String) {
+        let or_dash = |avg: Option<Average>| avg.map_or("-".into(), render_average);
+        let format = self.format();
+        let (n, rule) = format.window();
+        let session = self.session();
+        match tile {
+            StatTile::SessionAverage if self.blind => {
+                let successes = (0..self.length())
+                    .filter(|&idx| self.get_stat(idx).unwrap().time().is_some())
+                    .count();
+                (
+                    gettext("Successes"),
+                    format!("{successes}/{}", self.length()),
+                )
+            }
+            StatTile::SessionAverage => (
+                gettext("Session Average"),
+                or_dash((!session.is_empty()).then(|| session.mean())),
+            ),
+            StatTile::Aggregate => (format.titles().0, or_dash(session.current(n, rule))),
+            StatTile::BestAggregate => (format.titles().1, or_dash(session.best(n, rule))),
+            StatTile::Ao5 => (
+                gettext("Average of 5"),
+                or_dash(session.current(5, TrimRule::Wca)),
+            ),
+            StatTile::BestAo5 => (
+                gettext("Best Average of 5"),
+                or_dash(session.best(5, TrimRule::Wca)),
+            ),
+            StatTile::Ao12 => (
+                gettext("Average of 12"),
+                or_dash(session.current(12, TrimRule::Wca)),
+            ),
+            StatTile::BestAo12 => (
+                gettext("Best Average of 12"),
+                or_dash(session.best(12, TrimRule::Wca)),
+            ),
+            StatTile::BestSingle => (
+                gettext("Best Single"),
+                self.best_single()
+                    .map_or("-".into(), |time| render_single(&time)),
+            ),
+            StatTile::Count => (gettext("Solves"), self.length().to_string()),
+            StatTile::SolvesToday => {
+                let today = start_of_day(&glib::DateTime::now_local().unwrap());
+                let solves = (0..self.length())
+                    .filter(|&idx| self.get_stat(idx).unwrap().date() >= today)
+                    .count();
+                (gettext("Solves Today"), solves.to_string())
+            }
+            StatTile::Deviation => (
+                gettext("Standard Deviation"),
+                std_deviation_of(session.results())
+                    .map_or("-".into(), |dev| render_time(&dev, Precision::Hundredths)),
+            ),
+            StatTile::Iqr => (
+                gettext("Interquartile Range"),
+                interquartile_range_of(session.results())
+                    .map_or("-".into(), |iqr| render_time(&iqr, Precision::Hundredths)),
+            ),
+            StatTile::Consistency => (
+                gettext("Consistency"),
+                consistency_of(session.results())
+                    .map_or("-".into(), |score| format!("{score:.0}%")),
+            ),
+            StatTile::SubX => {
+                let threshold = settings().double("sub-x-threshold");
+                let times = session.results();
+                let Some(rate) = sub_x_rate_of(times, threshold) else {
+                    return (sub_x_title(threshold), "-".into());
+                };
+                // the latest solves, compared with the ones just before them
+                let recent = &times[times.len().saturating_sub(SUB_X_TREND)..];
+                let recent_rate = sub_x_rate_of(recent, threshold).unwrap();
+                let before = &times[..times.len() - recent.len()];
+                let before = &before[before.len().saturating_sub(SUB_X_TREND)..];
+                let trend = match sub_x_rate_of(before, threshold) {
+                    Some(rate) if recent_rate.round() > rate.round() => " ↑",
+                    Some(rate) if recent_rate.round() < rate.round() => " ↓",
+                    _ => "",
+                };
+                (
+                    ngettext_f(
+                        "{title} ({rate}% of last {count})",
+                        "{title} ({rate}% of last {count})",
+                        recent.len() as u32,
+                        &[
+                            ("title", &sub_x_title(threshold)),
+                            ("rate", &format!("{recent_rate:.0}")),
+                            ("count", &recent.len().to_string()),
+                        ],
+                    ),
+                    format!("{rate:.0}%{trend}"),
+                )
+            }
         }
+    }
 
-        times.sort_unstable_by(|a, b| match (a, b) {
-            (None, None) => Ordering::Equal,
-            (Some(_), None) => Ordering::Less,
-            (None, Some(_)) => Ordering::Greater,
-            (Some(l), Some(r)) => l.cmp(&r),
-        });
+    /// Show separate stats for each hand if there are any one-handed solves
+    fn update_hand_split(&self) {
+        let times_with = |hand| {
+            (0..self.length())
+                .map(|idx| self.get_stat(idx).unwrap())
+                .filter(|stat| stat.hand() == hand)
+                .map(|stat| stat.time())
+                .collect::<Vec<_>>()
+        };
+        let left = times_with(Hand::Left);
+        let right = times_with(Hand::Right);
+        self.hand_split
+            .set_visible(!left.is_empty() || !right.is_empty());
 
-        times.remove(4);
-        times.remove(0);
-        assert_eq!(times.len(), 3);
-        let sum: Duration = times.iter().flatten().sum();
+        for (times, mean_label, best_label) in [
+            (left, &self.left_mean, &self.left_best),
+            (right, &self.right_mean, &self.right_best),
+        ] {
+            let (mean, best) = mean_and_best(&times);
+            mean_label.set_label(&mean);
+            best_label.set_label(&best);
+        }
+    }
 
-        Average::Some(sum / 3)
+    /// Show a card for each cube that solves in the session were done on
+    fn update_cube_split(&self) {
+        let mut cubes: Vec<(String, Vec<Option<Duration>>)> = Vec::new();
+        for stat in (0..self.length()).map(|idx| self.get_stat(idx).unwrap()) {
+            let Some(cube) = stat.cube() else {
+                continue;
+            };
+            match cubes.iter_mut().find(|(name, _)| *name == cube) {
+                Some((_, times)) => times.push(stat.time()),
+                None => cubes.push((cube, vec![stat.time()])),
+            }
+        }
+
+        while let Some(child) = self.cube_split.first_child() {
+            self.cube_split.remove(&child);
+        }
+        self.cube_split.set_visible(!cubes.is_empty());
+        for (i, (name, times)) in cubes.iter().enumerate() {
+            let (mean, best) = mean_and_best(times);
+            let content = gtk::Box::builder()
+                .orientation(gtk::Orientation::Vertical)
+                .margin_top(4)
+                .margin_bottom(4)
+                .build();
+            let mean = gtk::Label::new(Some(&mean));
+            mean.add_css_class("title-3");
+            content.append(&mean);
+            let title = gtk::Label::builder()
+                .label(gettext_f("{cube} Mean", &[("cube", name)]))
+                .ellipsize(gtk::pango::EllipsizeMode::End)
+                .build();
+            title.add_css_class("dim-label");
+            content.append(&title);
+            let best = gtk::Label::new(Some(&best));
+            best.add_css_class("dim-label");
+            best.add_css_class("caption");
+            content.append(&best);
+            let card = gtk::Box::builder()
+                .orientation(gtk::Orientation::Vertical)
+                .hexpand(true)
+                .build();
+            card.add_css_class("card");
+            card.append(&content);
+            self.cube_split
+                .attach(&card, (i % 2) as i32, (i / 2) as i32, 1, 1);
+        }
     }
 
-    fn session_average(&self) -> Average {
-        let mut times = (0..self.length())
-            .map(|idx| self.get_stat(idx).unwrap().time())
-            .collect::<Vec<_>>();
+    pub fn set_official_records(&mut self, records: Option<OfficialRecords>) {
+        self.official_records = records;
+        self.update_official();
+    }
 
-        let num_dnfs = times.iter().filter(|x| x.is_none()).count() as u32;
+    /// Compare the best single and ao5 with the official records
+    fn update_official(&self) {
+        let Some(records) = self.official_records else {
+            self.official.set_visible(false);
+            return;
+        };
+        self.official.set_visible(true);
 
-        if num_dnfs == self.length() {
-            return Average::Dnf;
+        let best_ao5 = match self.session().best(5, TrimRule::Wca) {
+            Some(Average::Some(time)) => Some(time),
+            _ => None,
+        };
+        for (official, practice, official_label, practice_label, name, round) in [
+            (
+                records.single,
+                self.best_single(),
+                &self.official_single,
+                &self.practice_single,
+                gettext("Practice single"),
+                round_single as fn(Duration) -> Duration,
+            ),
+            (
+                records.average,
+                best_ao5,
+                &self.official_average,
+                &self.practice_average,
+                gettext("Practice ao5"),
+                round_average,
+            ),
+        ] {
+            // the differences are between the rounded times, as they're shown
+            let (official, practice) = (official.map(round), practice.map(round));
+            let render = |time| render_time(&time, Precision::Hundredths);
+            official_label.set_label(&official.map_or("-".into(), render));
+            practice_label.set_label(&match (practice, official) {
+                (None, _) => format!("{name} -"),
+                (Some(practice), None) => format!("{name} {}", render(practice)),
+                (Some(practice), Some(official)) if practice < official => format!(
+                    "{name} {} (-{})",
+                    render(practice),
+                    render(official - practice)
+                ),
+                (Some(practice), Some(official)) => format!(
+                    "{name} {} (+{})",
+                    render(practice),
+                    render(practice - official)
+                ),
+            });
         }
+    }
 
-        times.sort_unstable_by(|a, b| match (a, b) {
-            (None, None) => Ordering::Equal,
-            (Some(_), None) => Ordering::Less,
-            (None, Some(_)) => Ordering::Greater,
-            (Some(l), Some(r)) => l.cmp(&r),
-        });
+    /// Average of the last `n` stats, or `None` if there aren't `n` stats yet
+    pub fn current_average(&self, n: u32) -> Option<Average> {
+        self.session().current(n as usize, TrimRule::Wca)
+    }
+
+    /// Switch between normal stats and blindfolded stats, which show the success rate
+    /// and use means of 3 since most attempts are expected to be DNFs
+    pub fn set_blind(&mut self, blind: bool) {
+        self.blind = blind;
+        if blind {
+            self.format_dropdown.set_selected(ResultFormat::Mo3 as u32);
+        }
+        self.update_stats();
+    }
+
+    /// Results of all the stats, for the shared statistics (see [`solvestats`])
+    fn session(&self) -> Session {
+        (0..self.length())
+            .map(|idx| self.get_stat(idx).unwrap().to_solve())
+            .collect()
+    }
+
+    /// Best averages that the stat at `index` counts towards, which removing it would change
+    /// (see [`Session::record_changes`])
+    pub fn record_changes(&self, index: u32) -> Vec<RecordChange> {
+        self.session().record_changes(index as usize)
+    }
 
-        let sum: Duration = times.iter().flatten().sum();
+    /// The latest result in the session's format (see [`share_text`]), or `None` if there
+    /// aren't enough solves for one yet
+    pub fn share_latest(&self) -> Option<String> {
+        let format = self.format();
+        let (n, rule) = format.window();
+        let session = self.session();
+        let start_idx = session.len().checked_sub(n)?;
+        let latest = &session.results()[start_idx..];
+        let solves = self.shared_solves(start_idx, &rule.trimmed_indices(latest));
+        let result = match format {
+            ResultFormat::Bo1 => solves[0].0.clone(),
+            _ => render_average(rule.apply(latest)),
+        };
+        Some(share_text(&result, format.short_name(), &solves))
+    }
+
+    /// The latest average of `n` as Markdown (see [`share_markdown`]), or `None` if there
+    /// aren't `n` solves yet
+    pub fn share_latest_markdown(&self, n: u32) -> Option<String> {
+        let session = self.session();
+        let start_idx = session.len().checked_sub(n as usize)?;
+        let latest = &session.results()[start_idx..];
+        let solves = self.shared_solves(start_idx, &TrimRule::Wca.trimmed_indices(latest));
+        let result = render_average(TrimRule::Wca.apply(latest));
+        Some(share_markdown(&result, &format!("ao{n}"), &solves))
+    }
+
+    /// The time, whether it counts and the scramble of the solves from `start_idx` on, for
+    /// [`share_text`] and [`share_markdown`]. `trimmed` are the indices (counting from
+    /// `start_idx`) of the solves that don't count.
+    fn shared_solves(&self, start_idx: usize, trimmed: &[usize]) -> Vec<(String, bool, String)> {
+        (start_idx as u32..self.length())
+            .map(|idx| self.get_stat(idx).unwrap())
+            .enumerate()
+            .map(|(i, stat)| {
+                (
+                    share_time(&stat),
+                    !trimmed.contains(&i),
+                    render_moveseq(stat.scramble(), false),
+                )
+            })
+            .collect()
+    }
 
-        Average::Some(sum / (self.length() - num_dnfs))
+    /// When each solve in the session was done, and its time
+    pub fn dated_times(&self) -> Vec<(glib::DateTime, Option<Duration>)> {
+        (0..self.length())
+            .map(|idx| self.get_stat(idx).unwrap())
+            .map(|stat| (stat.date(), stat.time()))
+            .collect()
     }
 
-    fn best_ao5(&self) -> Average {
-        let mut averages = Vec::new();
-        for start_idx in 0..=self.length() - 5 {
-            averages.push(self.ao5_at(start_idx));
+    /// Fastest non-DNF time in the session
+    pub fn best_single(&self) -> Option<Duration> {
+        self.session().best_single()
+    }
+
+    /// Stage times (see [`SolveStat::stages`]) of the fastest non-DNF solve that has all
+    /// of them, to pace smart cube solves against
+    pub fn pb_stages(&self) -> Option<Vec<Duration>> {
+        (0..self.length())
+            .map(|idx| self.get_stat(idx).unwrap())
+            .filter(|stat| stat.stages().len() == crate::timer::PACE_STAGES.len())
+            .filter_map(|stat| Some((stat.time()?, stat.stages())))
+            .min_by_key(|&(time, _)| time)
+            .map(|(_, stages)| stages)
+    }
+
+    pub fn summary(&self) -> crate::summary::SessionSummary {
+        let session = self.session();
+        crate::summary::SessionSummary {
+            best_single: session.best_single(),
+            best_ao5: session.best(5, TrimRule::Wca),
+            best_ao12: session.best(12, TrimRule::Wca),
+            mean: (!session.is_empty()).then(|| session.mean()),
+            times: session.results().to_vec(),
+            scramble_seed: None,
         }
-        averages
-            .into_iter()
-            .min_by(|a, b| match (a, b) {
-                (Average::Dnf, Average::Dnf) => Ordering::Equal,
-                (Average::Some(_), Average::Dnf) => Ordering::Less,
-                (Average::Dnf, Average::Some(_)) => Ordering::Greater,
-                (Average::Some(x), Average::Some(y)) => x.cmp(y),
+    }
+
+    pub fn report(&self) -> crate::report::SessionReport {
+        let session = self.session();
+        let solves = (0..self.length())
+            .map(|idx| {
+                let stat = self.get_stat(idx).unwrap();
+                crate::report::ReportSolve {
+                    result: share_time(&stat),
+                    time: stat.time(),
+                    penalties: stat.penalties(),
+                    ao5: (idx as usize)
+                        .checked_sub(4)
+                        .map(|start_idx| session.window(start_idx, 5, TrimRule::Wca)),
+                    scramble: stat.scramble().to_vec(),
+                    date: stat.date().format("%c").unwrap().into(),
+                    comment: stat.comment(),
+                }
             })
-            .unwrap()
+            .collect();
+
+        crate::report::SessionReport {
+            summary: self.summary(),
+            solves,
+        }
     }
 }
 
-#[derive(Debug, Copy, Clone)]
-enum Average {
-    Dnf,
-    Some(Duration),
+/// Standard deviation of the non-DNF `times`, or `None` if there are fewer than 2
+fn std_deviation_of(times: &[Option<Duration>]) -> Option<Duration> {
+    let secs = times
+        .iter()
+        .flatten()
+        .map(Duration::as_secs_f64)
+        .collect::<Vec<_>>();
+    if secs.len() < 2 {
+        return None;
+    }
+    let mean = secs.iter().sum::<f64>() / secs.len() as f64;
+    let variance = secs.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / secs.len() as f64;
+    Some(Duration::from_secs_f64(variance.sqrt()))
 }
 
-impl std::fmt::Display for Average {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match *self {
-            Average::Dnf => write!(f, "DNF"),
-            Average::Some(time) => write!(f, "{}", crate::timer::render_time(&time, true)),
-        }
+/// Difference between the upper and lower quartiles of the non-DNF `times`, or `None` if
+/// there are fewer than 4. Unlike the standard deviation, this isn't thrown off by a few
+/// very slow solves.
+fn interquartile_range_of(times: &[Option<Duration>]) -> Option<Duration> {
+    let mut ok = times.iter().flatten().copied().collect::<Vec<_>>();
+    if ok.len() < 4 {
+        return None;
     }
+    ok.sort();
+    Some(percentile(&ok, 0.75).saturating_sub(percentile(&ok, 0.25)))
 }
 
-#[derive(Debug)]
-struct StatItemUi {
-    root: gtk::Box,
-    time_label: gtk::Label,
-    index_label: gtk::Label,
-    dnf_button: gtk::Button,
-    plus2_button: gtk::Button,
-    bindings: Vec<glib::Binding>,
-    gestureclick: gtk::GestureClick,
-    click_handler: Option<glib::SignalHandlerId>,
+/// Percentile `p` (from 0 to 1) of non-empty `sorted` times, interpolating between the
+/// closest two
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    let pos = p * (sorted.len() - 1) as f64;
+    let lower = pos.floor() as usize;
+    let upper = (lower + 1).min(sorted.len() - 1);
+    sorted[lower] + (sorted[upper] - sorted[lower]).mul_f64(pos.fract())
 }
 
-impl StatItemUi {
-    fn new() -> Self {
-        let builder =
-            gtk::Builder::from_resource("/io/github/flying_toast/PuzzleTime/stat-item.ui");
+/// Score from 0 to 100 of how consistent the non-DNF `times` are: 100 minus the standard
+/// deviation as a percentage of the mean. `None` if there are fewer than 2.
+fn consistency_of(times: &[Option<Duration>]) -> Option<f64> {
+    let deviation = std_deviation_of(times)?;
+    let Average::Some(mean) = session_mean_of(times) else {
+        return None;
+    };
+    if mean.is_zero() {
+        return None;
+    }
+    Some((100.0 * (1.0 - deviation.as_secs_f64() / mean.as_secs_f64())).clamp(0.0, 100.0))
+}
 
-        Self {
-            bindings: Vec::new(),
-            click_handler: None,
-            root: builder.object("root").unwrap(),
-            time_label: builder.object("time_label").unwrap(),
-            index_label: builder.object("index_label").unwrap(),
-            dnf_button: builder.object("dnf_btn").unwrap(),
-            plus2_button: builder.object("plus2_btn").unwrap(),
-            gestureclick: builder.object("gestureclick").unwrap(),
-        }
+/// How long each phase of a solve with manual `splits` took, the last one being from the
+/// last split to the end (`None` if `total` is, i.e. DNF)
+fn split_phases(splits: &[Duration], total: Option<Duration>) -> Vec<Option<Duration>> {
+    let mut phases = Vec::with_capacity(splits.len() + 1);
+    let mut previous = Duration::ZERO;
+    for &split in splits {
+        phases.push(Some(split.saturating_sub(previous)));
+        previous = split;
     }
+    phases.push(total.map(|total| total.saturating_sub(previous)));
+    phases
+}
+
+/// Title of the sub-X rate tile, like "Sub-15"
+fn sub_x_title(threshold: f64) -> String {
+    let threshold = format!("{threshold}").replace('.', decimal_separator());
+    gettext_f("Sub-{threshold}", &[("threshold", &threshold)])
+}
+
+/// Percentage of `times` under `threshold` seconds, with DNFs counting as not under it, or
+/// `None` if there are no times
+fn sub_x_rate_of(times: &[Option<Duration>], threshold: f64) -> Option<f64> {
+    if times.is_empty() {
+        return None;
+    }
+    let under = times
+        .iter()
+        .flatten()
+        .filter(|time| time.as_secs_f64() < threshold)
+        .count();
+    Some(100.0 * under as f64 / times.len() as f64)
+}
+
+/// `avg` as it's shown, with the locale's decimal separator
+pub fn render_average(avg: Average) -> String {
+    avg.render(decimal_separator())
 }
 
 /// `index`: index of the given `stat`
@@ -294,26 +1475,581 @@ pub fn stat_info_dialog(tx: EventSender, stat: &SolveStat, index: u32) -> adw::D
         gtk::Builder::from_resource("/io/github/flying_toast/PuzzleTime/stat-info-dialog.ui");
     let root = builder.object::<adw::Dialog>("root").unwrap();
     let delete_button: gtk::Button = builder.object("delete_button").unwrap();
+    let copy_result: gtk::Button = builder.object("copy_result").unwrap();
+    let export_animation: gtk::Button = builder.object("export_animation").unwrap();
     let copy_scramble: gtk::Button = builder.object("copy_scramble").unwrap();
     let toasts: adw::ToastOverlay = builder.object("toasts").unwrap();
     let scramblerow: adw::ActionRow = builder.object("scramblerow").unwrap();
+    let scramble_net: adw::Bin = builder.object("scramble_net").unwrap();
+    let memo_time_row: adw::ActionRow = builder.object("memo_time_row").unwrap();
+    let splits_row: adw::ActionRow = builder.object("splits_row").unwrap();
+    let edge_memo_row: adw::ActionRow = builder.object("edge_memo_row").unwrap();
+    let corner_memo_row: adw::ActionRow = builder.object("corner_memo_row").unwrap();
+    let penalty_row: adw::ExpanderRow = builder.object("penalty_row").unwrap();
+    let time_penalty_row: adw::SpinRow = builder.object("time_penalty_row").unwrap();
+    let add_time_penalty: gtk::Button = builder.object("add_time_penalty").unwrap();
+    let misscramble_row: adw::ExpanderRow = builder.object("misscramble_row").unwrap();
+
+    scramblerow.set_title(&render_moveseq(stat.scramble(), true));
+    scramble_net.set_child(Some(&crate::puzzle_diagram::widget(stat.scramble_state())));
+    root.set_title(&gettext_f(
+        "Result {number}",
+        &[("number", &(index + 1).to_string())],
+    ));
+
+    if let Some(moves) = stat.scramble_difficulty() {
+        let difficulty_row: adw::ActionRow = builder.object("difficulty_row").unwrap();
+        difficulty_row.set_title(&ngettext_f(
+            "Solvable in at most {moves} move",
+            "Solvable in at most {moves} moves",
+            moves.into(),
+            &[("moves", &moves.to_string())],
+        ));
+        difficulty_row.set_visible(true);
+    }
+
+    if let Some(state) = stat.misscrambled_state() {
+        let net = crate::puzzle_diagram::widget(state);
+        net.set_margin_top(12);
+        net.set_margin_bottom(12);
+        misscramble_row.add_row(&net);
+        misscramble_row.set_visible(true);
+    }
+
+    let splits = stat.splits();
+    if !splits.is_empty() {
+        let phases = split_phases(&splits, stat.time())
+            .iter()
+            .map(|phase| phase.map_or("DNF".into(), |t| render_time(&t, Precision::Hundredths)))
+            .collect::<Vec<_>>();
+        splits_row.set_title(&phases.join(" / "));
+        splits_row.set_visible(true);
+    }
+
+    if let Some(memo_time) = stat.memo_time() {
+        let execution = stat.time().map(|time| time.saturating_sub(memo_time));
+        memo_time_row.set_title(&format!(
+            "{} / {}",
+            render_time(&memo_time, Precision::Hundredths),
+            execution.map_or("DNF".into(), |t| render_time(&t, Precision::Hundredths))
+        ));
+        memo_time_row.set_visible(true);
+
+        let memo = stat.scramble_state().memo();
+        let mut edges = render_letter_pairs(&memo.edges);
+        if memo.has_parity() {
+            edges.push_str(&gettext(" (parity)"));
+        }
+        if !memo.flipped_edges.is_empty() {
+            edges.push_str(&gettext_f(
+                ", flip {letters}",
+                &[("letters", &render_letter_pairs(&memo.flipped_edges))],
+            ));
+        }
+        let mut corners = render_letter_pairs(&memo.corners);
+        if !memo.twisted_corners.is_empty() {
+            corners.push_str(&gettext_f(
+                ", twist {letters}",
+                &[("letters", &render_letter_pairs(&memo.twisted_corners))],
+            ));
+        }
+        edge_memo_row.set_title(&edges);
+        edge_memo_row.set_visible(true);
+        corner_memo_row.set_title(&corners);
+        corner_memo_row.set_visible(true);
+    }
+
+    let hand_row: adw::ComboRow = builder.object("hand_row").unwrap();
+    stat.bind_property("hand", &hand_row, "selected")
+        .transform_to(|_, hand: Hand| Some(Hand::ALL.iter().position(|&h| h == hand)? as u32))
+        .transform_from(|_, idx: u32| Hand::ALL.get(idx as usize).copied())
+        .bidirectional()
+        .sync_create()
+        .build();
+
+    let cube_row: adw::ComboRow = builder.object("cube_row").unwrap();
+    cube_row.set_model(Some(&cubes::model()));
+    // only offered once there are cubes to choose from, unless the solve has one
+    cube_row.set_visible(!cubes::cubes().is_empty() || stat.cube().is_some());
+    stat.bind_property("cube", &cube_row, "selected")
+        .transform_to(|_, cube: Option<String>| Some(cubes::index_of(cube.as_deref())))
+        .transform_from(|_, idx: u32| Some(cubes::at_index(idx)))
+        .bidirectional()
+        .sync_create()
+        .build();
 
-    scramblerow.set_title(&crate::timer::render_moveseq(stat.scramble(), true));
-    root.set_title(&format!("Result {}", index + 1));
+    let date_row: adw::ActionRow = builder.object("date_row").unwrap();
+    date_row.set_title(&stat.date().format("%c").unwrap());
+
+    let comment_row: adw::EntryRow = builder.object("comment_row").unwrap();
+    comment_row.set_text(&stat.comment());
+    let stat2 = stat.clone();
+    comment_row.connect_apply(move |row| stat2.set_comment(row.text().trim()));
+
+    let attachment_row: adw::EntryRow = builder.object("attachment_row").unwrap();
+    let choose_attachment: gtk::Button = builder.object("choose_attachment").unwrap();
+    let open_attachment: gtk::Button = builder.object("open_attachment").unwrap();
+    attachment_row.set_text(&stat.attachment().unwrap_or_default());
+    stat.bind_property("attachment", &open_attachment, "sensitive")
+        .transform_to(|_, attachment: Option<String>| Some(attachment.is_some()))
+        .sync_create()
+        .build();
+    let stat2 = stat.clone();
+    attachment_row.connect_apply(move |row| {
+        let text = row.text();
+        let text = text.trim();
+        stat2.set_attachment((!text.is_empty()).then_some(text));
+    });
+    let (stat2, root2, attachment_row2) = (stat.clone(), root.clone(), attachment_row.clone());
+    choose_attachment.connect_clicked(move |_| {
+        let dialog = gtk::FileDialog::builder()
+            .title(gettext("Attach File"))
+            .build();
+        let window = root2.root().and_downcast::<gtk::Window>();
+        let (stat, attachment_row) = (stat2.clone(), attachment_row2.clone());
+        glib::spawn_future_local(async move {
+            let Ok(file) = dialog.open_future(window.as_ref()).await else {
+                // cancelled
+                return;
+            };
+            if let Some(path) = file.path() {
+                let path = path.to_string_lossy();
+                attachment_row.set_text(&path);
+                stat.set_attachment(Some(&*path));
+            }
+        });
+    });
+    let (stat2, root2, toasts2) = (stat.clone(), root.clone(), toasts.clone());
+    open_attachment.connect_clicked(move |_| {
+        let Some(attachment) = stat2.attachment() else {
+            return;
+        };
+        // plain file paths need to be made into URIs
+        let uri = if glib::Uri::peek_scheme(&attachment).is_some() {
+            attachment
+        } else {
+            gio::File::for_path(&attachment).uri().into()
+        };
+        let window = root2.root().and_downcast::<gtk::Window>();
+        let toasts = toasts2.clone();
+        glib::spawn_future_local(async move {
+            let launch = gtk::UriLauncher::new(&uri)
+                .launch_future(window.as_ref())
+                .await;
+            if let Err(e) = launch {
+                toasts.add_toast(adw::Toast::new(&gettext_f(
+                    "Couldn't open attachment: {error}",
+                    &[("error", &e.to_string())],
+                )));
+            }
+        });
+    });
+
+    let penalty_history = stat.penalty_history();
+    if let Some(last) = penalty_history.last() {
+        penalty_row.set_title(&format!("{} ({})", last.penalties, last.source));
+        for change in penalty_history.iter().rev() {
+            let row = adw::ActionRow::builder()
+                .title(format!("{} ({})", change.penalties, change.source))
+                .subtitle(change.at.format("%X").unwrap())
+                .build();
+            penalty_row.add_row(&row);
+        }
+        penalty_row.set_visible(true);
+    }
+
+    let stat2 = stat.clone();
+    let toasts2 = toasts.clone();
+    add_time_penalty.connect_clicked(move |_| {
+        let seconds = time_penalty_row.value() as u32;
+        stat2.add_time_penalty(seconds, PenaltySource::Manual);
+        penalty_row.set_title(&format!(
+            "{} ({})",
+            stat2.penalties(),
+            PenaltySource::Manual
+        ));
+        penalty_row.set_visible(true);
+        toasts2.add_toast(adw::Toast::new(&gettext_f(
+            "+{seconds} added",
+            &[("seconds", &seconds.to_string())],
+        )));
+    });
 
     let root2 = root.clone();
     delete_button.connect_clicked(move |_| {
         root2.close();
-        send_evt(tx.clone(), Event::DeleteStat(index));
+        send_evt(tx.clone(), StatsEvent::DeleteStat(index));
     });
     let stat2 = stat.clone();
+    let toasts2 = toasts.clone();
     copy_scramble.connect_clicked(move |_| {
         gdk::Display::default()
             .unwrap()
             .clipboard()
-            .set_text(&crate::timer::render_moveseq(stat2.scramble(), false));
-        toasts.add_toast(adw::Toast::new("Copied to clipboard"));
+            .set_text(&render_moveseq(stat2.scramble(), false));
+        toasts2.add_toast(adw::Toast::new(&gettext("Copied to clipboard")));
+    });
+    // only solves on a smart cube have their turns
+    export_animation.set_visible(!stat.turns().is_empty());
+    let (stat2, root2, toasts2) = (stat.clone(), root.clone(), toasts.clone());
+    export_animation.connect_clicked(move |_| {
+        let animation = SolveAnimation {
+            start: stat2
+                .misscrambled_state()
+                .unwrap_or_else(|| stat2.scramble_state()),
+            turns: stat2.turns(),
+            time: stat2.to_solve().time,
+        };
+        let dialog = gtk::FileDialog::builder()
+            .title(gettext("Export Animation"))
+            .initial_name("solve.gif")
+            .build();
+        let Some(window) = root2.root().and_downcast::<gtk::Window>() else {
+            return;
+        };
+        let toasts = toasts2.clone();
+        glib::spawn_future_local(async move {
+            let Some(result) = files::save(&dialog, &window, move |_| animation.render_gif()).await
+            else {
+                // cancelled
+                return;
+            };
+            let msg = match result {
+                Ok(()) => gettext("Animation exported"),
+                Err(e) => gettext_f(
+                    "Couldn't export animation: {error}",
+                    &[("error", &e.to_string())],
+                ),
+            };
+            toasts.add_toast(adw::Toast::new(&msg));
+        });
+    });
+    let stat2 = stat.clone();
+    copy_result.connect_clicked(move |_| {
+        let time = share_time(&stat2);
+        let scramble = render_moveseq(stat2.scramble(), false);
+        gdk::Display::default()
+            .unwrap()
+            .clipboard()
+            .set_text(&share_text(
+                &time,
+                "single",
+                &[(time.clone(), true, scramble)],
+            ));
+        toasts.add_toast(adw::Toast::new(&gettext("Copied to clipboard")));
     });
 
     root
 }
+
+/// Lists the trashed `stats`, with buttons to restore or permanently delete each one
+pub fn trash_dialog(tx: EventSender, stats: &[(TrashId, SolveStat)]) -> adw::Dialog {
+    let builder = gtk::Builder::from_resource("/io/github/flying_toast/PuzzleTime/trash-dialog.ui");
+    let root: adw::Dialog = builder.object("root").unwrap();
+    let stack: gtk::Stack = builder.object("stack").unwrap();
+    let empty_page: adw::StatusPage = builder.object("empty_page").unwrap();
+    let list_page: gtk::ScrolledWindow = builder.object("list_page").unwrap();
+    let list: gtk::ListBox = builder.object("list").unwrap();
+
+    let show_page = {
+        let list = list.clone();
+        move || {
+            if list.first_child().is_some() {
+                stack.set_visible_child(&list_page);
+            } else {
+                stack.set_visible_child(&empty_page);
+            }
+        }
+    };
+
+    for (id, stat) in stats {
+        let row = adw::ActionRow::builder()
+            .title(stat.property::<String>("time-string"))
+            .subtitle(render_moveseq(stat.scramble(), false))
+            .subtitle_lines(1)
+            .build();
+
+        let restore_button = gtk::Button::from_icon_name("edit-undo-symbolic");
+        restore_button.set_tooltip_text(Some(&gettext("Restore")));
+        restore_button.set_valign(gtk::Align::Center);
+        restore_button.add_css_class("flat");
+        let delete_button = gtk::Button::from_icon_name("user-trash-symbolic");
+        delete_button.set_tooltip_text(Some(&gettext("Delete Permanently")));
+        delete_button.set_valign(gtk::Align::Center);
+        delete_button.add_css_class("flat");
+        delete_button.add_css_class("error");
+
+        let buttons: [(&gtk::Button, fn(TrashId) -> StatsEvent); 2] = [
+            (&restore_button, StatsEvent::RestoreTrashedStat),
+            (&delete_button, StatsEvent::DeleteTrashedStat),
+        ];
+        for (button, make_evt) in buttons {
+            let (tx, id, list, row, show_page) = (
+                tx.clone(),
+                *id,
+                list.clone(),
+                row.clone(),
+                show_page.clone(),
+            );
+            button.connect_clicked(move |_| {
+                list.remove(&row);
+                show_page();
+                send_evt(tx.clone(), make_evt(id));
+            });
+        }
+
+        row.add_suffix(&restore_button);
+        row.add_suffix(&delete_button);
+        list.append(&row);
+    }
+    show_page();
+
+    root
+}
+
+/// Which of `times` are dropped from the latest ao5 and ao12 (see [`Trim`])
+fn current_trims(times: &[Option<Duration>]) -> Vec<Trim> {
+    let mut trims = vec![Trim::None; times.len()];
+    for n in [5, 12] {
+        let Some(start) = times.len().checked_sub(n) else {
+            continue;
+        };
+        // the best ones come first
+        let trimmed = TrimRule::Wca.trimmed_indices(&times[start..]);
+        let (best, worst) = trimmed.split_at(trimmed.len() / 2);
+        for (indices, trim) in [(best, Trim::Best), (worst, Trim::Worst)] {
+            for &idx in indices {
+                trims[start + idx] = trim;
+            }
+        }
+    }
+    trims
+}
+
+/// A solve's time for sharing, with a `+` after times that include a +2
+fn share_time(stat: &SolveStat) -> String {
+    match (stat.penalty(), stat.time()) {
+        (Penalty::Plus2, Some(time)) => format!("{}+", render_single(&time)),
+        (_, Some(time)) => render_single(&time),
+        (_, None) => "DNF".into(),
+    }
+}
+
+/// Text for pasting a result into a forum or chat, in the usual style: the result and the
+/// times that went into it, with the ones that don't count in parentheses, then the
+/// scrambles. `solves` are the time, whether it counts and the scramble of each solve.
+fn share_text(result: &str, label: &str, solves: &[(String, bool, String)]) -> String {
+    let mut text = format!("{result} {label}");
+    if let [(_, _, scramble)] = solves {
+        text.push_str("\n\n");
+        text.push_str(&gettext_f(
+            "Scramble: {scramble}",
+            &[("scramble", scramble)],
+        ));
+        return text;
+    }
+    let times = solves
+        .iter()
+        .map(|(time, counts, _)| counted_time(time, *counts))
+        .collect::<Vec<_>>();
+    write!(text, ": {}\n", times.join(", ")).unwrap();
+    for (i, (time, counts, scramble)) in solves.iter().enumerate() {
+        let time = counted_time(time, *counts);
+        write!(text, "\n{}. {time}   {scramble}", i + 1).unwrap();
+    }
+    text
+}
+
+/// Like [`share_text`], but as Markdown in the style of weekly competition posts on forums
+/// and Reddit: the result in bold, then the numbered times and scrambles in a code block so
+/// the scrambles aren't mangled by Markdown formatting
+fn share_markdown(result: &str, label: &str, solves: &[(String, bool, String)]) -> String {
+    let times = solves
+        .iter()
+        .map(|(time, counts, _)| counted_time(time, *counts))
+        .collect::<Vec<_>>();
+    let mut text = format!("**{result} {label}**: {}\n\n```\n", times.join(", "));
+    for (i, (time, counts, scramble)) in solves.iter().enumerate() {
+        let time = counted_time(time, *counts);
+        writeln!(text, "{}. {time}   {scramble}", i + 1).unwrap();
+    }
+    text.push_str("```\n");
+    text
+}
+
+/// A time for sharing, in parentheses if it doesn't count towards the result
+fn counted_time(time: &str, counts: bool) -> String {
+    if counts {
+        time.to_owned()
+    } else {
+        format!("({time})")
+    }
+}
+
+/// Mean of `times`, and a description of the best single and ao5 in them
+fn mean_and_best(times: &[Option<Duration>]) -> (String, String) {
+    if times.is_empty() {
+        return ("-".into(), gettext("No solves"));
+    }
+    let best_single = times
+        .iter()
+        .flatten()
+        .min()
+        .map_or("DNF".into(), render_single);
+    let mut best = gettext_f("Best {time}", &[("time", &best_single)]);
+    if let Some(best_ao5) = Session::from_results(times.to_vec()).best(5, TrimRule::Wca) {
+        write!(best, ", ao5 {}", render_average(best_ao5)).unwrap();
+    }
+    (render_average(session_mean_of(times)), best)
+}
+
+/// Memo letters grouped into pairs, e.g. `"AB CD E"`
+fn render_letter_pairs(letters: &[char]) -> String {
+    let mut ret = String::new();
+    for (i, letter) in letters.iter().enumerate() {
+        if i > 0 && i % 2 == 0 {
+            ret.push(' ');
+        }
+        ret.push(*letter);
+    }
+    if ret.is_empty() {
+        ret.push('-');
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(times: &[f64]) -> Vec<Option<Duration>> {
+        times
+            .iter()
+            .map(|&t| (t >= 0.0).then(|| Duration::from_secs_f64(t)))
+            .collect()
+    }
+
+    #[test]
+    fn search_filter() {
+        let filter = SolveFilter::new(None, None, None, None, "  #PB R U'  #lucky ");
+        assert_eq!(filter.tags, ["pb", "lucky"]);
+        assert_eq!(filter.text, "r u'");
+        assert!(filter.is_active());
+        assert!(!SolveFilter::new(None, None, None, None, " ").is_active());
+    }
+
+    #[test]
+    fn sub_x_rates() {
+        assert_eq!(sub_x_rate_of(&[], 15.0), None);
+        // DNFs count against the rate, and a time right at the threshold doesn't beat it
+        let times = secs(&[12.0, 14.99, 15.0, 20.0, -1.0]);
+        assert_eq!(sub_x_rate_of(&times, 15.0), Some(40.0));
+        assert_eq!(sub_x_rate_of(&times, 30.0), Some(80.0));
+    }
+
+    #[test]
+    fn phases_between_splits() {
+        let splits = [Duration::from_secs(2), Duration::from_secs(9)];
+        assert_eq!(
+            split_phases(&splits, Some(Duration::from_secs(13))),
+            secs(&[2.0, 7.0, 4.0])
+        );
+        assert_eq!(split_phases(&splits, None), secs(&[2.0, 7.0, -1.0]));
+    }
+
+    #[test]
+    fn consistency_metrics() {
+        // DNFs (negative here) are left out
+        let times = secs(&[10.0, 12.0, 14.0, 16.0, 18.0, -1.0]);
+        let deviation = std_deviation_of(&times).unwrap().as_secs_f64();
+        assert!((deviation - 8f64.sqrt()).abs() < 1e-6);
+        assert_eq!(interquartile_range_of(&times), Some(Duration::from_secs(4)));
+        let consistency = consistency_of(&times).unwrap();
+        assert!((consistency - (100.0 - 100.0 * 8f64.sqrt() / 14.0)).abs() < 1e-6);
+
+        let same = secs(&[9.5; 4]);
+        assert_eq!(std_deviation_of(&same), Some(Duration::ZERO));
+        assert_eq!(interquartile_range_of(&same), Some(Duration::ZERO));
+        assert_eq!(consistency_of(&same), Some(100.0));
+
+        assert_eq!(std_deviation_of(&secs(&[10.0, -1.0])), None);
+        assert_eq!(interquartile_range_of(&secs(&[1.0, 2.0, 3.0])), None);
+        assert_eq!(consistency_of(&secs(&[-1.0, -1.0])), None);
+    }
+
+    #[test]
+    fn stacked_penalties() {
+        let penalties = Penalties::of(Penalty::Plus2).with(Penalty::Plus2);
+        assert_eq!(penalties.kind(), Penalty::Plus2);
+        assert_eq!(penalties.added_time(), Duration::from_secs(4));
+        assert_eq!(penalties.to_string(), "+2+2");
+
+        let mut penalties = penalties.with(Penalty::Dnf);
+        assert_eq!(penalties.kind(), Penalty::Dnf);
+        assert_eq!(penalties.to_string(), "DNF (+2+2)");
+        penalties.dnf = false;
+        penalties.added.push(5);
+        assert_eq!(penalties.to_string(), "+2+2+5");
+        assert_eq!(penalties.with(Penalty::None), Penalties::default());
+    }
+
+    #[test]
+    fn session_splits() {
+        let last = glib::DateTime::from_local(2024, 7, 1, 23, 0, 0.0).unwrap();
+        let same_day = glib::DateTime::from_local(2024, 7, 1, 23, 59, 0.0).unwrap();
+        let next_day = glib::DateTime::from_local(2024, 7, 2, 0, 30, 0.0).unwrap();
+        let later = glib::DateTime::from_local(2024, 7, 2, 3, 0, 0.0).unwrap();
+
+        assert!(!SessionSplit::Never.starts_session(&last, &later));
+        assert!(!SessionSplit::Midnight.starts_session(&last, &same_day));
+        assert!(SessionSplit::Midnight.starts_session(&last, &next_day));
+        let inactivity = SessionSplit::Inactivity(Duration::from_secs(4 * 60 * 60));
+        assert!(!inactivity.starts_session(&last, &later));
+        assert!(inactivity.starts_session(&last, &later.add_hours(1).unwrap()));
+    }
+
+    #[test]
+    fn trims() {
+        assert_eq!(current_trims(&secs(&[10.0, 9.0, 11.0])), [Trim::None; 3]);
+        let trims = current_trims(&secs(&[5.0, 10.0, 9.0, 11.0, -1.0, 12.0]));
+        assert_eq!(trims[2], Trim::Best);
+        assert_eq!(trims[4], Trim::Worst);
+        assert_eq!(trims.iter().filter(|&&trim| trim == Trim::None).count(), 4);
+        let mut times = secs(&[13.0; 12]);
+        times[0] = Some(Duration::from_secs(1));
+        times[11] = Some(Duration::from_secs(14));
+        let trims = current_trims(&times);
+        assert_eq!(trims[0], Trim::Best);
+        assert_eq!(trims[11], Trim::Worst);
+        // the ao5's best is one of the tied 13s
+        assert_eq!(trims.iter().filter(|&&trim| trim == Trim::Best).count(), 2);
+    }
+
+    #[test]
+    fn sharing() {
+        let times = secs(&[12.34, 10.56, 12.77, -1.0, 12.27]);
+        let mut trimmed = TrimRule::Wca.trimmed_indices(&times);
+        trimmed.sort();
+        assert_eq!(trimmed, [1, 3]);
+
+        let solves = [
+            ("12.34", true, "R U"),
+            ("10.56", false, "F2"),
+            ("12.77+", true, "D'"),
+        ]
+        .map(|(time, counts, scramble)| (time.to_owned(), counts, scramble.to_owned()));
+        assert_eq!(
+            share_text("11.89", "ao5", &solves),
+            "11.89 ao5: 12.34, (10.56), 12.77+\n\n1. 12.34   R U\n2. (10.56)   F2\n3. 12.77+   D'"
+        );
+        assert_eq!(
+            share_text("9.87", "single", &solves[..1]),
+            "9.87 single\n\nScramble: R U"
+        );
+        assert_eq!(
+            share_markdown("11.89", "ao5", &solves),
+            "**11.89 ao5**: 12.34, (10.56), 12.77+\n\n```\n\
+             1. 12.34   R U\n2. (10.56)   F2\n3. 12.77+   D'\n```\n"
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use crate::prelude::*;
+use crate::stats::Average;
+use crate::timer::{render_single, render_time, Precision};
+use gtk::cairo;
+use std::path::Path;
+use std::time::Duration;
+
+const CARD_WIDTH: i32 = 960;
+const CARD_HEIGHT: i32 = 540;
+const FONT: &str = "Cantarell";
+
+/// Snapshot of a session's stats, for rendering as a shareable image
+#[derive(Debug, Clone)]
+pub struct SessionSummary {
+    pub best_single: Option<Duration>,
+    pub best_ao5: Option<Average>,
+    pub best_ao12: Option<Average>,
+    pub mean: Option<Average>,
+    /// Every solve in the session, oldest first (`None` if DNF)
+    pub times: Vec<Option<Duration>>,
+    /// Seed of the session's scrambles, so others can race them
+    pub scramble_seed: Option<String>,
+}
+
+impl SessionSummary {
+    /// Render the summary card for saving as `file_name`: an SVG if the extension is `.svg`,
+    /// otherwise a PNG
+    pub fn render(&self, file_name: &str) -> anyhow::Result<Vec<u8>> {
+        let is_svg = Path::new(file_name)
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"));
+
+        if is_svg {
+            let surface =
+                cairo::SvgSurface::for_stream(CARD_WIDTH.into(), CARD_HEIGHT.into(), Vec::new())?;
+            self.draw(&cairo::Context::new(&surface)?)?;
+            let svg = surface
+                .finish_output_stream()
+                .map_err(std::io::Error::from)?;
+            Ok(*svg.downcast::<Vec<u8>>().unwrap())
+        } else {
+            let surface =
+                cairo::ImageSurface::create(cairo::Format::ARgb32, CARD_WIDTH, CARD_HEIGHT)?;
+            self.draw(&cairo::Context::new(&surface)?)?;
+            let mut png = Vec::new();
+            surface.write_to_png(&mut png)?;
+            Ok(png)
+        }
+    }
+
+    fn draw(&self, cr: &cairo::Context) -> Result<(), cairo::Error> {
+        let (width, height) = (f64::from(CARD_WIDTH), f64::from(CARD_HEIGHT));
+
+        let background = cairo::LinearGradient::new(0.0, 0.0, width, height);
+        background.add_color_stop_rgb(0.0, 0.14, 0.14, 0.16);
+        background.add_color_stop_rgb(1.0, 0.2, 0.2, 0.24);
+        cr.set_source(&background)?;
+        cr.paint()?;
+
+        cr.set_source_rgb(1.0, 1.0, 1.0);
+        cr.select_font_face(FONT, cairo::FontSlant::Normal, cairo::FontWeight::Bold);
+        cr.set_font_size(34.0);
+        cr.move_to(40.0, 70.0);
+        cr.show_text(&gettext("Session Summary"))?;
+
+        if let Some(seed) = &self.scramble_seed {
+            let text = gettext_f("Scramble seed: {seed}", &[("seed", seed)]);
+            cr.set_source_rgba(1.0, 1.0, 1.0, 0.6);
+            cr.select_font_face(FONT, cairo::FontSlant::Normal, cairo::FontWeight::Normal);
+            cr.set_font_size(18.0);
+            let extents = cr.text_extents(&text)?;
+            cr.move_to(width - 40.0 - extents.x_advance(), 70.0);
+            cr.show_text(&text)?;
+        }
+
+        let tiles = [
+            (gettext("Solves"), self.times.len().to_string()),
+            (
+                gettext("Best Single"),
+                self.best_single
+                    .map(|t| render_single(&t))
+                    .unwrap_or_else(|| "-".into()),
+            ),
+            (gettext("Best Ao5"), render_average(self.best_ao5)),
+            (gettext("Best Ao12"), render_average(self.best_ao12)),
+            (gettext("Mean"), render_average(self.mean)),
+        ];
+        let tile_width = (width - 80.0) / tiles.len() as f64;
+        for (i, (title, value)) in tiles.iter().enumerate() {
+            let x = 40.0 + i as f64 * tile_width;
+            cr.set_source_rgb(1.0, 1.0, 1.0);
+            cr.select_font_face(FONT, cairo::FontSlant::Normal, cairo::FontWeight::Bold);
+            cr.set_font_size(30.0);
+            cr.move_to(x, 150.0);
+            cr.show_text(value)?;
+
+            cr.set_source_rgba(1.0, 1.0, 1.0, 0.6);
+            cr.select_font_face(FONT, cairo::FontSlant::Normal, cairo::FontWeight::Normal);
+            cr.set_font_size(18.0);
+            cr.move_to(x, 180.0);
+            cr.show_text(title)?;
+        }
+
+        self.draw_graph(cr, 40.0, 220.0, width - 80.0, height - 260.0)
+    }
+
+    /// Line graph of all the non-DNF times
+    fn draw_graph(
+        &self,
+        cr: &cairo::Context,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    ) -> Result<(), cairo::Error> {
+        cr.set_source_rgba(1.0, 1.0, 1.0, 0.08);
+        cr.rectangle(x, y, width, height);
+        cr.fill()?;
+
+        let points = self
+            .times
+            .iter()
+            .enumerate()
+            .filter_map(|(i, t)| t.map(|t| (i, t.as_secs_f64())))
+            .collect::<Vec<_>>();
+        if points.len() < 2 {
+            return Ok(());
+        }
+
+        let min = points.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+        let max = points.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+        let range = if max > min { max - min } else { 1.0 };
+        let padding = 16.0;
+        let to_xy = |(i, secs): (usize, f64)| {
+            (
+                x + padding + (width - 2.0 * padding) * i as f64 / (self.times.len() - 1) as f64,
+                y + padding + (height - 2.0 * padding) * (1.0 - (secs - min) / range),
+            )
+        };
+
+        cr.set_source_rgb(0.208, 0.518, 0.894);
+        cr.set_line_width(3.0);
+        cr.set_line_join(cairo::LineJoin::Round);
+        for (i, &point) in points.iter().enumerate() {
+            let (px, py) = to_xy(point);
+            if i == 0 {
+                cr.move_to(px, py);
+            } else {
+                cr.line_to(px, py);
+            }
+        }
+        cr.stroke()?;
+
+        cr.set_source_rgba(1.0, 1.0, 1.0, 0.6);
+        cr.select_font_face(FONT, cairo::FontSlant::Normal, cairo::FontWeight::Normal);
+        cr.set_font_size(14.0);
+        cr.move_to(x + 4.0, y + 14.0);
+        cr.show_text(&render_time(
+            &Duration::from_secs_f64(max),
+            Precision::Hundredths,
+        ))?;
+        cr.move_to(x + 4.0, y + height - 4.0);
+        cr.show_text(&render_time(
+            &Duration::from_secs_f64(min),
+            Precision::Hundredths,
+        ))?;
+
+        Ok(())
+    }
+}
+
+fn render_average(avg: Option<Average>) -> String {
+    avg.map(crate::stats::render_average)
+        .unwrap_or_else(|| "-".into())
+}
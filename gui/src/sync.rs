@@ -0,0 +1,396 @@
+//! Opt-in syncing of the solve history with a file on a WebDAV server (or any other server
+//! that supports `GET` and `PUT`, ideally with ETags), so the same history can be used on
+//! several machines.
+//!
+//! Both sides are merged against the history as of the last successful sync, so solves
+//! added or removed on either side are added or removed on both. If the same solve was
+//! changed on both sides, the local change wins.
+use crate::autosave::{write_file, SavedSolve, SessionState};
+use crate::http;
+use crate::prelude::*;
+use crate::settings::{settings, APP_ID};
+use anyhow::bail;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// How long after a change to wait before syncing it, so that e.g. toggling a penalty and
+/// adding a comment are uploaded together
+const SYNC_DELAY: Duration = Duration::from_secs(10);
+
+/// Identifies a solve across machines
+type SolveKey<'a> = (i64, Duration, &'a [cubestruct::Move]);
+
+fn key(solve: &SavedSolve) -> SolveKey<'_> {
+    (solve.date, solve.time, &solve.scramble)
+}
+
+/// Whether `a` and `b` are the same solve, possibly with different penalties, comments etc.
+pub fn same_solve(a: &SavedSolve, b: &SavedSolve) -> bool {
+    key(a) == key(b)
+}
+
+/// Merge the `local` and `remote` histories. `base` is the history as of the last sync.
+pub fn merge(base: &[SavedSolve], local: &[SavedSolve], remote: &[SavedSolve]) -> Vec<SavedSolve> {
+    let base = base.iter().map(|s| (key(s), s)).collect::<HashMap<_, _>>();
+    let remote_by_key = remote
+        .iter()
+        .map(|s| (key(s), s))
+        .collect::<HashMap<_, _>>();
+    let local_by_key = local.iter().map(|s| (key(s), s)).collect::<HashMap<_, _>>();
+
+    let mut merged = Vec::new();
+    for solve in local {
+        match (remote_by_key.get(&key(solve)), base.get(&key(solve))) {
+            // unchanged here, so take any change made on the other side
+            (Some(&theirs), Some(&old)) if solve == old => merged.push(theirs.clone()),
+            (Some(_), _) => merged.push(solve.clone()),
+            // removed on the other side
+            (None, Some(_)) => {}
+            // added here
+            (None, None) => merged.push(solve.clone()),
+        }
+    }
+    merged.extend(
+        remote
+            .iter()
+            // if it's in `base` but not here, it was removed here
+            .filter(|s| !local_by_key.contains_key(&key(s)) && !base.contains_key(&key(s)))
+            .cloned(),
+    );
+    // stable, so solves finished in the same second stay in order
+    merged.sort_by_key(|s| s.date);
+    merged
+}
+
+fn base_path() -> PathBuf {
+    glib::user_data_dir().join(APP_ID).join("sync-base")
+}
+
+fn history_path() -> PathBuf {
+    glib::user_data_dir().join(APP_ID).join("sync-history")
+}
+
+fn read_solves(path: &std::path::Path) -> Vec<SavedSolve> {
+    std::fs::read_to_string(path)
+        .map(|contents| SessionState::deserialize(&contents).solves)
+        .unwrap_or_default()
+}
+
+fn solves_file_contents(solves: &[SavedSolve]) -> String {
+    SessionState {
+        solves: solves.to_vec(),
+        ..SessionState::default()
+    }
+    .serialize()
+}
+
+/// Whether syncing is turned on in the preferences
+pub fn enabled() -> bool {
+    settings().boolean("sync-enabled")
+}
+
+/// The local copy of the synced history, to start the session with
+pub fn load_history() -> Vec<SavedSolve> {
+    read_solves(&history_path())
+}
+
+/// Sync when syncing is turned on, and forget the last sync when it's turned off, so that
+/// turning it back on later doesn't treat solves that aren't in the session as removed
+pub fn connect_enabled(tx: EventSender) {
+    settings().connect_changed(Some("sync-enabled"), move |settings, key| {
+        if settings.boolean(key) {
+            send_evt(tx.clone(), Event::Sync);
+            return;
+        }
+        for path in [base_path(), history_path()] {
+            if let Err(e) = std::fs::remove_file(path) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    tracing::warn!("Failed to remove sync file: {e}");
+                }
+            }
+        }
+    });
+}
+
+#[derive(Debug, Default)]
+pub struct HistorySync {
+    running: bool,
+    /// Another sync was asked for while one was running
+    pending: bool,
+    /// A sync is due to start after [`SYNC_DELAY`]
+    scheduled: bool,
+    /// Whether the last sync failed, to only report the first of a run of failures
+    failing: bool,
+    /// Contents of the last successful write of the local history, to avoid rewriting it
+    /// when nothing changed (a failed write is tried again next time)
+    history_written: Option<String>,
+}
+
+impl HistorySync {
+    /// Keep the local copy of the history up to date
+    pub fn save_history(&mut self, solves: &[SavedSolve]) {
+        if !enabled() {
+            // the file is removed when syncing is turned off
+            self.history_written = None;
+            return;
+        }
+        let contents = solves_file_contents(solves);
+        if self.history_written.as_ref() == Some(&contents) {
+            return;
+        }
+        match write_file(&history_path(), &contents) {
+            Ok(()) => self.history_written = Some(contents),
+            Err(e) => tracing::warn!("Failed to write sync history: {e}"),
+        }
+    }
+
+    /// Sync after a short delay, unless a sync is already due
+    pub fn schedule(&mut self, tx: EventSender) {
+        if !enabled() || self.scheduled {
+            return;
+        }
+        self.scheduled = true;
+        glib::timeout_add_local_once(SYNC_DELAY, move || send_evt(tx, Event::Sync));
+    }
+
+    /// Sync the history with the server. [`Event::SyncFinished`] is sent when done.
+    pub fn start(&mut self, tx: EventSender, local: Vec<SavedSolve>) {
+        self.scheduled = false;
+        if !enabled() {
+            return;
+        }
+        if self.running {
+            self.pending = true;
+            return;
+        }
+        self.running = true;
+
+        let settings = settings();
+        let url = settings.string("sync-url").into();
+        let username = settings.string("sync-username").into();
+        // the history as of the last successful sync
+        let base = read_solves(&base_path());
+        glib::spawn_future_local(async move {
+            let result = async {
+                let server = Server {
+                    url,
+                    username,
+                    password: password().await?,
+                };
+                sync(&server, &base, &local).await
+            }
+            .await
+            .map_err(|e| format!("{e:#}"));
+            send_evt(tx, Event::SyncFinished { local, result });
+        });
+    }
+
+    /// Record the result of a sync. Returns the error to report to the user, if any.
+    pub fn finish(
+        &mut self,
+        tx: EventSender,
+        result: &Result<Vec<SavedSolve>, String>,
+    ) -> Option<String> {
+        self.running = false;
+        if std::mem::take(&mut self.pending) {
+            send_evt(tx, Event::Sync);
+        }
+        match result {
+            Ok(merged) => {
+                self.failing = false;
+                // unless it was turned off while syncing
+                if enabled() {
+                    if let Err(e) = write_file(&base_path(), &solves_file_contents(merged)) {
+                        tracing::warn!("Failed to write sync base: {e}");
+                    }
+                }
+                None
+            }
+            Err(e) => {
+                tracing::warn!("Sync failed: {e}");
+                let first_failure = !self.failing;
+                self.failing = true;
+                first_failure.then(|| e.clone())
+            }
+        }
+    }
+}
+
+fn password_schema() -> libsecret::Schema {
+    libsecret::Schema::new(
+        APP_ID,
+        libsecret::SchemaFlags::NONE,
+        HashMap::from([("purpose", libsecret::SchemaAttributeType::String)]),
+    )
+}
+
+fn password_attributes() -> HashMap<&'static str, &'static str> {
+    HashMap::from([("purpose", "sync")])
+}
+
+/// The sync server's password, from the keyring. Empty if there isn't one.
+pub async fn password() -> anyhow::Result<String> {
+    // older versions kept it in the settings
+    let settings = settings();
+    let old = settings.string("sync-password");
+    if !old.is_empty() {
+        set_password(&old).await?;
+        settings.reset("sync-password");
+        return Ok(old.into());
+    }
+    let password =
+        libsecret::password_lookup_future(Some(&password_schema()), password_attributes()).await?;
+    Ok(password.map(Into::into).unwrap_or_default())
+}
+
+/// Save the sync server's password in the keyring, or remove it from there if it's empty
+pub async fn set_password(password: &str) -> anyhow::Result<()> {
+    if password.is_empty() {
+        libsecret::password_clear_future(Some(&password_schema()), password_attributes()).await?;
+    } else {
+        libsecret::password_store_future(
+            Some(&password_schema()),
+            password_attributes(),
+            None,
+            &gettext("Puzzle Time sync password"),
+            password,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Where the history is synced to
+struct Server {
+    /// Of the history file itself
+    url: String,
+    username: String,
+    password: String,
+}
+
+impl Server {
+    fn headers(&self) -> Vec<(&'static str, String)> {
+        if self.username.is_empty() {
+            return Vec::new();
+        }
+        let credentials = format!("{}:{}", self.username, self.password);
+        vec![(
+            "Authorization",
+            format!("Basic {}", glib::base64_encode(credentials.as_bytes())),
+        )]
+    }
+}
+
+/// Download the other side's history, merge it with `local` and upload the result.
+/// Returns the merged history.
+async fn sync(
+    server: &Server,
+    base: &[SavedSolve],
+    local: &[SavedSolve],
+) -> anyhow::Result<Vec<SavedSolve>> {
+    // Basic auth sends the password as it is
+    if !server.username.is_empty()
+        && glib::Uri::peek_scheme(&server.url).as_deref() != Some("https")
+    {
+        bail!(gettext(
+            "the username and password are only sent to https URLs, to keep them private"
+        ));
+    }
+    // the file could be changed by another machine between downloading and uploading it,
+    // in which case the upload is rejected and the merge is done again
+    for _ in 0..3 {
+        let response = http::request(&server.url, "GET", &server.headers(), &[]).await?;
+        let (remote, condition) = match response.status {
+            200..=299 => (
+                SessionState::deserialize(&String::from_utf8_lossy(&response.body)).solves,
+                response.etag.map(|etag| ("If-Match", etag)),
+            ),
+            // nothing synced yet
+            404 => (Vec::new(), Some(("If-None-Match", "*".to_string()))),
+            401 | 403 => bail!(gettext("the server rejected the username or password")),
+            status => bail!(gettext_f(
+                "the server responded with status {status} to downloading",
+                &[("status", &status.to_string())]
+            )),
+        };
+
+        let merged = merge(base, local, &remote);
+        if merged == remote {
+            return Ok(merged);
+        }
+        let mut headers = server.headers();
+        headers.push(("Content-Type", "text/plain; charset=utf-8".to_string()));
+        headers.extend(condition);
+        let contents = solves_file_contents(&merged);
+        let response = http::request(&server.url, "PUT", &headers, contents.as_bytes()).await?;
+        match response.status {
+            200..=299 => return Ok(merged),
+            412 => continue,
+            status => bail!(gettext_f(
+                "the server responded with status {status} to uploading",
+                &[("status", &status.to_string())]
+            )),
+        }
+    }
+    bail!(gettext("the history kept changing on the server"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::{Hand, Penalties, Penalty};
+
+    fn solve(date: i64, penalty: Penalty) -> SavedSolve {
+        SavedSolve {
+            time: Duration::from_secs(10),
+            date,
+            penalties: Penalties::of(penalty),
+            memo_time: None,
+            splits: Vec::new(),
+            stages: Vec::new(),
+            turns: Vec::new(),
+            hand: Hand::Both,
+            scramble: vec![cubestruct::Move::R],
+            comment: String::new(),
+            attachment: None,
+            cube: None,
+            scramble_difficulty: None,
+        }
+    }
+
+    #[test]
+    fn three_way_merge() {
+        let base = [
+            solve(1, Penalty::None),
+            solve(2, Penalty::None),
+            solve(3, Penalty::None),
+        ];
+        // 1 penalized here, 2 removed here, 5 added here
+        let local = [
+            solve(1, Penalty::Dnf),
+            solve(3, Penalty::None),
+            solve(5, Penalty::None),
+        ];
+        // 1 penalized differently there, 3 penalized there, 4 added there
+        let remote = [
+            solve(1, Penalty::Plus2),
+            solve(2, Penalty::None),
+            solve(3, Penalty::Plus2),
+            solve(4, Penalty::None),
+        ];
+        assert_eq!(
+            merge(&base, &local, &remote),
+            [
+                solve(1, Penalty::Dnf),
+                solve(3, Penalty::Plus2),
+                solve(4, Penalty::None),
+                solve(5, Penalty::None),
+            ]
+        );
+        // removed there
+        assert_eq!(merge(&base, &base, &base[1..]), &base[1..]);
+        // first sync
+        assert_eq!(merge(&[], &local, &[]), local);
+    }
+}
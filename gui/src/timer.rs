@@ -1,42 +1,193 @@
+use crate::i18n::decimal_separator;
+use crate::input::TouchInput;
 use crate::prelude::*;
+use crate::puzzle_diagram::DiagramView;
+use crate::settings::settings;
+use crate::sounds::{self, Sound};
+use crate::stats::Penalty;
+use cubestruct::{CfopStage, Move};
+use std::cell::Cell;
+use std::collections::{HashMap, VecDeque};
 use std::fmt::Write;
+use std::rc::Rc;
 use std::time::{Duration, Instant};
 
+pub use solvestats::Precision;
+
+/// How many scrambles are kept in the scramble history popover
+const SCRAMBLE_HISTORY_LEN: usize = 50;
+
+/// How many scrambles are generated ahead of time
+const SCRAMBLE_QUEUE_LEN: usize = 5;
+
+/// How long the solver searches for short solutions to a scramble to say how hard it is
+const DIFFICULTY_SEARCH_TIME: Duration = Duration::from_millis(500);
+
+/// WCA inspection time. Starting after this is +2, and more than 2 seconds after it is a DNF.
+const INSPECTION_TIME: Duration = Duration::from_secs(15);
+
+/// The stages of a smart cube solve whose times are recorded, in order
+pub const PACE_STAGES: [CfopStage; 3] = [CfopStage::Cross, CfopStage::F2l, CfopStage::Oll];
+
+/// What keeps the displayed time up to date while the timer is running
+#[derive(Debug)]
+enum UpdateSource {
+    /// Every 100ms
+    Timeout(glib::SourceId),
+    /// Every frame
+    Tick(gtk::TickCallbackId),
+}
+
+impl UpdateSource {
+    fn remove(self) {
+        match self {
+            Self::Timeout(id) => id.remove(),
+            Self::Tick(id) => id.remove(),
+        }
+    }
+}
+
+/// Seconds into inspection at which a judge would call out the time
+const INSPECTION_CALLOUTS: [(u64, Sound); 2] = [(8, Sound::Inspection8), (12, Sound::Inspection12)];
+
 #[derive(Debug)]
 pub struct Timer {
     tx: EventSender,
+    toaster: adw::ToastOverlay,
     start_time: Option<Instant>,
-    update_timeout: Option<glib::SourceId>,
+    /// Wall clock time that the running solve started, in microseconds since the Unix
+    /// epoch
+    started_at: Option<i64>,
+    update_source: Option<UpdateSource>,
     main_box: gtk::Box,
     redlight: adw::Bin,
     greenlight: adw::Bin,
     time_label: gtk::Label,
+    memo_label: gtk::Label,
+    /// How the running solve compares with the best single, at the last stage reached
+    pace_label: gtk::Label,
     scramble_label: gtk::Label,
-    current_scramble: Vec<cubestruct::Move>,
+    difficulty_label: gtk::Label,
+    scramble_diagram: DiagramView,
+    scramble_controls: gtk::Box,
+    /// Buttons for penalizing or deleting the last result that are big enough to tap
+    touch_controls: gtk::Box,
+    /// Whether the timer has been used from a touchscreen, to show `touch_controls`
+    touch_used: Rc<Cell<bool>>,
+    history_popover: gtk::Popover,
+    history_list: gtk::ListBox,
+    /// Oldest scramble first
+    scramble_history: Vec<Vec<cubestruct::Move>>,
+    /// Index of the current scramble in `scramble_history`
+    history_pos: usize,
+    /// Generated ahead of time, so that there's no wait for the next one between solves
+    upcoming_scrambles: VecDeque<Vec<cubestruct::Move>>,
+    /// Scrambles loaded from a file, which are used in order before any generated ones
+    imported_scrambles: VecDeque<Vec<cubestruct::Move>>,
+    /// Whether scrambles are being generated on a worker thread
+    generating_scrambles: bool,
+    /// Algs of the cases that new scrambles set up (see [`crate::alg_trainer`]), or empty
+    /// for random-state scrambles
+    case_algs: Vec<Vec<cubestruct::Move>>,
+    /// Whether the scrambles being generated are for the cases or seed from before they
+    /// last changed, so should be thrown away
+    stale_scrambles: bool,
+    /// Where random-state scrambles come from, if they're seeded
+    scramble_seed: Option<ScrambleSeed>,
+    /// Index in the stream of `scramble_seed` of each of its scrambles in the history
+    seed_indices: HashMap<Vec<cubestruct::Move>, u64>,
+    /// Solution length bounds (see [`cubestruct::CubieCube::solution_length_bound`]) of
+    /// scrambles in the history, or `None` while it's being worked out
+    difficulties: HashMap<Vec<cubestruct::Move>, Option<u8>>,
+    /// Once its tables are built, for random-state scrambles and difficulties
+    solver: Option<cubestruct::SolverContext>,
+    focus_mode: bool,
+    /// Split memo from execution during solves
+    blind: bool,
+    /// Time at which memo ended in the current (or last) solve
+    memo_time: Option<Duration>,
+    /// Manual splits in the current (or last) solve, as times since the start
+    splits: Vec<Duration>,
+    /// When each of [`PACE_STAGES`] was first reached in the current (or last) smart cube
+    /// solve, as times since the start
+    stages: Vec<Duration>,
+    /// Each turn of the current (or last) smart cube solve, as a time since the start.
+    /// `None` once the cube reports a change that isn't a single turn.
+    turns: Option<Vec<(Move, Duration)>>,
+    inspection_start: Option<Instant>,
+    /// Number of `INSPECTION_CALLOUTS` played in the current inspection
+    callouts_played: usize,
+    /// Penalty for going over inspection time in the current (or last) solve
+    inspection_penalty: Penalty,
 }
 
 impl Timer {
-    pub fn new(tx: EventSender) -> Self {
+    pub fn new(tx: EventSender, toaster: adw::ToastOverlay) -> Self {
         let builder = gtk::Builder::from_resource("/io/github/flying_toast/PuzzleTime/timer.ui");
         let mut this = Self {
             tx,
+            toaster,
             main_box: builder.object("main_box").unwrap(),
             redlight: builder.object("redlight").unwrap(),
             greenlight: builder.object("greenlight").unwrap(),
             time_label: builder.object("time_label").unwrap(),
+            memo_label: builder.object("memo_label").unwrap(),
+            pace_label: builder.object("pace_label").unwrap(),
             scramble_label: builder.object("scramble").unwrap(),
+            difficulty_label: builder.object("difficulty").unwrap(),
+            scramble_diagram: DiagramView::new(builder.object("scramble_diagram").unwrap()),
+            scramble_controls: builder.object("scramble_controls").unwrap(),
+            touch_controls: builder.object("touch_controls").unwrap(),
+            touch_used: Rc::new(Cell::new(false)),
+            history_popover: builder.object("history_popover").unwrap(),
+            history_list: builder.object("history_list").unwrap(),
             start_time: None,
-            update_timeout: None,
-            current_scramble: Vec::new(),
+            started_at: None,
+            update_source: None,
+            scramble_history: Vec::new(),
+            history_pos: 0,
+            upcoming_scrambles: VecDeque::new(),
+            imported_scrambles: VecDeque::new(),
+            generating_scrambles: false,
+            case_algs: Vec::new(),
+            stale_scrambles: false,
+            scramble_seed: None,
+            seed_indices: HashMap::new(),
+            solver: None,
+            difficulties: HashMap::new(),
+            focus_mode: false,
+            blind: false,
+            memo_time: None,
+            splits: Vec::new(),
+            stages: Vec::new(),
+            turns: None,
+            inspection_start: None,
+            callouts_played: 0,
+            inspection_penalty: Penalty::None,
         };
-        this.gen_new_scramble();
+        let scramble_diagram: gtk::DrawingArea = builder.object("scramble_diagram").unwrap();
+        settings()
+            .bind("show-scramble-diagram", &scramble_diagram, "visible")
+            .get()
+            .build();
+        this.next_scramble();
         this
     }
 
+    /// Touching and holding anywhere on the timer works like holding the timer key
+    pub fn touch_input(&self) -> TouchInput {
+        TouchInput::new(&self.main_box, self.touch_used.clone())
+    }
+
     pub fn widget(&self) -> &impl IsA<gtk::Widget> {
         &self.main_box
     }
 
+    /// Buttons for navigating the scramble history, to be put in the headerbar
+    pub fn header_widget(&self) -> &impl IsA<gtk::Widget> {
+        &self.scramble_controls
+    }
+
     pub fn red_light_on(&self) {
         self.redlight
             .set_css_classes(["timer-light", "timer-light-red"].as_slice());
@@ -55,125 +206,793 @@ impl Timer {
             .set_css_classes(&["timer-light", "timer-light-off"].as_slice());
     }
 
-    pub fn start(&mut self) {
+    /// Enlarge the time, and dim the scramble while solving (if enabled in the settings)
+    pub fn set_focus_mode(&mut self, focus_mode: bool) {
+        self.focus_mode = focus_mode;
+        if focus_mode {
+            self.main_box.add_css_class("focus-mode");
+        } else {
+            self.main_box.remove_css_class("focus-mode");
+            self.scramble_label.remove_css_class("dimmed");
+        }
+    }
+
+    /// Whether to show the memo time of blindfolded solves
+    pub fn set_blind(&mut self, blind: bool) {
+        self.blind = blind;
+        self.memo_time = None;
+        self.memo_label.set_visible(false);
+    }
+
+    /// Start counting down WCA inspection. The solve is started with [`Self::start`].
+    pub fn start_inspection(&mut self) {
         assert!(!self.running(), "Timer already running");
-        assert!(self.update_timeout.is_none());
+        assert!(self.update_source.is_none());
+        self.memo_label.set_visible(false);
+        self.touch_controls.set_visible(false);
+        self.inspection_start = Some(Instant::now());
+        self.callouts_played = 0;
+        self.start_updating();
+        self.update_displayed_time();
+    }
+
+    pub fn inspecting(&self) -> bool {
+        self.inspection_start.is_some()
+    }
+
+    /// Penalty for going over inspection time in the current (or last) solve
+    pub fn inspection_penalty(&self) -> Penalty {
+        self.inspection_penalty
+    }
+
+    /// Start a solve at `at`, which is when the input that started it happened
+    pub fn start(&mut self, at: Instant) {
+        assert!(!self.running(), "Timer already running");
+        self.inspection_penalty = match self.inspection_start.take() {
+            Some(inspection_start) => {
+                if let Some(source) = self.update_source.take() {
+                    source.remove();
+                }
+                inspection_penalty(at.saturating_duration_since(inspection_start))
+            }
+            None => Penalty::None,
+        };
+        assert!(self.update_source.is_none());
+        self.memo_time = None;
+        self.splits.clear();
+        self.stages.clear();
+        self.turns = Some(Vec::new());
+        self.memo_label.set_visible(false);
+        self.pace_label.set_visible(false);
+        self.touch_controls.set_visible(false);
+        if self.focus_mode && settings().boolean("dim-scramble-while-solving") {
+            self.scramble_label.add_css_class("dimmed");
+        }
+        self.start_time = Some(at);
+        // the wall clock can't be read as of `at`, so go back by how long ago it was
+        let ago = i64::try_from(at.elapsed().as_micros()).unwrap_or(0);
+        self.started_at = Some(glib::real_time() - ago);
+        self.start_updating();
+        sounds::play(Sound::Start);
+    }
+
+    /// Continue a solve that was interrupted by a crash, as if it had been running since
+    /// `started_at` (see [`Self::started_at()`])
+    pub fn resume(&mut self, started_at: i64) {
+        let elapsed = Duration::from_micros((glib::real_time() - started_at).max(0) as u64);
+        let now = Instant::now();
+        self.start(now);
+        self.start_time = Some(now.checked_sub(elapsed).unwrap_or(now));
+        self.started_at = Some(started_at);
+        self.update_displayed_time();
+    }
+
+    fn start_updating(&mut self) {
         let tx = self.tx.clone();
-        self.start_time = Some(Instant::now());
-        self.update_timeout = Some(glib::timeout_add(Duration::from_millis(100), move || {
-            send_evt(tx.clone(), Event::UpdateDisplayTime);
-            glib::ControlFlow::Continue
-        }));
-    }
-
-    pub fn stop(&mut self) -> Duration {
-        self.update_timeout.take().unwrap().remove();
-        let elapsed = self
-            .start_time
-            .take()
-            .expect("Timer isn't running")
-            .elapsed();
-        self.set_displayed_time(&elapsed, true);
+        self.update_source = Some(if settings().boolean("smooth-timer") {
+            UpdateSource::Tick(self.time_label.add_tick_callback(move |_, _| {
+                send_evt(tx.clone(), TimerEvent::UpdateDisplayTime);
+                glib::ControlFlow::Continue
+            }))
+        } else {
+            UpdateSource::Timeout(glib::timeout_add(Duration::from_millis(100), move || {
+                send_evt(tx.clone(), TimerEvent::UpdateDisplayTime);
+                glib::ControlFlow::Continue
+            }))
+        });
+    }
+
+    /// Stop the solve at `at`, which is when the input that stopped it happened, and return
+    /// its time
+    pub fn stop(&mut self, at: Instant) -> Duration {
+        self.update_source.take().unwrap().remove();
+        self.scramble_label.remove_css_class("dimmed");
+        let elapsed =
+            at.saturating_duration_since(self.start_time.take().expect("Timer isn't running"));
+        self.started_at = None;
+        // the result as it's recorded, unless the full precision was asked for
+        let time = if settings().boolean("show-milliseconds") {
+            render_time(&elapsed, Precision::Milliseconds)
+        } else {
+            render_single(&elapsed)
+        };
+        self.time_label.set_label(&time);
+        self.touch_controls.set_visible(self.touch_used.get());
+        sounds::play(Sound::Stop);
 
         elapsed
     }
 
+    /// End the memo phase of a blindfolded solve at `at`. Does nothing if memo already
+    /// ended.
+    pub fn split_memo(&mut self, at: Instant) {
+        let Some(start_time) = self.start_time else {
+            return;
+        };
+        if !self.blind || self.memo_time.is_some() {
+            return;
+        }
+        let memo_time = at.saturating_duration_since(start_time);
+        self.memo_time = Some(memo_time);
+        self.memo_label.set_label(&gettext_f(
+            "Memo: {time}",
+            &[("time", &render_time(&memo_time, Precision::Hundredths))],
+        ));
+        self.memo_label.set_visible(true);
+    }
+
+    /// Memo time of the current (or just stopped) blindfolded solve, if memo has ended
+    pub fn memo_time(&self) -> Option<Duration> {
+        self.memo_time
+    }
+
+    /// Record a manual split in the running solve, shown where the memo time would be
+    pub fn split(&mut self, at: Instant) {
+        let Some(start_time) = self.start_time else {
+            return;
+        };
+        self.splits.push(at.saturating_duration_since(start_time));
+        let splits = self
+            .splits
+            .iter()
+            .map(|split| render_time(split, Precision::Hundredths))
+            .collect::<Vec<_>>();
+        self.memo_label.set_label(&gettext_f(
+            "Splits: {times}",
+            &[("times", &splits.join(" / "))],
+        ));
+        self.memo_label.set_visible(true);
+    }
+
+    /// Manual splits of the current (or just stopped) solve
+    pub fn splits(&self) -> &[Duration] {
+        &self.splits
+    }
+
+    /// Record a smart cube's turn (`None` if it wasn't a single turn) at `at`, and the
+    /// stage it reached in the running solve. If `pb_stages` (the stage times of the best
+    /// single) is given, show how the solve compares at a newly reached stage.
+    pub fn cube_turned(
+        &mut self,
+        moov: Option<Move>,
+        at: Instant,
+        stage: CfopStage,
+        pb_stages: Option<&[Duration]>,
+    ) {
+        let Some(start_time) = self.start_time else {
+            return;
+        };
+        let elapsed = at.saturating_duration_since(start_time);
+        match moov {
+            Some(moov) => {
+                if let Some(turns) = &mut self.turns {
+                    turns.push((moov, elapsed));
+                }
+            }
+            None => self.turns = None,
+        }
+        let reached = PACE_STAGES.iter().filter(|&&s| s <= stage).count();
+        if reached <= self.stages.len() {
+            return;
+        }
+        // skipped stages are reached at the same time as the next one
+        self.stages.resize(reached, elapsed);
+        let Some(&pb) = pb_stages.and_then(|pb| pb.get(reached - 1)) else {
+            return;
+        };
+        self.pace_label
+            .set_label(&render_pace(PACE_STAGES[reached - 1], elapsed, pb));
+        let (ahead, behind) = if elapsed <= pb {
+            ("success", "error")
+        } else {
+            ("error", "success")
+        };
+        self.pace_label.add_css_class(ahead);
+        self.pace_label.remove_css_class(behind);
+        self.pace_label.set_visible(true);
+    }
+
+    /// Stage times of the current (or just stopped) smart cube solve (see [`PACE_STAGES`])
+    pub fn stages(&self) -> &[Duration] {
+        &self.stages
+    }
+
+    /// Turns of the current (or just stopped) smart cube solve, if they were all single
+    /// turns
+    pub fn turns(&self) -> &[(Move, Duration)] {
+        self.turns.as_deref().unwrap_or_default()
+    }
+
+    /// Like [`Self::turns()`], but `None` if they weren't all single turns
+    pub fn known_turns(&self) -> Option<&[(Move, Duration)]> {
+        self.turns.as_deref()
+    }
+
+    /// Pick the turns of a resumed solve back up from a checkpoint
+    pub fn restore_turns(&mut self, turns: Vec<(Move, Duration)>) {
+        self.turns = Some(turns);
+    }
+
+    /// The time as currently displayed
+    pub fn displayed_time(&self) -> String {
+        self.time_label.label().into()
+    }
+
     pub fn running(&self) -> bool {
         self.start_time.is_some()
     }
 
-    pub fn update_displayed_time(&self) {
-        if let Some(start_time) = &self.start_time {
-            self.set_displayed_time(&start_time.elapsed(), false);
-        }
+    /// Time since the running solve started, or `None` if the timer isn't running
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.start_time.map(|start_time| start_time.elapsed())
     }
 
-    fn set_displayed_time(&self, dur: &Duration, show_hunds: bool) {
-        self.time_label.set_label(&render_time(dur, show_hunds));
+    /// When the running solve started, in microseconds since the Unix epoch
+    pub fn started_at(&self) -> Option<i64> {
+        self.started_at
+    }
+
+    pub fn update_displayed_time(&mut self) {
+        if let Some(start_time) = &self.start_time {
+            let elapsed = start_time.elapsed();
+            self.time_label
+                .set_label(&match settings().string("running-time-display").as_str() {
+                    "hidden" => gettext("Solving"),
+                    "seconds" => render_time(&elapsed, Precision::Seconds),
+                    "hundredths" => render_time(&elapsed, Precision::Hundredths),
+                    _ => render_time(&elapsed, Precision::Tenths),
+                });
+        } else if let Some(inspection_start) = &self.inspection_start {
+            let elapsed = inspection_start.elapsed();
+            if let Some(&(secs, sound)) = INSPECTION_CALLOUTS.get(self.callouts_played) {
+                if elapsed.as_secs() >= secs {
+                    self.callouts_played += 1;
+                    sounds::play(sound);
+                }
+            }
+            self.time_label
+                .set_label(&match inspection_penalty(elapsed) {
+                    Penalty::None => {
+                        let remaining = INSPECTION_TIME - elapsed;
+                        format!("{}", remaining.as_secs_f64().ceil())
+                    }
+                    penalty => penalty.to_string(),
+                });
+        }
     }
 
+    /// Empty while waiting for a new scramble to be generated
     pub fn current_scramble(&self) -> &[cubestruct::Move] {
-        &self.current_scramble
+        &self.scramble_history[self.history_pos]
     }
 
-    /// Get the current scramble, replacing it with a newly generated random one
+    /// Get the current scramble, moving on to the next one
     pub fn take_scramble(&mut self) -> Vec<cubestruct::Move> {
-        let ret = self.current_scramble.clone();
-        self.gen_new_scramble();
+        let ret = self.current_scramble().to_vec();
+        self.next_scramble();
         ret
     }
 
-    fn gen_new_scramble(&mut self) {
-        // TODO: actually generate scrambles lol
-        if self.current_scramble.is_empty() {
-            use cubestruct::Move::*;
-            self.current_scramble = vec![
-                D, F2, D2, Ui, F2, R2, F2, Ri, D, L, Bi, Li, B, F2, Di, Fi, L, Bi, Ui,
-            ];
+    /// Go forward in the scramble history, generating a new scramble if we're already
+    /// at the newest one
+    pub fn next_scramble(&mut self) {
+        if self.history_pos + 1 < self.scramble_history.len() {
+            self.history_pos += 1;
+        } else if let Some(scramble) = self.imported_scrambles.pop_front() {
+            if self.imported_scrambles.is_empty() {
+                self.toaster.add_toast(adw::Toast::new(&gettext(
+                    "Last imported scramble, generated ones come next",
+                )));
+            }
+            self.push_scramble(scramble);
+        } else {
+            let scramble = match self.pop_upcoming_scramble() {
+                Some(scramble) => scramble,
+                // the stream of seeded scrambles has to be followed, so the current scramble
+                // is left empty until the next one is ready (and solves can't start until then)
+                None if self.active_seed().is_some() => Vec::new(),
+                // anything else would have to wait for the solver
+                None => self.scramble_without_solver(),
+            };
+            self.push_scramble(scramble);
+            self.generate_scrambles();
+        }
+        self.scramble_changed();
+    }
+
+    /// A scramble that doesn't need the solver, for while its tables are being built
+    fn scramble_without_solver(&self) -> Vec<cubestruct::Move> {
+        match random_case(&self.case_algs) {
+            Some(alg) => cubestruct::case_setup(alg),
+            None => cubestruct::random_move_scramble(),
         }
-        self.current_scramble.reverse();
-        self.scramble_label
-            .set_label(&render_moveseq(&self.current_scramble, true));
     }
-}
 
-pub fn render_time(dur: &Duration, show_hunds: bool) -> String {
-    let mut rem = dur.as_millis() / 10;
-    let hunds = rem % 100;
-    rem /= 100;
-    let secs = rem % 60;
-    rem /= 60;
-    let mins = rem;
-
-    if mins == 0 {
-        if show_hunds {
-            format!("{secs}.{hunds:02}")
+    /// Set the cases that new scrambles set up, or random-state scrambles if `algs` is
+    /// empty. Moves on to a new scramble if they changed.
+    pub fn set_case_algs(&mut self, algs: Vec<Vec<cubestruct::Move>>) {
+        if algs == self.case_algs {
+            return;
+        }
+        self.case_algs = algs;
+        self.upcoming_scrambles.clear();
+        self.stale_scrambles = self.generating_scrambles;
+        self.next_scramble();
+    }
+
+    /// Use `scrambles` in order, starting now, before going back to generated ones
+    pub fn import_scrambles(&mut self, scrambles: Vec<Vec<cubestruct::Move>>) {
+        self.imported_scrambles = scrambles.into();
+        self.next_scramble();
+    }
+
+    pub fn scramble_seed(&self) -> Option<&ScrambleSeed> {
+        self.scramble_seed.as_ref()
+    }
+
+    /// Seed random-state scrambles, or make them truly random again if `seed` is `None`.
+    /// Moves on to a new scramble if it changed.
+    pub fn set_scramble_seed(&mut self, seed: Option<ScrambleSeed>) {
+        if seed == self.scramble_seed {
+            return;
+        }
+        self.scramble_seed = seed;
+        self.seed_indices.clear();
+        self.upcoming_scrambles.clear();
+        self.stale_scrambles = self.generating_scrambles;
+        self.next_scramble();
+    }
+
+    /// Index of `scramble` in the stream of the current seed, if it came from there
+    pub fn seed_index(&self, scramble: &[cubestruct::Move]) -> Option<u64> {
+        self.seed_indices.get(scramble).copied()
+    }
+
+    /// The seed new scrambles come from, unless they're for the alg trainer's cases
+    fn active_seed(&self) -> Option<&ScrambleSeed> {
+        self.scramble_seed
+            .as_ref()
+            .filter(|_| self.case_algs.is_empty())
+    }
+
+    /// Take the next scramble from the queue, keeping track of where the seeded stream is
+    fn pop_upcoming_scramble(&mut self) -> Option<Vec<cubestruct::Move>> {
+        let scramble = self.upcoming_scrambles.pop_front()?;
+        if self.case_algs.is_empty() {
+            if let Some(seed) = &mut self.scramble_seed {
+                self.seed_indices.insert(scramble.clone(), seed.position);
+                seed.position += 1;
+            }
+        }
+        Some(scramble)
+    }
+
+    pub fn previous_scramble(&mut self) {
+        if self.history_pos > 0 {
+            self.history_pos -= 1;
+            self.scramble_changed();
+        }
+    }
+
+    /// Make the scramble at the given index of the history current again
+    pub fn reuse_scramble(&mut self, history_idx: usize) {
+        if let Some(scramble) = self.scramble_history.get(history_idx).cloned() {
+            self.use_scramble(scramble);
+        }
+    }
+
+    /// Make `scramble` the current scramble
+    pub fn use_scramble(&mut self, scramble: Vec<cubestruct::Move>) {
+        self.push_scramble(scramble);
+        self.scramble_changed();
+    }
+
+    fn push_scramble(&mut self, scramble: Vec<cubestruct::Move>) {
+        // don't keep a placeholder for a scramble that's still being generated
+        if self.scramble_history.last().is_some_and(Vec::is_empty) {
+            self.scramble_history.pop();
+        }
+        self.scramble_history.push(scramble);
+        if self.scramble_history.len() > SCRAMBLE_HISTORY_LEN {
+            self.scramble_history.remove(0);
+            let history = &self.scramble_history;
+            self.difficulties
+                .retain(|scramble, _| history.contains(scramble));
+            self.seed_indices
+                .retain(|scramble, _| history.contains(scramble));
+        }
+        self.history_pos = self.scramble_history.len() - 1;
+    }
+
+    /// Start generating scrambles to fill up the queue of upcoming ones, unless they're
+    /// already being generated. [`TimerEvent::ScramblesReady`] is sent when they're done.
+    fn generate_scrambles(&mut self) {
+        let Some(solver) = self.solver.clone() else {
+            return;
+        };
+        if self.generating_scrambles || self.upcoming_scrambles.len() >= SCRAMBLE_QUEUE_LEN {
+            return;
+        }
+        // when the current scramble is waiting, get it on its own so it's ready sooner
+        let count = if self.current_scramble().is_empty() {
+            1
         } else {
-            format!("{secs}.{}", hunds / 10)
+            SCRAMBLE_QUEUE_LEN - self.upcoming_scrambles.len()
+        };
+        self.generating_scrambles = true;
+        let tx = self.tx.clone();
+        // `None` for random-state scrambles
+        let cases: Option<Vec<_>> = (0..count)
+            .map(|_| random_case(&self.case_algs).cloned())
+            .collect();
+        // the queue already has the seeded scrambles up to these
+        let seeded = self.active_seed().map(|seed| {
+            let first = seed.position + self.upcoming_scrambles.len() as u64;
+            (seed.value(), first..first + count as u64)
+        });
+        std::thread::spawn(move || {
+            let scrambles = match (cases, seeded) {
+                (Some(algs), _) => algs.iter().map(|alg| solver.case_scramble(alg)).collect(),
+                (None, Some((seed, indices))) => {
+                    solver.seeded_scramble_batch::<cubestruct::CubieCube>(seed, indices)
+                }
+                (None, None) => solver.scramble_batch::<cubestruct::CubieCube>(count),
+            };
+            send_evt(tx, TimerEvent::ScramblesReady(scrambles));
+        });
+    }
+
+    /// Start generating random-state scrambles, once the solver's tables are built
+    pub fn solver_ready(&mut self, solver: cubestruct::SolverContext) {
+        self.solver = Some(solver);
+        self.generate_scrambles();
+        self.estimate_difficulty();
+    }
+
+    /// The solver, once its tables are built
+    pub fn solver(&self) -> Option<&cubestruct::SolverContext> {
+        self.solver.as_ref()
+    }
+
+    /// Length of the shortest solution to `scramble` the solver found, if it's been worked
+    /// out
+    pub fn difficulty(&self, scramble: &[cubestruct::Move]) -> Option<u8> {
+        self.difficulties.get(scramble).copied().flatten()
+    }
+
+    /// Start working out the difficulty of the current scramble on a worker thread, unless
+    /// it's already known. [`TimerEvent::ScrambleDifficulty`] is sent when it's done.
+    fn estimate_difficulty(&mut self) {
+        let Some(solver) = self.solver.clone() else {
+            return;
+        };
+        let scramble = self.current_scramble().to_vec();
+        if scramble.is_empty() || self.difficulties.contains_key(&scramble) {
+            return;
         }
-    } else {
-        if show_hunds {
-            format!("{mins}:{secs:02}.{hunds:02}")
+        self.difficulties.insert(scramble.clone(), None);
+        let tx = self.tx.clone();
+        std::thread::spawn(move || {
+            let mut state = cubestruct::CubieCube::SOLVED;
+            state.apply_moves(&scramble);
+            // any sequence of moves leads to a possible state
+            let moves = solver
+                .solution_length_bound(&state, DIFFICULTY_SEARCH_TIME)
+                .unwrap();
+            send_evt(tx, TimerEvent::ScrambleDifficulty { scramble, moves });
+        });
+    }
+
+    /// Record a difficulty from [`TimerEvent::ScrambleDifficulty`]
+    pub fn difficulty_ready(&mut self, scramble: Vec<cubestruct::Move>, moves: u8) {
+        // unless it's no longer in the history
+        if let Some(difficulty) = self.difficulties.get_mut(&scramble) {
+            *difficulty = Some(moves);
+            self.show_difficulty();
+        }
+    }
+
+    fn show_difficulty(&self) {
+        let difficulty = self.difficulty(self.current_scramble());
+        if let Some(moves) = difficulty {
+            self.difficulty_label.set_label(&ngettext_f(
+                "Solvable in at most {moves} move",
+                "Solvable in at most {moves} moves",
+                moves.into(),
+                &[("moves", &moves.to_string())],
+            ));
+        }
+        self.difficulty_label.set_visible(difficulty.is_some());
+    }
+
+    /// Use scrambles from [`TimerEvent::ScramblesReady`]. Returns whether one became the current
+    /// scramble.
+    pub fn scrambles_ready(&mut self, scrambles: Vec<Vec<cubestruct::Move>>) -> bool {
+        self.generating_scrambles = false;
+        if std::mem::take(&mut self.stale_scrambles) {
+            self.generate_scrambles();
+            return false;
+        }
+        self.upcoming_scrambles.extend(scrambles);
+        let mut is_current = false;
+        if self.scramble_history.last().is_some_and(Vec::is_empty) {
+            if let Some(scramble) = self.pop_upcoming_scramble() {
+                *self.scramble_history.last_mut().unwrap() = scramble;
+                is_current = self.history_pos + 1 == self.scramble_history.len();
+                self.scramble_changed();
+            }
+        }
+        // and start on the ones after it
+        self.generate_scrambles();
+        is_current
+    }
+
+    fn scramble_changed(&mut self) {
+        if self.current_scramble().is_empty() {
+            self.scramble_label
+                .set_label(&gettext("Generating scramble…"));
+            self.scramble_diagram.set_diagram(None);
         } else {
-            format!("{mins}:{secs:02}.{}", hunds / 10)
+            let rotations = if settings().boolean("random-orientation") {
+                cubestruct::Rotation::random_orientation()
+            } else {
+                Vec::new()
+            };
+            let mut label = render_moveseq(self.current_scramble(), true);
+            let mut state = cubestruct::CubieCube::SOLVED;
+            state.apply_moves(self.current_scramble());
+            // the diagram shows the cube as it's held after the rotations
+            for rotation in rotations {
+                write!(label, "  {rotation}").unwrap();
+                state.rotate(rotation);
+            }
+            self.scramble_label.set_label(&label);
+            self.scramble_diagram.set_diagram(Some(Box::new(state)));
+        }
+        self.estimate_difficulty();
+        self.show_difficulty();
+
+        self.history_list.remove_all();
+        for (idx, scramble) in self.scramble_history.iter().enumerate().rev() {
+            if scramble.is_empty() {
+                continue;
+            }
+            let row = adw::ActionRow::builder()
+                .title(render_moveseq(scramble, false))
+                .title_lines(0)
+                .build();
+            if idx == self.history_pos {
+                row.set_subtitle(&gettext("Current"));
+            }
+
+            let copy_button = gtk::Button::from_icon_name("edit-copy-symbolic");
+            copy_button.set_tooltip_text(Some(&gettext("Copy")));
+            copy_button.set_valign(gtk::Align::Center);
+            copy_button.add_css_class("flat");
+            let text = render_moveseq(scramble, false);
+            let toaster = self.toaster.clone();
+            let popover = self.history_popover.clone();
+            copy_button.connect_clicked(move |_| {
+                gdk::Display::default().unwrap().clipboard().set_text(&text);
+                popover.popdown();
+                toaster.add_toast(adw::Toast::new(&gettext("Copied to clipboard")));
+            });
+
+            let reuse_button = gtk::Button::from_icon_name("media-playlist-repeat-symbolic");
+            reuse_button.set_tooltip_text(Some(&gettext("Use Again")));
+            reuse_button.set_valign(gtk::Align::Center);
+            reuse_button.add_css_class("flat");
+            let tx = self.tx.clone();
+            let popover = self.history_popover.clone();
+            reuse_button.connect_clicked(move |_| {
+                popover.popdown();
+                send_evt(tx.clone(), TimerEvent::ReuseScramble(idx));
+            });
+
+            row.add_suffix(&copy_button);
+            row.add_suffix(&reuse_button);
+            self.history_list.append(&row);
         }
     }
 }
 
-pub fn render_moveseq(moves: &[cubestruct::Move], double_space: bool) -> String {
-    let mut s = format!("{}", moves[0]);
-    for moov in moves.iter().skip(1) {
-        if double_space {
-            write!(s, "  {moov}").unwrap();
-        } else {
-            write!(s, " {moov}").unwrap();
+/// Picks the stream of random-state scrambles, so that people using the same seed can race
+/// each other on the same scrambles
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ScrambleSeed {
+    /// As the user entered it
+    pub text: String,
+    /// Index in the stream of the next scramble to be used
+    pub position: u64,
+}
+
+impl ScrambleSeed {
+    /// A new stream, starting at its first scramble
+    pub fn new(text: String) -> Self {
+        Self { text, position: 0 }
+    }
+
+    /// The seed for [`cubestruct::seeded_scramble`]
+    pub fn value(&self) -> u64 {
+        cubestruct::seed_from_text(&self.text)
+    }
+}
+
+/// One of `case_algs`, chosen at random
+fn random_case(case_algs: &[Vec<cubestruct::Move>]) -> Option<&Vec<cubestruct::Move>> {
+    if case_algs.is_empty() {
+        return None;
+    }
+    case_algs.get(glib::random_int_range(0, case_algs.len() as i32) as usize)
+}
+
+/// Penalty for starting a solve after inspecting for `elapsed`
+fn inspection_penalty(elapsed: Duration) -> Penalty {
+    if elapsed <= INSPECTION_TIME {
+        Penalty::None
+    } else if elapsed <= INSPECTION_TIME + Duration::from_secs(2) {
+        Penalty::Plus2
+    } else {
+        Penalty::Dnf
+    }
+}
+
+/// `dur` to the given precision (rounded down), with the locale's decimal separator
+pub fn render_time(dur: &Duration, precision: Precision) -> String {
+    solvestats::render_time(dur, precision, decimal_separator())
+}
+
+/// A single result, rounded as the WCA does (see [`solvestats::round_single()`])
+pub fn render_single(time: &Duration) -> String {
+    solvestats::render_single(time, decimal_separator())
+}
+
+/// An average or mean, rounded as the WCA does (see [`solvestats::round_average()`])
+pub fn render_average(time: &Duration) -> String {
+    solvestats::Average::Some(*time).render(decimal_separator())
+}
+
+/// How far ahead of (`-`) or behind (`+`) `pb` a `stage` reached at `elapsed` is, e.g.
+/// "+0.40 at cross"
+fn render_pace(stage: CfopStage, elapsed: Duration, pb: Duration) -> String {
+    let delta = if elapsed > pb {
+        format!("+{}", render_time(&(elapsed - pb), Precision::Hundredths))
+    } else {
+        format!("-{}", render_time(&(pb - elapsed), Precision::Hundredths))
+    };
+    let args = [("delta", delta.as_str())];
+    match stage {
+        CfopStage::Cross => gettext_f("{delta} at cross", &args),
+        CfopStage::F2l => gettext_f("{delta} at F2L", &args),
+        _ => gettext_f("{delta} at OLL", &args),
+    }
+}
+
+/// Parse a list of scrambles, one per line. Blank lines are skipped, and numbering like `1.`
+/// or `1)` at the start of a line is ignored, as in competition scramble files.
+pub fn parse_scramble_list(text: &str) -> anyhow::Result<Vec<Vec<cubestruct::Move>>> {
+    let mut scrambles = Vec::new();
+    for (idx, line) in text.lines().enumerate() {
+        let line = line.trim();
+        let line = match line.split_once(['.', ')']) {
+            Some((number, rest))
+                if !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()) =>
+            {
+                rest
+            }
+            _ => line,
+        };
+        if line.trim().is_empty() {
+            continue;
         }
+        let scramble = cubestruct::parse_moves(line).map_err(|e| {
+            anyhow::anyhow!(gettext_f(
+                "Line {line}: {error}",
+                &[("line", &(idx + 1).to_string()), ("error", &e.to_string())],
+            ))
+        })?;
+        scrambles.push(scramble);
+    }
+    if scrambles.is_empty() {
+        anyhow::bail!(gettext("There are no scrambles in the file"));
     }
-    s
+    Ok(scrambles)
+}
+
+pub fn render_moveseq(moves: &[cubestruct::Move], double_space: bool) -> String {
+    cubestruct::MoveSequence::new(moves)
+        .wide_spacing(double_space)
+        .to_string()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    #[test]
+    fn pace() {
+        let millis = Duration::from_millis;
+        assert_eq!(
+            render_pace(CfopStage::Cross, millis(2_400), millis(2_000)),
+            format!("+0{}40 at cross", decimal_separator())
+        );
+        assert_eq!(
+            render_pace(CfopStage::Oll, millis(9_000), millis(9_250)),
+            format!("-0{}25 at OLL", decimal_separator())
+        );
+    }
+
+    #[test]
+    fn scramble_lists() {
+        let scrambles = parse_scramble_list("1. R U F\n\n  2) D' L2\nB\n").unwrap();
+        assert_eq!(
+            scrambles,
+            ["R U F", "D' L2", "B"].map(|s| cubestruct::parse_moves(s).unwrap())
+        );
+        let error = parse_scramble_list("R U\nR X\n").unwrap_err();
+        assert!(error.to_string().starts_with("Line 2:"));
+        assert!(parse_scramble_list("\n  \n").is_err());
+    }
+
     #[test]
     fn time_rendering() {
         let d_0m12s83 = Duration::from_secs(12) + Duration::from_millis(830);
-        assert_eq!(render_time(&d_0m12s83, true), "12.83");
+        assert_eq!(render_time(&d_0m12s83, Precision::Hundredths), "12.83");
 
         let d_0m0s00 = Duration::default();
-        assert_eq!(render_time(&d_0m0s00, true), "0.00");
-        assert_eq!(render_time(&d_0m0s00, false), "0.0");
+        assert_eq!(render_time(&d_0m0s00, Precision::Hundredths), "0.00");
+        assert_eq!(render_time(&d_0m0s00, Precision::Tenths), "0.0");
 
         let d_10m0s00 = Duration::from_secs(60 * 10);
-        assert_eq!(render_time(&d_10m0s00, true), "10:00.00");
-        assert_eq!(render_time(&d_10m0s00, false), "10:00.0");
+        assert_eq!(render_time(&d_10m0s00, Precision::Hundredths), "10:00.00");
+        assert_eq!(render_time(&d_10m0s00, Precision::Tenths), "10:00.0");
 
         let d_0m1s09 = Duration::from_millis(1090);
-        assert_eq!(render_time(&d_0m1s09, true), "1.09");
-        assert_eq!(render_time(&d_0m1s09, false), "1.0");
+        assert_eq!(render_time(&d_0m1s09, Precision::Hundredths), "1.09");
+        assert_eq!(render_time(&d_0m1s09, Precision::Tenths), "1.0");
 
         let d_0m4s30 = Duration::from_millis(4300);
-        assert_eq!(render_time(&d_0m4s30, true), "4.30");
-        assert_eq!(render_time(&d_0m4s30, false), "4.3");
+        assert_eq!(render_time(&d_0m4s30, Precision::Hundredths), "4.30");
+        assert_eq!(render_time(&d_0m4s30, Precision::Tenths), "4.3");
+
+        let d_1m5s678 = Duration::from_millis(65678);
+        assert_eq!(render_time(&d_1m5s678, Precision::Seconds), "1:05");
+        assert_eq!(render_time(&d_1m5s678, Precision::Milliseconds), "1:05.678");
+        assert_eq!(render_time(&d_0m1s09, Precision::Seconds), "1");
+        assert_eq!(render_time(&d_0m1s09, Precision::Milliseconds), "1.090");
+    }
+
+    #[test]
+    fn inspection_penalties() {
+        assert_eq!(inspection_penalty(Duration::from_secs(15)), Penalty::None);
+        assert_eq!(
+            inspection_penalty(Duration::from_millis(15010)),
+            Penalty::Plus2
+        );
+        assert_eq!(inspection_penalty(Duration::from_secs(17)), Penalty::Plus2);
+        assert_eq!(
+            inspection_penalty(Duration::from_millis(17010)),
+            Penalty::Dnf
+        );
     }
 }
@@ -0,0 +1,131 @@
+//! Official 3x3 results from the user's WCA profile, to compare practice times against
+use crate::http;
+use crate::prelude::*;
+use crate::settings::settings;
+use anyhow::{bail, Context};
+use std::time::Duration;
+
+const API_URL: &str = "https://www.worldcubeassociation.org/api/v0/persons/";
+
+/// Official personal records for 3x3
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub struct OfficialRecords {
+    pub single: Option<Duration>,
+    pub average: Option<Duration>,
+}
+
+/// The WCA ID from the preferences, if it's a valid one (like `2009ZEMD01`)
+pub fn wca_id() -> Option<String> {
+    let id = settings().string("wca-id").trim().to_uppercase();
+    let bytes = id.as_bytes();
+    let valid = bytes.len() == 10
+        && bytes[..4].iter().all(u8::is_ascii_digit)
+        && bytes[4..8].iter().all(u8::is_ascii_uppercase)
+        && bytes[8..].iter().all(u8::is_ascii_digit);
+    valid.then_some(id)
+}
+
+/// Fetch the records now and whenever the WCA ID is changed, sending
+/// [`Event::WcaRecords`] each time
+pub fn watch(tx: EventSender) {
+    fetch(tx.clone());
+    settings().connect_changed(Some("wca-id"), move |_, _| fetch(tx.clone()));
+}
+
+fn fetch(tx: EventSender) {
+    let Some(wca_id) = wca_id() else {
+        send_evt(
+            tx,
+            Event::WcaRecords {
+                wca_id: None,
+                result: Ok(OfficialRecords::default()),
+            },
+        );
+        return;
+    };
+    glib::spawn_future_local(async move {
+        let result = fetch_records(&wca_id).await.map_err(|e| format!("{e:#}"));
+        send_evt(
+            tx,
+            Event::WcaRecords {
+                wca_id: Some(wca_id),
+                result,
+            },
+        );
+    });
+}
+
+async fn fetch_records(wca_id: &str) -> anyhow::Result<OfficialRecords> {
+    let response = http::request(&format!("{API_URL}{wca_id}"), "GET", &[], &[]).await?;
+    match response.status {
+        200..=299 => {}
+        404 => bail!(gettext_f(
+            "there's no WCA profile with the ID {id}",
+            &[("id", wca_id)]
+        )),
+        status => bail!(gettext_f(
+            "the WCA website responded with status {status}",
+            &[("status", &status.to_string())]
+        )),
+    }
+    let json = std::str::from_utf8(&response.body).context(gettext("malformed response"))?;
+    parse_records(json)
+}
+
+/// Get the 3x3 records out of the WCA API's response for a person
+fn parse_records(json: &str) -> anyhow::Result<OfficialRecords> {
+    let json: serde_json::Value =
+        serde_json::from_str(json).context(gettext("malformed response"))?;
+    let records = json
+        .get("personal_records")
+        .context(gettext("no personal records in response"))?;
+    // in centiseconds, with 0 or less meaning there's no result
+    let best = |kind: &str| {
+        records
+            .get("333")?
+            .get(kind)?
+            .get("best")?
+            .as_f64()
+            .filter(|&best| best > 0.0)
+            .map(|best| Duration::from_millis(best as u64 * 10))
+    };
+    Ok(OfficialRecords {
+        single: best("single"),
+        average: best("average"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records() {
+        let response = r#"{
+            "person": {"wca_id": "2009ZEMD01", "name": "Feliks Zemdegs", "avatar": null},
+            "competition_count": 150,
+            "personal_records": {
+                "333": {
+                    "single": {"best": 473, "world_rank": 12},
+                    "average": {"best": 621, "world_rank": 5}
+                },
+                "333oh": {"single": {"best": 933}}
+            },
+            "medals": {"gold": 1, "silver": 2},
+            "tags": ["a\"bé", true, -1.5e2, []]
+        }"#;
+        assert_eq!(
+            parse_records(response).unwrap(),
+            OfficialRecords {
+                single: Some(Duration::from_millis(4730)),
+                average: Some(Duration::from_millis(6210)),
+            }
+        );
+        assert_eq!(
+            parse_records(r#"{"personal_records": {}}"#).unwrap(),
+            OfficialRecords::default()
+        );
+        assert!(parse_records(r#"{"personal_records": "#).is_err());
+        assert!(parse_records("{} {}").is_err());
+    }
+}
@@ -5,14 +5,14 @@ use aes::{
 };
 use btleplug::api::{Characteristic, Peripheral as _, WriteType};
 use btleplug::platform::Peripheral;
-use std::time::Instant;
+use smartcube::CubeClock;
 
 pub struct Cube {
     pub perip: Peripheral,
     pub fff6: Characteristic,
     pub last_bat: Option<u8>,
     pub cipher: Aes128,
-    pub epoch: Instant,
+    pub clock: CubeClock,
 }
 
 impl Cube {
@@ -27,7 +27,7 @@ impl Cube {
                 ]
                 .into(),
             ),
-            epoch: Instant::now(),
+            clock: CubeClock::new(),
         }
     }
 
@@ -37,7 +37,7 @@ impl Cube {
     /// - adds zero-padding;
     /// - encrypts the message;
     /// - writes it to the fff6 characteristic
-    pub async fn write_cmd_inner_bytes(&mut self, bytes: &[u8]) {
+    pub async fn write_cmd_inner_bytes(&mut self, bytes: &[u8]) -> btleplug::Result<()> {
         // +2 for checksum, +2 for fe/length prefix
         let cmdlen = bytes.len() + 2 + 2;
         let npad = if cmdlen % 16 == 0 {
@@ -66,6 +66,5 @@ impl Cube {
         self.perip
             .write(&self.fff6, &bytes, WriteType::WithoutResponse)
             .await
-            .unwrap();
     }
 }
@@ -3,9 +3,10 @@ mod cube;
 mod messages;
 
 use aes::{cipher::BlockDecrypt, Block};
+use anyhow::anyhow;
 use async_stream::stream;
 use btleplug::{
-    api::{bleuuid::uuid_from_u16, Peripheral as _},
+    api::{bleuuid::uuid_from_u16, Peripheral as _, ValueNotification},
     platform::Peripheral,
 };
 use futures::stream::Stream;
@@ -13,7 +14,9 @@ use messages::{C2aBody, CubeHello, StateChange};
 use smartcube::SmartcubeEvent;
 use std::future::Future;
 use std::pin::Pin;
-use std::time::Duration;
+use std::time::Instant;
+
+type Notifications = Pin<Box<dyn Stream<Item = ValueNotification> + Send>>;
 
 #[derive(Debug)]
 pub struct Driver;
@@ -46,45 +49,70 @@ impl smartcube::Driver for Driver {
     }
 }
 
-fn run_protocol(perip: Peripheral) -> impl Stream<Item = SmartcubeEvent> + Send {
-    stream! {
-        perip.discover_services().await.unwrap();
+/// Subscribe to the cube's messages, and say hello so it starts sending them
+#[tracing::instrument(skip_all, fields(address = %perip.address()))]
+async fn start(perip: Peripheral) -> anyhow::Result<(cube::Cube, Notifications)> {
+    perip.discover_services().await?;
 
-        let fff6 = perip
-            .characteristics()
-            .into_iter()
-            .find(|c| c.uuid == uuid_from_u16(0xfff6))
-            .unwrap();
+    let fff6 = perip
+        .characteristics()
+        .into_iter()
+        .find(|c| c.uuid == uuid_from_u16(0xfff6))
+        .ok_or_else(|| anyhow!("No fff6 characteristic"))?;
 
-        perip.subscribe(&fff6).await.unwrap();
+    perip.subscribe(&fff6).await?;
 
-        let mut cube = cube::Cube::new(perip, fff6);
-        let notifs = cube.perip.notifications().await.unwrap();
+    let mut cube = cube::Cube::new(perip, fff6);
+    let notifs = cube.perip.notifications().await?;
 
-        // send App Hello
-        cube.write_cmd_inner_bytes(&messages::make_app_hello(cube.perip.address()))
-            .await;
+    cube.write_cmd_inner_bytes(&messages::make_app_hello(cube.perip.address()))
+        .await?;
+    tracing::debug!("Sent App Hello");
+
+    Ok((cube, notifs))
+}
+
+fn run_protocol(perip: Peripheral) -> impl Stream<Item = SmartcubeEvent> + Send {
+    stream! {
+        let (mut cube, notifs) = match start(perip).await {
+            Ok(started) => started,
+            Err(e) => {
+                tracing::error!("Failed to start protocol: {e:#}");
+                return;
+            }
+        };
 
         for await n in notifs {
-            assert!(n.uuid == cube.fff6.uuid);
+            if n.uuid != cube.fff6.uuid {
+                tracing::debug!(uuid = %n.uuid, "Ignoring notification");
+                continue;
+            }
             let mut bytes = n.value;
-            assert!(bytes.len() % 16 == 0);
+            if bytes.len() % 16 != 0 {
+                tracing::warn!(?bytes, "Notification isn't whole blocks");
+                continue;
+            }
 
             for mut block in bytes.chunks_mut(16).map(Block::from_mut_slice) {
                 cube.cipher.decrypt_block(&mut block);
             }
 
-            let msg = messages::parse_c2a_message(&bytes).unwrap();
+            let msg = match messages::parse_c2a_message(&bytes) {
+                Ok(msg) => msg,
+                Err(e) => {
+                    tracing::warn!(?bytes, "Failed to parse message: {e:#}");
+                    continue;
+                }
+            };
+            tracing::trace!(?msg, "Message");
 
             if let Some(pkt) = msg.make_ack() {
-                cube.write_cmd_inner_bytes(pkt).await;
+                if let Err(e) = cube.write_cmd_inner_bytes(pkt).await {
+                    tracing::warn!("Failed to send ACK: {e}");
+                }
             }
 
-            let timestamp = msg.timestamp();
-            let instant = cube
-                .epoch
-                .checked_add(Duration::from_millis(timestamp.into()))
-                .unwrap();
+            let instant = cube.clock.instant(msg.timestamp(), Instant::now());
 
             match msg.into_body() {
                 C2aBody::CubeHello(CubeHello { state, battery })
@@ -5,7 +5,7 @@ use cubestruct::{Color, CubieCube, FaceletCube, Move};
 use thiserror::Error;
 
 #[derive(Debug)]
-enum Opcode {
+pub enum Opcode {
     CubeHello,
     StateChange,
     SyncConfirmation,
@@ -112,6 +112,10 @@ pub enum ParseError {
     BadOpcode { bad_opcode: u8 },
     #[error("Invalid turn ({turn} is not a valid move)")]
     BadTurn { turn: u8 },
+    #[error("Invalid facelet color ({color})")]
+    BadColor { color: u8 },
+    #[error("Unsupported message (opcode {opcode:?})")]
+    Unsupported { opcode: Opcode },
 }
 
 struct Parser<'a> {
@@ -170,7 +174,8 @@ pub fn parse_c2a_message(bytes: &[u8]) -> Result<C2aMessage> {
     }
 
     let length = p.get_u8(1)?;
-    if p.bytes.len() < length as usize {
+    // every message has at least the header (up to the timestamp) and the checksum
+    if length < 9 || p.bytes.len() < length as usize {
         bail!(ParseError::TooShort);
     }
     p.trim_padding(length);
@@ -187,7 +192,7 @@ pub fn parse_c2a_message(bytes: &[u8]) -> Result<C2aMessage> {
             let battery = p.get_u8(35)?;
 
             C2aBody::CubeHello(CubeHello {
-                state: cubie_cube_from_bytes(rawstate),
+                state: cubie_cube_from_bytes(rawstate)?,
                 battery,
             })
         }
@@ -201,7 +206,7 @@ pub fn parse_c2a_message(bytes: &[u8]) -> Result<C2aMessage> {
             let state = if needs_ack {
                 CubieCube::SOLVED
             } else {
-                cubie_cube_from_bytes(rawstate)
+                cubie_cube_from_bytes(rawstate)?
             };
 
             C2aBody::StateChange(StateChange {
@@ -211,13 +216,9 @@ pub fn parse_c2a_message(bytes: &[u8]) -> Result<C2aMessage> {
                 battery,
             })
         }
-        Opcode::SyncConfirmation => {
-            todo!()
-        }
+        opcode @ Opcode::SyncConfirmation => bail!(ParseError::Unsupported { opcode }),
     };
 
-    assert!(p.bytes.len() >= 7);
-
     Ok(C2aMessage {
         ack_head: p.get_bytes(2, 5)?,
         millis_timestamp,
@@ -225,7 +226,7 @@ pub fn parse_c2a_message(bytes: &[u8]) -> Result<C2aMessage> {
     })
 }
 
-fn cubie_cube_from_bytes(raw: &[u8]) -> CubieCube {
+fn cubie_cube_from_bytes(raw: &[u8]) -> Result<CubieCube> {
     let color_order = [
         Color::White,
         Color::Red,
@@ -239,15 +240,17 @@ fn cubie_cube_from_bytes(raw: &[u8]) -> CubieCube {
     let mut facelet_colors = raw
         .iter()
         .flat_map(|&x| [x & 0xf, (x & 0xF0) >> 4])
-        .map(|x| color_from_u8(x).unwrap());
+        .map(|x| color_from_u8(x).ok_or(ParseError::BadColor { color: x }));
 
     for face_color in color_order {
         for i in 0..9 {
-            builder.set(face_color, i, facelet_colors.next().unwrap());
+            let color = facelet_colors.next().ok_or(ParseError::TooShort)??;
+            builder.set(face_color, i, color);
         }
     }
 
-    builder.build().unwrap().to_cubie_cube().unwrap()
+    // all 54 facelets were set
+    Ok(builder.build().unwrap().to_cubie_cube()?)
 }
 
 fn color_from_u8(x: u8) -> Option<Color> {
@@ -0,0 +1,181 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Number of recent samples that the cube's clock is fitted to
+const MAX_SAMPLES: usize = 256;
+
+/// Seconds of cube time that each sample covers. Only the least delayed message in that
+/// time is kept, so that the fit follows the messages that were delayed the least.
+const SAMPLE_INTERVAL: f64 = 2.0;
+
+/// How much of the cube's clock has to have passed before its rate is estimated. Over
+/// shorter spans the Bluetooth latency jitter would swamp the drift.
+const MIN_RATE_SPAN: f64 = 30.0;
+
+/// Cube clocks are crystal oscillators, so a fitted rate further off than this is noise
+const MAX_DRIFT: f64 = 0.005;
+
+/// Maps the millisecond timestamps a cube puts in its messages to [`Instant`]s.
+///
+/// The cube's clock runs at a slightly different rate to the host's, so simply adding its
+/// timestamps to the time of the first message drifts over a long session. Instead, the
+/// rate is fitted to the recent messages, and the offset is chosen so that no message is
+/// mapped to after it was received (since Bluetooth latency can only delay messages).
+#[derive(Debug, Clone)]
+pub struct CubeClock {
+    /// Host time that the other times are relative to
+    epoch: Option<Instant>,
+    /// `(cube time, time received)` of the least delayed recent messages, in seconds
+    samples: VecDeque<(f64, f64)>,
+    /// Host seconds per cube second
+    rate: f64,
+}
+
+impl Default for CubeClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CubeClock {
+    pub fn new() -> Self {
+        Self {
+            epoch: None,
+            samples: VecDeque::with_capacity(MAX_SAMPLES),
+            rate: 1.0,
+        }
+    }
+
+    /// When a message timestamped `cube_millis` by the cube happened, given that it was
+    /// `received` at that time
+    pub fn instant(&mut self, cube_millis: u32, received: Instant) -> Instant {
+        let epoch = *self.epoch.get_or_insert(received);
+        let cube_time = f64::from(cube_millis) / 1000.0;
+        let received_secs = received.saturating_duration_since(epoch).as_secs_f64();
+        let time = self.map(cube_time, received_secs);
+        epoch + Duration::from_secs_f64(time.max(0.0))
+    }
+
+    /// Host time in seconds since the epoch of cube time `cube_time`, after recording that
+    /// it was received at `received`
+    fn map(&mut self, cube_time: f64, received: f64) -> f64 {
+        // the cube restarted its clock (e.g. it was reset), so the old samples don't apply
+        if self
+            .samples
+            .back()
+            .is_some_and(|&(last, _)| cube_time < last)
+        {
+            self.samples.clear();
+        }
+        let interval = |cube_time: f64| (cube_time / SAMPLE_INTERVAL).floor();
+        match self.samples.back_mut() {
+            // the intervals are fixed, since if each sample started its own, one that's
+            // replaced by a less delayed message would move its interval along
+            Some(last) if interval(cube_time) == interval(last.0) => {
+                if received - cube_time < last.1 - last.0 {
+                    *last = (cube_time, received);
+                }
+            }
+            _ => {
+                if self.samples.len() == MAX_SAMPLES {
+                    self.samples.pop_front();
+                }
+                self.samples.push_back((cube_time, received));
+            }
+        }
+        self.fit_rate();
+
+        // the latest `offset` that doesn't put any message after it was received, so the
+        // least delayed recent message is taken to have had no delay
+        let offset = self
+            .samples
+            .iter()
+            .map(|&(cube, host)| host - self.rate * cube)
+            .fold(f64::INFINITY, f64::min);
+        (offset + self.rate * cube_time).min(received)
+    }
+
+    /// Least-squares fit of the rate to the samples, if they span enough time
+    fn fit_rate(&mut self) {
+        let (first, last) = (self.samples.front().unwrap(), self.samples.back().unwrap());
+        if last.0 - first.0 < MIN_RATE_SPAN {
+            return;
+        }
+        let n = self.samples.len() as f64;
+        let mean_cube = self.samples.iter().map(|s| s.0).sum::<f64>() / n;
+        let mean_host = self.samples.iter().map(|s| s.1).sum::<f64>() / n;
+        let (mut covariance, mut variance) = (0.0, 0.0);
+        for &(cube, host) in &self.samples {
+            covariance += (cube - mean_cube) * (host - mean_host);
+            variance += (cube - mean_cube).powi(2);
+        }
+        self.rate = (covariance / variance).clamp(1.0 - MAX_DRIFT, 1.0 + MAX_DRIFT);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Messages every 100ms of cube time for `secs` seconds, from a cube whose clock runs
+    /// at `1 / rate` of the host's, each delayed by 30-70ms. Returns the host time of the
+    /// last one and when it was mapped to.
+    fn simulate(clock: &mut CubeClock, rate: f64, start: f64, secs: f64) -> (f64, f64) {
+        // xorshift, so the jitter's the same every run
+        let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+        let mut jitter = || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed % 1000) as f64 / 1000.0 * 0.04
+        };
+        let mut cube_time = start;
+        let mut last = (0.0, 0.0);
+        while cube_time < start + secs {
+            let happened = (cube_time - start) * rate;
+            let received = happened + 0.03 + jitter();
+            let mapped = clock.map(cube_time, received);
+            assert!(mapped <= received);
+            last = (happened, mapped);
+            cube_time += 0.1;
+        }
+        last
+    }
+
+    #[test]
+    fn fits_drift() {
+        for rate in [1.002, 0.998] {
+            let mut clock = CubeClock::new();
+            let (happened, mapped) = simulate(&mut clock, rate, 0.0, 300.0);
+            assert!((clock.rate - rate).abs() < 0.0002, "rate {}", clock.rate);
+            // off by about the least delay, rather than the 0.6s that 300s of drift adds up to
+            assert!((mapped - happened).abs() < 0.04, "{mapped} vs {happened}");
+        }
+    }
+
+    #[test]
+    fn clamps_drift() {
+        let mut clock = CubeClock::new();
+        simulate(&mut clock, 1.05, 0.0, 120.0);
+        assert_eq!(clock.rate, 1.0 + MAX_DRIFT);
+        simulate(&mut clock, 0.95, 0.0, 120.0);
+        assert_eq!(clock.rate, 1.0 - MAX_DRIFT);
+    }
+
+    #[test]
+    fn clock_resets() {
+        let mut clock = CubeClock::new();
+        simulate(&mut clock, 1.002, 1000.0, 120.0);
+        // the cube restarted counting from 0
+        assert_eq!(clock.map(0.5, 200.0), 200.0);
+        assert_eq!(clock.samples.len(), 1);
+
+        // its millisecond timestamps wrap around after about 49 days
+        let mut clock = CubeClock::new();
+        let start = Instant::now();
+        clock.instant(u32::MAX - 100, start);
+        let received = start + Duration::from_millis(500);
+        assert_eq!(clock.instant(300, received), received);
+        assert_eq!(clock.samples.len(), 1);
+    }
+}
@@ -1,4 +1,9 @@
 ///! Utils for generically interacting with smartcubes
+mod clock;
+pub mod plugin;
+mod robot;
+mod watchdog;
+
 use btleplug::api::{Central as _, CentralEvent, Manager as _, Peripheral as _};
 use btleplug::platform::{Adapter, Manager, Peripheral, PeripheralId};
 use futures::stream::{Stream, StreamExt};
@@ -6,6 +11,10 @@ use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
 
+pub use clock::CubeClock;
+pub use robot::{GanRobot, Robot, RobotError, SerialRobot};
+pub use watchdog::watchdog;
+
 pub trait Driver: std::fmt::Debug + Send + Sync {
     /// Name of this driver
     fn name(&self) -> &'static str;
@@ -26,6 +35,9 @@ pub enum SmartcubeEvent {
     Battery(u8),
     /// State change with timestamp
     StateChange(cubestruct::CubieCube, std::time::Instant),
+    /// The cube hasn't been turned for this long. Only sent by streams wrapped in a
+    /// [`watchdog`].
+    Idle(std::time::Duration),
 }
 
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
@@ -51,38 +63,99 @@ impl Device {
         &self.local_name.trim()
     }
 
-    /// Connect to the device and start receiving events
+    /// Connect to the device and start receiving events. If it can't be connected to, the
+    /// events end straight away.
     pub async fn connect(&self) -> impl Stream<Item = SmartcubeEvent> + Send + 'static {
-        self.perip.connect().await.unwrap();
+        if let Err(e) = self.perip.connect().await {
+            tracing::error!("Failed to connect: {e}");
+            return futures::stream::empty().boxed();
+        }
 
         self.driver.events(self.perip.clone())
     }
 
     pub async fn disconnect(&self) {
-        self.perip.disconnect().await.unwrap();
+        if let Err(e) = self.perip.disconnect().await {
+            tracing::warn!("Failed to disconnect: {e}");
+        }
     }
 
-    async fn new(perip: Peripheral, driver: &'static dyn Driver) -> Self {
-        let local_name = perip
-            .properties()
-            .await
-            .unwrap()
-            .unwrap()
-            .local_name
-            .unwrap();
+    /// `None` if the peripheral doesn't have a name
+    async fn new(perip: Peripheral, driver: &'static dyn Driver) -> Option<Self> {
+        let local_name = match perip.properties().await {
+            Ok(props) => props?.local_name?,
+            Err(e) => {
+                tracing::warn!("Failed to get properties of {}: {e}", perip.id());
+                return None;
+            }
+        };
 
-        Self {
+        Some(Self {
             perip,
             driver,
             local_name,
-        }
+        })
     }
 }
 
-pub async fn init_bluetooth(drivers: &'static [&'static dyn Driver]) -> BluetoothManager {
+pub async fn init_bluetooth(
+    drivers: &'static [&'static dyn Driver],
+) -> Result<BluetoothManager, BluetoothError> {
     BluetoothManager::new(drivers).await
 }
 
+/// Why Bluetooth can't be used
+#[derive(Debug)]
+pub enum BluetoothError {
+    /// The system's Bluetooth service (BlueZ on Linux) isn't available
+    Unavailable(btleplug::Error),
+    /// There's no Bluetooth adapter
+    NoAdapter,
+    /// The adapter is turned off, or blocked (e.g. by rfkill or airplane mode)
+    AdapterOff,
+    /// Not allowed to use Bluetooth, e.g. by a sandbox
+    PermissionDenied,
+    Other(btleplug::Error),
+}
+
+impl BluetoothError {
+    /// Sort an error from btleplug by its cause. The platform's errors are only available
+    /// as their debug output, which for BlueZ includes the D-Bus error name.
+    fn from_btleplug(e: btleplug::Error) -> Self {
+        if let btleplug::Error::PermissionDenied = e {
+            return Self::PermissionDenied;
+        }
+        let details = format!("{e:?}");
+        if details.contains("org.bluez.Error.NotReady") {
+            Self::AdapterOff
+        } else if details.contains("org.freedesktop.DBus.Error.AccessDenied")
+            || details.contains("org.bluez.Error.NotPermitted")
+        {
+            Self::PermissionDenied
+        } else if details.contains("org.freedesktop.DBus.Error.ServiceUnknown")
+            || details.contains("org.freedesktop.DBus.Error.NameHasNoOwner")
+        {
+            Self::Unavailable(e)
+        } else {
+            Self::Other(e)
+        }
+    }
+}
+
+impl std::fmt::Display for BluetoothError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unavailable(e) => write!(f, "Bluetooth service unavailable: {e}"),
+            Self::NoAdapter => write!(f, "No Bluetooth adapter"),
+            Self::AdapterOff => write!(f, "Bluetooth adapter is off"),
+            Self::PermissionDenied => write!(f, "Permission to use Bluetooth denied"),
+            Self::Other(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for BluetoothError {}
+
 #[derive(Debug)]
 pub enum ConnectionEvent {
     Connect(DeviceId),
@@ -99,41 +172,53 @@ pub struct BluetoothManager {
 impl BluetoothManager {
     pub fn events(
         &self,
-    ) -> impl Future<Output = impl Stream<Item = ConnectionEvent> + Send + 'static> + 'static {
+    ) -> impl Future<
+        Output = Result<impl Stream<Item = ConnectionEvent> + Send + 'static, BluetoothError>,
+    > + 'static {
         let adapter = Arc::clone(&self.adapter);
         let drivers = self.drivers;
 
         async move {
-            adapter
+            Ok(adapter
                 .events()
                 .await
-                .unwrap()
-                .filter_map(move |evt| filter_map_event(drivers, Arc::clone(&adapter), evt))
+                .map_err(BluetoothError::from_btleplug)?
+                .filter_map(move |evt| filter_map_event(drivers, Arc::clone(&adapter), evt)))
         }
     }
 
-    pub async fn start_scan(&self) {
-        self.adapter.start_scan(Default::default()).await.unwrap();
+    /// Fails with [`BluetoothError::AdapterOff`] if the adapter is turned off
+    pub async fn start_scan(&self) -> Result<(), BluetoothError> {
+        self.adapter
+            .start_scan(Default::default())
+            .await
+            .map_err(BluetoothError::from_btleplug)
     }
 
-    pub async fn stop_scan(&self) {
-        self.adapter.stop_scan().await.unwrap();
+    pub async fn stop_scan(&self) -> Result<(), BluetoothError> {
+        self.adapter
+            .stop_scan()
+            .await
+            .map_err(BluetoothError::from_btleplug)
     }
 
-    async fn new(drivers: &'static [&'static dyn Driver]) -> Self {
-        let adapter = Arc::new(
-            Manager::new()
-                .await
-                .unwrap()
-                .adapters()
-                .await
-                .unwrap()
-                .into_iter()
-                .nth(0)
-                .expect("Can't get bluetooth adapter"),
-        );
+    async fn new(drivers: &'static [&'static dyn Driver]) -> Result<Self, BluetoothError> {
+        let manager = Manager::new().await.map_err(BluetoothError::Unavailable)?;
+        let adapter = manager
+            .adapters()
+            .await
+            .map_err(|e| match BluetoothError::from_btleplug(e) {
+                BluetoothError::Other(e) => BluetoothError::Unavailable(e),
+                e => e,
+            })?
+            .into_iter()
+            .next()
+            .ok_or(BluetoothError::NoAdapter)?;
 
-        Self { drivers, adapter }
+        Ok(Self {
+            drivers,
+            adapter: Arc::new(adapter),
+        })
     }
 }
 
@@ -144,7 +229,13 @@ async fn filter_map_event(
 ) -> Option<ConnectionEvent> {
     match evt {
         CentralEvent::DeviceDiscovered(perip_id) => {
-            let perip = adapter.peripheral(&perip_id).await.unwrap();
+            let perip = match adapter.peripheral(&perip_id).await {
+                Ok(perip) => perip,
+                Err(e) => {
+                    tracing::warn!("Failed to get discovered peripheral {perip_id}: {e}");
+                    return None;
+                }
+            };
 
             make_device_if_supported(drivers, perip)
                 .await
@@ -166,7 +257,7 @@ async fn make_device_if_supported(
 ) -> Option<Device> {
     for driver in drivers {
         if driver.check_compat(&perip).await {
-            return Some(Device::new(perip, *driver).await);
+            return Device::new(perip, *driver).await;
         }
     }
 
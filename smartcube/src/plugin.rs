@@ -0,0 +1,106 @@
+//! Drivers loaded at runtime from shared libraries, so support for more cubes can be added
+//! without changing the app.
+//!
+//! A plugin is a `cdylib` crate that depends on this one and exports its driver with
+//! [`export_driver!`](crate::export_driver). Drivers are passed between the app and plugins as
+//! Rust trait objects, which have no stable ABI, so a plugin has to be built with the same
+//! compiler and the same version of this crate as the app. The version is checked when
+//! loading; the compiler can't be.
+use crate::Driver;
+use std::ffi::{c_char, CStr};
+use std::path::{Path, PathBuf};
+
+/// Version of this crate, which plugins must have been built against
+#[doc(hidden)]
+pub const PLUGIN_VERSION: &CStr =
+    match CStr::from_bytes_with_nul(concat!(env!("CARGO_PKG_VERSION"), "\0").as_bytes()) {
+        Ok(version) => version,
+        Err(_) => panic!("version contains a nul"),
+    };
+
+const VERSION_SYMBOL: &[u8] = b"smartcube_plugin_version";
+const DRIVER_SYMBOL: &[u8] = b"smartcube_driver";
+
+/// Exports a driver from a plugin. `$driver` has to be a constant expression, like a unit
+/// struct.
+///
+/// ```ignore
+/// smartcube::export_driver!(MyDriver);
+/// ```
+#[macro_export]
+macro_rules! export_driver {
+    ($driver:expr) => {
+        #[no_mangle]
+        pub extern "C" fn smartcube_plugin_version() -> *const ::std::ffi::c_char {
+            $crate::plugin::PLUGIN_VERSION.as_ptr()
+        }
+
+        #[no_mangle]
+        pub fn smartcube_driver() -> &'static dyn $crate::Driver {
+            &$driver
+        }
+    };
+}
+
+/// Why a plugin couldn't be loaded
+#[derive(Debug)]
+pub enum PluginError {
+    /// The library couldn't be opened, or doesn't export a driver
+    Load(PathBuf, libloading::Error),
+    /// It was built against a different version of this crate
+    Version { path: PathBuf, found: String },
+}
+
+impl std::fmt::Display for PluginError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Load(path, e) => write!(f, "{}: {e}", path.display()),
+            Self::Version { path, found } => write!(
+                f,
+                "{}: built for smartcube {found}, but this is {}",
+                path.display(),
+                PLUGIN_VERSION.to_string_lossy(),
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PluginError {}
+
+/// Load the driver exported by the plugin at `path`. The library is never unloaded, since
+/// the driver has to live as long as the app.
+///
+/// # Safety
+///
+/// Loading the library runs its initializers, and the driver runs its code, so it has to be
+/// trusted like any other code in the app. It also has to be a plugin that exports its
+/// driver with [`export_driver!`](crate::export_driver), built with the same compiler as
+/// the app: only the version of this crate is checked, and a library that exports the same
+/// symbols with other types, or lays out `dyn Driver` differently, is undefined behavior.
+pub unsafe fn load_driver(path: &Path) -> Result<&'static dyn Driver, PluginError> {
+    let load_error = |e| PluginError::Load(path.to_owned(), e);
+    // SAFETY: the caller trusts the library's initializers
+    let library = unsafe { libloading::Library::new(path) }.map_err(load_error)?;
+
+    // SAFETY: the caller makes sure the symbols have the types that `export_driver!` gives
+    // them, and the version check rules out the plugin having been built against a
+    // different `Driver`
+    let driver = unsafe {
+        let version = library
+            .get::<extern "C" fn() -> *const c_char>(VERSION_SYMBOL)
+            .map_err(load_error)?;
+        let version = CStr::from_ptr(version());
+        if version != PLUGIN_VERSION {
+            return Err(PluginError::Version {
+                path: path.to_owned(),
+                found: version.to_string_lossy().into_owned(),
+            });
+        }
+        let driver = library
+            .get::<fn() -> &'static dyn Driver>(DRIVER_SYMBOL)
+            .map_err(load_error)?;
+        driver()
+    };
+    std::mem::forget(library);
+    Ok(driver)
+}
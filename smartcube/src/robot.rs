@@ -0,0 +1,203 @@
+//! Robots that scramble a cube by turning it. Each kind of robot is a [`Robot`] backend,
+//! which only has to send it the moves.
+use crate::BluetoothManager;
+use btleplug::api::{bleuuid::uuid_from_u16, Central as _, Peripheral as _, WriteType};
+use btleplug::platform::Peripheral;
+use cubestruct::{Move, MoveSequence};
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use tokio::io::AsyncWriteExt;
+
+pub trait Robot: std::fmt::Debug + Send + Sync {
+    /// Name of the robot, to show the user
+    fn name(&self) -> String;
+
+    /// Have the robot turn the cube with `moves`. Finishes once the moves are sent, which can
+    /// be before the robot is done turning.
+    fn scramble<'a>(
+        &'a self,
+        moves: &'a [Move],
+    ) -> Pin<Box<dyn Future<Output = Result<(), RobotError>> + Send + 'a>>;
+}
+
+/// Why a scramble couldn't be sent to a robot
+#[derive(Debug)]
+pub enum RobotError {
+    /// No robot was found, or the device that was found doesn't work like one
+    NotFound,
+    Bluetooth(btleplug::Error),
+    Io(std::io::Error),
+}
+
+impl From<btleplug::Error> for RobotError {
+    fn from(e: btleplug::Error) -> Self {
+        Self::Bluetooth(e)
+    }
+}
+
+impl From<std::io::Error> for RobotError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl std::fmt::Display for RobotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "No robot found"),
+            Self::Bluetooth(e) => write!(f, "{e}"),
+            Self::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for RobotError {}
+
+/// Beginning of the name that GAN robots advertise
+const GAN_NAME_PREFIX: &str = "GanRobot";
+
+/// Characteristic that moves are written to
+const GAN_MOVE_CHARACTERISTIC: u16 = 0xfff3;
+
+/// Each packet has room for two moves per byte, and unused moves are `0xf`
+const GAN_PACKET_LEN: usize = 18;
+
+/// Moves the GAN robot can do, in the order of their codes. It holds the cube by its U face.
+const GAN_MOVES: [Move; 15] = {
+    use Move::*;
+    [R, R2, Ri, F, F2, Fi, D, D2, Di, L, L2, Li, B, B2, Bi]
+};
+
+/// GAN's Bluetooth scrambling robot
+#[derive(Debug, Clone)]
+pub struct GanRobot {
+    perip: Peripheral,
+    local_name: String,
+}
+
+impl GanRobot {
+    /// Find a robot among the devices the adapter has seen, so it needs to be scanning
+    pub async fn find(manager: &BluetoothManager) -> Result<Self, RobotError> {
+        for perip in manager.adapter.peripherals().await? {
+            let Some(props) = perip.properties().await? else {
+                continue;
+            };
+            if let Some(local_name) = props
+                .local_name
+                .filter(|name| name.starts_with(GAN_NAME_PREFIX))
+            {
+                return Ok(Self { perip, local_name });
+            }
+        }
+        Err(RobotError::NotFound)
+    }
+}
+
+impl Robot for GanRobot {
+    fn name(&self) -> String {
+        self.local_name.trim().to_string()
+    }
+
+    fn scramble<'a>(
+        &'a self,
+        moves: &'a [Move],
+    ) -> Pin<Box<dyn Future<Output = Result<(), RobotError>> + Send + 'a>> {
+        Box::pin(async move {
+            if !self.perip.is_connected().await? {
+                self.perip.connect().await?;
+            }
+            self.perip.discover_services().await?;
+            let characteristic = self
+                .perip
+                .characteristics()
+                .into_iter()
+                .find(|c| c.uuid == uuid_from_u16(GAN_MOVE_CHARACTERISTIC))
+                .ok_or(RobotError::NotFound)?;
+            for packet in gan_packets(&without_u(moves)) {
+                self.perip
+                    .write(&characteristic, &packet, WriteType::WithResponse)
+                    .await?;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Encode moves that the GAN robot can do, in packets that are each written separately
+fn gan_packets(moves: &[Move]) -> Vec<[u8; GAN_PACKET_LEN]> {
+    moves
+        .chunks(2 * GAN_PACKET_LEN)
+        .map(|chunk| {
+            let mut packet = [0xff; GAN_PACKET_LEN];
+            for (i, moov) in chunk.iter().enumerate() {
+                let code = GAN_MOVES.iter().position(|m| m == moov).unwrap() as u8;
+                // the first move of each byte is in the high nibble
+                let shift = if i % 2 == 0 { 4 } else { 0 };
+                packet[i / 2] = (packet[i / 2] & !(0xf << shift)) | (code << shift);
+            }
+            packet
+        })
+        .collect()
+}
+
+/// The same scramble without turning the U face, for robots that hold the cube by it. Each
+/// U turn is done as a D turn, with the U and D layers swapped before and swapped back after
+/// (leaving the centers in place).
+fn without_u(moves: &[Move]) -> Vec<Move> {
+    use Move::*;
+    const SWAP: [Move; 6] = [R, L, F2, B2, Ri, Li];
+    const SWAP_BACK: [Move; 6] = [L, R, B2, F2, Li, Ri];
+    let mut result = Vec::with_capacity(moves.len());
+    for &moov in moves {
+        let d = match moov {
+            U => D,
+            Ui => Di,
+            U2 => D2,
+            _ => {
+                result.push(moov);
+                continue;
+            }
+        };
+        result.extend(SWAP);
+        result.push(d);
+        result.extend(SWAP_BACK);
+    }
+    result
+}
+
+/// A robot on a serial port that takes each scramble as a line of text in WCA notation, as
+/// homemade (e.g. Arduino-based) robots usually do. The port has to be set up already, e.g.
+/// its speed with `stty`.
+#[derive(Debug, Clone)]
+pub struct SerialRobot {
+    path: PathBuf,
+}
+
+impl SerialRobot {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl Robot for SerialRobot {
+    fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn scramble<'a>(
+        &'a self,
+        moves: &'a [Move],
+    ) -> Pin<Box<dyn Future<Output = Result<(), RobotError>> + Send + 'a>> {
+        Box::pin(async move {
+            let line = format!("{}\n", MoveSequence::new(moves));
+            let mut port = tokio::fs::OpenOptions::new()
+                .write(true)
+                .open(&self.path)
+                .await?;
+            port.write_all(line.as_bytes()).await?;
+            port.flush().await?;
+            Ok(())
+        })
+    }
+}
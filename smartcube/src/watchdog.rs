@@ -0,0 +1,112 @@
+use crate::SmartcubeEvent;
+use futures::stream::{self, Stream, StreamExt};
+use std::pin::Pin;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Passes `events` through, adding a [`SmartcubeEvent::Idle`] whenever the cube goes
+/// `timeout` without being turned. It's only sent once for each idle period, so the next one
+/// comes `timeout` after the cube is turned again. Must be polled within a Tokio runtime.
+pub fn watchdog(
+    events: impl Stream<Item = SmartcubeEvent> + Send + 'static,
+    timeout: Duration,
+) -> impl Stream<Item = SmartcubeEvent> + Send + 'static {
+    let events: Pin<Box<dyn Stream<Item = SmartcubeEvent> + Send>> = Box::pin(events);
+    // `None` once the idle event for the current idle period has been sent
+    let deadline = Some(Instant::now() + timeout);
+    stream::unfold(
+        (events, deadline),
+        move |(mut events, deadline)| async move {
+            let evt = match deadline {
+                Some(deadline) => match tokio::time::timeout_at(deadline, events.next()).await {
+                    Ok(evt) => evt?,
+                    Err(_) => return Some((SmartcubeEvent::Idle(timeout), (events, None))),
+                },
+                None => events.next().await?,
+            };
+            // only turning the cube counts as using it, not e.g. battery updates
+            let deadline = match evt {
+                SmartcubeEvent::StateChange(..) => Some(Instant::now() + timeout),
+                _ => deadline,
+            };
+            Some((evt, (events, deadline)))
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cubestruct::CubieCube;
+    use futures::channel::mpsc;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    fn turn() -> SmartcubeEvent {
+        SmartcubeEvent::StateChange(CubieCube::SOLVED, std::time::Instant::now())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn idle_once_per_period() {
+        let (tx, rx) = mpsc::unbounded();
+        let mut events = std::pin::pin!(watchdog(rx, TIMEOUT));
+        let start = Instant::now();
+
+        assert!(matches!(
+            events.next().await,
+            Some(SmartcubeEvent::Idle(TIMEOUT))
+        ));
+        assert_eq!(start.elapsed(), TIMEOUT);
+        // still idle, but it's already been said
+        assert!(tokio::time::timeout(TIMEOUT * 3, events.next())
+            .await
+            .is_err());
+
+        tx.unbounded_send(turn()).unwrap();
+        assert!(matches!(
+            events.next().await,
+            Some(SmartcubeEvent::StateChange(..))
+        ));
+        let turned = Instant::now();
+        assert!(matches!(
+            events.next().await,
+            Some(SmartcubeEvent::Idle(TIMEOUT))
+        ));
+        assert_eq!(turned.elapsed(), TIMEOUT);
+
+        drop(tx);
+        assert!(events.next().await.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn only_turns_reset_timer() {
+        let (tx, rx) = mpsc::unbounded();
+        let mut events = std::pin::pin!(watchdog(rx, TIMEOUT));
+        let start = Instant::now();
+
+        tokio::time::sleep(TIMEOUT / 2).await;
+        tx.unbounded_send(SmartcubeEvent::Battery(50)).unwrap();
+        assert!(matches!(
+            events.next().await,
+            Some(SmartcubeEvent::Battery(50))
+        ));
+        assert!(matches!(events.next().await, Some(SmartcubeEvent::Idle(_))));
+        assert_eq!(start.elapsed(), TIMEOUT);
+
+        tokio::time::sleep(TIMEOUT / 2).await;
+        tx.unbounded_send(turn()).unwrap();
+        assert!(matches!(
+            events.next().await,
+            Some(SmartcubeEvent::StateChange(..))
+        ));
+        let turned = Instant::now();
+        tokio::time::sleep(TIMEOUT / 2).await;
+        tx.unbounded_send(turn()).unwrap();
+        assert!(matches!(
+            events.next().await,
+            Some(SmartcubeEvent::StateChange(..))
+        ));
+        assert!(matches!(events.next().await, Some(SmartcubeEvent::Idle(_))));
+        assert_eq!(turned.elapsed(), TIMEOUT * 3 / 2);
+    }
+}
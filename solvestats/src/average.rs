@@ -0,0 +1,276 @@
+use crate::time::{render_time, round_average, Precision};
+use std::cmp::Ordering;
+use std::fmt;
+use std::time::Duration;
+
+/// An average or mean of results
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Average {
+    Dnf,
+    Some(Duration),
+}
+
+impl Average {
+    /// Better averages first, with DNFs last
+    pub fn cmp_rank(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Average::Dnf, Average::Dnf) => Ordering::Equal,
+            (Average::Some(_), Average::Dnf) => Ordering::Less,
+            (Average::Dnf, Average::Some(_)) => Ordering::Greater,
+            (Average::Some(x), Average::Some(y)) => x.cmp(y),
+        }
+    }
+
+    /// `DNF`, or the time rounded as the WCA does (see [`round_average`]) with the given
+    /// decimal separator, e.g. `1:02,35`
+    pub fn render(&self, decimal_separator: &str) -> String {
+        match self {
+            Average::Dnf => "DNF".into(),
+            Average::Some(time) => render_time(
+                &round_average(*time),
+                Precision::Hundredths,
+                decimal_separator,
+            ),
+        }
+    }
+}
+
+/// Like [`Average::render`], with a `.` as the decimal separator
+impl fmt::Display for Average {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render("."))
+    }
+}
+
+/// Which results are dropped from an average before the rest are meaned
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TrimRule {
+    /// The best and worst 5% (rounded up) are dropped, as per the WCA regulations, so an
+    /// average of 5 or 12 drops one at each end. It's a DNF if more results are DNFs than
+    /// are dropped from the slow end.
+    Wca,
+    /// Nothing is dropped, so it's a DNF if any result is, as for a mean of 3
+    Mean,
+}
+
+impl TrimRule {
+    /// How many of `n` results are dropped from each end. At least one result is always
+    /// left, so fewer than 3 results have nothing dropped.
+    pub fn trimmed_each_end(self, n: usize) -> usize {
+        match self {
+            TrimRule::Wca => (n * 5).div_ceil(100).min(n.saturating_sub(1) / 2),
+            TrimRule::Mean => 0,
+        }
+    }
+
+    /// Indices of the `results` that are dropped: the best ones, fastest first, then the
+    /// worst ones, slowest last. Of equal results, the earlier ones count as faster.
+    pub fn trimmed_indices(self, results: &[Option<Duration>]) -> Vec<usize> {
+        let num_trimmed = self.trimmed_each_end(results.len());
+        let mut order = (0..results.len()).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| compare_times(&results[a], &results[b]));
+        let mut trimmed = order[..num_trimmed].to_vec();
+        trimmed.extend_from_slice(&order[order.len() - num_trimmed..]);
+        trimmed
+    }
+
+    /// The average of `results` with this rule: the mean of the ones that aren't dropped,
+    /// which is a DNF if any of them are. Nothing averages to a DNF.
+    pub fn apply(self, results: &[Option<Duration>]) -> Average {
+        let trimmed = self.trimmed_indices(results);
+        let counting = results
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !trimmed.contains(idx))
+            .map(|(_, &result)| result)
+            .collect::<Option<Vec<_>>>();
+        match counting {
+            Some(ok) if !ok.is_empty() => {
+                Average::Some(ok.iter().sum::<Duration>() / ok.len() as u32)
+            }
+            _ => Average::Dnf,
+        }
+    }
+}
+
+/// Average of `results` as per the WCA regulations (see [`TrimRule::Wca`])
+pub fn average_of(results: &[Option<Duration>]) -> Average {
+    TrimRule::Wca.apply(results)
+}
+
+/// Mean of `results`, which is a DNF if any of them are
+pub fn mean_of(results: &[Option<Duration>]) -> Average {
+    TrimRule::Mean.apply(results)
+}
+
+/// Mean of the non-DNF `results`, or a DNF if they're all DNFs
+pub fn session_mean_of(results: &[Option<Duration>]) -> Average {
+    let ok = results.iter().flatten().collect::<Vec<_>>();
+    if ok.is_empty() {
+        return Average::Dnf;
+    }
+    Average::Some(ok.iter().copied().sum::<Duration>() / ok.len() as u32)
+}
+
+/// Faster results first, with DNFs (`None`) last
+pub fn compare_times(a: &Option<Duration>, b: &Option<Duration>) -> Ordering {
+    match (a, b) {
+        (None, None) => Ordering::Equal,
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(l), Some(r)) => l.cmp(r),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(times: &[f64]) -> Vec<Option<Duration>> {
+        times
+            .iter()
+            .map(|&t| (t >= 0.0).then(|| Duration::from_secs_f64(t)))
+            .collect()
+    }
+
+    #[test]
+    fn averages() {
+        assert_eq!(
+            TrimRule::Wca.apply(&secs(&[10.0, 12.0, 11.0, 14.0, 9.0])),
+            Average::Some(Duration::from_secs(11))
+        );
+        assert_eq!(
+            TrimRule::Wca.apply(&secs(&[10.0, -1.0, 11.0, 14.0, 12.0])),
+            Average::Some(Duration::from_secs(37) / 3)
+        );
+        assert_eq!(
+            TrimRule::Wca.apply(&secs(&[10.0, -1.0, 11.0, -1.0, 12.0])),
+            Average::Dnf
+        );
+        assert_eq!(
+            mean_of(&secs(&[10.0, 11.0, 15.0])),
+            Average::Some(Duration::from_secs(12))
+        );
+        assert_eq!(mean_of(&secs(&[10.0, -1.0, 15.0])), Average::Dnf);
+        assert_eq!(
+            session_mean_of(&secs(&[10.0, -1.0, 15.0])),
+            Average::Some(Duration::from_millis(12_500))
+        );
+        assert_eq!(session_mean_of(&secs(&[-1.0])), Average::Dnf);
+    }
+
+    #[test]
+    fn wca_trim_counts() {
+        for (n, trimmed) in [
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (3, 1),
+            (5, 1),
+            (12, 1),
+            (20, 1),
+            (21, 2),
+            (50, 3),
+            (100, 5),
+            (101, 6),
+        ] {
+            assert_eq!(TrimRule::Wca.trimmed_each_end(n), trimmed, "{n}");
+            assert_eq!(TrimRule::Mean.trimmed_each_end(n), 0);
+        }
+    }
+
+    #[test]
+    fn every_dnf_placement() {
+        let base = [11.0, 9.0, 14.0, 10.0, 13.0];
+        for mask in 0..1 << base.len() {
+            let results = secs(&base)
+                .into_iter()
+                .enumerate()
+                .map(|(i, t)| t.filter(|_| mask & (1 << i) == 0))
+                .collect::<Vec<_>>();
+            let dnfs = results.iter().filter(|t| t.is_none()).count();
+
+            let ao5 = TrimRule::Wca.apply(&results);
+            let mo5 = TrimRule::Mean.apply(&results);
+            match dnfs {
+                0 => {
+                    // 9 and 14 are dropped
+                    assert_eq!(ao5, Average::Some(Duration::from_secs(34) / 3));
+                    assert_eq!(mo5, Average::Some(Duration::from_secs(57) / 5));
+                }
+                1 => {
+                    // the DNF is dropped as the worst, along with the best of the rest
+                    let mut ok = results.iter().flatten().copied().collect::<Vec<_>>();
+                    ok.sort();
+                    let counting = ok[1..].iter().sum::<Duration>() / 3;
+                    assert_eq!(ao5, Average::Some(counting), "{mask:05b}");
+                    assert_eq!(mo5, Average::Dnf);
+                }
+                _ => {
+                    assert_eq!(ao5, Average::Dnf, "{mask:05b}");
+                    assert_eq!(mo5, Average::Dnf);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ao12_dnfs() {
+        let mut results = secs(&[10.0; 12]);
+        results[3] = None;
+        results[7] = Some(Duration::from_secs(4));
+        assert_eq!(average_of(&results), Average::Some(Duration::from_secs(10)));
+        results[0] = None;
+        assert_eq!(average_of(&results), Average::Dnf);
+    }
+
+    #[test]
+    fn trimmed_ties() {
+        let results = secs(&[10.0; 5]);
+        assert_eq!(TrimRule::Wca.trimmed_indices(&results), [0, 4]);
+        // the later DNF counts as the worst
+        let results = secs(&[-1.0, 9.0, -1.0, 9.0, 9.0]);
+        assert_eq!(TrimRule::Wca.trimmed_indices(&results), [1, 2]);
+        assert!(TrimRule::Mean.trimmed_indices(&results).is_empty());
+        assert_eq!(TrimRule::Wca.apply(&[]), Average::Dnf);
+        assert_eq!(mean_of(&[]), Average::Dnf);
+    }
+
+    #[test]
+    fn plus2_changes_the_trim() {
+        use crate::{Session, Solve};
+
+        let solves = [10, 11, 12, 13, 14].map(|secs| Solve::new(Duration::from_secs(secs)));
+        let mut penalized = solves.clone();
+        // the 10 becomes a 12, so the 11 is the best
+        penalized[0] = penalized[0].clone().plus2();
+        let session = penalized.iter().cloned().collect::<Session>();
+        assert_eq!(
+            session.current(5, TrimRule::Wca),
+            Some(Average::Some(Duration::from_secs(37) / 3))
+        );
+        assert_eq!(TrimRule::Wca.trimmed_indices(session.results()), [1, 4]);
+        // a +2 on a DNF doesn't bring it back
+        penalized[2] = penalized[2].clone().dnf().plus2();
+        penalized[3] = penalized[3].clone().dnf();
+        let session = penalized.into_iter().collect::<Session>();
+        assert_eq!(session.current(5, TrimRule::Wca), Some(Average::Dnf));
+        assert_eq!(
+            solves.into_iter().collect::<Session>().mean(),
+            Average::Some(Duration::from_secs(12))
+        );
+    }
+
+    #[test]
+    fn rendering() {
+        // averages are rounded to the nearest hundredth, unlike singles
+        let average = Average::Some(Duration::from_millis(62_359));
+        assert_eq!(average.to_string(), "1:02.36");
+        assert_eq!(average.render(","), "1:02,36");
+        assert_eq!(
+            Average::Some(Duration::from_millis(9_080)).to_string(),
+            "9.08"
+        );
+        assert_eq!(Average::Dnf.to_string(), "DNF");
+    }
+}
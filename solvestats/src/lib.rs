@@ -0,0 +1,15 @@
+//! Statistics of speedsolving results, independent of any UI: averages and means as the WCA
+//! defines them, the solves and sessions they're worked out from, and showing times rounded
+//! as the WCA rounds them.
+//!
+//! A result is an `Option<Duration>`, the time including any penalties, with `None` for a
+//! DNF.
+mod average;
+mod session;
+mod solve;
+mod time;
+
+pub use average::{average_of, compare_times, mean_of, session_mean_of, Average, TrimRule};
+pub use session::{NextSolve, RecordChange, Session};
+pub use solve::Solve;
+pub use time::{render_single, render_time, round_average, round_single, Precision};
@@ -0,0 +1,264 @@
+use crate::{average_of, session_mean_of, Average, Solve, TrimRule};
+use std::cmp::Ordering;
+use std::time::Duration;
+
+/// The results of a run of solves, oldest first
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Session {
+    results: Vec<Option<Duration>>,
+}
+
+impl Session {
+    /// From results, where `None` is a DNF
+    pub fn from_results(results: Vec<Option<Duration>>) -> Self {
+        Self { results }
+    }
+
+    pub fn results(&self) -> &[Option<Duration>] {
+        &self.results
+    }
+
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    pub fn push(&mut self, result: Option<Duration>) {
+        self.results.push(result);
+    }
+
+    /// Fastest non-DNF result
+    pub fn best_single(&self) -> Option<Duration> {
+        self.results.iter().flatten().min().copied()
+    }
+
+    /// Mean of the non-DNF results (see [`session_mean_of`])
+    pub fn mean(&self) -> Average {
+        session_mean_of(&self.results)
+    }
+
+    /// `rule` applied to the `n` results starting at `start`
+    ///
+    /// # Panics
+    /// If there aren't `n` results from `start`
+    pub fn window(&self, start: usize, n: usize, rule: TrimRule) -> Average {
+        rule.apply(&self.results[start..start + n])
+    }
+
+    /// `rule` applied to the last `n` results, or `None` if there aren't `n` yet
+    pub fn current(&self, n: usize, rule: TrimRule) -> Option<Average> {
+        let start = self.len().checked_sub(n)?;
+        Some(self.window(start, n, rule))
+    }
+
+    /// Best result of `rule` over every `n` consecutive results, or `None` if there aren't
+    /// `n` yet
+    pub fn best(&self, n: usize, rule: TrimRule) -> Option<Average> {
+        self.results
+            .windows(n)
+            .map(|window| rule.apply(window))
+            .min_by(Average::cmp_rank)
+    }
+
+    /// What the next result has to be for the average of the last `n` (see [`average_of`])
+    /// to be at most `target`, or `None` if there aren't `n - 1` results yet
+    pub fn next_solve_needed(&self, n: usize, target: Duration) -> Option<NextSolve> {
+        let start = (self.len() + 1).checked_sub(n)?;
+        Some(next_solve_needed(&self.results[start..], target))
+    }
+
+    /// The best ao5 and ao12 that the result at `index` is part of, and what they'd be
+    /// without it. Averages that another one just as good would replace aren't included.
+    pub fn record_changes(&self, index: usize) -> Vec<RecordChange> {
+        [5, 12]
+            .into_iter()
+            .filter_map(|n| {
+                let (start, before) = self
+                    .results
+                    .windows(n)
+                    .map(average_of)
+                    .enumerate()
+                    .min_by(|(_, a), (_, b)| a.cmp_rank(b))?;
+                // an average that's a DNF isn't much of a record
+                if before == Average::Dnf || !(start..start + n).contains(&index) {
+                    return None;
+                }
+                let mut remaining = self.clone();
+                remaining.results.remove(index);
+                let after = remaining.best(n, TrimRule::Wca);
+                // another average that's just as good would still be there
+                if after.is_some_and(|after| after.cmp_rank(&before) == Ordering::Equal) {
+                    return None;
+                }
+                Some(RecordChange { n, before, after })
+            })
+            .collect()
+    }
+}
+
+/// What the next solve has to be for an average to reach a target
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum NextSolve {
+    /// Even a DNF would do
+    Any,
+    /// A time of at most this
+    Under(Duration),
+    /// Not even a 0.00 would do
+    Impossible,
+}
+
+/// What the next result has to be for the average (see [`average_of`]) of `recent` and it
+/// to be at most `target`
+fn next_solve_needed(recent: &[Option<Duration>], target: Duration) -> NextSolve {
+    let reaches = |next: Option<Duration>| {
+        let mut results = recent.to_vec();
+        results.push(next);
+        matches!(average_of(&results), Average::Some(average) if average <= target)
+    };
+    if reaches(None) {
+        return NextSolve::Any;
+    }
+    if !reaches(Some(Duration::ZERO)) {
+        return NextSolve::Impossible;
+    }
+    // A time this slow is either dropped as the worst, so it's no better than a DNF, or counts
+    // (if there's already a DNF) and makes the average slower than `target`. Either way it
+    // doesn't reach it, and slower times don't either.
+    let slowest = recent.iter().flatten().max().copied().unwrap_or_default();
+    let too_slow = slowest.max(target) * (recent.len() as u32 + 1);
+    // in hundredths, since that's how times are shown
+    let hundredths = |n: u64| Some(Duration::from_millis(n * 10));
+    let (mut fast_enough, mut too_slow) = (0, too_slow.as_millis() as u64 / 10 + 1);
+    while too_slow - fast_enough > 1 {
+        let mid = (fast_enough + too_slow) / 2;
+        if reaches(hundredths(mid)) {
+            fast_enough = mid;
+        } else {
+            too_slow = mid;
+        }
+    }
+    NextSolve::Under(hundredths(fast_enough).unwrap())
+}
+
+/// A best average that would change if a result was removed
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct RecordChange {
+    /// Number of results in the average
+    pub n: usize,
+    pub before: Average,
+    /// `None` if there wouldn't be enough results left for one
+    pub after: Option<Average>,
+}
+
+impl FromIterator<Solve> for Session {
+    fn from_iter<I: IntoIterator<Item = Solve>>(solves: I) -> Self {
+        Self::from_results(solves.into_iter().map(|solve| solve.result()).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(results: &[f64]) -> Vec<Option<Duration>> {
+        results
+            .iter()
+            .map(|&t| (t >= 0.0).then(|| Duration::from_secs_f64(t)))
+            .collect()
+    }
+
+    #[test]
+    fn windows() {
+        let secs = Duration::from_secs;
+        let session = [12, 10, 14, 11, 13, 9]
+            .map(|s| Solve::new(secs(s)))
+            .into_iter()
+            .collect::<Session>();
+        assert_eq!(session.best_single(), Some(secs(9)));
+        assert_eq!(session.mean(), Average::Some(secs(23) / 2));
+        assert_eq!(
+            session.current(5, TrimRule::Wca),
+            Some(Average::Some(secs(34) / 3))
+        );
+        assert_eq!(
+            session.best(5, TrimRule::Wca),
+            Some(Average::Some(secs(34) / 3))
+        );
+        assert_eq!(
+            session.best(3, TrimRule::Mean),
+            Some(Average::Some(secs(11)))
+        );
+        assert_eq!(session.current(7, TrimRule::Wca), None);
+        assert_eq!(Session::default().best_single(), None);
+    }
+
+    #[test]
+    fn next_solve_predictions() {
+        let session = Session::from_results(secs(&[10.0, 11.0, 12.0, 13.0]));
+        let needed = |target: f64| {
+            session
+                .next_solve_needed(5, Duration::from_secs_f64(target))
+                .unwrap()
+        };
+        assert_eq!(needed(12.0), NextSolve::Any);
+        assert_eq!(
+            needed(11.5),
+            NextSolve::Under(Duration::from_secs_f64(11.5))
+        );
+        assert_eq!(needed(11.0), NextSolve::Under(Duration::from_secs(10)));
+        assert_eq!(needed(10.5), NextSolve::Impossible);
+        // only the last 4 count towards the next ao5
+        let longer = Session::from_results(secs(&[1.0, 10.0, 11.0, 12.0, 13.0]));
+        assert_eq!(
+            longer.next_solve_needed(5, Duration::from_secs(11)),
+            Some(NextSolve::Under(Duration::from_secs(10)))
+        );
+        assert_eq!(session.next_solve_needed(12, Duration::from_secs(11)), None);
+
+        // with a DNF already, the next time counts however slow it is
+        let session = Session::from_results(secs(&[10.0, 11.0, 12.0, -1.0]));
+        assert_eq!(
+            session.next_solve_needed(5, Duration::from_secs(12)),
+            Some(NextSolve::Under(Duration::from_secs(13)))
+        );
+        // and with two, nothing will do
+        let session = Session::from_results(secs(&[10.0, -1.0, 12.0, -1.0]));
+        assert_eq!(
+            session.next_solve_needed(5, Duration::from_secs(60)),
+            Some(NextSolve::Impossible)
+        );
+    }
+
+    #[test]
+    fn record_changes_on_removal() {
+        let session = Session::from_results(secs(&[20.0, 10.0, 11.0, 12.0, 13.0, 14.0, 20.0]));
+        assert!(session.record_changes(6).is_empty());
+        // the next ao5 is just as good
+        assert!(session.record_changes(0).is_empty());
+        assert_eq!(
+            session.record_changes(2),
+            [RecordChange {
+                n: 5,
+                before: Average::Some(Duration::from_secs(12)),
+                after: Some(Average::Some(Duration::from_secs(13))),
+            }]
+        );
+
+        // not enough left for an ao5 afterwards
+        let session = Session::from_results(session.results()[..5].to_vec());
+        assert_eq!(
+            session.record_changes(4),
+            [RecordChange {
+                n: 5,
+                before: Average::Some(Duration::from_secs(12)),
+                after: None,
+            }]
+        );
+        assert!(Session::from_results(secs(&[-1.0; 5]))
+            .record_changes(0)
+            .is_empty());
+    }
+}
@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+/// One attempt: the time the timer stopped at, and the penalties it was given
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Solve {
+    /// Without any penalty
+    pub time: Duration,
+    pub dnf: bool,
+    /// Seconds added by each time penalty. They're kept when the solve is a DNF, so that
+    /// they're back if the DNF is removed.
+    pub added: Vec<u32>,
+}
+
+impl Solve {
+    /// A solve with no penalties
+    pub fn new(time: Duration) -> Self {
+        Self {
+            time,
+            ..Default::default()
+        }
+    }
+
+    /// The same solve with a +2 on top of any time penalties it has
+    pub fn plus2(mut self) -> Self {
+        self.added.push(2);
+        self
+    }
+
+    /// The same solve as a DNF
+    pub fn dnf(mut self) -> Self {
+        self.dnf = true;
+        self
+    }
+
+    /// Time added by all the time penalties
+    pub fn added_time(&self) -> Duration {
+        Duration::from_secs(self.added.iter().copied().map(u64::from).sum())
+    }
+
+    /// The time including penalties, or `None` if it's a DNF
+    pub fn result(&self) -> Option<Duration> {
+        (!self.dnf).then(|| self.time + self.added_time())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn results() {
+        let solve = Solve::new(Duration::from_millis(9_870));
+        assert_eq!(solve.result(), Some(Duration::from_millis(9_870)));
+        assert_eq!(
+            solve.clone().plus2().plus2().result(),
+            Some(Duration::from_millis(13_870))
+        );
+        assert_eq!(solve.plus2().dnf().result(), None);
+    }
+}
@@ -0,0 +1,146 @@
+//! Showing times. They're kept at full precision, and only rounded when they're shown, the
+//! way the WCA rounds results (regulation 9f).
+use std::time::Duration;
+
+/// How finely a time is shown
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Precision {
+    Seconds,
+    Tenths,
+    Hundredths,
+    Milliseconds,
+}
+
+impl Precision {
+    /// Number of digits after the decimal separator
+    fn digits(self) -> u32 {
+        match self {
+            Precision::Seconds => 0,
+            Precision::Tenths => 1,
+            Precision::Hundredths => 2,
+            Precision::Milliseconds => 3,
+        }
+    }
+
+    /// The smallest step shown, in microseconds
+    fn step_micros(self) -> u128 {
+        10u128.pow(6 - self.digits())
+    }
+}
+
+/// Results this long or longer are rounded to the second
+const ROUNDED_TO_SECONDS: Duration = Duration::from_secs(10 * 60);
+
+const SECOND_MICROS: u128 = 1_000_000;
+const HUNDREDTH_MICROS: u128 = 10_000;
+
+/// A single result as the WCA records it: truncated to the hundredth if it's under 10
+/// minutes, otherwise rounded to the nearest second
+pub fn round_single(time: Duration) -> Duration {
+    if time < ROUNDED_TO_SECONDS {
+        round_down(time, HUNDREDTH_MICROS)
+    } else {
+        round_nearest(time, SECOND_MICROS)
+    }
+}
+
+/// An average or mean as the WCA records it: rounded to the nearest hundredth if it's
+/// under 10 minutes, otherwise to the nearest second
+pub fn round_average(time: Duration) -> Duration {
+    if time < ROUNDED_TO_SECONDS {
+        round_nearest(time, HUNDREDTH_MICROS)
+    } else {
+        round_nearest(time, SECOND_MICROS)
+    }
+}
+
+fn round_down(time: Duration, step_micros: u128) -> Duration {
+    micros_duration(time.as_micros() / step_micros * step_micros)
+}
+
+/// Halves are rounded up
+fn round_nearest(time: Duration, step_micros: u128) -> Duration {
+    micros_duration((time.as_micros() + step_micros / 2) / step_micros * step_micros)
+}
+
+fn micros_duration(micros: u128) -> Duration {
+    Duration::from_micros(micros.try_into().unwrap_or(u64::MAX))
+}
+
+/// `time` to the given precision (rounded down, so a running timer never shows a time it
+/// hasn't reached), with minutes if it's at least one, e.g. `1:02,35`. Round it with
+/// [`round_single`] or [`round_average`] first if it's a result.
+pub fn render_time(time: &Duration, precision: Precision, decimal_separator: &str) -> String {
+    let steps_per_second = SECOND_MICROS / precision.step_micros();
+    let steps = time.as_micros() / precision.step_micros();
+    let fraction = steps % steps_per_second;
+    let total_secs = steps / steps_per_second;
+    let (mins, secs) = (total_secs / 60, total_secs % 60);
+
+    let fraction = match precision.digits() {
+        0 => String::new(),
+        digits => format!(
+            "{decimal_separator}{fraction:0width$}",
+            width = digits as usize
+        ),
+    };
+    if mins == 0 {
+        format!("{secs}{fraction}")
+    } else {
+        format!("{mins}:{secs:02}{fraction}")
+    }
+}
+
+/// A single result to the hundredth, rounded as the WCA does (see [`round_single`])
+pub fn render_single(time: &Duration, decimal_separator: &str) -> String {
+    render_time(
+        &round_single(*time),
+        Precision::Hundredths,
+        decimal_separator,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wca_rounding() {
+        let millis = Duration::from_millis;
+        assert_eq!(round_single(millis(12_349)), millis(12_340));
+        assert_eq!(round_average(millis(12_345)), millis(12_350));
+        assert_eq!(round_average(millis(12_344)), millis(12_340));
+        // the micros that smart cubes and the timer record are kept until shown
+        assert_eq!(
+            round_single(Duration::from_micros(9_999_999)),
+            millis(9_990)
+        );
+
+        // from 10 minutes, both are to the nearest second
+        assert_eq!(round_single(millis(600_499)), millis(600_000));
+        assert_eq!(round_single(millis(600_500)), millis(601_000));
+        assert_eq!(round_average(millis(754_720)), millis(755_000));
+        // just under 10 minutes is still to the nearest hundredth
+        assert_eq!(round_average(millis(599_996)), millis(600_000));
+    }
+
+    #[test]
+    fn rendering() {
+        let millis = Duration::from_millis;
+        assert_eq!(
+            render_time(&millis(65_678), Precision::Hundredths, ","),
+            "1:05,67"
+        );
+        assert_eq!(
+            render_time(&millis(65_678), Precision::Seconds, "."),
+            "1:05"
+        );
+        assert_eq!(
+            render_time(&millis(3_723_004), Precision::Milliseconds, "."),
+            "62:03.004"
+        );
+        assert_eq!(render_time(&millis(7), Precision::Tenths, "."), "0.0");
+        assert_eq!(render_single(&millis(9_087), "."), "9.08");
+        assert_eq!(render_single(&millis(612_730), "."), "10:13.00");
+    }
+}